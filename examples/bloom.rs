@@ -0,0 +1,210 @@
+// Renders a triangle into an `OffscreenTexture` at half the resolution of the window, then draws
+// it onto the window at full resolution with a blurred copy of the texture added on top.
+
+use std::sync::Arc;
+use test03::hammer::{self, DrawTarget};
+use vulkano::{
+    format::Format,
+    pipeline::{
+        graphics::{
+            color_blend::ColorBlendState, input_assembly::InputAssemblyState,
+            vertex_input::BuffersDefinition, viewport::ViewportState,
+        },
+        GraphicsPipeline,
+    },
+    render_pass::{RenderPass, Subpass},
+    sampler::{Sampler, SamplerCreateInfo},
+};
+use winit::{
+    event::{Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    window::WindowBuilder,
+};
+
+mod common;
+
+fn main() {
+    env_logger::init();
+    let event_loop = EventLoop::new();
+    mod scene_vs {
+        vulkano_shaders::shader! {
+            ty: "vertex",
+            src: "
+                #version 450
+                const vec2 positions[3] = vec2[](vec2(-0.5, -0.25), vec2(0.0, 0.5), vec2(0.25, -0.1));
+                void main() {
+                    gl_Position = vec4(positions[gl_VertexIndex], 0.0, 1.0);
+                }
+            "
+        }
+    }
+
+    mod scene_fs {
+        vulkano_shaders::shader! {
+            ty: "fragment",
+            src: "
+                #version 450
+                layout(location = 0) out vec4 f_color;
+                void main() {
+                    f_color = vec4(1.0, 0.0, 0.0, 1.0);
+                }
+            "
+        }
+    }
+
+    // A single triangle covering the whole target, with texture coordinates from 0 to 1 on it.
+    mod post_vs {
+        vulkano_shaders::shader! {
+            ty: "vertex",
+            src: "
+                #version 450
+                layout(location = 0) out vec2 uv;
+                void main() {
+                    uv = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2);
+                    gl_Position = vec4(uv * 2.0 - 1.0, 0.0, 1.0);
+                }
+            "
+        }
+    }
+
+    mod blur_fs {
+        vulkano_shaders::shader! {
+            ty: "fragment",
+            src: "
+                #version 450
+                layout(location = 0) in vec2 uv;
+                layout(location = 0) out vec4 f_color;
+                layout(set = 0, binding = 0) uniform sampler2D glow;
+                void main() {
+                    vec2 texel = 1.0 / vec2(textureSize(glow, 0));
+                    vec3 sum = vec3(0.0);
+                    for (int x = -2; x <= 2; x++) {
+                        for (int y = -2; y <= 2; y++) {
+                            sum += texture(glow, uv + vec2(x, y) * texel).rgb;
+                        }
+                    }
+                    f_color = vec4(sum / 25.0, 1.0);
+                }
+            "
+        }
+    }
+
+    let (mut surface, device, queue) =
+        common::init(&event_loop, WindowBuilder::new().with_title("Bloom"));
+
+    let half_extent = |extent: [u32; 2]| [(extent[0] / 2).max(1), (extent[1] / 2).max(1)];
+    let mut glow = hammer::OffscreenTexture::new(
+        device.clone(),
+        half_extent(surface.extent().unwrap()),
+        Format::R8G8B8A8_UNORM,
+    )
+    .unwrap();
+    let glow_pass = glow.create_render_pass().unwrap();
+    let surface_pass = surface.create_render_pass().unwrap();
+
+    let scene_vs = scene_vs::load(device.clone()).unwrap();
+    let scene_fs = scene_fs::load(device.clone()).unwrap();
+    let post_vs = post_vs::load(device.clone()).unwrap();
+    let blur_fs = blur_fs::load(device.clone()).unwrap();
+    // The triangle is drawn into both render passes, which have different formats.
+    let scene_pipeline = |render_pass: &Arc<RenderPass>| {
+        GraphicsPipeline::start()
+            .vertex_input_state(BuffersDefinition::new())
+            .vertex_shader(scene_vs.entry_point("main").unwrap(), ())
+            .input_assembly_state(InputAssemblyState::new())
+            .viewport_state(ViewportState::viewport_dynamic_scissor_irrelevant())
+            .fragment_shader(scene_fs.entry_point("main").unwrap(), ())
+            .render_pass(Subpass::from(render_pass.clone(), 0).unwrap())
+            .build(device.clone())
+            .unwrap()
+    };
+    let glow_pipeline = scene_pipeline(&glow_pass);
+    let surface_scene_pipeline = scene_pipeline(&surface_pass);
+    // The blurred glow is added to the triangle already on the window.
+    let blur_pipeline = GraphicsPipeline::start()
+        .vertex_input_state(BuffersDefinition::new())
+        .vertex_shader(post_vs.entry_point("main").unwrap(), ())
+        .input_assembly_state(InputAssemblyState::new())
+        .viewport_state(ViewportState::viewport_dynamic_scissor_irrelevant())
+        .fragment_shader(blur_fs.entry_point("main").unwrap(), ())
+        .color_blend_state(ColorBlendState::new(1).blend_additive())
+        .render_pass(Subpass::from(surface_pass.clone(), 0).unwrap())
+        .build(device.clone())
+        .unwrap();
+
+    let sampler = Sampler::new(
+        device.clone(),
+        SamplerCreateInfo::simple_repeat_linear_no_mipmap(),
+    )
+    .unwrap();
+    let glow_pipeline_layout = blur_pipeline.clone();
+    let glow_group = move |glow: &hammer::OffscreenTexture| {
+        hammer::BindGroup::builder(&glow_pipeline_layout, 0)
+            .texture(0, glow.sampled_view())
+            .sampler(0, &sampler)
+            .build()
+            .unwrap()
+    };
+    let mut bind_group = glow_group(&glow);
+
+    // Every frame writes the same texture, so only one frame can be in flight.
+    let mut frame_sync = hammer::FrameSync::new(&device, 1);
+
+    event_loop.run(move |event, _, control_flow| match event {
+        Event::WindowEvent {
+            event: WindowEvent::CloseRequested,
+            ..
+        } => {
+            if let Err(err) = frame_sync.shutdown(&device) {
+                println!("Failed to shut down: {}", err);
+            }
+            *control_flow = ControlFlow::Exit;
+        }
+        Event::WindowEvent { event, .. } => surface.handle_window_event(&event),
+        Event::RedrawEventsCleared => {
+            let frame = match frame_sync.begin_frame(&mut surface) {
+                Ok(frame) => frame,
+                Err(hammer::FrameError::RecreateNeeded) => return,
+                Err(e) => panic!("Failed to begin frame: {}", e),
+            };
+            // The glow keeps half the size of the window.
+            let extent = half_extent(surface.extent().unwrap());
+            if glow.extent() != extent {
+                glow.resize(extent).unwrap();
+                bind_group = glow_group(&glow);
+            }
+
+            let mut encoder = device.create_command_encoder(&queue).unwrap();
+            common::draw_to_target(
+                &mut encoder,
+                &glow,
+                &glow_pass,
+                &glow_pipeline,
+                None,
+                [0.0, 0.0, 0.0, 1.0],
+            );
+            let target = surface.draw_target(&frame.image);
+            let mut pass = encoder
+                .begin_render_pass_with(
+                    target.framebuffer(&surface_pass).unwrap(),
+                    target.clear_values([0.0, 0.0, 0.0, 1.0]),
+                )
+                .unwrap();
+            pass.set_viewport(target.viewport())
+                .bind_pipeline(&surface_scene_pipeline)
+                .draw(3, 1, 0, 0)
+                .unwrap()
+                .bind_pipeline(&blur_pipeline)
+                .bind_group(0, &bind_group)
+                .draw(3, 1, 0, 0)
+                .unwrap();
+            pass.finish().unwrap();
+            let command_buffer = encoder.finish().unwrap();
+            match frame_sync.end_frame(&mut surface, frame, command_buffer, &queue) {
+                Ok(()) | Err(hammer::FrameError::RecreateNeeded) => {}
+                Err(e) => println!("Failed to end frame: {}", e),
+            }
+        }
+        _ => (),
+    })
+}
@@ -0,0 +1,75 @@
+// Setup shared by the examples. Every example only uses some of it.
+#![allow(dead_code)]
+
+use std::sync::Arc;
+use test03::hammer::{self, DrawTarget};
+use vulkano::instance::{InstanceCreateInfo, InstanceExtensions};
+use vulkano::pipeline::GraphicsPipeline;
+use vulkano::render_pass::RenderPass;
+use winit::event_loop::EventLoop;
+use winit::window::{Window, WindowBuilder};
+
+/// Creates an instance with the extensions needed to draw to a window.
+///
+/// In debug builds the debug utils extension is enabled as well where it is available, so the
+/// passes and buffers of the examples show up with their names in debuggers like RenderDoc.
+pub fn instance() -> hammer::Instance {
+    let enabled_extensions = InstanceExtensions {
+        ext_debug_utils: cfg!(debug_assertions)
+            && InstanceExtensions::supported_by_core()
+                .is_ok_and(|supported| supported.ext_debug_utils),
+        ..vulkano_win::required_extensions()
+    };
+    hammer::Instance::new(InstanceCreateInfo {
+        enabled_extensions,
+        ..Default::default()
+    })
+}
+
+/// Opens `window` and creates a device on the first adapter that can present to it, along with
+/// the swapchain of the window.
+pub fn init(
+    event_loop: &EventLoop<()>,
+    window: WindowBuilder,
+) -> (hammer::Surface<Window>, hammer::Device, hammer::Queue) {
+    let instance = instance();
+    let mut surface = hammer::Surface::new(window.build(event_loop).unwrap(), &instance);
+    let desc = hammer::AdapterDescriptor {
+        supports_surface: Some(&surface),
+        ..hammer::AdapterDescriptor::graphics()
+    };
+    let adapter = instance
+        .request_adapter(&desc)
+        .unwrap_or_else(|err| panic!("Failed to find an adapter: {}", err));
+    let device = adapter
+        .request_device(vulkano::device::Features::default())
+        .unwrap_or_else(|err| panic!("Failed to create the device: {}", err));
+    let queue = device.graphics_queue().unwrap().clone();
+    surface
+        .create_swapchain(device.clone(), &adapter)
+        .unwrap_or_else(|err| panic!("Failed to create the swapchain: {}", err));
+    (surface, device, queue)
+}
+
+/// Records a render pass into `target` that draws a single triangle with `pipeline`, so the same
+/// code renders into an offscreen texture and onto the window.
+pub fn draw_to_target(
+    encoder: &mut hammer::CommandEncoder,
+    target: &dyn DrawTarget,
+    render_pass: &Arc<RenderPass>,
+    pipeline: &Arc<GraphicsPipeline>,
+    bind_group: Option<&hammer::BindGroup>,
+    clear_color: [f32; 4],
+) {
+    let mut pass = encoder
+        .begin_render_pass_with(
+            target.framebuffer(render_pass).unwrap(),
+            target.clear_values(clear_color),
+        )
+        .unwrap();
+    pass.set_viewport(target.viewport()).bind_pipeline(pipeline);
+    if let Some(bind_group) = bind_group {
+        pass.bind_group(0, bind_group);
+    }
+    pass.draw(3, 1, 0, 0).unwrap();
+}
@@ -0,0 +1,111 @@
+// Doubles the index of every element of a storage buffer on the GPU and checks the result.
+//
+// The work is split into chunks by a `CancellableJob`, which a watchdog thread cancels if it takes
+// longer than a few seconds, the way a cancel button would. Only the chunks that were submitted
+// before are checked then.
+
+use std::time::{Duration, Instant};
+use test03::hammer;
+use vulkano::{
+    descriptor_set::{PersistentDescriptorSet, WriteDescriptorSet},
+    pipeline::Pipeline,
+};
+use winit::window::Window;
+
+mod common;
+
+fn main() {
+    env_logger::init();
+    let instance = common::instance();
+    mod cs {
+        vulkano_shaders::shader! {
+            ty: "compute",
+            src: "
+                #version 450
+                layout(local_size_x = 64) in;
+                // Index of the first element of the chunk.
+                layout(push_constant) uniform Chunk {
+                    uint offset;
+                };
+                layout(set = 0, binding = 0) buffer Data {
+                    uint data[];
+                };
+                void main() {
+                    uint index = offset + gl_GlobalInvocationID.x;
+                    if (index < data.length()) {
+                        data[index] = index * 2;
+                    }
+                }
+            "
+        }
+    }
+
+    const COUNT: u32 = 1 << 20;
+    // Dispatched one after another, whole work groups of 64 each.
+    const CHUNK: u32 = 1 << 16;
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    // Any adapter with a compute queue will do, no surface is needed.
+    let desc = hammer::AdapterDescriptor::<Window> {
+        device_extensions: vulkano::device::DeviceExtensions::none(),
+        optional_extensions: vulkano::device::DeviceExtensions::none(),
+        supports_graphics: false,
+        supports_compute: true,
+        ..hammer::AdapterDescriptor::graphics()
+    };
+    let adapter = instance
+        .request_adapter(&desc)
+        .unwrap_or_else(|err| panic!("Failed to find an adapter: {}", err));
+    let device = adapter
+        .request_device(vulkano::device::Features::default())
+        .unwrap_or_else(|err| panic!("Failed to create the device: {}", err));
+
+    let cs = cs::load(device.clone()).unwrap();
+    let pipeline = device
+        .create_compute_pipeline(&cs, "main", &hammer::SpecializationMap::new())
+        .unwrap_or_else(|err| panic!("Failed to create the pipeline: {}", err));
+    let buffer = hammer::Buffer::<u32>::storage(device.clone(), &[0; COUNT as usize]).unwrap();
+    let set = PersistentDescriptorSet::new(
+        pipeline.layout().set_layouts()[0].clone(),
+        [WriteDescriptorSet::buffer(0, buffer.buffer().clone())],
+    )
+    .unwrap();
+
+    let queue = device.compute_queue().unwrap().clone();
+    let job = hammer::CancellableJob::new(queue.handle().clone(), COUNT, CHUNK, |range| {
+        let mut encoder = device.create_command_encoder(&queue).unwrap();
+        encoder
+            .dispatch(&pipeline, set.clone(), &range.start, [CHUNK / 64, 1, 1])
+            .unwrap();
+        encoder.finish().unwrap()
+    });
+    let token = job.cancel_token();
+    let progress = job.progress();
+    std::thread::spawn(move || {
+        let start = Instant::now();
+        while progress.completed() < progress.total() {
+            if start.elapsed() > TIMEOUT {
+                println!("Cancelling after {:?}", TIMEOUT);
+                token.cancel();
+                return;
+            }
+            println!("{:.0}% done", progress.fraction() * 100.0);
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    });
+    let progress = job.progress();
+    let done = match job.wait() {
+        Ok(hammer::JobStatus::Completed) => COUNT,
+        Ok(hammer::JobStatus::Cancelled) => (progress.completed() as u32 * CHUNK).min(COUNT),
+        Err(err) => panic!("Failed to run the compute shader: {}", err),
+    };
+
+    let data = buffer.read(0..done as usize).unwrap();
+    let wrong = (0..done).filter(|&i| data[i as usize] != i * 2).count();
+    if wrong > 0 {
+        println!("{} of {} elements are wrong", wrong, done);
+        std::process::exit(1);
+    }
+    println!("{} of {} elements were doubled on the GPU", done, COUNT);
+    std::process::exit(0)
+}
@@ -0,0 +1,79 @@
+// Renders a triangle with shaders compiled at runtime from `shaders/`. Editing
+// `shaders/hot_reload.frag` while the example runs changes the triangle without a restart.
+
+use test03::hammer;
+use vulkano::render_pass::Subpass;
+use winit::{
+    event::{Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    window::WindowBuilder,
+};
+
+mod common;
+
+fn main() {
+    env_logger::init();
+    let event_loop = EventLoop::new();
+    use hammer::hot_reload::{ShaderSource, ShaderWatcher};
+
+    let (mut surface, device, queue) = common::init(
+        &event_loop,
+        WindowBuilder::new().with_title("Edit shaders/hot_reload.frag"),
+    );
+    let render_pass = surface.create_render_pass().unwrap();
+
+    let shaders = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("shaders");
+    let mut watcher =
+        ShaderWatcher::new().unwrap_or_else(|err| panic!("Failed to watch the shaders: {}", err));
+    // A shader that does not compile is printed, the triangle keeps the last one that did.
+    watcher.on_error(|err| println!("{}", err));
+    let pipeline = watcher
+        .watch_pipeline(
+            &device,
+            ShaderSource::vertex(shaders.join("hot_reload.vert")),
+            ShaderSource::fragment(shaders.join("hot_reload.frag")),
+            Subpass::from(render_pass.clone(), 0).unwrap(),
+            |desc| desc,
+        )
+        .unwrap_or_else(|err| panic!("Failed to create the pipeline: {}", err));
+
+    event_loop.run(move |event, _, control_flow| match event {
+        Event::WindowEvent {
+            event: WindowEvent::CloseRequested,
+            ..
+        } => *control_flow = ControlFlow::Exit,
+        Event::LoopDestroyed => {
+            if let Err(err) = surface.shutdown(&device) {
+                println!("Failed to shut down: {}", err);
+            }
+        }
+        Event::WindowEvent { event, .. } => surface.handle_window_event(&event),
+        Event::RedrawEventsCleared => {
+            // Saved shaders are swapped in before the frame is recorded.
+            watcher.poll_updates(&device);
+            let mut frame = match surface.begin_frame(&device) {
+                Ok(frame) => frame,
+                Err(hammer::FrameError::RecreateNeeded) => return,
+                Err(e) => panic!("Failed to begin frame: {}", e),
+            };
+            let mut encoder = frame.device().create_command_encoder(&queue).unwrap();
+            let target = frame.surface().draw_target(frame.image());
+            common::draw_to_target(
+                &mut encoder,
+                &target,
+                &render_pass,
+                &pipeline.get(),
+                None,
+                [0.0, 0.0, 0.0, 1.0],
+            );
+            if let Err(e) = frame.submit(&queue, encoder.finish().unwrap()) {
+                println!("Failed to submit the frame: {}", e);
+            }
+            match frame.present(&queue) {
+                Ok(()) | Err(hammer::FrameError::RecreateNeeded) => {}
+                Err(e) => println!("Failed to present the frame: {}", e),
+            }
+        }
+        _ => (),
+    })
+}
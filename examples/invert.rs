@@ -0,0 +1,192 @@
+// Renders a triangle into an `OffscreenTexture` and composites it onto the window with inverted
+// colors, a post effect in two passes that are both recorded by `draw_to_target`.
+
+use test03::hammer;
+use vulkano::{
+    format::Format,
+    pipeline::{
+        graphics::{
+            input_assembly::InputAssemblyState, vertex_input::BuffersDefinition,
+            viewport::ViewportState,
+        },
+        GraphicsPipeline,
+    },
+    render_pass::Subpass,
+    sampler::{Sampler, SamplerCreateInfo},
+};
+use winit::{
+    event::{Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    window::WindowBuilder,
+};
+
+mod common;
+
+fn main() {
+    env_logger::init();
+    let event_loop = EventLoop::new();
+    mod scene_vs {
+        vulkano_shaders::shader! {
+            ty: "vertex",
+            src: "
+                #version 450
+                const vec2 positions[3] = vec2[](vec2(-0.5, -0.25), vec2(0.0, 0.5), vec2(0.25, -0.1));
+                void main() {
+                    gl_Position = vec4(positions[gl_VertexIndex], 0.0, 1.0);
+                }
+            "
+        }
+    }
+
+    mod scene_fs {
+        vulkano_shaders::shader! {
+            ty: "fragment",
+            src: "
+                #version 450
+                layout(location = 0) out vec4 f_color;
+                void main() {
+                    f_color = vec4(1.0, 0.0, 0.0, 1.0);
+                }
+            "
+        }
+    }
+
+    // A single triangle covering the whole target, with texture coordinates from 0 to 1 on it.
+    mod post_vs {
+        vulkano_shaders::shader! {
+            ty: "vertex",
+            src: "
+                #version 450
+                layout(location = 0) out vec2 uv;
+                void main() {
+                    uv = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2);
+                    gl_Position = vec4(uv * 2.0 - 1.0, 0.0, 1.0);
+                }
+            "
+        }
+    }
+
+    mod post_fs {
+        vulkano_shaders::shader! {
+            ty: "fragment",
+            src: "
+                #version 450
+                layout(location = 0) in vec2 uv;
+                layout(location = 0) out vec4 f_color;
+                layout(set = 0, binding = 0) uniform sampler2D scene;
+                void main() {
+                    vec4 color = texture(scene, uv);
+                    f_color = vec4(1.0 - color.rgb, color.a);
+                }
+            "
+        }
+    }
+
+    let (mut surface, device, queue) = common::init(
+        &event_loop,
+        WindowBuilder::new().with_title("Inverted colors"),
+    );
+
+    // The scene is rendered at the size of the window, in a format every device can sample. The
+    // format never changes, so the render pass and the pipeline stay valid after resizing.
+    let mut texture = hammer::OffscreenTexture::new(
+        device.clone(),
+        surface.extent().unwrap(),
+        Format::R8G8B8A8_UNORM,
+    )
+    .unwrap();
+    let scene_pass = texture.create_render_pass().unwrap();
+    let surface_pass = surface.create_render_pass().unwrap();
+
+    let scene_vs = scene_vs::load(device.clone()).unwrap();
+    let scene_fs = scene_fs::load(device.clone()).unwrap();
+    let post_vs = post_vs::load(device.clone()).unwrap();
+    let post_fs = post_fs::load(device.clone()).unwrap();
+    let scene_pipeline = GraphicsPipeline::start()
+        .vertex_input_state(BuffersDefinition::new())
+        .vertex_shader(scene_vs.entry_point("main").unwrap(), ())
+        .input_assembly_state(InputAssemblyState::new())
+        .viewport_state(ViewportState::viewport_dynamic_scissor_irrelevant())
+        .fragment_shader(scene_fs.entry_point("main").unwrap(), ())
+        .render_pass(Subpass::from(scene_pass.clone(), 0).unwrap())
+        .build(device.clone())
+        .unwrap();
+    let post_pipeline = GraphicsPipeline::start()
+        .vertex_input_state(BuffersDefinition::new())
+        .vertex_shader(post_vs.entry_point("main").unwrap(), ())
+        .input_assembly_state(InputAssemblyState::new())
+        .viewport_state(ViewportState::viewport_dynamic_scissor_irrelevant())
+        .fragment_shader(post_fs.entry_point("main").unwrap(), ())
+        .render_pass(Subpass::from(surface_pass.clone(), 0).unwrap())
+        .build(device.clone())
+        .unwrap();
+
+    let sampler = Sampler::new(
+        device.clone(),
+        SamplerCreateInfo::simple_repeat_linear_no_mipmap(),
+    )
+    .unwrap();
+    // The texture and the sampler at binding 0 are the `sampler2D` of the post shader.
+    let scene_pipeline_layout = post_pipeline.clone();
+    let scene_group = move |texture: &hammer::OffscreenTexture| {
+        hammer::BindGroup::builder(&scene_pipeline_layout, 0)
+            .texture(0, texture.sampled_view())
+            .sampler(0, &sampler)
+            .build()
+            .unwrap()
+    };
+    let mut bind_group = scene_group(&texture);
+
+    // Every frame writes the same texture, so only one frame can be in flight.
+    let mut frame_sync = hammer::FrameSync::new(&device, 1);
+
+    event_loop.run(move |event, _, control_flow| match event {
+        Event::WindowEvent {
+            event: WindowEvent::CloseRequested,
+            ..
+        } => {
+            if let Err(err) = frame_sync.shutdown(&device) {
+                println!("Failed to shut down: {}", err);
+            }
+            *control_flow = ControlFlow::Exit;
+        }
+        Event::WindowEvent { event, .. } => surface.handle_window_event(&event),
+        Event::RedrawEventsCleared => {
+            let frame = match frame_sync.begin_frame(&mut surface) {
+                Ok(frame) => frame,
+                Err(hammer::FrameError::RecreateNeeded) => return,
+                Err(e) => panic!("Failed to begin frame: {}", e),
+            };
+            // The texture follows the size of the window.
+            let extent = surface.extent().unwrap();
+            if texture.extent() != extent {
+                texture.resize(extent).unwrap();
+                bind_group = scene_group(&texture);
+            }
+
+            let mut encoder = device.create_command_encoder(&queue).unwrap();
+            common::draw_to_target(
+                &mut encoder,
+                &texture,
+                &scene_pass,
+                &scene_pipeline,
+                None,
+                [0.0, 0.0, 1.0, 1.0],
+            );
+            common::draw_to_target(
+                &mut encoder,
+                &surface.draw_target(&frame.image),
+                &surface_pass,
+                &post_pipeline,
+                Some(&bind_group),
+                [0.0, 0.0, 0.0, 1.0],
+            );
+            let command_buffer = encoder.finish().unwrap();
+            match frame_sync.end_frame(&mut surface, frame, command_buffer, &queue) {
+                Ok(()) | Err(hammer::FrameError::RecreateNeeded) => {}
+                Err(e) => println!("Failed to end frame: {}", e),
+            }
+        }
+        _ => (),
+    })
+}
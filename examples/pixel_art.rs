@@ -0,0 +1,125 @@
+// The triangle of `triangle.rs`, rendered at a fixed 320x180 and scaled onto the window with
+// integer scaling, the way pixel-art games are drawn. The part of the window the scaled image does
+// not cover is cleared to a grey border, and clicking prints the cursor in virtual pixels.
+
+use std::sync::Arc;
+use test03::hammer;
+use vulkano::pipeline::GraphicsPipeline;
+use vulkano::render_pass::{RenderPass, Subpass};
+use winit::event::{ElementState, WindowEvent};
+use winit::event_loop::EventLoop;
+use winit::window::{Window, WindowBuilder};
+
+mod common;
+
+mod vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        src: "
+            #version 450
+            const vec2 positions[3] = vec2[](vec2(-0.5, -0.25), vec2(0.0, 0.5), vec2(0.25, -0.1));
+            void main() {
+                gl_Position = vec4(positions[gl_VertexIndex], 0.0, 1.0);
+            }
+        "
+    }
+}
+
+mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        src: "
+            #version 450
+            layout(location = 0) out vec4 f_color;
+            void main() {
+                f_color = vec4(1.0, 0.0, 0.0, 1.0);
+            }
+        "
+    }
+}
+
+struct PixelArt {
+    queue: hammer::Queue,
+    virtual_resolution: hammer::VirtualResolution,
+    render_pass: Arc<RenderPass>,
+    pipeline: Arc<GraphicsPipeline>,
+    window_extent: [u32; 2],
+    cursor: [f64; 2],
+}
+
+impl hammer::App for PixelArt {
+    fn resize(&mut self, extent: [u32; 2]) {
+        // The virtual target keeps its size, only the placement changes.
+        self.window_extent = extent;
+    }
+    fn render(&mut self, frame: &mut hammer::Frame<Window>, _dt: f32, _frame_index: u64) {
+        let mut encoder = frame.device().create_command_encoder(&self.queue).unwrap();
+        common::draw_to_target(
+            &mut encoder,
+            &self.virtual_resolution,
+            &self.render_pass,
+            &self.pipeline,
+            None,
+            [0.0, 0.0, 1.0, 1.0],
+        );
+        frame
+            .blit_virtual(&mut encoder, &self.virtual_resolution)
+            .unwrap();
+        if let Err(e) = frame.submit(&self.queue, encoder.finish().unwrap()) {
+            println!("Failed to submit the frame: {}", e);
+        }
+    }
+    fn event(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor = [position.x, position.y];
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                ..
+            } => match self
+                .virtual_resolution
+                .window_to_virtual(self.window_extent, self.cursor)
+            {
+                Some(position) => println!("Clicked virtual pixel {:?}", position),
+                None => println!("Clicked the border"),
+            },
+            _ => {}
+        }
+        false
+    }
+}
+
+fn main() {
+    env_logger::init();
+    let event_loop = EventLoop::new();
+    let (surface, device, queue) =
+        common::init(&event_loop, WindowBuilder::new().with_title("Pixel art"));
+
+    let mut virtual_resolution =
+        hammer::VirtualResolution::new(device.clone(), [320, 180], surface.image_format().unwrap())
+            .unwrap();
+    virtual_resolution.border_color = [0.2, 0.2, 0.2, 1.0];
+    let render_pass = hammer::DrawTarget::create_render_pass(&virtual_resolution).unwrap();
+    let (vs, fs) = (
+        vs::load(device.clone()).unwrap(),
+        fs::load(device.clone()).unwrap(),
+    );
+    let desc = hammer::PipelineDescriptor::new(
+        vs.entry_point("main").unwrap(),
+        fs.entry_point("main").unwrap(),
+    );
+    let pipeline = device
+        .create_graphics_pipeline(&desc, Subpass::from(render_pass.clone(), 0).unwrap())
+        .unwrap_or_else(|err| panic!("Failed to create the pipeline: {}", err));
+
+    let app = PixelArt {
+        queue: queue.clone(),
+        virtual_resolution,
+        render_pass,
+        pipeline,
+        window_extent: [0, 0],
+        cursor: [0.0, 0.0],
+    };
+    hammer::run(event_loop, surface, device, queue, app)
+}
@@ -0,0 +1,118 @@
+// The triangle of `triangle.rs`, drawn by command buffers recorded once per swapchain image up
+// front, which are only resubmitted every frame. Resizing the window records them again for the
+// new swapchain.
+
+use std::sync::Arc;
+use test03::hammer::{self, DrawTarget};
+use vulkano::command_buffer::PrimaryAutoCommandBuffer;
+use vulkano::pipeline::GraphicsPipeline;
+use vulkano::render_pass::{RenderPass, Subpass};
+use winit::event_loop::EventLoop;
+use winit::window::{Window, WindowBuilder};
+
+mod common;
+
+mod vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        src: "
+            #version 450
+            const vec2 positions[3] = vec2[](vec2(-0.5, -0.25), vec2(0.0, 0.5), vec2(0.25, -0.1));
+            void main() {
+                gl_Position = vec4(positions[gl_VertexIndex], 0.0, 1.0);
+            }
+        "
+    }
+}
+
+mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        src: "
+            #version 450
+            layout(location = 0) out vec4 f_color;
+            void main() {
+                f_color = vec4(1.0, 0.0, 0.0, 1.0);
+            }
+        "
+    }
+}
+
+struct Triangle {
+    queue: hammer::Queue,
+    render_pass: Arc<RenderPass>,
+    pipeline: Arc<GraphicsPipeline>,
+    command_buffers: Arc<hammer::CommandBufferCache>,
+}
+
+fn record(
+    mut encoder: hammer::CommandEncoder,
+    target: &dyn DrawTarget,
+    render_pass: &Arc<RenderPass>,
+    pipeline: &Arc<GraphicsPipeline>,
+) -> PrimaryAutoCommandBuffer {
+    common::draw_to_target(
+        &mut encoder,
+        target,
+        render_pass,
+        pipeline,
+        None,
+        [0.0, 0.0, 1.0, 1.0],
+    );
+    encoder.finish().unwrap()
+}
+
+impl hammer::App for Triangle {
+    fn render(&mut self, frame: &mut hammer::Frame<Window>, _dt: f32, _frame_index: u64) {
+        // Only recorded again after the swapchain was recreated.
+        let command_buffer =
+            self.command_buffers
+                .get_or_insert_with(frame.image().image_num, || {
+                    let encoder = frame.reusable_command_encoder(&self.queue).unwrap();
+                    let target = frame.surface().draw_target(frame.image());
+                    record(encoder, &target, &self.render_pass, &self.pipeline)
+                });
+        if let Err(e) = frame.submit(&self.queue, command_buffer) {
+            println!("Failed to submit the frame: {}", e);
+        }
+    }
+}
+
+fn main() {
+    env_logger::init();
+    let event_loop = EventLoop::new();
+    let (mut surface, device, queue) = common::init(
+        &event_loop,
+        WindowBuilder::new().with_title("Static triangle"),
+    );
+
+    let render_pass = surface.create_render_pass().unwrap();
+    let (vs, fs) = (
+        vs::load(device.clone()).unwrap(),
+        fs::load(device.clone()).unwrap(),
+    );
+    let desc = hammer::PipelineDescriptor::new(
+        vs.entry_point("main").unwrap(),
+        fs.entry_point("main").unwrap(),
+    );
+    let pipeline = device
+        .create_graphics_pipeline(&desc, Subpass::from(render_pass.clone(), 0).unwrap())
+        .unwrap_or_else(|err| panic!("Failed to create the pipeline: {}", err));
+
+    // The command buffers of all images are recorded up front.
+    let command_buffers = Arc::new(hammer::CommandBufferCache::new());
+    command_buffers.invalidate_on_recreate(&mut surface);
+    for image_num in 0..surface.image_count().unwrap() as usize {
+        let encoder = device.create_reusable_command_encoder(&queue).unwrap();
+        let target = surface.image_target(image_num).unwrap();
+        command_buffers.insert(image_num, record(encoder, &target, &render_pass, &pipeline));
+    }
+
+    let app = Triangle {
+        queue: queue.clone(),
+        render_pass,
+        pipeline,
+        command_buffers,
+    };
+    hammer::run(event_loop, surface, device, queue, app)
+}
@@ -0,0 +1,209 @@
+// Renders a triangle and inverts its colors in two subpasses of the same render pass, the second
+// one reading the intermediate image of the first one as an input attachment.
+
+use std::sync::Arc;
+use test03::hammer;
+use vulkano::{
+    descriptor_set::{PersistentDescriptorSet, WriteDescriptorSet},
+    image::{view::ImageView, ImageViewAbstract},
+    pipeline::{
+        graphics::{
+            input_assembly::InputAssemblyState, vertex_input::BuffersDefinition,
+            viewport::ViewportState,
+        },
+        GraphicsPipeline, Pipeline,
+    },
+};
+use winit::{
+    event::{Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    window::WindowBuilder,
+};
+
+mod common;
+
+fn main() {
+    env_logger::init();
+    let event_loop = EventLoop::new();
+    mod scene_vs {
+        vulkano_shaders::shader! {
+            ty: "vertex",
+            src: "
+                #version 450
+                const vec2 positions[3] = vec2[](vec2(-0.5, -0.25), vec2(0.0, 0.5), vec2(0.25, -0.1));
+                void main() {
+                    gl_Position = vec4(positions[gl_VertexIndex], 0.0, 1.0);
+                }
+            "
+        }
+    }
+
+    mod scene_fs {
+        vulkano_shaders::shader! {
+            ty: "fragment",
+            src: "
+                #version 450
+                layout(location = 0) out vec4 f_color;
+                void main() {
+                    f_color = vec4(1.0, 0.0, 0.0, 1.0);
+                }
+            "
+        }
+    }
+
+    // A single triangle covering the whole target.
+    mod post_vs {
+        vulkano_shaders::shader! {
+            ty: "vertex",
+            src: "
+                #version 450
+                void main() {
+                    vec2 uv = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2);
+                    gl_Position = vec4(uv * 2.0 - 1.0, 0.0, 1.0);
+                }
+            "
+        }
+    }
+
+    mod post_fs {
+        vulkano_shaders::shader! {
+            ty: "fragment",
+            src: "
+                #version 450
+                layout(location = 0) out vec4 f_color;
+                layout(input_attachment_index = 0, set = 0, binding = 0) uniform subpassInput scene;
+                void main() {
+                    vec4 color = subpassLoad(scene);
+                    f_color = vec4(1.0 - color.rgb, color.a);
+                }
+            "
+        }
+    }
+
+    let (mut surface, device, queue) = common::init(
+        &event_loop,
+        WindowBuilder::new().with_title("Inverted colors in subpasses"),
+    );
+
+    // Attachment 0 is the swapchain image, attachment 1 the triangle, which is only needed
+    // during the render pass.
+    let format = surface.image_format().unwrap();
+    let builder = hammer::RenderPassBuilder::new(hammer::AttachmentDesc::new(format));
+    let scene = builder.next_attachment();
+    let render_pass = builder
+        .attachment(
+            hammer::AttachmentDesc::new(format).store(vulkano::render_pass::StoreOp::DontCare),
+        )
+        .subpass(hammer::SubpassDesc {
+            color: vec![scene],
+            ..Default::default()
+        })
+        .subpass(hammer::SubpassDesc {
+            input: vec![scene],
+            color: vec![0],
+            ..Default::default()
+        })
+        .build(device.clone())
+        .unwrap_or_else(|err| panic!("Failed to create the render pass: {}", err));
+    let subpasses = hammer::subpasses(&render_pass);
+
+    let scene_vs = scene_vs::load(device.clone()).unwrap();
+    let scene_fs = scene_fs::load(device.clone()).unwrap();
+    let post_vs = post_vs::load(device.clone()).unwrap();
+    let post_fs = post_fs::load(device.clone()).unwrap();
+    let scene_pipeline = GraphicsPipeline::start()
+        .vertex_input_state(BuffersDefinition::new())
+        .vertex_shader(scene_vs.entry_point("main").unwrap(), ())
+        .input_assembly_state(InputAssemblyState::new())
+        .viewport_state(ViewportState::viewport_dynamic_scissor_irrelevant())
+        .fragment_shader(scene_fs.entry_point("main").unwrap(), ())
+        .render_pass(subpasses[0].clone())
+        .build(device.clone())
+        .unwrap();
+    let post_pipeline = GraphicsPipeline::start()
+        .vertex_input_state(BuffersDefinition::new())
+        .vertex_shader(post_vs.entry_point("main").unwrap(), ())
+        .input_assembly_state(InputAssemblyState::new())
+        .viewport_state(ViewportState::viewport_dynamic_scissor_irrelevant())
+        .fragment_shader(post_fs.entry_point("main").unwrap(), ())
+        .render_pass(subpasses[1].clone())
+        .build(device.clone())
+        .unwrap();
+
+    // The intermediate image follows the size of the window.
+    let layout = post_pipeline.layout().set_layouts()[0].clone();
+    let new_scene = {
+        let device = device.clone();
+        move |extent| {
+            let image = vulkano::image::AttachmentImage::transient_input_attachment(
+                device.clone(),
+                extent,
+                format,
+            )
+            .unwrap();
+            let view: Arc<dyn ImageViewAbstract> = ImageView::new_default(image).unwrap();
+            let descriptor_set = PersistentDescriptorSet::new(
+                layout.clone(),
+                [WriteDescriptorSet::image_view(0, view.clone())],
+            )
+            .unwrap();
+            (extent, view, descriptor_set)
+        }
+    };
+    let mut scene = new_scene(surface.extent().unwrap());
+
+    let mut frame_sync = hammer::FrameSync::new(&device, 1);
+
+    event_loop.run(move |event, _, control_flow| match event {
+        Event::WindowEvent {
+            event: WindowEvent::CloseRequested,
+            ..
+        } => {
+            if let Err(err) = frame_sync.shutdown(&device) {
+                println!("Failed to shut down: {}", err);
+            }
+            *control_flow = ControlFlow::Exit;
+        }
+        Event::WindowEvent { event, .. } => surface.handle_window_event(&event),
+        Event::RedrawEventsCleared => {
+            let frame = match frame_sync.begin_frame(&mut surface) {
+                Ok(frame) => frame,
+                Err(hammer::FrameError::RecreateNeeded) => return,
+                Err(e) => panic!("Failed to begin frame: {}", e),
+            };
+            let extent = surface.extent().unwrap();
+            if scene.0 != extent {
+                scene = new_scene(extent);
+            }
+            let framebuffer = frame
+                .image
+                .create_framebuffer(&render_pass, &[scene.1.clone()])
+                .unwrap();
+
+            let mut encoder = device.create_command_encoder(&queue).unwrap();
+            let mut pass = encoder
+                .begin_render_pass_with(
+                    framebuffer,
+                    vec![[0.0, 0.0, 0.0, 1.0].into(), [0.0, 0.0, 1.0, 1.0].into()],
+                )
+                .unwrap();
+            pass.set_viewport(frame.image.viewport())
+                .bind_pipeline(&scene_pipeline)
+                .draw(3, 1, 0, 0)
+                .unwrap()
+                .next_subpass()
+                .unwrap()
+                .bind_pipeline(&post_pipeline)
+                .bind_descriptor_sets(0, scene.2.clone())
+                .draw(3, 1, 0, 0)
+                .unwrap();
+            pass.finish().unwrap();
+            let command_buffer = encoder.finish().unwrap();
+            match frame_sync.end_frame(&mut surface, frame, command_buffer, &queue) {
+                Ok(()) | Err(hammer::FrameError::RecreateNeeded) => {}
+                Err(e) => println!("Failed to end frame: {}", e),
+            }
+        }
+        _ => (),
+    })
+}
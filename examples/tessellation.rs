@@ -0,0 +1,188 @@
+// Renders a quad as a single patch that is tessellated into a grid with a bump in the middle.
+// `W` toggles the wireframe view, which shows the triangles the tessellator generated.
+
+use std::sync::Arc;
+use test03::hammer::{self, DrawTarget};
+use vulkano::render_pass::{RenderPass, Subpass};
+use winit::{
+    event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent},
+    event_loop::EventLoop,
+    window::{Window, WindowBuilder},
+};
+
+mod common;
+
+fn main() {
+    env_logger::init();
+    let instance = common::instance();
+    let event_loop = EventLoop::new();
+    mod vs {
+        vulkano_shaders::shader! {
+            ty: "vertex",
+            src: "
+                #version 450
+                const vec2 corners[4] = vec2[](vec2(-0.5, 0.5), vec2(0.5, 0.5), vec2(0.5, -0.5), vec2(-0.5, -0.5));
+                void main() {
+                    gl_Position = vec4(corners[gl_VertexIndex], 0.0, 1.0);
+                }
+            "
+        }
+    }
+
+    mod tcs {
+        vulkano_shaders::shader! {
+            ty: "tess_ctrl",
+            src: "
+                #version 450
+                layout(vertices = 4) out;
+                void main() {
+                    gl_out[gl_InvocationID].gl_Position = gl_in[gl_InvocationID].gl_Position;
+                    if (gl_InvocationID == 0) {
+                        gl_TessLevelOuter[0] = 8.0;
+                        gl_TessLevelOuter[1] = 8.0;
+                        gl_TessLevelOuter[2] = 8.0;
+                        gl_TessLevelOuter[3] = 8.0;
+                        gl_TessLevelInner[0] = 8.0;
+                        gl_TessLevelInner[1] = 8.0;
+                    }
+                }
+            "
+        }
+    }
+
+    mod tes {
+        vulkano_shaders::shader! {
+            ty: "tess_eval",
+            src: "
+                #version 450
+                layout(quads, equal_spacing, ccw) in;
+                void main() {
+                    vec4 bottom = mix(gl_in[0].gl_Position, gl_in[1].gl_Position, gl_TessCoord.x);
+                    vec4 top = mix(gl_in[3].gl_Position, gl_in[2].gl_Position, gl_TessCoord.x);
+                    vec4 position = mix(bottom, top, gl_TessCoord.y);
+                    // Moving the generated vertices shows that they are evaluated one by one.
+                    position.y -= 0.2 * sin(3.14159 * gl_TessCoord.x) * sin(3.14159 * gl_TessCoord.y);
+                    gl_Position = position;
+                }
+            "
+        }
+    }
+
+    mod fs {
+        vulkano_shaders::shader! {
+            ty: "fragment",
+            src: "
+                #version 450
+                layout(location = 0) out vec4 f_color;
+                void main() {
+                    f_color = vec4(0.0, 1.0, 0.0, 1.0);
+                }
+            "
+        }
+    }
+
+    struct Quad {
+        queue: hammer::Queue,
+        render_pass: Arc<RenderPass>,
+        pipelines: hammer::WireframePipelines,
+        wireframe: bool,
+    }
+
+    impl hammer::App for Quad {
+        fn render(&mut self, frame: &mut hammer::Frame<Window>, _dt: f32, _frame_index: u64) {
+            let mut encoder = frame.device().create_command_encoder(&self.queue).unwrap();
+            let target = frame.surface().draw_target(frame.image());
+            {
+                let mut pass = encoder
+                    .begin_render_pass_with(
+                        target.framebuffer(&self.render_pass).unwrap(),
+                        target.clear_values([0.0, 0.0, 0.0, 1.0]),
+                    )
+                    .unwrap();
+                pass.set_viewport(target.viewport())
+                    .bind_pipeline(self.pipelines.select(self.wireframe));
+                // The four corners are a single patch.
+                pass.draw(4, 1, 0, 0).unwrap();
+            }
+            if let Err(e) = frame.submit(&self.queue, encoder.finish().unwrap()) {
+                println!("Failed to submit the frame: {}", e);
+            }
+        }
+        fn event(&mut self, event: &WindowEvent) -> bool {
+            match event {
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            state: ElementState::Pressed,
+                            virtual_keycode: Some(VirtualKeyCode::W),
+                            ..
+                        },
+                    ..
+                } => {
+                    self.wireframe = !self.wireframe;
+                    true
+                }
+                _ => false,
+            }
+        }
+    }
+
+    let mut surface =
+        hammer::Surface::new(WindowBuilder::new().build(&event_loop).unwrap(), &instance);
+    // Only adapters with tessellation shaders are considered.
+    let desc = hammer::AdapterDescriptor {
+        supports_surface: Some(&surface),
+        required_features: vulkano::device::Features {
+            tessellation_shader: true,
+            ..vulkano::device::Features::none()
+        },
+        ..hammer::AdapterDescriptor::graphics()
+    };
+    let adapter = instance
+        .request_adapter(&desc)
+        .unwrap_or_else(|err| panic!("Failed to find an adapter: {}", err));
+    let features = vulkano::device::Features {
+        fill_mode_non_solid: adapter
+            .physical_device()
+            .supported_features()
+            .fill_mode_non_solid,
+        ..vulkano::device::Features::default()
+    };
+    let device = adapter
+        .request_device(features)
+        .unwrap_or_else(|err| panic!("Failed to create the device: {}", err));
+    let queue = device.graphics_queue().unwrap().clone();
+    surface
+        .create_swapchain(device.clone(), &adapter)
+        .unwrap_or_else(|err| panic!("Failed to create the swapchain: {}", err));
+
+    let render_pass = surface.create_render_pass().unwrap();
+    let (vs, tcs, tes, fs) = (
+        vs::load(device.clone()).unwrap(),
+        tcs::load(device.clone()).unwrap(),
+        tes::load(device.clone()).unwrap(),
+        fs::load(device.clone()).unwrap(),
+    );
+    let desc = hammer::PipelineDescriptor {
+        tessellation: Some(hammer::TessellationStages::new(
+            tcs.entry_point("main").unwrap(),
+            tes.entry_point("main").unwrap(),
+            4,
+        )),
+        ..hammer::PipelineDescriptor::new(
+            vs.entry_point("main").unwrap(),
+            fs.entry_point("main").unwrap(),
+        )
+    };
+    let pipelines = device
+        .create_wireframe_pipelines(&desc, Subpass::from(render_pass.clone(), 0).unwrap())
+        .unwrap_or_else(|err| panic!("Failed to create the pipelines: {}", err));
+
+    let app = Quad {
+        queue: queue.clone(),
+        render_pass,
+        pipelines,
+        wireframe: false,
+    };
+    hammer::run(event_loop, surface, device, queue, app)
+}
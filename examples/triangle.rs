@@ -0,0 +1,95 @@
+// The smallest example, a triangle on a pulsing background. `hammer::run` handles the event loop,
+// resizes and shutdown, the app only records its frames. See `triangle_detailed.rs` for what
+// happens underneath, step by step.
+
+use std::sync::Arc;
+use test03::hammer;
+use vulkano::pipeline::GraphicsPipeline;
+use vulkano::render_pass::{RenderPass, Subpass};
+use winit::event_loop::EventLoop;
+use winit::window::{Window, WindowBuilder};
+
+mod common;
+
+mod vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        src: "
+            #version 450
+            const vec2 positions[3] = vec2[](vec2(-0.5, -0.25), vec2(0.0, 0.5), vec2(0.25, -0.1));
+            void main() {
+                gl_Position = vec4(positions[gl_VertexIndex], 0.0, 1.0);
+            }
+        "
+    }
+}
+
+mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        src: "
+            #version 450
+            layout(location = 0) out vec4 f_color;
+            void main() {
+                f_color = vec4(1.0, 0.0, 0.0, 1.0);
+            }
+        "
+    }
+}
+
+struct Triangle {
+    queue: hammer::Queue,
+    render_pass: Arc<RenderPass>,
+    pipeline: Arc<GraphicsPipeline>,
+    time: f32,
+}
+
+impl hammer::App for Triangle {
+    fn render(&mut self, frame: &mut hammer::Frame<Window>, dt: f32, _frame_index: u64) {
+        self.time += dt;
+        let mut encoder = frame.device().create_command_encoder(&self.queue).unwrap();
+        let target = frame.surface().draw_target(frame.image());
+        let clear_color = [0.0, 0.0, 0.5 + 0.5 * self.time.sin(), 1.0];
+        common::draw_to_target(
+            &mut encoder,
+            &target,
+            &self.render_pass,
+            &self.pipeline,
+            None,
+            clear_color,
+        );
+        if let Err(e) = frame.submit(&self.queue, encoder.finish().unwrap()) {
+            println!("Failed to submit the frame: {}", e);
+        }
+    }
+}
+
+fn main() {
+    env_logger::init();
+    let event_loop = EventLoop::new();
+    let (surface, device, queue) =
+        common::init(&event_loop, WindowBuilder::new().with_title("Triangle"));
+
+    let render_pass = surface.create_render_pass().unwrap();
+    // The defaults of the descriptor draw the triangle list of the shaders without depth test or
+    // blending.
+    let (vs, fs) = (
+        vs::load(device.clone()).unwrap(),
+        fs::load(device.clone()).unwrap(),
+    );
+    let desc = hammer::PipelineDescriptor::new(
+        vs.entry_point("main").unwrap(),
+        fs.entry_point("main").unwrap(),
+    );
+    let pipeline = device
+        .create_graphics_pipeline(&desc, Subpass::from(render_pass.clone(), 0).unwrap())
+        .unwrap_or_else(|err| panic!("Failed to create the pipeline: {}", err));
+
+    let app = Triangle {
+        queue: queue.clone(),
+        render_pass,
+        pipeline,
+        time: 0.0,
+    };
+    hammer::run(event_loop, surface, device, queue, app)
+}
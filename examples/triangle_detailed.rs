@@ -0,0 +1,820 @@
+// Copyright (c) 2016 The vulkano developers
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or https://opensource.org/licenses/MIT>,
+// at your option. All files in the project carrying such
+// notice may not be copied, modified, or distributed except
+// according to those terms.
+
+// Welcome to the triangle example!
+//
+// This is the only example that is entirely detailed. All the other examples avoid code
+// duplication by using helper functions, `triangle.rs` draws a triangle in a few lines with
+// `hammer::run`.
+//
+// This example assumes that you are already more or less familiar with graphics programming
+// and that you want to learn Vulkan. This means that for example it won't go into details about
+// what a vertex or a shader is.
+
+use bytemuck::{Pod, Zeroable};
+use std::sync::Arc;
+use test03::hammer;
+use vulkano::{
+    buffer::{BufferUsage, TypedBufferAccess},
+    image::SampleCount,
+    impl_vertex,
+    instance::InstanceCreateInfo,
+    pipeline::graphics::viewport::Viewport,
+    render_pass::{RenderPass, Subpass},
+    swapchain::{CompositeAlpha, PresentMode},
+};
+use winit::{
+    event::{ElementState, Event, KeyboardInput, ModifiersState, VirtualKeyCode, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    window::WindowBuilder,
+};
+
+// The vertices of the triangles.
+// We use #[repr(C)] here to force rustc to not do anything funky with our data, although for this
+// particular example, it doesn't actually change the in-memory representation.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Zeroable, Pod)]
+struct Vertex {
+    position: [f32; 2],
+}
+impl_vertex!(Vertex, position);
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Zeroable, Pod)]
+struct InstanceData {
+    offset: [f32; 2],
+}
+impl_vertex!(InstanceData, offset);
+
+fn main() {
+    env_logger::init();
+
+    // The first step of any Vulkan program is to create an instance.
+    //
+    // When we create an instance, we have to pass a list of extensions that we want to enable.
+    //
+    // All the window-drawing functionalities are part of non-core extensions that we need
+    // to enable manually. To do so, we ask the `vulkano_win` crate for the list of extensions
+    // required to draw to a window.
+    //
+    // In debug builds the debug utils extension is enabled as well where it is available, so the
+    // passes and buffers of the example show up with their names in debuggers like RenderDoc.
+    let required_extensions = vulkano::instance::InstanceExtensions {
+        ext_debug_utils: cfg!(debug_assertions)
+            && vulkano::instance::InstanceExtensions::supported_by_core()
+                .is_ok_and(|supported| supported.ext_debug_utils),
+        ..vulkano_win::required_extensions()
+    };
+
+    // Now creating the instance.
+    let instance = hammer::Instance::new(InstanceCreateInfo {
+        enabled_extensions: required_extensions,
+        ..Default::default()
+    });
+
+    // The objective of this example is to draw a triangle on a window. To do so, we first need to
+    // create the window.
+    //
+    // This is done by creating a `WindowBuilder` from the `winit` crate, then calling the
+    // `build_vk_surface` method provided by the `VkSurfaceBuild` trait from `vulkano_win`. If you
+    // ever get an error about `build_vk_surface` being undefined in one of your projects, this
+    // probably means that you forgot to import this trait.
+    //
+    // This returns a `vulkano::swapchain::Surface` object that contains both a cross-platform winit
+    // window and a cross-platform Vulkan surface that represents the surface of the window.
+    //
+    // With `--transparent` the triangle floats over the desktop, where the window system supports
+    // transparent windows.
+    let transparent = std::env::args().any(|arg| arg == "--transparent");
+    // With `--dirty-rect` only the bounding box of the triangle is presented as changed, where the
+    // device supports incremental presentation.
+    let dirty_rect = std::env::args().any(|arg| arg == "--dirty-rect");
+    let event_loop = EventLoop::new();
+    let mut surface = hammer::Surface::new(
+        WindowBuilder::new()
+            .with_transparent(transparent)
+            .build(&event_loop)
+            .unwrap(),
+        &instance,
+    );
+
+    let desc = hammer::AdapterDescriptor {
+        supports_surface: Some(&surface),
+        ..hammer::AdapterDescriptor::graphics()
+    };
+
+    let adapter = instance
+        .request_adapter(&desc)
+        .unwrap_or_else(|err| panic!("Failed to find an adapter: {}", err));
+    println!("GPU: {}", adapter.info());
+    println!(
+        "VRAM: {} MiB",
+        adapter.device_local_memory() / (1024 * 1024)
+    );
+
+    // The wireframe view needs the `fill_mode_non_solid` feature, which is enabled where the
+    // adapter supports it.
+    let features = vulkano::device::Features {
+        fill_mode_non_solid: adapter
+            .physical_device()
+            .supported_features()
+            .fill_mode_non_solid,
+        ..vulkano::device::Features::default()
+    };
+    let device = adapter
+        .request_device(features)
+        .unwrap_or_else(|err| panic!("Failed to create the device: {}", err));
+    let queue = device.graphics_queue().unwrap().clone();
+
+    // With `--raw-handle` another surface is created from the raw handle of the window, the way a
+    // window of another toolkit, e.g. Qt, would be embedded. Its swapchain is destroyed again
+    // before the one of `surface` is created, since a window can only have one at a time.
+    #[cfg(feature = "raw-window-handle")]
+    if std::env::args().any(|arg| arg == "--raw-handle") {
+        use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+
+        struct RawHandle(RawWindowHandle);
+        // The handle stays valid as long as the winit window, which outlives the surface.
+        unsafe impl HasRawWindowHandle for RawHandle {
+            fn raw_window_handle(&self) -> RawWindowHandle {
+                self.0
+            }
+        }
+        unsafe impl Send for RawHandle {}
+        unsafe impl Sync for RawHandle {}
+
+        let window = surface.window();
+        let mut raw_surface = hammer::Surface::from_raw(
+            RawHandle(window.raw_window_handle()),
+            window.inner_size().into(),
+            &instance,
+        )
+        .unwrap_or_else(|err| panic!("Failed to create the surface from the raw handle: {}", err));
+        // Fails with `UnsupportedExtent` while the window is minimized.
+        match raw_surface.create_swapchain_with(
+            device.clone(),
+            &adapter,
+            &hammer::SwapchainDescriptor::default(),
+        ) {
+            Ok(()) => {
+                let [width, height] = raw_surface.extent().unwrap();
+                println!(
+                    "Created a {}x{} swapchain from the raw window handle",
+                    width, height
+                );
+            }
+            Err(err) => println!(
+                "Failed to create the swapchain from the raw handle: {}",
+                err
+            ),
+        }
+    }
+
+    // A window that starts minimized has no extent to create the swapchain with. It is created
+    // with the first frame the window is visible, together with everything depending on it.
+    let swapchain_desc = hammer::SwapchainDescriptor {
+        composite_alpha: transparent.then_some(CompositeAlpha::PreMultiplied),
+        ..Default::default()
+    };
+
+    // We now create a buffer that will store the shape of our triangle, made of the `Vertex`
+    // declared below.
+    let vertices = [
+        Vertex {
+            position: [-0.5, -0.25],
+        },
+        Vertex {
+            position: [0.0, 0.5],
+        },
+        Vertex {
+            position: [0.25, -0.1],
+        },
+    ];
+    // The vertices never change, so they are uploaded once into device local memory. For data
+    // that is updated every frame a `CpuAccessibleBuffer` would be the better fit:
+    //
+    // CpuAccessibleBuffer::from_iter(device.clone(), BufferUsage::all(), false, vertices)
+    let vertex_buffer = device
+        .create_buffer_init(&vertices, BufferUsage::vertex_buffer())
+        .unwrap();
+    device.set_buffer_name(&*vertex_buffer, "triangle vertices");
+
+    // A quad in the corner of the window, drawn from four vertices and six indices. `Mesh` takes
+    // care of uploading and binding both buffers.
+    let quad_vertices = [
+        Vertex {
+            position: [-0.9, -0.9],
+        },
+        Vertex {
+            position: [-0.6, -0.9],
+        },
+        Vertex {
+            position: [-0.6, -0.6],
+        },
+        Vertex {
+            position: [-0.9, -0.6],
+        },
+    ];
+    // A second triangle overlapping the first one. It is drawn afterwards but further away, so
+    // the depth test hides it behind the first one.
+    let behind_vertices = [
+        Vertex {
+            position: [-0.25, -0.5],
+        },
+        Vertex {
+            position: [0.5, -0.5],
+        },
+        Vertex {
+            position: [0.1, 0.3],
+        },
+    ];
+    let behind_buffer = device
+        .create_buffer_init(&behind_vertices, BufferUsage::vertex_buffer())
+        .unwrap();
+
+    let quad_indices: [u16; 6] = [0, 1, 2, 2, 3, 0];
+    let quad = hammer::Mesh::new(
+        &device,
+        &quad_vertices,
+        Some(hammer::Indices::U16(&quad_indices)),
+    )
+    .unwrap();
+
+    // The next step is to create the shaders.
+    //
+    // The raw shader creation API provided by the vulkano library is unsafe, for various reasons.
+    //
+    // An overview of what the `shader!` macro generates can be found in the
+    // `vulkano-shaders` crate docs. You can view them at https://docs.rs/vulkano-shaders/
+    //
+    // TODO: explain this in details
+    mod vs {
+        vulkano_shaders::shader! {
+            ty: "vertex",
+            src: "
+                                #version 450
+                                layout(location = 0) in vec2 position;
+                                void main() {
+                                        gl_Position = vec4(position, 0.0, 1.0);
+                                }
+                        "
+        }
+    }
+
+    mod fs {
+        vulkano_shaders::shader! {
+            ty: "fragment",
+            src: "
+                #version 450
+                layout(location = 0) out vec4 f_color;
+            void main() {
+                f_color = vec4(1.0, 0.0, 0.0, 1.0);
+            }
+                        "
+        }
+    }
+
+    // Same as `vs`, but further away and green.
+    mod vs_behind {
+        vulkano_shaders::shader! {
+            ty: "vertex",
+            src: "
+                #version 450
+                layout(location = 0) in vec2 position;
+                void main() {
+                    gl_Position = vec4(position, 0.5, 1.0);
+                }
+            "
+        }
+    }
+
+    mod fs_behind {
+        vulkano_shaders::shader! {
+            ty: "fragment",
+            src: "
+                #version 450
+                layout(location = 0) out vec4 f_color;
+                void main() {
+                    f_color = vec4(0.0, 1.0, 0.0, 1.0);
+                }
+            "
+        }
+    }
+
+    // Same as `vs`, but every instance is moved by its own offset.
+    mod vs_instanced {
+        vulkano_shaders::shader! {
+            ty: "vertex",
+            src: "
+                #version 450
+                layout(location = 0) in vec2 position;
+                layout(location = 1) in vec2 offset;
+                void main() {
+                    gl_Position = vec4(position + offset, 0.0, 1.0);
+                }
+            "
+        }
+    }
+
+    let vs = vs::load(device.clone()).unwrap();
+    let fs = fs::load(device.clone()).unwrap();
+    let vs_instanced = vs_instanced::load(device.clone()).unwrap();
+    let vs_behind = vs_behind::load(device.clone()).unwrap();
+    let fs_behind = fs_behind::load(device.clone()).unwrap();
+
+    // A grid of 100 x 100 tiny triangles, drawn with a single instanced draw call. Every instance
+    // is moved by the offset of its `InstanceData`.
+    let instances = (0..100)
+        .flat_map(|y| {
+            (0..100).map(move |x| InstanceData {
+                offset: [x as f32 * 0.02 - 1.0, y as f32 * 0.02 - 1.0],
+            })
+        })
+        .collect::<Vec<_>>();
+    let instance_buffer = hammer::InstanceBuffer::new(device.clone(), &instances).unwrap();
+    let tiny_triangle = hammer::Mesh::new(
+        &device,
+        &[
+            Vertex {
+                position: [0.0, 0.0],
+            },
+            Vertex {
+                position: [0.01, 0.0],
+            },
+            Vertex {
+                position: [0.0, 0.01],
+            },
+        ],
+        None,
+    )
+    .unwrap();
+
+    // At this point, OpenGL initialization would be finished. However in Vulkan it is not. OpenGL
+    // implicitly does a lot of computation whenever you draw. In Vulkan, you have to do all this
+    // manually.
+
+    // The next step is to create a *render pass*, which is an object that describes where the
+    // output of the graphics pipeline will go. It describes the layout of the images
+    // where the colors, depth and/or stencil information will be written.
+    //
+    // The forward rendering preset adds a depth buffer in the best format the device supports to
+    // the swapchain and creates a render pass with the swapchain image as the color attachment,
+    // which is cleared at the start of the drawing and stored afterwards, followed by the depth
+    // buffer.
+    //
+    // It needs the format of the swapchain, so it is created in the event loop.
+    let mut render_pass: Option<(Arc<RenderPass>, hammer::ClearValues)> = None;
+
+    // Before we draw we have to create what is called a pipeline. This is similar to an OpenGL
+    // program, but much more specific.
+    //
+    // The pipelines have to be created again whenever the render pass changes, e.g. when
+    // toggling MSAA.
+    let create_pipelines = move |device: &hammer::Device, render_pass: &Arc<RenderPass>| {
+        // We have to indicate which subpass of which render pass this pipeline is going to be used
+        // in. The pipeline will only be usable from this particular subpass.
+        // The pipelines rasterize with as many samples as the attachments of the subpass have.
+        let subpass = Subpass::from(render_pass.clone(), 0).unwrap();
+
+        // The descriptor defaults to a list of triangles, a resizable viewport set to draw over
+        // the entire window and no blending. A Vulkan shader can in theory contain multiple entry
+        // points, so we have to specify which one.
+        let desc = hammer::PipelineDescriptor {
+            // We need to indicate the layout of the vertices.
+            vertex_layout: hammer::VertexLayout::of::<Vertex>(),
+            // Discard fragments behind what has been drawn already.
+            depth: Some(hammer::DepthState::default()),
+            ..hammer::PipelineDescriptor::new(
+                vs.entry_point("main").unwrap(),
+                fs.entry_point("main").unwrap(),
+            )
+        };
+        // Every pipeline comes with a wireframe variant, toggled with the W key.
+        let pipeline = device
+            .create_wireframe_pipelines(&desc, subpass.clone())
+            .unwrap();
+
+        // The instanced pipeline reads the vertices from binding 0 and the instances from
+        // binding 1.
+        let instanced_pipeline = device
+            .create_wireframe_pipelines(
+                &hammer::PipelineDescriptor {
+                    vertex_shader: vs_instanced.entry_point("main").unwrap(),
+                    vertex_layout: hammer::VertexLayout::instanced::<Vertex, InstanceData>(),
+                    ..desc.clone()
+                },
+                subpass.clone(),
+            )
+            .unwrap();
+
+        let behind_pipeline = device
+            .create_wireframe_pipelines(
+                &hammer::PipelineDescriptor {
+                    vertex_shader: vs_behind.entry_point("main").unwrap(),
+                    fragment_shader: fs_behind.entry_point("main").unwrap(),
+                    // The depth bias is set while recording, the way shadow maps set one per light.
+                    dynamic_states: hammer::DynamicStates {
+                        depth_bias: true,
+                        ..Default::default()
+                    },
+                    ..desc
+                },
+                subpass,
+            )
+            .unwrap();
+
+        (pipeline, instanced_pipeline, behind_pipeline)
+    };
+    let mut pipelines = None;
+
+    // The render pass we created above only describes the layout of our framebuffers. Before we
+    // can draw we also need to create the actual framebuffers.
+    //
+    // Since we need to draw to multiple images, we are going to create a different framebuffer for
+    // each image.
+    //let mut framebuffers = window_size_dependent_setup(&images, render_pass.clone());
+
+    // Initialization is finally finished!
+
+    // In the loop below we are going to submit commands to the GPU. Submitting a command produces
+    // an object that implements the `GpuFuture` trait, which holds the resources for as long as
+    // they are in use by the GPU.
+    //
+    // Each `Frame` keeps these futures until the next frame begins, so the CPU can record the
+    // next frame while the GPU is still busy with the previous one.
+    let present_queue = device.present_queue().unwrap_or(&queue).clone();
+
+    // The frame times are shown in the title of the window, updated once per second.
+    surface.enable_frame_stats(&device);
+    let mut title_updated = std::time::Instant::now();
+
+    // Toggled with the V key, the L key toggles a limit of 60 frames per second.
+    let mut vsync = true;
+    // Set with the F12 key, captures the next frame before presenting it.
+    let mut screenshot = false;
+    // Tracked for Alt+Enter, which toggles exclusive fullscreen.
+    let mut modifiers = ModifiersState::empty();
+    // Toggled with the M key, using up to 4 samples per pixel.
+    let msaa_samples = adapter.clamp_samples(SampleCount::Sample4);
+    // Toggled with the W key, draws the edges of the triangles only.
+    let mut wireframe = false;
+
+    event_loop.run(move |event, _target, control_flow| {
+        match event {
+            Event::WindowEvent {
+                event: WindowEvent::CloseRequested,
+                ..
+            } => {
+                // Make sure the GPU is done with the last frame before anything gets
+                // destroyed.
+                if let Err(err) = surface.shutdown(&device) {
+                    println!("Failed to shut down: {}", err);
+                }
+                *control_flow = ControlFlow::Exit;
+            }
+            Event::WindowEvent {
+                event:
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                state: ElementState::Pressed,
+                                virtual_keycode: Some(VirtualKeyCode::V),
+                                ..
+                            },
+                        ..
+                    },
+                ..
+            } => {
+                // Fifo is always supported, without vsync Mailbox avoids tearing if available.
+                let result = if vsync {
+                    surface
+                        .set_present_mode(PresentMode::Mailbox)
+                        .or_else(|_| surface.set_present_mode(PresentMode::Immediate))
+                } else {
+                    surface.set_present_mode(PresentMode::Fifo)
+                };
+                match result {
+                    Ok(()) => vsync = !vsync,
+                    Err(e) => println!("Failed to toggle vsync: {}", e),
+                }
+            }
+            Event::WindowEvent {
+                event:
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                state: ElementState::Pressed,
+                                virtual_keycode: Some(VirtualKeyCode::L),
+                                ..
+                            },
+                        ..
+                    },
+                ..
+            } => {
+                // Without vsync the frame rate is only limited by the GPU.
+                let fps = match surface.target_fps() {
+                    Some(_) => None,
+                    None => Some(60.0),
+                };
+                surface.set_target_fps(fps);
+            }
+            Event::WindowEvent {
+                event:
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                state: ElementState::Pressed,
+                                virtual_keycode: Some(VirtualKeyCode::M),
+                                ..
+                            },
+                        ..
+                    },
+                ..
+            } => {
+                let samples = match surface.sample_count() {
+                    Some(SampleCount::Sample1) => msaa_samples,
+                    _ => SampleCount::Sample1,
+                };
+                if let Err(e) = surface.set_sample_count(samples) {
+                    println!("Failed to toggle MSAA: {}", e);
+                }
+            }
+            Event::WindowEvent {
+                event:
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                state: ElementState::Pressed,
+                                virtual_keycode: Some(VirtualKeyCode::F12),
+                                ..
+                            },
+                        ..
+                    },
+                ..
+            } => screenshot = true,
+            Event::WindowEvent {
+                event:
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                state: ElementState::Pressed,
+                                virtual_keycode: Some(VirtualKeyCode::W),
+                                ..
+                            },
+                        ..
+                    },
+                ..
+            } => {
+                if !device.enabled_features().fill_mode_non_solid {
+                    println!("The device does not support wireframe rendering");
+                }
+                wireframe = !wireframe;
+            }
+            Event::WindowEvent {
+                event: WindowEvent::ModifiersChanged(state),
+                ..
+            } => modifiers = state,
+            Event::WindowEvent {
+                event:
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                state: ElementState::Pressed,
+                                virtual_keycode: Some(VirtualKeyCode::Return),
+                                ..
+                            },
+                        ..
+                    },
+                ..
+            } if modifiers.alt() => {
+                // Switch to the largest video mode with the highest refresh rate, or to a
+                // borderless window if the monitor does not report any.
+                let mode = match surface.fullscreen_mode() {
+                    hammer::FullscreenMode::Windowed => {
+                        surface.select_video_mode(None, None).map_or(
+                            hammer::FullscreenMode::Borderless,
+                            hammer::FullscreenMode::Exclusive,
+                        )
+                    }
+                    _ => hammer::FullscreenMode::Windowed,
+                };
+                surface.set_fullscreen(mode);
+            }
+            // On Android the window is destroyed while the app is suspended and a new one is
+            // created when it resumes, so the surface has to be created again.
+            #[cfg(target_os = "android")]
+            Event::Suspended => {
+                if let Err(e) = surface.invalidate(&device) {
+                    println!("Failed to invalidate the surface: {}", e);
+                }
+            }
+            #[cfg(target_os = "android")]
+            Event::Resumed if !surface.is_valid() => {
+                let window = WindowBuilder::new().build(_target).unwrap();
+                if let Err(e) = surface.rebuild(window, &instance) {
+                    println!("Failed to rebuild the surface: {}", e);
+                }
+            }
+            // Resizes and scale factor changes make the next frame recreate the swapchain.
+            Event::WindowEvent { event, .. } => surface.handle_window_event(&event),
+            Event::RedrawEventsCleared => {
+                // Before we can draw on the output, we have to *acquire* an image from the swapchain.
+                // Whenever the window resizes or the swapchain becomes invalid by itself, e.g. on
+                // Android when the application went to the background, it is recreated. While the
+                // window is minimized there is nothing to draw on, so the frame is skipped.
+                //
+                // This also frees the resources of the frames the GPU has finished.
+                //
+                // The swapchain is created once the window is visible for the first time.
+                if surface.extent().is_none() {
+                    let size = surface.window().inner_size();
+                    if size.width == 0 || size.height == 0 {
+                        return;
+                    }
+                    surface
+                        .create_swapchain_with(device.clone(), &adapter, &swapchain_desc)
+                        .unwrap_or_else(|err| panic!("Failed to create the swapchain: {}", err));
+                }
+                // After toggling MSAA the swapchain has attachments with a different sample count,
+                // which needs a new render pass and pipelines.
+                let outdated = match &render_pass {
+                    Some((render_pass, _)) => {
+                        surface.sample_count()
+                            != Subpass::from(render_pass.clone(), 0).unwrap().num_samples()
+                    }
+                    None => true,
+                };
+                if outdated {
+                    let (new_render_pass, clear_values) =
+                        hammer::forward_render_pass(&adapter, &mut surface, None).unwrap();
+                    pipelines = Some(create_pipelines(&device, &new_render_pass));
+                    render_pass = Some((new_render_pass, clear_values));
+                }
+                let (render_pass, clear_values) = render_pass.as_mut().unwrap();
+                let (pipeline, instanced_pipeline, behind_pipeline) = pipelines.as_ref().unwrap();
+                let mut frame = match surface.begin_frame(&device) {
+                    Ok(frame) => frame,
+                    Err(hammer::FrameError::RecreateNeeded) => return,
+                    Err(e) => panic!("Failed to begin frame: {}", e),
+                };
+                // Acquiring the image can recreate the swapchain with the new sample count. The
+                // render pass is updated with the next frame, this one is skipped.
+                if frame.surface().sample_count()
+                    != Subpass::from(render_pass.clone(), 0).unwrap().num_samples()
+                {
+                    frame.abandon();
+                    return;
+                }
+
+                // Framebuffers are created once per swapchain image and reused every frame.
+                let framebuffer = frame.framebuffer(render_pass).unwrap();
+                let viewport = frame.surface().viewport().unwrap();
+                if dirty_rect {
+                    frame.dirty_regions.push(bounding_rect(
+                        vertices.iter().map(|vertex| vertex.position),
+                        &viewport,
+                    ));
+                }
+
+                // Specify the color to clear the framebuffer with i.e. blue, or fully transparent
+                // for a transparent window.
+                let clear_color = if transparent {
+                    [0.0, 0.0, 0.0, 0.0]
+                } else {
+                    [0.0, 0.0, 1.0, 1.0]
+                };
+                // The depth buffer is cleared to the far plane by the preset.
+                *clear_values = clear_values.clone().color("color", clear_color);
+
+                // In order to draw, we have to build a *command buffer*. The command buffer object holds
+                // the list of commands that are going to be executed.
+                //
+                // Building a command buffer is an expensive operation (usually a few hundred
+                // microseconds), but it is known to be a hot path in the driver and is expected to be
+                // optimized.
+                //
+                // Note that we have to pass a queue when we create the command buffer. The command
+                // buffer will only be executable on the family of that queue.
+                let mut encoder = device.create_command_encoder(&queue).unwrap();
+
+                // Before we can draw, we have to *enter a render pass*. The render pass encoder
+                // borrows the command encoder until the render pass ended.
+                //
+                // The clear values are set by attachment name, `begin_render_pass` puts them in the
+                // order of the attachments.
+                encoder.push_debug_group("triangle pass", [0.0, 0.0, 1.0, 1.0]);
+                let mut pass = encoder
+                    .begin_render_pass(framebuffer.clone(), clear_values)
+                    .unwrap();
+                pass
+                    // We are now inside the first subpass of the render pass. We add a draw command.
+                    //
+                    // Dynamic viewports allow us to recreate just the viewport when the window is resized
+                    // Otherwise we would have to recreate the whole pipeline.
+                    .set_viewport(viewport)
+                    .bind_pipeline(pipeline.select(wireframe))
+                    .bind_vertex_buffers(0, vertex_buffer.clone())
+                    .draw(vertex_buffer.len() as u32, 1, 0, 0)
+                    .unwrap()
+                    .bind_pipeline(behind_pipeline.select(wireframe))
+                    // Pushes the triangle a bit further back, which keeps it behind where the depths are close.
+                    .set_depth_bias(1.0, 0.0, 1.0)
+                    .unwrap()
+                    .bind_vertex_buffers(0, behind_buffer.clone())
+                    .draw(behind_buffer.len() as u32, 1, 0, 0)
+                    .unwrap()
+                    .draw_mesh(&quad)
+                    .unwrap()
+                    .bind_pipeline(instanced_pipeline.select(wireframe))
+                    .draw_mesh_instanced(&tiny_triangle, &instance_buffer)
+                    .unwrap();
+
+                // We leave the render pass by finishing the render pass encoder. Note that if we had
+                // multiple subpasses we would have called `next_subpass` to jump to the next one.
+                pass.finish().unwrap();
+                encoder.pop_debug_group();
+
+                // Finish building the command buffer by calling `finish`.
+                let command_buffer = encoder.finish().unwrap();
+
+                // The color output is now expected to contain our triangle. But in order to show it on
+                // the screen, we have to *present* the image.
+                //
+                // This does not actually present the image immediately. Instead it submits a present
+                // command at the end of the queue. This means that it will only be presented once the
+                // GPU has finished executing the command buffer that draws the triangle.
+                //
+                // For a screenshot the image is copied to the host in between, after the
+                // rendering finished.
+                if let Err(e) = frame.submit(&queue, command_buffer) {
+                    println!("Failed to submit the frame: {}", e);
+                    frame.abandon();
+                    return;
+                }
+                if screenshot {
+                    screenshot = false;
+                    match frame.capture(&device, &queue) {
+                        Ok(capture) => save_screenshot(&capture),
+                        Err(e) => println!("Failed to capture the screenshot: {}", e),
+                    }
+                }
+                match frame.present(&present_queue) {
+                    // The next `begin_frame` recreates the swapchain.
+                    Ok(()) | Err(hammer::FrameError::RecreateNeeded) => {}
+                    Err(e) => println!("Failed to present the frame: {}", e),
+                }
+
+                if title_updated.elapsed() >= std::time::Duration::from_secs(1) {
+                    title_updated = std::time::Instant::now();
+                    if let Some(stats) = surface.frame_stats() {
+                        surface.window().set_title(&stats.to_string());
+                    }
+                }
+            }
+            _ => (),
+        }
+    });
+}
+
+/// Bounding box in pixels of `positions`, given in normalized device coordinates.
+fn bounding_rect(
+    positions: impl IntoIterator<Item = [f32; 2]>,
+    viewport: &Viewport,
+) -> hammer::Rect {
+    let mut min = [f32::MAX; 2];
+    let mut max = [f32::MIN; 2];
+    for position in positions {
+        for i in 0..2 {
+            let pixel = viewport.origin[i] + (position[i] + 1.0) / 2.0 * viewport.dimensions[i];
+            min[i] = min[i].min(pixel);
+            max[i] = max[i].max(pixel);
+        }
+    }
+    hammer::Rect {
+        offset: [min[0].floor() as i32, min[1].floor() as i32],
+        extent: [
+            (max[0].ceil() - min[0].floor()) as u32,
+            (max[1].ceil() - min[1].floor()) as u32,
+        ],
+    }
+}
+
+/// Saves `capture` as `screenshot.png`, which needs the `image` feature.
+fn save_screenshot(capture: &hammer::RgbaImageData) {
+    #[cfg(feature = "image")]
+    match capture.save_png("screenshot.png") {
+        Ok(()) => println!("Saved the screenshot to screenshot.png"),
+        Err(e) => println!("Failed to save the screenshot: {}", e),
+    }
+    #[cfg(not(feature = "image"))]
+    println!(
+        "Captured a {}x{} screenshot, enable the `image` feature to save it",
+        capture.width, capture.height
+    );
+}
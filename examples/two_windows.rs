@@ -0,0 +1,107 @@
+// Opens two windows and clears them to different colors, rendering both with the same device.
+//
+// Each window has its own swapchain and frames in flight, so resizing one window only recreates
+// the swapchain of that window.
+
+use test03::hammer::{self, RenderTarget};
+use winit::{
+    event::{Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    window::WindowBuilder,
+};
+
+mod common;
+
+fn main() {
+    env_logger::init();
+    let instance = common::instance();
+    let event_loop = EventLoop::new();
+    let first = hammer::Surface::new(
+        WindowBuilder::new()
+            .with_title("First window")
+            .build(&event_loop)
+            .unwrap(),
+        &instance,
+    );
+    let second = hammer::Surface::new(
+        WindowBuilder::new()
+            .with_title("Second window")
+            .build(&event_loop)
+            .unwrap(),
+        &instance,
+    );
+
+    let desc = hammer::AdapterDescriptor {
+        supports_surface: Some(&first),
+        ..hammer::AdapterDescriptor::graphics()
+    };
+    let adapter = instance
+        .request_adapter(&desc)
+        .unwrap_or_else(|err| panic!("Failed to find an adapter: {}", err));
+    // Windows on the same screen are practically always supported by the same queue family.
+    assert!(
+        adapter.is_surface_supported(&second),
+        "The adapter cannot present to the second window"
+    );
+    let device = adapter
+        .request_device(vulkano::device::Features::default())
+        .unwrap_or_else(|err| panic!("Failed to create the device: {}", err));
+    let queue = device.graphics_queue().unwrap().clone();
+
+    // The windows might use different formats, so every window gets its own render pass.
+    let mut windows = hammer::MultiSurface::new(2);
+    let mut targets = Vec::new();
+    for (mut surface, color) in [
+        (first, [1.0, 0.0, 0.0, 1.0]),
+        (second, [0.0, 1.0, 0.0, 1.0]),
+    ] {
+        surface
+            .create_swapchain_with(
+                device.clone(),
+                &adapter,
+                &hammer::SwapchainDescriptor::default(),
+            )
+            .unwrap_or_else(|err| panic!("Failed to create the swapchain: {}", err));
+        let render_pass = surface.create_render_pass().unwrap();
+        let id = windows.add(&device, surface);
+        targets.push((id, render_pass, color));
+    }
+
+    event_loop.run(move |event, _, control_flow| match event {
+        // Closing either window ends the example.
+        Event::WindowEvent {
+            event: WindowEvent::CloseRequested,
+            ..
+        } => {
+            if let Err(err) = windows.shutdown(&device) {
+                println!("Failed to shut down: {}", err);
+            }
+            *control_flow = ControlFlow::Exit;
+        }
+        Event::WindowEvent { window_id, event } => windows.handle_window_event(window_id, &event),
+        Event::RedrawEventsCleared => {
+            // Both windows are rendered in the same pass, each one only waits for its own frames.
+            for (id, render_pass, color) in &targets {
+                let frame = match windows.begin_frame(*id) {
+                    Ok(frame) => frame,
+                    Err(hammer::FrameError::RecreateNeeded) => continue,
+                    Err(e) => panic!("Failed to begin frame: {}", e),
+                };
+                let surface = windows.get(*id).unwrap();
+                let framebuffer = surface.framebuffer(&frame.image, render_pass).unwrap();
+                let mut encoder = device.create_command_encoder(&queue).unwrap();
+                encoder
+                    .begin_render_pass_with(framebuffer, surface.clear_values(*color).unwrap())
+                    .unwrap()
+                    .finish()
+                    .unwrap();
+                let command_buffer = encoder.finish().unwrap();
+                match windows.end_frame(*id, frame, command_buffer, &queue) {
+                    Ok(()) | Err(hammer::FrameError::RecreateNeeded) => {}
+                    Err(e) => println!("Failed to end frame: {}", e),
+                }
+            }
+        }
+        _ => (),
+    })
+}
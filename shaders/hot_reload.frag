@@ -0,0 +1,11 @@
+#version 450
+
+// Edit and save this file while the example runs with `--hot-reload`, the triangle changes on
+// the next frame.
+
+layout(location = 0) in vec2 v_position;
+layout(location = 0) out vec4 f_color;
+
+void main() {
+    f_color = vec4(1.0, 0.5 + v_position.x, 0.0, 1.0);
+}
@@ -0,0 +1,10 @@
+#version 450
+
+const vec2 positions[3] = vec2[](vec2(-0.5, -0.25), vec2(0.0, 0.5), vec2(0.25, -0.1));
+
+layout(location = 0) out vec2 v_position;
+
+void main() {
+    v_position = positions[gl_VertexIndex];
+    gl_Position = vec4(positions[gl_VertexIndex], 0.0, 1.0);
+}
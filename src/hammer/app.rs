@@ -0,0 +1,89 @@
+use std::time::Instant;
+use winit::event::{Event, WindowEvent};
+use winit::event_loop::{ControlFlow, EventLoop};
+use winit::window::Window;
+
+use super::{Device, Frame, FrameError, Queue, Surface};
+
+/// An application whose event loop is run by `run`.
+pub trait App {
+    /// Called before rendering a frame whose image has another extent than the one before, and
+    /// before the first frame, e.g. to resize offscreen targets.
+    fn resize(&mut self, extent: [u32; 2]) {
+        let _ = extent;
+    }
+    /// Records the commands of a frame and submits them with `Frame::submit`, `run` presents
+    /// the frame afterwards.
+    ///
+    /// `dt` is the time since the previous frame in seconds, `frame_index` counts the frames from
+    /// zero.
+    fn render(&mut self, frame: &mut Frame<'_, Window>, dt: f32, frame_index: u64);
+    /// Called with every event of the window before `run` handles it. Returning `true` consumes
+    /// the event, e.g. to keep the window open on `CloseRequested`.
+    fn event(&mut self, event: &WindowEvent) -> bool {
+        let _ = event;
+        false
+    }
+}
+
+/// Runs `app` in the window of `surface` until the window is closed.
+///
+/// Resizes recreate the swapchain and frames are skipped while the window is minimized. Frames
+/// are presented on `queue`. Once the window is closed the loop exits after
+/// `Surface::shutdown`, so the resources of `app` can be dropped safely.
+///
+/// Applications that need more control, e.g. over several windows, run their own event loop
+/// with `Surface::begin_frame` instead.
+pub fn run<A: App + 'static>(
+    event_loop: EventLoop<()>,
+    mut surface: Surface<Window>,
+    device: Device,
+    queue: Queue,
+    mut app: A,
+) -> ! {
+    let mut extent = None;
+    let mut last_frame = Instant::now();
+    let mut frame_index = 0;
+    event_loop.run(move |event, _, control_flow| match event {
+        Event::WindowEvent { event, .. } if app.event(&event) => {}
+        Event::WindowEvent {
+            event: WindowEvent::CloseRequested,
+            ..
+        } => *control_flow = ControlFlow::Exit,
+        // The surface and `app` are dropped after this, when nothing uses them anymore.
+        Event::LoopDestroyed => {
+            if let Err(err) = surface.shutdown(&device) {
+                log::error!("Failed to shut down: {}", err);
+            }
+        }
+        Event::WindowEvent { event, .. } => surface.handle_window_event(&event),
+        Event::RedrawEventsCleared => {
+            let mut frame = match surface.begin_frame(&device) {
+                Ok(frame) => frame,
+                Err(FrameError::RecreateNeeded) => return,
+                Err(err) => {
+                    log::error!("Failed to begin frame: {}", err);
+                    return;
+                }
+            };
+            let image_extent = frame.surface().extent();
+            if image_extent != extent {
+                extent = image_extent;
+                app.resize(image_extent.unwrap());
+            }
+
+            let now = Instant::now();
+            let dt = (now - last_frame).as_secs_f32();
+            last_frame = now;
+            app.render(&mut frame, dt, frame_index);
+            frame_index += 1;
+
+            match frame.present(&queue) {
+                // The next `begin_frame` recreates the swapchain.
+                Ok(()) | Err(FrameError::RecreateNeeded) => {}
+                Err(err) => log::error!("Failed to present the frame: {}", err),
+            }
+        }
+        _ => (),
+    })
+}
@@ -0,0 +1,333 @@
+use bytemuck::{Pod, Zeroable};
+use derive_more::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use super::{Device, Indices, Mesh, TransferError};
+
+/// Vertex layout all loaded meshes are converted to. Missing normals and texture coordinates are
+/// zero.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Zeroable, Pod)]
+pub struct ModelVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub uv: [f32; 2],
+}
+::vulkano::impl_vertex!(ModelVertex, position, normal, uv);
+
+/// Where the texture of a material comes from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TextureRef {
+    /// Image file referenced by an OBJ material, relative paths are resolved against the OBJ.
+    Path(PathBuf),
+    /// Index into the images of a GLTF document.
+    GltfImage(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Material {
+    pub name: Option<String>,
+    pub base_color: [f32; 4],
+    pub base_color_texture: Option<TextureRef>,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            name: None,
+            base_color: [1.0; 4],
+            base_color_texture: None,
+        }
+    }
+}
+
+pub struct ModelMesh {
+    pub mesh: Mesh<ModelVertex>,
+    /// Index into `Model::materials`.
+    pub material: Option<usize>,
+}
+
+/// Column major 4x4 matrix, as stored in GLTF.
+pub type Transform = [[f32; 4]; 4];
+
+pub const IDENTITY: Transform = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+/// Placement of meshes in the scene.
+pub struct ModelNode {
+    /// Indices into `Model::meshes`. Nodes sharing a GLTF mesh share the range.
+    pub meshes: Range<usize>,
+    /// Transform from the local space of the meshes to the space of the model.
+    pub transform: Transform,
+}
+
+/// Meshes uploaded to device local memory together with the materials they reference and the
+/// nodes placing them.
+pub struct Model {
+    pub meshes: Vec<ModelMesh>,
+    pub materials: Vec<Material>,
+    pub nodes: Vec<ModelNode>,
+}
+
+/// Loads all objects of an OBJ file, triangulating faces.
+///
+/// Materials that fail to load are logged and skipped instead of failing the whole model.
+pub fn load_obj(device: &Device, path: impl AsRef<Path>) -> Result<Model, AssetError> {
+    let path = path.as_ref();
+    let (models, materials) = tobj::load_obj(
+        path,
+        &tobj::LoadOptions {
+            single_index: true,
+            triangulate: true,
+            ..Default::default()
+        },
+    )?;
+    let materials = materials.unwrap_or_else(|err| {
+        log::warn!(
+            "Failed to load the materials of {}: {}",
+            path.display(),
+            err
+        );
+        Vec::new()
+    });
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let meshes = models
+        .iter()
+        .map(|model| {
+            let mesh = &model.mesh;
+            let vertices = (0..mesh.positions.len() / 3)
+                .map(|i| ModelVertex {
+                    position: [
+                        mesh.positions[3 * i],
+                        mesh.positions[3 * i + 1],
+                        mesh.positions[3 * i + 2],
+                    ],
+                    normal: match mesh.normals.get(3 * i..3 * i + 3) {
+                        Some(n) => [n[0], n[1], n[2]],
+                        None => [0.0; 3],
+                    },
+                    uv: match mesh.texcoords.get(2 * i..2 * i + 2) {
+                        Some(uv) => [uv[0], uv[1]],
+                        None => [0.0; 2],
+                    },
+                })
+                .collect::<Vec<_>>();
+            Ok(ModelMesh {
+                mesh: Mesh::new(device, &vertices, Some(Indices::U32(&mesh.indices)))?,
+                material: mesh.material_id,
+            })
+        })
+        .collect::<Result<Vec<_>, AssetError>>()?;
+
+    let materials = materials
+        .into_iter()
+        .map(|material| Material {
+            name: Some(material.name),
+            base_color: [
+                material.diffuse[0],
+                material.diffuse[1],
+                material.diffuse[2],
+                material.dissolve,
+            ],
+            base_color_texture: if material.diffuse_texture.is_empty() {
+                None
+            } else {
+                Some(TextureRef::Path(directory.join(material.diffuse_texture)))
+            },
+        })
+        .collect();
+
+    let nodes = (0..meshes.len())
+        .map(|i| ModelNode {
+            meshes: i..i + 1,
+            transform: IDENTITY,
+        })
+        .collect();
+
+    Ok(Model {
+        meshes,
+        materials,
+        nodes,
+    })
+}
+
+/// Loads the indexed triangle primitives of the default scene of a GLTF file, or its first scene
+/// if there is no default.
+///
+/// Every GLTF mesh is uploaded once, `Model::nodes` places it with the accumulated transforms of
+/// the nodes referencing it.
+pub fn load_gltf(device: &Device, path: impl AsRef<Path>) -> Result<Model, AssetError> {
+    let (document, buffers, _) = gltf::import(path)?;
+    let scene = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+        .ok_or(AssetError::NoScene)?;
+
+    let mut pending = scene
+        .nodes()
+        .map(|node| (node, IDENTITY))
+        .collect::<Vec<_>>();
+    let mut meshes = Vec::new();
+    let mut nodes = Vec::new();
+    let mut uploaded = HashMap::<usize, Range<usize>>::new();
+    while let Some((node, parent)) = pending.pop() {
+        let transform = multiply(&parent, &node.transform().matrix());
+        pending.extend(node.children().map(|child| (child, transform)));
+        let mesh = match node.mesh() {
+            Some(mesh) => mesh,
+            None => continue,
+        };
+        if let Some(range) = uploaded.get(&mesh.index()) {
+            nodes.push(ModelNode {
+                meshes: range.clone(),
+                transform,
+            });
+            continue;
+        }
+        let start = meshes.len();
+        for primitive in mesh.primitives() {
+            if primitive.mode() != gltf::mesh::Mode::Triangles {
+                return Err(AssetError::UnsupportedPrimitiveMode(primitive.mode()));
+            }
+            let reader = primitive.reader(|buffer| Some(&*buffers[buffer.index()]));
+
+            let positions = reader
+                .read_positions()
+                .ok_or(AssetError::MissingPositions)?
+                .collect::<Vec<_>>();
+            let mut normals = reader.read_normals().into_iter().flatten();
+            let mut uvs = reader
+                .read_tex_coords(0)
+                .map(|uvs| uvs.into_f32())
+                .into_iter()
+                .flatten();
+            let vertices = positions
+                .into_iter()
+                .map(|position| ModelVertex {
+                    position,
+                    normal: normals.next().unwrap_or([0.0; 3]),
+                    uv: uvs.next().unwrap_or([0.0; 2]),
+                })
+                .collect::<Vec<_>>();
+            let indices = reader
+                .read_indices()
+                .ok_or(AssetError::MissingIndices)?
+                .into_u32()
+                .collect::<Vec<_>>();
+
+            meshes.push(ModelMesh {
+                mesh: Mesh::new(device, &vertices, Some(Indices::U32(&indices)))?,
+                material: primitive.material().index(),
+            });
+        }
+        uploaded.insert(mesh.index(), start..meshes.len());
+        nodes.push(ModelNode {
+            meshes: start..meshes.len(),
+            transform,
+        });
+    }
+
+    let materials = document
+        .materials()
+        .map(|material| {
+            let pbr = material.pbr_metallic_roughness();
+            Material {
+                name: material.name().map(str::to_string),
+                base_color: pbr.base_color_factor(),
+                base_color_texture: pbr
+                    .base_color_texture()
+                    .map(|info| TextureRef::GltfImage(info.texture().source().index())),
+            }
+        })
+        .collect();
+
+    Ok(Model {
+        meshes,
+        materials,
+        nodes,
+    })
+}
+
+/// `a * b` of column major matrices, i.e. `b` is applied first.
+fn multiply(a: &Transform, b: &Transform) -> Transform {
+    let mut result = [[0.0; 4]; 4];
+    for (column, b_column) in result.iter_mut().zip(b) {
+        for (row, value) in column.iter_mut().enumerate() {
+            *value = (0..4).map(|k| a[k][row] * b_column[k]).sum();
+        }
+    }
+    result
+}
+
+#[derive(Debug, From)]
+pub enum AssetError {
+    Obj(tobj::LoadError),
+    Gltf(gltf::Error),
+    /// The GLTF file does not contain a scene.
+    NoScene,
+    /// Only triangle lists are supported.
+    UnsupportedPrimitiveMode(gltf::mesh::Mode),
+    MissingPositions,
+    /// Only indexed primitives are supported.
+    MissingIndices,
+    Upload(TransferError),
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Obj(err) => write!(f, "failed to load OBJ: {}", err),
+            Self::Gltf(err) => write!(f, "failed to load GLTF: {}", err),
+            Self::NoScene => write!(f, "the GLTF file does not contain a scene"),
+            Self::UnsupportedPrimitiveMode(mode) => write!(
+                f,
+                "unsupported primitive mode {:?}, only triangle lists are supported",
+                mode
+            ),
+            Self::MissingPositions => write!(f, "a primitive has no vertex positions"),
+            Self::MissingIndices => write!(f, "a primitive is not indexed"),
+            Self::Upload(err) => write!(f, "failed to upload a mesh: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for AssetError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translation(x: f32, y: f32, z: f32) -> Transform {
+        let mut transform = IDENTITY;
+        transform[3] = [x, y, z, 1.0];
+        transform
+    }
+
+    #[test]
+    fn multiply_identity() {
+        let transform = translation(1.0, 2.0, 3.0);
+        assert_eq!(multiply(&IDENTITY, &transform), transform);
+        assert_eq!(multiply(&transform, &IDENTITY), transform);
+    }
+
+    #[test]
+    fn multiply_applies_child_first() {
+        // The parent scales by 2, the child translates by 1 along x, so the child's origin ends
+        // up at x = 2.
+        let mut scale = IDENTITY;
+        scale[0][0] = 2.0;
+        scale[1][1] = 2.0;
+        scale[2][2] = 2.0;
+        let world = multiply(&scale, &translation(1.0, 0.0, 0.0));
+        assert_eq!(world[3], [2.0, 0.0, 0.0, 1.0]);
+        assert_eq!(world[0][0], 2.0);
+    }
+}
@@ -0,0 +1,320 @@
+use bytemuck::Pod;
+use derive_more::*;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+use super::{
+    resource_id, BindingKey, Buffer, DescriptorCache, DescriptorKey, UniformBlocks, UniformLayout,
+};
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::buffer::BufferAccess;
+    pub use vulkano::descriptor_set::layout::*;
+    pub use vulkano::descriptor_set::*;
+    pub use vulkano::device::DeviceOwned;
+    pub use vulkano::image::view::ImageViewAbstract;
+    pub use vulkano::pipeline::Pipeline;
+    pub use vulkano::sampler::Sampler;
+}
+
+/// A descriptor set built for a set of a pipeline layout, see `BindGroup::builder`.
+///
+/// Bind it with `RenderPassEncoder::bind_group`, or pass `descriptor_set` to commands taking
+/// descriptor sets, e.g. `CommandEncoder::dispatch`.
+#[derive(Clone)]
+pub struct BindGroup {
+    set_index: u32,
+    descriptor_set: Arc<vulkano::PersistentDescriptorSet>,
+}
+
+impl BindGroup {
+    /// Starts a bind group for set `set_index` of the layout of `pipeline`. Building it reuses the
+    /// descriptor set of an earlier group with the same resources from the `DescriptorCache` of
+    /// the device of `pipeline`.
+    pub fn builder<P: vulkano::Pipeline + ?Sized>(
+        pipeline: &Arc<P>,
+        set_index: u32,
+    ) -> BindGroupBuilder {
+        use vulkano::DeviceOwned;
+        BindGroupBuilder {
+            layout: pipeline
+                .layout()
+                .set_layouts()
+                .get(set_index as usize)
+                .cloned(),
+            set_index,
+            resources: BTreeMap::new(),
+            cache: DescriptorCache::of(pipeline.device()),
+        }
+    }
+    /// The set of the pipeline layout the group was built for.
+    pub fn set_index(&self) -> u32 {
+        self.set_index
+    }
+    pub fn descriptor_set(&self) -> &Arc<vulkano::PersistentDescriptorSet> {
+        &self.descriptor_set
+    }
+}
+
+// What a binding of a `BindGroupBuilder` was given.
+enum Resource {
+    Buffer(vulkano::DescriptorType, Arc<dyn vulkano::BufferAccess>),
+    // A texture and a sampler at the same binding are a combined image sampler.
+    Image {
+        view: Option<Arc<dyn vulkano::ImageViewAbstract>>,
+        sampler: Option<Arc<vulkano::Sampler>>,
+    },
+}
+
+impl Resource {
+    fn descriptor_type(&self) -> vulkano::DescriptorType {
+        match self {
+            Self::Buffer(descriptor_type, _) => *descriptor_type,
+            Self::Image {
+                view: Some(_),
+                sampler: Some(_),
+            } => vulkano::DescriptorType::CombinedImageSampler,
+            Self::Image { view: Some(_), .. } => vulkano::DescriptorType::SampledImage,
+            Self::Image { .. } => vulkano::DescriptorType::Sampler,
+        }
+    }
+    fn key(&self) -> BindingKey {
+        match self {
+            Self::Buffer(descriptor_type, buffer) => {
+                BindingKey::Buffer(*descriptor_type, resource_id(buffer))
+            }
+            Self::Image { view, sampler } => BindingKey::Image {
+                view: view.as_ref().map(resource_id),
+                sampler: sampler.as_ref().map(resource_id),
+            },
+        }
+    }
+    fn write(&self, binding: u32) -> vulkano::WriteDescriptorSet {
+        match self {
+            Self::Buffer(_, buffer) => vulkano::WriteDescriptorSet::buffer(binding, buffer.clone()),
+            Self::Image {
+                view: Some(view),
+                sampler: Some(sampler),
+            } => vulkano::WriteDescriptorSet::image_view_sampler(
+                binding,
+                view.clone(),
+                sampler.clone(),
+            ),
+            Self::Image {
+                view: Some(view), ..
+            } => vulkano::WriteDescriptorSet::image_view(binding, view.clone()),
+            Self::Image {
+                sampler: Some(sampler),
+                ..
+            } => vulkano::WriteDescriptorSet::sampler(binding, sampler.clone()),
+            Self::Image { .. } => unreachable!("image bindings are created with a resource"),
+        }
+    }
+}
+
+/// Collects the resources of a `BindGroup` by binding and checks them against the layout of the
+/// set when building it.
+///
+/// Setting a binding again replaces the resource, except that `texture` and `sampler` on the
+/// same binding combine into a combined image sampler.
+pub struct BindGroupBuilder {
+    layout: Option<Arc<vulkano::DescriptorSetLayout>>,
+    set_index: u32,
+    resources: BTreeMap<u32, Resource>,
+    cache: Option<Arc<DescriptorCache>>,
+}
+
+impl BindGroupBuilder {
+    /// Reuses the descriptor sets of `cache` instead of the one of the device.
+    pub fn cache(mut self, cache: Arc<DescriptorCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+    /// Creates a new descriptor set when building the group, e.g. for a set that is only used
+    /// once.
+    pub fn uncached(mut self) -> Self {
+        self.cache = None;
+        self
+    }
+    /// Binds `buffer` as a uniform buffer.
+    pub fn buffer<T: Pod + Send + Sync>(self, binding: u32, buffer: &Buffer<T>) -> Self {
+        self.bind_buffer(binding, vulkano::DescriptorType::UniformBuffer, buffer)
+    }
+    /// Like `buffer`, but in debug builds panics if the layout of `T` does not match the uniform
+    /// block the shader declares at this binding, e.g. `Shader::uniform_blocks`.
+    pub fn uniform<T: UniformLayout>(
+        self,
+        binding: u32,
+        buffer: &Buffer<T>,
+        blocks: &UniformBlocks,
+    ) -> Self {
+        blocks.debug_check::<T>(self.set_index, binding);
+        self.buffer(binding, buffer)
+    }
+    pub fn storage_buffer<T: Pod + Send + Sync>(self, binding: u32, buffer: &Buffer<T>) -> Self {
+        self.bind_buffer(binding, vulkano::DescriptorType::StorageBuffer, buffer)
+    }
+    /// Binds `view` as a sampled image, or with the `sampler` of the same binding as a combined
+    /// image sampler, e.g. `OffscreenTexture::sampled_view`.
+    pub fn texture(mut self, binding: u32, view: Arc<dyn vulkano::ImageViewAbstract>) -> Self {
+        match self.resources.get_mut(&binding) {
+            Some(Resource::Image { view: slot, .. }) => *slot = Some(view),
+            _ => {
+                self.resources.insert(
+                    binding,
+                    Resource::Image {
+                        view: Some(view),
+                        sampler: None,
+                    },
+                );
+            }
+        }
+        self
+    }
+    /// Binds `sampler`, or with the `texture` of the same binding a combined image sampler.
+    pub fn sampler(mut self, binding: u32, sampler: &Arc<vulkano::Sampler>) -> Self {
+        let sampler = sampler.clone();
+        match self.resources.get_mut(&binding) {
+            Some(Resource::Image { sampler: slot, .. }) => *slot = Some(sampler),
+            _ => {
+                self.resources.insert(
+                    binding,
+                    Resource::Image {
+                        view: None,
+                        sampler: Some(sampler),
+                    },
+                );
+            }
+        }
+        self
+    }
+    /// Creates the descriptor set, after checking that every binding of the layout has a
+    /// resource of the type the shaders declared.
+    pub fn build(self) -> Result<BindGroup, BindGroupError> {
+        let layout = self.layout.ok_or(BindGroupError::NoSet(self.set_index))?;
+        let bindings = layout.bindings();
+        if let Some(&binding) = self
+            .resources
+            .keys()
+            .find(|binding| !bindings.contains_key(binding))
+        {
+            return Err(BindGroupError::UnknownBinding(binding));
+        }
+
+        for (&binding, layout_binding) in bindings {
+            let expected = layout_binding.descriptor_type;
+            let resource = self
+                .resources
+                .get(&binding)
+                .ok_or(BindGroupError::MissingBinding { binding, expected })?;
+            let provided = resource.descriptor_type();
+            // Combined image samplers with immutable samplers only take the image.
+            let immutable_sampler = expected == vulkano::DescriptorType::CombinedImageSampler
+                && provided == vulkano::DescriptorType::SampledImage
+                && !layout_binding.immutable_samplers.is_empty();
+            if provided != expected && !immutable_sampler {
+                return Err(BindGroupError::TypeMismatch {
+                    binding,
+                    expected,
+                    provided,
+                });
+            }
+        }
+
+        let resources = &self.resources;
+        let create = || {
+            let writes = resources
+                .iter()
+                .map(|(&binding, resource)| resource.write(binding));
+            vulkano::PersistentDescriptorSet::new(layout.clone(), writes)
+        };
+        let descriptor_set = match &self.cache {
+            Some(cache) => {
+                let key = DescriptorKey {
+                    layout: resource_id(&layout),
+                    bindings: resources
+                        .iter()
+                        .map(|(&binding, resource)| (binding, resource.key()))
+                        .collect(),
+                };
+                match cache.get(key) {
+                    Ok(descriptor_set) => descriptor_set,
+                    Err(miss) => cache.insert(miss, create()?),
+                }
+            }
+            None => create()?,
+        };
+        Ok(BindGroup {
+            set_index: self.set_index,
+            descriptor_set,
+        })
+    }
+    fn bind_buffer<T: Pod + Send + Sync>(
+        mut self,
+        binding: u32,
+        descriptor_type: vulkano::DescriptorType,
+        buffer: &Buffer<T>,
+    ) -> Self {
+        self.resources.insert(
+            binding,
+            Resource::Buffer(descriptor_type, buffer.buffer().clone()),
+        );
+        self
+    }
+}
+
+#[derive(Debug, From)]
+pub enum BindGroupError {
+    /// The pipeline layout has no set with this index.
+    #[from(ignore)]
+    NoSet(u32),
+    /// A resource was given for a binding the set does not have.
+    #[from(ignore)]
+    UnknownBinding(u32),
+    /// The set has a binding no resource was given for.
+    #[from(ignore)]
+    MissingBinding {
+        binding: u32,
+        expected: vulkano::DescriptorType,
+    },
+    /// The resource of a binding is not of the type the shaders declared.
+    #[from(ignore)]
+    TypeMismatch {
+        binding: u32,
+        expected: vulkano::DescriptorType,
+        provided: vulkano::DescriptorType,
+    },
+    Creation(vulkano::DescriptorSetCreationError),
+}
+
+impl fmt::Display for BindGroupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoSet(set_index) => {
+                write!(f, "the pipeline layout has no descriptor set {}", set_index)
+            }
+            Self::UnknownBinding(binding) => {
+                write!(f, "the descriptor set has no binding {}", binding)
+            }
+            Self::MissingBinding { binding, expected } => write!(
+                f,
+                "binding {} expects a {:?}, but nothing was bound",
+                binding, expected
+            ),
+            Self::TypeMismatch {
+                binding,
+                expected,
+                provided,
+            } => write!(
+                f,
+                "binding {} expects a {:?}, but a {:?} was bound",
+                binding, expected, provided
+            ),
+            Self::Creation(err) => write!(f, "failed to create the descriptor set: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for BindGroupError {}
@@ -0,0 +1,502 @@
+use bytemuck::Pod;
+use derive_more::*;
+use std::fmt;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::buffer::cpu_access::*;
+    pub use vulkano::buffer::sys::*;
+    pub use vulkano::buffer::*;
+    pub use vulkano::command_buffer::*;
+    pub use vulkano::device::*;
+    pub use vulkano::memory::*;
+    pub use vulkano::sync::*;
+    pub use vulkano::*;
+}
+
+/// Sorted, non overlapping byte ranges that have been written on the host but not flushed yet.
+///
+/// The ranges are kept as written, so copying them does not touch the bytes around them. `align`
+/// expands them to `non_coherent_atom_size`, as required for flushing non-coherent memory.
+#[derive(Clone, Debug)]
+pub struct DirtyRanges {
+    atom_size: u64,
+    size: u64,
+    ranges: Vec<Range<u64>>,
+}
+
+impl DirtyRanges {
+    pub fn new(atom_size: u64, size: u64) -> Self {
+        Self {
+            atom_size: atom_size.max(1),
+            size,
+            ranges: Vec::new(),
+        }
+    }
+    /// Expands `range` to the atom size, clamping the end to the size of the buffer.
+    pub fn align(&self, range: Range<u64>) -> Range<u64> {
+        let start = range.start / self.atom_size * self.atom_size;
+        let end = range.end.div_ceil(self.atom_size) * self.atom_size;
+        start..end.min(self.size)
+    }
+    /// Marks `range` as dirty, merging it with overlapping or adjacent ranges.
+    pub fn insert(&mut self, range: Range<u64>) {
+        if range.start >= range.end {
+            return;
+        }
+        let mut range = range.start..range.end.min(self.size);
+
+        let first = self.ranges.partition_point(|r| r.end < range.start);
+        let last = self.ranges.partition_point(|r| r.start <= range.end);
+        if first < last {
+            range.start = range.start.min(self.ranges[first].start);
+            range.end = range.end.max(self.ranges[last - 1].end);
+        }
+        self.ranges.splice(first..last, [range]);
+    }
+    pub fn ranges(&self) -> &[Range<u64>] {
+        &self.ranges
+    }
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+    /// Returns all dirty ranges and marks the buffer as clean.
+    pub fn take(&mut self) -> Vec<Range<u64>> {
+        std::mem::take(&mut self.ranges)
+    }
+}
+
+/// Host visible buffer whose writes only become visible to the device once they are flushed.
+///
+/// Writes go into a host side copy, so they never race with the device reading the buffer. The
+/// dirty ranges are copied into the mapped memory and flushed when a command buffer using the
+/// buffer is submitted, or earlier by calling `flush`. Coherent memory is preferred where the
+/// device has it, flushing then only copies.
+///
+/// Writes made while the device still uses the buffer from an earlier submission cannot be
+/// flushed, submitting the buffer again before that finished fails with `AlreadyInUse`.
+pub struct MappedBuffer<T: Pod + Send + Sync> {
+    buffer: vulkano::UnsafeBuffer,
+    memory: vulkano::MappedDeviceMemory,
+    len: usize,
+    state: Mutex<MappedState<T>>,
+}
+
+struct MappedState<T> {
+    data: Vec<T>,
+    dirty: DirtyRanges,
+    gpu_locks: usize,
+}
+
+#[derive(Debug, From)]
+pub enum MappedBufferError {
+    /// Zero sized buffers cannot be created.
+    Empty,
+    /// None of the memory types the buffer supports is host visible.
+    NoHostVisibleMemory,
+    Creation(vulkano::BufferCreationError),
+    Allocation(vulkano::DeviceMemoryAllocationError),
+    Bind(vulkano::OomError),
+    /// Mapping the memory failed, vulkano does not export the reason.
+    Map,
+}
+
+impl fmt::Display for MappedBufferError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "cannot create a zero sized buffer"),
+            Self::NoHostVisibleMemory => write!(f, "no host visible memory type fits the buffer"),
+            Self::Creation(err) => write!(f, "failed to create the buffer: {}", err),
+            Self::Allocation(err) => write!(f, "failed to allocate the memory: {}", err),
+            Self::Bind(err) => write!(f, "failed to bind the memory: {}", err),
+            Self::Map => write!(f, "failed to map the memory"),
+        }
+    }
+}
+
+impl std::error::Error for MappedBufferError {}
+
+impl<T: Pod + Send + Sync> MappedBuffer<T> {
+    pub fn new(
+        device: Arc<vulkano::Device>,
+        usage: vulkano::BufferUsage,
+        data: &[T],
+    ) -> Result<Arc<Self>, MappedBufferError> {
+        let size = std::mem::size_of_val(data) as vulkano::DeviceSize;
+        if size == 0 {
+            return Err(MappedBufferError::Empty);
+        }
+        let buffer = vulkano::UnsafeBuffer::new(
+            device.clone(),
+            vulkano::UnsafeBufferCreateInfo {
+                size,
+                usage,
+                ..Default::default()
+            },
+        )?;
+        let requirements = buffer.memory_requirements();
+        let memory_type = select_memory_type(
+            device
+                .physical_device()
+                .memory_types()
+                .map(|ty| (ty.is_host_visible(), ty.is_host_coherent())),
+            requirements.memory_type_bits,
+        )
+        .ok_or(MappedBufferError::NoHostVisibleMemory)?;
+        let memory = vulkano::DeviceMemory::allocate(
+            device.clone(),
+            vulkano::MemoryAllocateInfo {
+                allocation_size: requirements.size,
+                memory_type_index: memory_type,
+                ..Default::default()
+            },
+        )?;
+        unsafe { buffer.bind_memory(&memory, 0)? };
+        let memory = vulkano::MappedDeviceMemory::new(memory, 0..requirements.size)
+            .map_err(|_| MappedBufferError::Map)?;
+
+        // Sized to the allocation, so ranges aligned up to the atom size stay inside it.
+        let atom_size = device.physical_device().properties().non_coherent_atom_size;
+        let mut dirty = DirtyRanges::new(atom_size, requirements.size);
+        dirty.insert(0..size);
+        Ok(Arc::new(Self {
+            buffer,
+            memory,
+            len: data.len(),
+            state: Mutex::new(MappedState {
+                data: data.to_vec(),
+                dirty,
+                gpu_locks: 0,
+            }),
+        }))
+    }
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Writes `data` to the elements in `range`. The device only sees the change after the next
+    /// submission using the buffer, or `flush`.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if `range` is out of bounds or its length does not match `data`.
+    pub fn write(&self, range: Range<usize>, data: &[T]) {
+        let stride = std::mem::size_of::<T>() as u64;
+        let mut state = self.state.lock().unwrap();
+        state.data[range.clone()].copy_from_slice(data);
+        state
+            .dirty
+            .insert(range.start as u64 * stride..range.end as u64 * stride);
+    }
+    /// Copies all dirty ranges into the mapped memory and flushes them.
+    ///
+    /// Fails with `AlreadyInUse` while the device is using the buffer.
+    pub fn flush(&self) -> Result<(), vulkano::AccessError> {
+        let mut state = self.state.lock().unwrap();
+        if state.gpu_locks > 0 {
+            return Err(vulkano::AccessError::AlreadyInUse);
+        }
+        self.flush_dirty(&mut state);
+        Ok(())
+    }
+    /// Reads the elements in `range` as the device sees them, invalidating the host caches first.
+    ///
+    /// Writes that have not been flushed yet are not included. Fails with `AlreadyInUse` while the
+    /// device is using the buffer.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if `range` is out of bounds.
+    pub fn read(&self, range: Range<usize>) -> Result<Vec<T>, vulkano::AccessError> {
+        let stride = std::mem::size_of::<T>() as u64;
+        let state = self.state.lock().unwrap();
+        if state.gpu_locks > 0 {
+            return Err(vulkano::AccessError::AlreadyInUse);
+        }
+        let bytes = range.start as u64 * stride..range.end as u64 * stride;
+        // The device does not use the memory and the state is locked, the invalidated range is
+        // atom aligned or ends at the allocation size.
+        let mapped = unsafe {
+            self.memory
+                .invalidate_range(state.dirty.align(bytes.clone()))
+                .unwrap();
+            self.memory.read(bytes).unwrap()
+        };
+        Ok(bytemuck::cast_slice(mapped).to_vec())
+    }
+    fn flush_dirty(&self, state: &mut MappedState<T>) {
+        let src: &[u8] = bytemuck::cast_slice(&state.data);
+        for range in state.dirty.take() {
+            // The device does not use the memory and the state is locked, the flushed range is
+            // atom aligned or ends at the allocation size.
+            unsafe {
+                self.memory
+                    .write(range.clone())
+                    .unwrap()
+                    .copy_from_slice(&src[range.start as usize..range.end as usize]);
+                self.memory.flush_range(state.dirty.align(range)).unwrap();
+            }
+        }
+    }
+}
+
+/// Index of the first host visible memory type allowed by `memory_type_bits`, preferring
+/// coherent ones. `memory_types` yields whether each type is host visible and host coherent.
+fn select_memory_type(
+    memory_types: impl Iterator<Item = (bool, bool)>,
+    memory_type_bits: u32,
+) -> Option<u32> {
+    let candidates: Vec<_> = memory_types
+        .enumerate()
+        .filter(|&(index, (visible, _))| visible && memory_type_bits & (1 << index) != 0)
+        .collect();
+    candidates
+        .iter()
+        .find(|(_, (_, coherent))| *coherent)
+        .or_else(|| candidates.first())
+        .map(|&(index, _)| index as u32)
+}
+
+unsafe impl<T: Pod + Send + Sync> vulkano::BufferAccess for MappedBuffer<T> {
+    fn inner(&self) -> vulkano::BufferInner<'_> {
+        vulkano::BufferInner {
+            buffer: &self.buffer,
+            offset: 0,
+        }
+    }
+    fn size(&self) -> vulkano::DeviceSize {
+        self.buffer.size()
+    }
+    fn conflict_key(&self) -> (u64, u64) {
+        (self.buffer.key(), 0)
+    }
+    // Called by vulkano when submitting a command buffer using the buffer, which flushes the
+    // writes made since the last submission.
+    fn try_gpu_lock(
+        &self,
+        _exclusive: bool,
+        _queue: &vulkano::Queue,
+    ) -> Result<(), vulkano::AccessError> {
+        let mut state = self.state.lock().unwrap();
+        if !state.dirty.is_empty() {
+            if state.gpu_locks > 0 {
+                return Err(vulkano::AccessError::AlreadyInUse);
+            }
+            self.flush_dirty(&mut state);
+        }
+        state.gpu_locks += 1;
+        Ok(())
+    }
+    unsafe fn increase_gpu_lock(&self) {
+        self.state.lock().unwrap().gpu_locks += 1;
+    }
+    unsafe fn unlock(&self) {
+        self.state.lock().unwrap().gpu_locks -= 1;
+    }
+}
+
+unsafe impl<T: Pod + Send + Sync> vulkano::TypedBufferAccess for MappedBuffer<T> {
+    type Content = [T];
+}
+
+unsafe impl<T: Pod + Send + Sync> vulkano::DeviceOwned for MappedBuffer<T> {
+    fn device(&self) -> &Arc<vulkano::Device> {
+        self.buffer.device()
+    }
+}
+
+/// Host visible buffer created with the usage its constructor is named after.
+///
+/// Derefs to the `Arc<vulkano::CpuAccessibleBuffer<[T]>>`, so `buffer.clone()` can be passed to
+/// vulkano directly.
+#[derive(Deref)]
+pub struct Buffer<T: Pod + Send + Sync> {
+    buffer: Arc<vulkano::CpuAccessibleBuffer<[T]>>,
+}
+
+impl<T: Pod + Send + Sync> Buffer<T> {
+    pub fn new(
+        device: Arc<vulkano::Device>,
+        usage: vulkano::BufferUsage,
+        data: &[T],
+    ) -> Result<Self, vulkano::DeviceMemoryAllocationError> {
+        let buffer =
+            vulkano::CpuAccessibleBuffer::from_iter(device, usage, false, data.iter().copied())?;
+        Ok(Self { buffer })
+    }
+    pub fn vertex(
+        device: Arc<vulkano::Device>,
+        data: &[T],
+    ) -> Result<Self, vulkano::DeviceMemoryAllocationError> {
+        Self::new(device, vulkano::BufferUsage::vertex_buffer(), data)
+    }
+    pub fn index(
+        device: Arc<vulkano::Device>,
+        data: &[T],
+    ) -> Result<Self, vulkano::DeviceMemoryAllocationError> {
+        Self::new(device, vulkano::BufferUsage::index_buffer(), data)
+    }
+    /// Buffer holding a single value.
+    pub fn uniform(
+        device: Arc<vulkano::Device>,
+        value: T,
+    ) -> Result<Self, vulkano::DeviceMemoryAllocationError> {
+        Self::new(device, vulkano::BufferUsage::uniform_buffer(), &[value])
+    }
+    pub fn storage(
+        device: Arc<vulkano::Device>,
+        data: &[T],
+    ) -> Result<Self, vulkano::DeviceMemoryAllocationError> {
+        Self::new(device, vulkano::BufferUsage::storage_buffer(), data)
+    }
+    pub fn buffer(&self) -> &Arc<vulkano::CpuAccessibleBuffer<[T]>> {
+        &self.buffer
+    }
+    pub fn len(&self) -> usize {
+        vulkano::TypedBufferAccess::len(&*self.buffer) as usize
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Writes `data` to the elements in `range`.
+    ///
+    /// Fails instead of blocking if the device or another mapping is using the buffer.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if `range` is out of bounds or its length does not match `data`.
+    pub fn write(&self, range: Range<usize>, data: &[T]) -> Result<(), vulkano::WriteLockError> {
+        let mut mapping = self.buffer.write()?;
+        mapping[range].copy_from_slice(data);
+        Ok(())
+    }
+    /// Reads the elements in `range`.
+    ///
+    /// Fails instead of blocking if the device is writing to the buffer.
+    pub fn read(&self, range: Range<usize>) -> Result<Vec<T>, vulkano::ReadLockError> {
+        let mapping = self.buffer.read()?;
+        Ok(mapping[range].to_vec())
+    }
+}
+
+impl<T: Pod + Send + Sync> Drop for Buffer<T> {
+    /// Drops the cached descriptor sets using the buffer, which would keep it alive otherwise.
+    fn drop(&mut self) {
+        use vulkano::DeviceOwned;
+        if let Some(cache) = super::DescriptorCache::of(self.buffer.device()) {
+            cache.mark_dirty(&self.buffer);
+        }
+    }
+}
+
+/// Index data of an [`IndexBuffer`] with indices of type `I`.
+pub type IndexData<I> = Arc<dyn vulkano::TypedBufferAccess<Content = [I]>>;
+
+/// Index buffer with either 16 or 32 bit indices, in host visible or device local memory.
+///
+/// vulkano binds index buffers through an `Arc` of a sized type, so the trait object is wrapped
+/// once here instead of on every bind. Use `IndexBuffer::u16` and `IndexBuffer::u32` to wrap it.
+#[derive(Clone)]
+pub enum IndexBuffer {
+    U16(Arc<IndexData<u16>>),
+    U32(Arc<IndexData<u32>>),
+}
+
+impl IndexBuffer {
+    pub fn u16(buffer: IndexData<u16>) -> Self {
+        Self::U16(Arc::new(buffer))
+    }
+    pub fn u32(buffer: IndexData<u32>) -> Self {
+        Self::U32(Arc::new(buffer))
+    }
+    pub fn len(&self) -> u64 {
+        match self {
+            Self::U16(buffer) => vulkano::TypedBufferAccess::len(&***buffer),
+            Self::U32(buffer) => vulkano::TypedBufferAccess::len(&***buffer),
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    pub fn bind<L, P>(&self, builder: &mut vulkano::AutoCommandBufferBuilder<L, P>) {
+        match self {
+            Self::U16(buffer) => builder.bind_index_buffer(buffer.clone()),
+            Self::U32(buffer) => builder.bind_index_buffer(buffer.clone()),
+        };
+    }
+}
+
+impl From<&Buffer<u16>> for IndexBuffer {
+    fn from(buffer: &Buffer<u16>) -> Self {
+        Self::u16(buffer.buffer.clone())
+    }
+}
+
+/// Lets `&buffer` be passed where vulkano takes vertex buffers, e.g. to
+/// `RenderPassEncoder::bind_vertex_buffers`.
+impl<T: Pod + Send + Sync> vulkano::BufferAccessObject for &Buffer<T> {
+    fn as_buffer_access_object(&self) -> Arc<dyn vulkano::BufferAccess> {
+        self.buffer.clone()
+    }
+}
+
+impl From<&Buffer<u32>> for IndexBuffer {
+    fn from(buffer: &Buffer<u32>) -> Self {
+        Self::u32(buffer.buffer.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dirty_ranges_coalesce() {
+        let mut dirty = DirtyRanges::new(64, 1024);
+        dirty.insert(100..120);
+        dirty.insert(300..310);
+        // Adjacent to the first range.
+        dirty.insert(120..130);
+        assert_eq!(dirty.ranges(), &[100..130, 300..310]);
+        // Overlapping both.
+        dirty.insert(110..305);
+        assert_eq!(dirty.ranges().first(), Some(&(100..310)));
+        assert_eq!(dirty.ranges().len(), 1);
+        // Empty ranges are ignored.
+        dirty.insert(500..500);
+        assert_eq!(dirty.take().len(), 1);
+        assert!(dirty.is_empty());
+    }
+
+    #[test]
+    fn dirty_ranges_align() {
+        let dirty = DirtyRanges::new(64, 1000);
+        assert_eq!(dirty.align(100..130), 64..192);
+        assert_eq!(dirty.align(128..192), 128..192);
+        // The end is clamped to the size, which does not need to be aligned.
+        assert_eq!(dirty.align(960..990), 960..1000);
+        // A zero atom size is treated as 1.
+        assert_eq!(DirtyRanges::new(0, 1000).align(3..7), 3..7);
+    }
+
+    #[test]
+    fn dirty_ranges_clamp() {
+        let mut dirty = DirtyRanges::new(64, 1000);
+        dirty.insert(990..1100);
+        assert_eq!(dirty.ranges().first(), Some(&(990..1000)));
+        assert_eq!(dirty.ranges().len(), 1);
+    }
+
+    #[test]
+    fn memory_type_prefers_coherent() {
+        let types = [(false, false), (true, false), (true, true)];
+        assert_eq!(select_memory_type(types.into_iter(), 0b111), Some(2));
+        // Falls back to non-coherent memory where the buffer cannot use coherent memory.
+        assert_eq!(select_memory_type(types.into_iter(), 0b011), Some(1));
+        assert_eq!(select_memory_type(types.into_iter(), 0b001), None);
+    }
+}
@@ -0,0 +1,134 @@
+use std::fmt;
+use std::sync::Arc;
+
+use super::{Device, Queue, Surface, SurfaceImage, TransferError};
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::format::*;
+    pub use vulkano::image::*;
+    pub use vulkano::sync::*;
+}
+
+use vulkano::GpuFuture;
+
+/// Pixels of a captured image, 8 bit RGBA without padding between the rows.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RgbaImageData {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl RgbaImageData {
+    /// The pixel at `x`, `y`, counted from the top left corner.
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let offset = (y as usize * self.width as usize + x as usize) * 4;
+        let mut pixel = [0; 4];
+        pixel.copy_from_slice(&self.pixels[offset..offset + 4]);
+        pixel
+    }
+    /// Saves the image as a PNG file.
+    #[cfg(feature = "image")]
+    pub fn save_png(&self, path: impl AsRef<std::path::Path>) -> Result<(), CaptureError> {
+        ::image::save_buffer_with_format(
+            path,
+            &self.pixels,
+            self.width,
+            self.height,
+            ::image::ColorType::Rgba8,
+            ::image::ImageFormat::Png,
+        )?;
+        Ok(())
+    }
+}
+
+impl<W: Send + Sync + 'static> Surface<W> {
+    /// Copies `image` to the host, e.g. for screenshots, blocking until the copy finished.
+    ///
+    /// The copy runs on `queue` after `after`, which has to include the rendering into the image.
+    /// Present the image afterwards, after `vulkano::sync::now` since `after` has been consumed.
+    /// The swapchain has to be created with `transfer_source` usage, which
+    /// `SwapchainDescriptor::default` includes.
+    pub fn capture<F: GpuFuture + 'static>(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        image: &SurfaceImage<W>,
+        after: F,
+    ) -> Result<RgbaImageData, CaptureError> {
+        device.capture_image(queue, image.image.clone(), after)
+    }
+}
+
+impl Device {
+    /// Copies an 8 bit RGBA or BGRA `image` to the host in RGBA order, see `Surface::capture`.
+    pub fn capture_image<F: GpuFuture + 'static>(
+        &self,
+        queue: &Queue,
+        image: Arc<dyn vulkano::ImageAccess>,
+        after: F,
+    ) -> Result<RgbaImageData, CaptureError> {
+        let swizzle = match image.format() {
+            vulkano::Format::R8G8B8A8_UNORM | vulkano::Format::R8G8B8A8_SRGB => false,
+            vulkano::Format::B8G8R8A8_UNORM | vulkano::Format::B8G8R8A8_SRGB => true,
+            format => return Err(CaptureError::UnsupportedFormat(format)),
+        };
+        if !image.inner().image.usage().transfer_source {
+            return Err(CaptureError::NoTransferSource);
+        }
+
+        let [width, height] = image.dimensions().width_height();
+        let mut pixels = self.read_image_after(queue, image, after)?;
+        if swizzle {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+        Ok(RgbaImageData {
+            width,
+            height,
+            pixels,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum CaptureError {
+    /// Only 8 bit RGBA and BGRA images can be captured.
+    UnsupportedFormat(vulkano::Format),
+    /// The swapchain images cannot be copied, see `SwapchainDescriptor::image_usage`.
+    NoTransferSource,
+    Transfer(TransferError),
+    #[cfg(feature = "image")]
+    Save(::image::ImageError),
+}
+
+impl From<TransferError> for CaptureError {
+    fn from(err: TransferError) -> Self {
+        Self::Transfer(err)
+    }
+}
+
+#[cfg(feature = "image")]
+impl From<::image::ImageError> for CaptureError {
+    fn from(err: ::image::ImageError) -> Self {
+        Self::Save(err)
+    }
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnsupportedFormat(format) => {
+                write!(f, "cannot capture images of format {:?}", format)
+            }
+            Self::NoTransferSource => write!(f, "the swapchain images cannot be copied from"),
+            Self::Transfer(err) => write!(f, "failed to copy the image: {}", err),
+            #[cfg(feature = "image")]
+            Self::Save(err) => write!(f, "failed to save the image: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {}
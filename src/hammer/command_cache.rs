@@ -0,0 +1,89 @@
+use std::sync::{Arc, Mutex};
+
+use super::Surface;
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::command_buffer::*;
+}
+
+/// Command buffers recorded once per swapchain image and resubmitted every frame, e.g. for a
+/// scene that does not change between frames.
+///
+/// The command buffer of an image can still be executing when the image is acquired again, so
+/// they have to be recorded with `CommandBufferUsage::SimultaneousUse`, see
+/// `Device::create_reusable_command_encoder`. They render into the framebuffers of the swapchain
+/// they were recorded for, `invalidate_on_recreate` drops them together with it. Anything else
+/// they depend on changing, e.g. the scene, needs an explicit `invalidate`.
+#[derive(Default)]
+pub struct CommandBufferCache {
+    // Indexed by the image number.
+    entries: Mutex<Vec<Option<Arc<vulkano::PrimaryAutoCommandBuffer>>>>,
+}
+
+impl CommandBufferCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// The command buffer of image `image_num`, `None` if it has not been recorded since the
+    /// last invalidation.
+    pub fn get(&self, image_num: usize) -> Option<Arc<vulkano::PrimaryAutoCommandBuffer>> {
+        self.entries
+            .lock()
+            .unwrap()
+            .get(image_num)
+            .cloned()
+            .flatten()
+    }
+    /// Caches `command_buffer` for image `image_num`, replacing the one recorded before.
+    pub fn insert(
+        &self,
+        image_num: usize,
+        command_buffer: vulkano::PrimaryAutoCommandBuffer,
+    ) -> Arc<vulkano::PrimaryAutoCommandBuffer> {
+        let command_buffer = Arc::new(command_buffer);
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() <= image_num {
+            entries.resize(image_num + 1, None);
+        }
+        entries[image_num] = Some(command_buffer.clone());
+        command_buffer
+    }
+    /// The command buffer of image `image_num`, recorded with `record` if there is none.
+    ///
+    /// The cache is not locked while recording, so `record` can use it as well.
+    pub fn get_or_insert_with<F>(
+        &self,
+        image_num: usize,
+        record: F,
+    ) -> Arc<vulkano::PrimaryAutoCommandBuffer>
+    where
+        F: FnOnce() -> vulkano::PrimaryAutoCommandBuffer,
+    {
+        match self.get(image_num) {
+            Some(command_buffer) => command_buffer,
+            None => self.insert(image_num, record()),
+        }
+    }
+    /// Drops all command buffers, so they are recorded again, e.g. after the scene changed.
+    ///
+    /// Command buffers still executing stay alive until the frames submitting them finished.
+    pub fn invalidate(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+    /// Invalidates the cache whenever the swapchain of `surface` is recreated, e.g. after a
+    /// resize, see `Surface::on_recreate`.
+    ///
+    /// The surface only keeps a weak reference, so the cache can be dropped before it.
+    pub fn invalidate_on_recreate<W: Send + Sync + 'static>(
+        self: &Arc<Self>,
+        surface: &mut Surface<W>,
+    ) {
+        let cache = Arc::downgrade(self);
+        surface.on_recreate(move |_| {
+            if let Some(cache) = cache.upgrade() {
+                cache.invalidate();
+            }
+        });
+    }
+}
@@ -0,0 +1,232 @@
+use bytemuck::Pod;
+use derive_more::*;
+use std::fmt;
+use std::sync::Arc;
+
+use super::{
+    check_push_constants_size, push_constants_span, CommandEncoder, Device, PushConstantsError,
+    SpecializationError, SpecializationMap, SubmitError, SubmitFuture,
+};
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::buffer::*;
+    pub use vulkano::command_buffer::*;
+    pub use vulkano::descriptor_set::*;
+    pub use vulkano::device::DeviceOwned;
+    pub use vulkano::pipeline::compute::ComputePipelineCreationError;
+    pub use vulkano::pipeline::*;
+    pub use vulkano::shader::ShaderModule;
+    pub use vulkano::OomError;
+}
+
+use vulkano::{DeviceOwned, Pipeline};
+
+impl Device {
+    /// Creates a compute pipeline from the entry point `entry` of `module`, through the pipeline
+    /// cache of the device.
+    ///
+    /// The descriptor set layouts and push constant ranges are reflected from the shader.
+    pub fn create_compute_pipeline(
+        &self,
+        module: &vulkano::ShaderModule,
+        entry: &str,
+        specialization: &SpecializationMap,
+    ) -> Result<Arc<vulkano::ComputePipeline>, ComputePipelineError> {
+        let entry_point = module
+            .entry_point(entry)
+            .ok_or_else(|| ComputePipelineError::NoEntryPoint(entry.to_string()))?;
+        specialization.check(&entry_point)?;
+        Ok(vulkano::ComputePipeline::new(
+            self.handle().clone(),
+            entry_point,
+            &specialization.constants(),
+            self.pipeline_cache().cloned(),
+            |_| {},
+        )?)
+    }
+    /// Records a single dispatch of `pipeline` with `descriptor_sets` bound from set 0 and
+    /// submits it on the compute queue, e.g. for one-off work at load time.
+    ///
+    /// Wait for the returned future before reading the results.
+    pub fn compute_once(
+        &self,
+        pipeline: &Arc<vulkano::ComputePipeline>,
+        descriptor_sets: impl vulkano::DescriptorSetsCollection,
+        group_counts: [u32; 3],
+    ) -> Result<SubmitFuture, ComputeOnceError> {
+        let queue = self
+            .compute_queue()
+            .ok_or(ComputeOnceError::NoComputeQueue)?;
+        let mut encoder = self.create_command_encoder(queue)?;
+        if let Err(err) = encoder.dispatch(pipeline, descriptor_sets, &(), group_counts) {
+            encoder.abandon();
+            return Err(err.into());
+        }
+        Ok(queue.submit(encoder.finish()?)?)
+    }
+}
+
+impl CommandEncoder {
+    /// Records dispatching `group_counts` work groups of `pipeline`, with `descriptor_sets`
+    /// bound from set 0 and `push_constants` pushed for all push constant ranges of the pipeline.
+    ///
+    /// Pass `&()` as `push_constants` for shaders without push constants. The group counts are
+    /// checked against the `max_compute_work_group_count` limit of the device.
+    pub fn dispatch<Pc: Pod>(
+        &mut self,
+        pipeline: &Arc<vulkano::ComputePipeline>,
+        descriptor_sets: impl vulkano::DescriptorSetsCollection,
+        push_constants: &Pc,
+        group_counts: [u32; 3],
+    ) -> Result<&mut Self, DispatchError> {
+        let max = self
+            .builder_mut()
+            .device()
+            .physical_device()
+            .properties()
+            .max_compute_work_group_count;
+        if group_counts
+            .iter()
+            .zip(max)
+            .any(|(&count, max)| count > max)
+        {
+            return Err(DispatchError::GroupCountExceeded { group_counts, max });
+        }
+        self.bind_compute(pipeline, descriptor_sets, push_constants)?;
+        self.builder_mut().dispatch(group_counts)?;
+        Ok(self)
+    }
+    /// Like `dispatch`, but reads the group counts from element `index` of `buffer` when the
+    /// command executes, e.g. after an earlier dispatch wrote them. They cannot be checked here.
+    pub fn dispatch_indirect<Pc, B>(
+        &mut self,
+        pipeline: &Arc<vulkano::ComputePipeline>,
+        descriptor_sets: impl vulkano::DescriptorSetsCollection,
+        push_constants: &Pc,
+        buffer: Arc<B>,
+        index: u64,
+    ) -> Result<&mut Self, DispatchError>
+    where
+        Pc: Pod,
+        B: vulkano::TypedBufferAccess<Content = [vulkano::DispatchIndirectCommand]> + 'static,
+    {
+        let len = buffer.len();
+        let command = vulkano::BufferSlice::from_typed_buffer_access(buffer)
+            .slice(index..index + 1)
+            .ok_or(DispatchError::IndirectOutOfBounds { index, len })?;
+        self.bind_compute(pipeline, descriptor_sets, push_constants)?;
+        self.builder_mut().dispatch_indirect(command)?;
+        Ok(self)
+    }
+    fn bind_compute<Pc: Pod>(
+        &mut self,
+        pipeline: &Arc<vulkano::ComputePipeline>,
+        descriptor_sets: impl vulkano::DescriptorSetsCollection,
+        push_constants: &Pc,
+    ) -> Result<(), PushConstantsError> {
+        let layout = pipeline.layout().clone();
+        let builder = self.builder_mut();
+        builder.bind_pipeline_compute(pipeline.clone());
+        builder.bind_descriptor_sets(
+            vulkano::PipelineBindPoint::Compute,
+            layout.clone(),
+            0,
+            descriptor_sets,
+        );
+        // Nothing to push for shaders without push constants.
+        if std::mem::size_of::<Pc>() == 0 {
+            return Ok(());
+        }
+        let (offset, size) = push_constants_span(&layout)?;
+        check_push_constants_size::<Pc>(size)?;
+        builder.push_constants(layout, offset, *push_constants);
+        Ok(())
+    }
+}
+
+#[derive(Debug, From)]
+pub enum ComputePipelineError {
+    /// The shader module has no entry point with this name.
+    #[from(ignore)]
+    NoEntryPoint(String),
+    Specialization(SpecializationError),
+    Creation(vulkano::ComputePipelineCreationError),
+}
+
+impl fmt::Display for ComputePipelineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoEntryPoint(entry) => write!(f, "the shader has no entry point `{}`", entry),
+            Self::Specialization(err) => write!(f, "{}", err),
+            Self::Creation(err) => write!(f, "failed to create the compute pipeline: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ComputePipelineError {}
+
+#[derive(Debug, From)]
+pub enum DispatchError {
+    /// A group count exceeds the `max_compute_work_group_count` limit of the device.
+    #[from(ignore)]
+    GroupCountExceeded {
+        group_counts: [u32; 3],
+        max: [u32; 3],
+    },
+    /// `buffer` of `dispatch_indirect` has no element at `index`.
+    #[from(ignore)]
+    IndirectOutOfBounds {
+        index: u64,
+        len: u64,
+    },
+    PushConstants(PushConstantsError),
+    Dispatch(vulkano::DispatchError),
+    DispatchIndirect(vulkano::DispatchIndirectError),
+}
+
+impl fmt::Display for DispatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::GroupCountExceeded { group_counts, max } => write!(
+                f,
+                "dispatching {:?} work groups exceeds the device limit of {:?}",
+                group_counts, max
+            ),
+            Self::IndirectOutOfBounds { index, len } => write!(
+                f,
+                "indirect dispatch {} is out of bounds of a buffer with {} commands",
+                index, len
+            ),
+            Self::PushConstants(err) => write!(f, "{}", err),
+            Self::Dispatch(err) => write!(f, "failed to record the dispatch: {}", err),
+            Self::DispatchIndirect(err) => write!(f, "failed to record the dispatch: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for DispatchError {}
+
+#[derive(Debug, From)]
+pub enum ComputeOnceError {
+    /// The device has no queue supporting compute.
+    NoComputeQueue,
+    OutOfMemory(vulkano::OomError),
+    Dispatch(DispatchError),
+    Build(vulkano::BuildError),
+    Submit(SubmitError),
+}
+
+impl fmt::Display for ComputeOnceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoComputeQueue => write!(f, "the device has no compute queue"),
+            Self::OutOfMemory(err) => write!(f, "out of memory: {}", err),
+            Self::Dispatch(err) => write!(f, "{}", err),
+            Self::Build(err) => write!(f, "failed to build the command buffer: {}", err),
+            Self::Submit(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for ComputeOnceError {}
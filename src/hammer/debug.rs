@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::sync::Mutex;
+
+use super::Device;
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::buffer::*;
+    pub use vulkano::command_buffer::*;
+    pub use vulkano::device::*;
+    pub use vulkano::image::*;
+    pub use vulkano::VulkanObject;
+}
+
+use vulkano::VulkanObject;
+
+// vulkano only records labels that live forever, so every distinct label is leaked once.
+static LABELS: Mutex<Option<HashMap<String, &'static CStr>>> = Mutex::new(None);
+
+impl Device {
+    /// Whether the instance was created with `ext_debug_utils`. Without it the debug names and
+    /// labels do nothing, so they can be left in release builds.
+    pub fn debug_utils_enabled(&self) -> bool {
+        self.instance().enabled_extensions().ext_debug_utils
+    }
+    /// Names `object` in debuggers like RenderDoc and in validation messages.
+    pub fn set_object_name<T>(&self, object: &T, name: &str)
+    where
+        T: vulkano::VulkanObject + vulkano::DeviceOwned,
+    {
+        if !self.debug_utils_enabled() {
+            return;
+        }
+        let name = c_string(name);
+        if let Err(err) = self.handle().set_object_name(object, &name) {
+            log::warn!("Failed to name object {:?}: {}", name, err);
+        }
+    }
+    /// Names the device itself, which vulkano cannot name through `set_object_name`.
+    pub(crate) fn set_device_name(&self, name: &str) {
+        if !self.debug_utils_enabled() {
+            return;
+        }
+        let name = c_string(name);
+        let result = unsafe {
+            self.handle().set_object_name_raw(
+                ash::vk::ObjectType::DEVICE,
+                ash::vk::Handle::as_raw(self.handle().internal_object()),
+                &name,
+            )
+        };
+        if let Err(err) = result {
+            log::warn!("Failed to name device {:?}: {}", name, err);
+        }
+    }
+    /// Names the buffer `buffer` is part of, see `set_object_name`.
+    pub fn set_buffer_name(&self, buffer: &dyn vulkano::BufferAccess, name: &str) {
+        self.set_object_name(buffer.inner().buffer, name);
+    }
+    /// Names the image behind `image`, see `set_object_name`.
+    pub fn set_image_name(&self, image: &dyn vulkano::ImageAccess, name: &str) {
+        self.set_object_name(image.inner().image, name);
+    }
+}
+
+/// Debug groups opened by a `CommandEncoder` and its `RenderPassEncoder`s.
+pub(crate) struct DebugGroups {
+    enabled: bool,
+    depth: u32,
+}
+
+impl DebugGroups {
+    pub(crate) fn new<L, P>(builder: &vulkano::AutoCommandBufferBuilder<L, P>) -> Self {
+        Self {
+            enabled: vulkano::DeviceOwned::device(builder)
+                .instance()
+                .enabled_extensions()
+                .ext_debug_utils,
+            depth: 0,
+        }
+    }
+    pub(crate) fn push<L, P>(
+        &mut self,
+        builder: &mut vulkano::AutoCommandBufferBuilder<L, P>,
+        label: &str,
+        color: [f32; 4],
+    ) {
+        // Failures, e.g. on queues vulkano does not record labels for, only cost the label.
+        if self.enabled
+            && builder
+                .debug_marker_begin(intern(label), clamp(color))
+                .is_ok()
+        {
+            self.depth += 1;
+        }
+    }
+    /// Closes the innermost group, unbalanced pops are ignored.
+    pub(crate) fn pop<L, P>(&mut self, builder: &mut vulkano::AutoCommandBufferBuilder<L, P>) {
+        if self.depth > 0 && builder.debug_marker_end().is_ok() {
+            self.depth -= 1;
+        }
+    }
+    pub(crate) fn insert<L, P>(
+        &mut self,
+        builder: &mut vulkano::AutoCommandBufferBuilder<L, P>,
+        label: &str,
+        color: [f32; 4],
+    ) {
+        if self.enabled {
+            let _ = builder.debug_marker_insert(intern(label), clamp(color));
+        }
+    }
+    /// Closes the groups left open, so they do not leak into the command buffers submitted after.
+    pub(crate) fn pop_all<L, P>(&mut self, builder: &mut vulkano::AutoCommandBufferBuilder<L, P>) {
+        if self.depth > 0 {
+            log::warn!("{} debug groups were not popped", self.depth);
+        }
+        for _ in 0..self.depth {
+            let _ = builder.debug_marker_end();
+        }
+        self.depth = 0;
+    }
+}
+
+fn intern(label: &str) -> &'static CStr {
+    let mut labels = LABELS.lock().unwrap_or_else(|err| err.into_inner());
+    labels
+        .get_or_insert_with(HashMap::new)
+        .entry(label.to_owned())
+        .or_insert_with(|| Box::leak(c_string(label).into_boxed_c_str()))
+}
+
+// Names cannot contain nul bytes, which are dropped instead of failing.
+fn c_string(name: &str) -> CString {
+    CString::new(name.replace('\0', "")).unwrap()
+}
+
+// vulkano rejects color components outside of `0.0..=1.0`.
+fn clamp(color: [f32; 4]) -> [f32; 4] {
+    color.map(|component| component.clamp(0.0, 1.0))
+}
@@ -0,0 +1,109 @@
+use std::collections::VecDeque;
+
+/// Keeps retired objects alive for a fixed number of frames before dropping them.
+///
+/// This is used for objects the GPU or the presentation engine may still be using, but for which
+/// there is no fence telling us when it is done with them, e.g. swapchains without
+/// `VK_EXT_swapchain_maintenance1`, which vulkano does not expose.
+pub struct DeferredDeletionQueue<T> {
+    frames: u64,
+    limit: usize,
+    frame: u64,
+    entries: VecDeque<(u64, T)>,
+}
+
+impl<T> DeferredDeletionQueue<T> {
+    /// Creates a queue that keeps every object alive for `frames` frames.
+    pub fn new(frames: usize) -> Self {
+        Self::bounded(frames, usize::MAX)
+    }
+    /// Like `new`, but keeps at most `limit` objects, dropping the oldest one early once more
+    /// are retired.
+    pub fn bounded(frames: usize, limit: usize) -> Self {
+        Self {
+            frames: frames as u64,
+            limit,
+            frame: 0,
+            entries: VecDeque::new(),
+        }
+    }
+    /// Schedules `value` for deletion `frames` frames from now, or once more than `limit` objects
+    /// have been retired after it.
+    pub fn retire(&mut self, value: T) {
+        self.entries.push_back((self.frame, value));
+        while self.entries.len() > self.limit {
+            self.entries.pop_front();
+        }
+    }
+    /// Advances the frame counter and drops every object that has been retired for `frames` frames.
+    pub fn advance_frame(&mut self) {
+        self.frame += 1;
+        while let Some((retired_at, _)) = self.entries.front() {
+            if retired_at + self.frames > self.frame {
+                break;
+            }
+            self.entries.pop_front();
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    /// Drops all retired objects, only call this once the device is idle.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_after_frames() {
+        let mut queue = DeferredDeletionQueue::new(2);
+        queue.retire(0);
+        queue.advance_frame();
+        queue.retire(1);
+        assert_eq!(queue.len(), 2);
+        queue.advance_frame();
+        assert_eq!(queue.len(), 1);
+        queue.advance_frame();
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn keeps_many_retired_in_one_frame() {
+        let mut queue = DeferredDeletionQueue::new(2);
+        for value in 0..5 {
+            queue.retire(value);
+        }
+        assert_eq!(queue.len(), 5);
+        queue.advance_frame();
+        assert_eq!(queue.len(), 5);
+        queue.advance_frame();
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn bounded_drops_the_oldest_beyond_the_limit() {
+        let mut queue = DeferredDeletionQueue::bounded(3, 2);
+        queue.retire(0);
+        queue.retire(1);
+        queue.retire(2);
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.entries.front().map(|(_, value)| *value), Some(1));
+        queue.advance_frame();
+        queue.retire(3);
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.entries.front().map(|(_, value)| *value), Some(2));
+        // The limit does not extend the lifetime of the ones kept.
+        queue.advance_frame();
+        queue.advance_frame();
+        assert_eq!(queue.len(), 1);
+        queue.advance_frame();
+        assert!(queue.is_empty());
+    }
+}
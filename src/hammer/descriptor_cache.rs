@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak};
+
+use super::Device;
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::descriptor_set::layout::*;
+    pub use vulkano::descriptor_set::*;
+    pub use vulkano::device::Device;
+}
+
+/// Number of descriptor sets a `DescriptorCache` keeps by default.
+pub const DEFAULT_DESCRIPTOR_CACHE_CAPACITY: usize = 1024;
+
+// Identity of a resource bound to a descriptor set, the address of its `Arc`.
+pub(crate) type ResourceId = usize;
+
+// Caches of the devices created by `Instance::request_device`, looked up by `BindGroup::builder`
+// through the vulkano device of the pipeline.
+static CACHES: Mutex<Vec<(ResourceId, Weak<DescriptorCache>)>> = Mutex::new(Vec::new());
+
+// Identity of what was bound at a binding.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum BindingKey {
+    Buffer(vulkano::DescriptorType, ResourceId),
+    Image {
+        view: Option<ResourceId>,
+        sampler: Option<ResourceId>,
+    },
+}
+
+impl BindingKey {
+    fn uses(&self, resource: ResourceId) -> bool {
+        match *self {
+            Self::Buffer(_, buffer) => buffer == resource,
+            Self::Image { view, sampler } => view == Some(resource) || sampler == Some(resource),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct DescriptorKey {
+    pub(crate) layout: ResourceId,
+    pub(crate) bindings: Vec<(u32, BindingKey)>,
+}
+
+struct Entry {
+    descriptor_set: Arc<vulkano::PersistentDescriptorSet>,
+    last_used: u64,
+}
+
+/// A lookup that missed, pass it to `DescriptorCache::insert` with the set created for it.
+pub(crate) struct Miss {
+    key: DescriptorKey,
+    generation: u64,
+}
+
+struct Inner {
+    entries: HashMap<DescriptorKey, Entry>,
+    capacity: usize,
+    // Incremented on every lookup, entries remember when they were used last.
+    clock: u64,
+    hits: u64,
+    misses: u64,
+    // Incremented whenever sets are dropped for a resource, sets created before are not cached.
+    generation: u64,
+}
+
+/// Descriptor sets of the bind groups built by `BindGroup::builder`, so binding the same
+/// resources again reuses the set instead of allocating a new one every frame.
+///
+/// Sets are keyed by the layout and the identity of the bound resources. A cached set keeps its
+/// resources alive. Dropping a `Buffer` drops its sets, anything else that is replaced, e.g. a
+/// resized texture, stays in memory until its sets are evicted as the least recently used, or
+/// dropped with `mark_dirty`.
+pub struct DescriptorCache {
+    inner: Mutex<Inner>,
+}
+
+impl DescriptorCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                entries: HashMap::new(),
+                capacity,
+                clock: 0,
+                hits: 0,
+                misses: 0,
+                generation: 0,
+            }),
+        }
+    }
+    /// Changes the number of sets kept, evicting the least recently used ones if there are more.
+    pub fn set_capacity(&self, capacity: usize) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.capacity = capacity;
+        while inner.entries.len() > capacity {
+            inner.evict();
+        }
+    }
+    pub fn capacity(&self) -> usize {
+        self.inner.lock().unwrap().capacity
+    }
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Number of builds that reused a cached set.
+    pub fn hits(&self) -> u64 {
+        self.inner.lock().unwrap().hits
+    }
+    /// Number of builds that created a new set.
+    pub fn misses(&self) -> u64 {
+        self.inner.lock().unwrap().misses
+    }
+    /// Drops the sets using `resource`, e.g. a texture that is no longer used, which frees it
+    /// once nothing else holds it. Groups built with it afterwards get a new set.
+    pub fn mark_dirty<T: ?Sized>(&self, resource: &Arc<T>) {
+        self.forget(resource_id(resource));
+    }
+    /// Drops all sets.
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.generation += 1;
+        inner.entries.clear();
+    }
+    /// The cache of the device created by `Instance::request_device` for `device`.
+    pub(crate) fn of(device: &Arc<vulkano::Device>) -> Option<Arc<Self>> {
+        let id = resource_id(device);
+        CACHES
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(device, _)| *device == id)
+            .and_then(|(_, cache)| cache.upgrade())
+    }
+    // Makes `cache` the one `of` returns for `device`.
+    pub(crate) fn register(device: &Arc<vulkano::Device>, cache: &Arc<Self>) {
+        let id = resource_id(device);
+        let mut caches = CACHES.lock().unwrap();
+        caches.retain(|(device, cache)| *device != id && cache.strong_count() > 0);
+        caches.push((id, Arc::downgrade(cache)));
+    }
+    pub(crate) fn forget(&self, resource: ResourceId) {
+        let mut inner = self.inner.lock().unwrap();
+        // Sets still being created with the resource are not cached either.
+        inner.generation += 1;
+        inner.entries.retain(|key, _| {
+            !key.bindings
+                .iter()
+                .any(|(_, binding)| binding.uses(resource))
+        });
+    }
+    /// The set cached for `key`, or the `Miss` to insert the set created for it with.
+    pub(crate) fn get(
+        &self,
+        key: DescriptorKey,
+    ) -> Result<Arc<vulkano::PersistentDescriptorSet>, Miss> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.clock += 1;
+        let clock = inner.clock;
+        if let Some(entry) = inner.entries.get_mut(&key) {
+            entry.last_used = clock;
+            let descriptor_set = entry.descriptor_set.clone();
+            inner.hits += 1;
+            return Ok(descriptor_set);
+        }
+        inner.misses += 1;
+        Err(Miss {
+            key,
+            generation: inner.generation,
+        })
+    }
+    /// Caches `descriptor_set`, created without holding the lock after `get` missed, and returns
+    /// the set to use, which is the one of another build that was faster.
+    pub(crate) fn insert(
+        &self,
+        miss: Miss,
+        descriptor_set: Arc<vulkano::PersistentDescriptorSet>,
+    ) -> Arc<vulkano::PersistentDescriptorSet> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.capacity == 0 || inner.generation != miss.generation {
+            return descriptor_set;
+        }
+        if let Some(entry) = inner.entries.get(&miss.key) {
+            return entry.descriptor_set.clone();
+        }
+        if inner.entries.len() >= inner.capacity {
+            inner.evict();
+        }
+        let last_used = inner.clock;
+        inner.entries.insert(
+            miss.key,
+            Entry {
+                descriptor_set: descriptor_set.clone(),
+                last_used,
+            },
+        );
+        descriptor_set
+    }
+}
+
+impl Default for DescriptorCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_DESCRIPTOR_CACHE_CAPACITY)
+    }
+}
+
+impl Inner {
+    // Removes the least recently used entry.
+    fn evict(&mut self) {
+        let oldest = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(key, _)| key.clone());
+        if let Some(oldest) = oldest {
+            self.entries.remove(&oldest);
+        }
+    }
+}
+
+// Identity of `resource`, e.g. of `buffer.buffer()` or `texture.sampled_view()`.
+pub(crate) fn resource_id<T: ?Sized>(resource: &Arc<T>) -> ResourceId {
+    Arc::as_ptr(resource) as *const () as ResourceId
+}
+
+impl Device {
+    /// Cache of the descriptor sets of the bind groups built for pipelines of the device.
+    pub fn descriptor_cache(&self) -> &Arc<DescriptorCache> {
+        &self.descriptor_cache
+    }
+}
@@ -1,16 +1,243 @@
 use derive_more::*;
+use std::fmt;
 use std::sync::Arc;
 
 // Getting rust analyzer problems when not defining the module here again.
 mod vulkano {
+    pub use vulkano::command_buffer::*;
     pub use vulkano::device::physical::*;
     pub use vulkano::device::*;
-    pub use vulkano::image::view::*;
-    pub use vulkano::image::*;
-    pub use vulkano::instance::*;
-    pub use vulkano::pipeline::graphics::viewport::*;
-    pub use vulkano::render_pass::*;
-    pub use vulkano::swapchain::*;
     pub use vulkano::*;
 }
+
+use vulkano::{SynchronizedVulkanObject, VulkanObject};
 use super::*;
+
+/// Describes the device created by `Adapter::request_device_with`.
+#[derive(Clone, Debug)]
+pub struct DeviceDescriptor {
+    /// Features to enable in addition to the ones required by the adapter descriptor.
+    pub features: vulkano::Features,
+    /// Extensions to enable in addition to the ones requested by the adapter descriptor.
+    pub extra_extensions: vulkano::DeviceExtensions,
+    /// Number of queues to create from the adapter's queue family.
+    pub queue_count: u32,
+    /// Priority of each queue, clamped to `0.0..=1.0`. Missing entries default to `0.5`.
+    pub queue_priorities: Vec<f32>,
+    /// Name used when logging about the device.
+    pub label: Option<String>,
+}
+
+impl Default for DeviceDescriptor {
+    fn default() -> Self {
+        Self {
+            features: vulkano::Features::none(),
+            extra_extensions: vulkano::DeviceExtensions::none(),
+            queue_count: 1,
+            queue_priorities: Vec::new(),
+            label: None,
+        }
+    }
+}
+
+impl DeviceDescriptor {
+    pub(crate) fn priorities(&self) -> Vec<f32> {
+        (0..self.queue_count as usize)
+            .map(|i| {
+                self.queue_priorities
+                    .get(i)
+                    .copied()
+                    .unwrap_or(0.5)
+                    .clamp(0.0, 1.0)
+            })
+            .collect()
+    }
+}
+
+/// Logical device together with the queues created for it.
+///
+/// Derefs to the `Arc<vulkano::Device>`, so `device.clone()` still yields the vulkano device.
+#[derive(Deref)]
+pub struct Device {
+    #[deref]
+    device: Arc<vulkano::Device>,
+    queues: Vec<Queue>,
+    graphics: Option<usize>,
+    compute: Option<usize>,
+    transfer: Option<usize>,
+    present: Option<usize>,
+    pub(crate) pipeline_cache: Option<Arc<vulkano::pipeline::cache::PipelineCache>>,
+    pub(crate) descriptor_cache: Arc<DescriptorCache>,
+}
+
+impl Device {
+    /// Sorts `queues` into categories. Compute and transfer work prefer a queue other than the
+    /// graphics queue if there is more than one. The present queue is the graphics queue if it
+    /// belongs to `present_family`.
+    pub(crate) fn new(
+        device: Arc<vulkano::Device>,
+        queues: Vec<Queue>,
+        present_family: Option<u32>,
+    ) -> Self {
+        let graphics = queues.iter().position(|q| q.family().supports_graphics());
+        let compute = Self::pick(&queues, graphics, |family| family.supports_compute());
+        let transfer = Self::pick(&queues, compute.or(graphics), |family| {
+            family.supports_graphics()
+                || family.supports_compute()
+                || family.explicitly_supports_transfers()
+        });
+        let present = present_family.and_then(|family| {
+            graphics
+                .filter(|&i| queues[i].family().id() == family)
+                .or_else(|| queues.iter().position(|q| q.family().id() == family))
+        });
+        // Pipelines are only slower to create without it.
+        let pipeline_cache = vulkano::pipeline::cache::PipelineCache::empty(device.clone())
+            .map_err(|err| log::warn!("Failed to create the pipeline cache: {}", err))
+            .ok();
+        let descriptor_cache = Arc::new(DescriptorCache::default());
+        DescriptorCache::register(&device, &descriptor_cache);
+        Self {
+            device,
+            queues,
+            graphics,
+            compute,
+            transfer,
+            present,
+            pipeline_cache,
+            descriptor_cache,
+        }
+    }
+    fn pick(
+        queues: &[Queue],
+        avoid: Option<usize>,
+        supports: impl Fn(vulkano::QueueFamily) -> bool,
+    ) -> Option<usize> {
+        let mut candidates = queues
+            .iter()
+            .enumerate()
+            .filter(|(_, q)| supports(q.family()))
+            .map(|(i, _)| i);
+        let first = candidates.next()?;
+        if Some(first) != avoid {
+            return Some(first);
+        }
+        candidates.next().or(Some(first))
+    }
+    pub fn handle(&self) -> &Arc<vulkano::Device> {
+        &self.device
+    }
+    pub fn queues(&self) -> &[Queue] {
+        &self.queues
+    }
+    pub fn graphics_queue(&self) -> Option<&Queue> {
+        self.graphics.map(|i| &self.queues[i])
+    }
+    pub fn compute_queue(&self) -> Option<&Queue> {
+        self.compute.map(|i| &self.queues[i])
+    }
+    pub fn transfer_queue(&self) -> Option<&Queue> {
+        self.transfer.map(|i| &self.queues[i])
+    }
+    /// Queue that can present to the surface the adapter was selected for, if any.
+    pub fn present_queue(&self) -> Option<&Queue> {
+        self.present.map(|i| &self.queues[i])
+    }
+    pub fn queue_by_index(&self, index: usize) -> Option<&Queue> {
+        self.queues.get(index)
+    }
+    /// Indices of the queue families the queues were created from, without duplicates.
+    pub fn queue_family_indices(&self) -> Vec<u32> {
+        let mut indices = self
+            .queues
+            .iter()
+            .map(|q| q.family().id())
+            .collect::<Vec<_>>();
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+    /// Starts recording a primary command buffer for `queue`, submitted once.
+    ///
+    /// The command buffer is allocated from vulkano's standard command pool, which keeps one pool
+    /// per thread and reuses the command buffers that finished executing.
+    #[track_caller]
+    pub fn create_command_encoder(
+        &self,
+        queue: &Queue,
+    ) -> Result<CommandEncoder, vulkano::OomError> {
+        let builder = vulkano::AutoCommandBufferBuilder::primary(
+            self.device.clone(),
+            queue.family(),
+            vulkano::CommandBufferUsage::OneTimeSubmit,
+        )?;
+        Ok(CommandEncoder::from_builder(builder))
+    }
+    /// Like `create_command_encoder`, but the command buffer can be submitted again while it is
+    /// still executing, e.g. to cache it in a `CommandBufferCache`.
+    #[track_caller]
+    pub fn create_reusable_command_encoder(
+        &self,
+        queue: &Queue,
+    ) -> Result<CommandEncoder, vulkano::OomError> {
+        let builder = vulkano::AutoCommandBufferBuilder::primary(
+            self.device.clone(),
+            queue.family(),
+            vulkano::CommandBufferUsage::SimultaneousUse,
+        )?;
+        Ok(CommandEncoder::from_builder(builder))
+    }
+    /// Blocks until all work submitted to the device has finished.
+    ///
+    /// The queues are locked while waiting, so nothing can be submitted in the meantime. Unlike
+    /// `vulkano::Device::wait`, a lost device is reported as an error instead of panicking.
+    pub fn wait_idle(&self) -> Result<(), WaitIdleError> {
+        let _locks = self
+            .queues
+            .iter()
+            .map(|q| (q.lock(), q.internal_object_guard()))
+            .collect::<Vec<_>>();
+        let result = unsafe {
+            (self.device.fns().v1_0.device_wait_idle)(self.device.internal_object())
+        };
+        match result {
+            ash::vk::Result::SUCCESS => Ok(()),
+            ash::vk::Result::ERROR_OUT_OF_HOST_MEMORY => {
+                Err(vulkano::OomError::OutOfHostMemory.into())
+            }
+            ash::vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => {
+                Err(vulkano::OomError::OutOfDeviceMemory.into())
+            }
+            _ => Err(WaitIdleError::DeviceLost),
+        }
+    }
+}
+
+impl Drop for Device {
+    /// Waits for in flight work, so the queues and the resources used by it are not released
+    /// while the device still uses them.
+    fn drop(&mut self) {
+        if let Err(err) = self.wait_idle() {
+            log::error!("Failed to wait for the device before dropping it: {}", err);
+        }
+    }
+}
+
+#[derive(Clone, Debug, From)]
+pub enum WaitIdleError {
+    OutOfMemory(vulkano::OomError),
+    /// The device was lost, e.g. because of a driver crash or a timeout.
+    #[from(ignore)]
+    DeviceLost,
+}
+
+impl fmt::Display for WaitIdleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::OutOfMemory(err) => write!(f, "ran out of memory while waiting: {}", err),
+            Self::DeviceLost => write!(f, "the device was lost"),
+        }
+    }
+}
+
+impl std::error::Error for WaitIdleError {}
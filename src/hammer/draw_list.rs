@@ -0,0 +1,356 @@
+use derive_more::*;
+use std::fmt;
+use std::sync::Arc;
+
+use super::{check_push_constants_size, push_constants_span, IndexBuffer, PushConstantsError};
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::buffer::*;
+    pub use vulkano::command_buffer::*;
+    pub use vulkano::descriptor_set::*;
+    pub use vulkano::pipeline::*;
+}
+
+use vulkano::Pipeline;
+
+/// A single draw recorded by `DrawList`.
+#[derive(Clone)]
+pub struct DrawItem<Pc = ()> {
+    pub pipeline: Arc<vulkano::GraphicsPipeline>,
+    /// Descriptor sets bound starting at set 0.
+    pub descriptor_sets: Vec<Arc<dyn vulkano::DescriptorSet>>,
+    /// Vertex buffers bound starting at binding 0.
+    pub vertex_buffers: Vec<Arc<dyn vulkano::BufferAccess>>,
+    pub index_buffer: Option<IndexBuffer>,
+    /// Number of indices if an index buffer is set, vertices otherwise.
+    pub count: u32,
+    pub instance_count: u32,
+    pub push_constants: Option<Pc>,
+    /// User defined key, e.g. the depth of the object.
+    pub sort_key: u64,
+}
+
+/// Bind calls issued and avoided by `DrawList::record`.
+///
+/// Pass them to `Frame::add_draw_list_stats` to report them with the `FrameStats`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, AddAssign)]
+pub struct DrawListStats {
+    pub draws: usize,
+    pub pipeline_binds: usize,
+    pub pipeline_binds_saved: usize,
+    pub descriptor_set_binds: usize,
+    pub descriptor_set_binds_saved: usize,
+    pub vertex_buffer_binds: usize,
+    pub vertex_buffer_binds_saved: usize,
+}
+
+/// Collects draws in arbitrary order and records them sorted by state to avoid redundant binds.
+///
+/// Opaque items are sorted by pipeline, descriptor sets and then the sort key (front to back if
+/// the key is the depth). Transparent items are drawn after the opaque ones, sorted by descending
+/// sort key (back to front), since their order matters for blending.
+pub struct DrawList<Pc = ()> {
+    opaque: Vec<DrawItem<Pc>>,
+    transparent: Vec<DrawItem<Pc>>,
+}
+
+impl DrawListStats {
+    /// Binds of all kinds that were skipped because the state was bound already.
+    pub fn binds_saved(&self) -> usize {
+        self.pipeline_binds_saved + self.descriptor_set_binds_saved + self.vertex_buffer_binds_saved
+    }
+}
+
+impl<Pc> Default for DrawList<Pc> {
+    fn default() -> Self {
+        Self {
+            opaque: Vec::new(),
+            transparent: Vec::new(),
+        }
+    }
+}
+
+impl<Pc: Copy> DrawList<Pc> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn push_opaque(&mut self, item: DrawItem<Pc>) {
+        self.opaque.push(item);
+    }
+    pub fn push_transparent(&mut self, item: DrawItem<Pc>) {
+        self.transparent.push(item);
+    }
+    pub fn len(&self) -> usize {
+        self.opaque.len() + self.transparent.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.opaque.is_empty() && self.transparent.is_empty()
+    }
+    pub fn clear(&mut self) {
+        self.opaque.clear();
+        self.transparent.clear();
+    }
+    /// Sorts the items in the order they will be recorded.
+    pub fn sort(&mut self) {
+        sort_opaque(&mut self.opaque, |item| (StateKey::of(item), item.sort_key));
+        sort_transparent(&mut self.transparent, |item| item.sort_key);
+    }
+    /// Sorts the items and records them into `builder`, which has to be inside a render pass.
+    ///
+    /// The list is emptied afterwards so it can be reused for the next frame.
+    pub fn record<L, P>(
+        &mut self,
+        builder: &mut vulkano::AutoCommandBufferBuilder<L, P>,
+    ) -> Result<DrawListStats, DrawListError> {
+        self.sort();
+
+        let mut stats = DrawListStats::default();
+        let mut state = BoundState::default();
+        for item in self.opaque.drain(..).chain(self.transparent.drain(..)) {
+            record_item(builder, &mut state, &item, &mut stats)?;
+        }
+        Ok(stats)
+    }
+}
+
+/// The state a `DrawItem` binds, identified by the addresses of its objects.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct StateKey {
+    pipeline: usize,
+    descriptor_sets: Vec<usize>,
+    vertex_buffers: Vec<usize>,
+}
+
+impl StateKey {
+    fn of<Pc>(item: &DrawItem<Pc>) -> Self {
+        Self {
+            pipeline: Arc::as_ptr(&item.pipeline) as *const () as usize,
+            descriptor_sets: item
+                .descriptor_sets
+                .iter()
+                .map(|set| Arc::as_ptr(set) as *const () as usize)
+                .collect(),
+            vertex_buffers: item
+                .vertex_buffers
+                .iter()
+                .map(|buffer| Arc::as_ptr(buffer) as *const () as usize)
+                .collect(),
+        }
+    }
+}
+
+/// Sorts by pipeline, descriptor sets and then the sort key returned by `key`.
+fn sort_opaque<T>(items: &mut [T], key: impl Fn(&T) -> (StateKey, u64)) {
+    items.sort_by_cached_key(|item| {
+        let (state, sort_key) = key(item);
+        (state.pipeline, state.descriptor_sets, sort_key)
+    });
+}
+
+/// Sorts by descending sort key, keeping the order of items with the same key.
+fn sort_transparent<T>(items: &mut [T], sort_key: impl Fn(&T) -> u64) {
+    items.sort_by_key(|item| std::cmp::Reverse(sort_key(item)));
+}
+
+/// Which parts of the state have to be bound again for the next item.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Rebinds {
+    pipeline: bool,
+    descriptor_sets: bool,
+    vertex_buffers: bool,
+}
+
+#[derive(Default)]
+struct BoundState {
+    pipeline: Option<usize>,
+    descriptor_sets: Vec<usize>,
+    vertex_buffers: Vec<usize>,
+}
+
+impl BoundState {
+    /// Compares `key` to the bound state, counting the binds in `stats`, and updates the state as
+    /// if the returned rebinds were recorded.
+    fn update(&mut self, key: StateKey, stats: &mut DrawListStats) -> Rebinds {
+        let mut rebinds = Rebinds::default();
+        if self.pipeline == Some(key.pipeline) {
+            stats.pipeline_binds_saved += 1;
+        } else {
+            self.pipeline = Some(key.pipeline);
+            // Binding a different pipeline may disturb previously bound descriptor sets.
+            self.descriptor_sets.clear();
+            stats.pipeline_binds += 1;
+            rebinds.pipeline = true;
+        }
+
+        if !key.descriptor_sets.is_empty() {
+            if self.descriptor_sets == key.descriptor_sets {
+                stats.descriptor_set_binds_saved += 1;
+            } else {
+                self.descriptor_sets = key.descriptor_sets;
+                stats.descriptor_set_binds += 1;
+                rebinds.descriptor_sets = true;
+            }
+        }
+
+        if !key.vertex_buffers.is_empty() {
+            if self.vertex_buffers == key.vertex_buffers {
+                stats.vertex_buffer_binds_saved += 1;
+            } else {
+                self.vertex_buffers = key.vertex_buffers;
+                stats.vertex_buffer_binds += 1;
+                rebinds.vertex_buffers = true;
+            }
+        }
+        rebinds
+    }
+}
+
+fn record_item<L, P, Pc: Copy>(
+    builder: &mut vulkano::AutoCommandBufferBuilder<L, P>,
+    state: &mut BoundState,
+    item: &DrawItem<Pc>,
+    stats: &mut DrawListStats,
+) -> Result<(), DrawListError> {
+    let rebinds = state.update(StateKey::of(item), stats);
+    if rebinds.pipeline {
+        builder.bind_pipeline_graphics(item.pipeline.clone());
+    }
+    if rebinds.descriptor_sets {
+        builder.bind_descriptor_sets(
+            vulkano::PipelineBindPoint::Graphics,
+            item.pipeline.layout().clone(),
+            0,
+            item.descriptor_sets
+                .iter()
+                .map(|set| vulkano::DescriptorSetWithOffsets::new(set.clone(), []))
+                .collect::<Vec<_>>(),
+        );
+    }
+    if rebinds.vertex_buffers {
+        builder.bind_vertex_buffers(0, item.vertex_buffers.clone());
+    }
+
+    if let Some(push_constants) = item.push_constants {
+        let layout = item.pipeline.layout();
+        let (offset, size) = push_constants_span(layout)?;
+        check_push_constants_size::<Pc>(size)?;
+        builder.push_constants(layout.clone(), offset, push_constants);
+    }
+
+    match &item.index_buffer {
+        Some(index_buffer) => {
+            index_buffer.bind(builder);
+            builder.draw_indexed(item.count, item.instance_count, 0, 0, 0)?;
+        }
+        None => {
+            builder.draw(item.count, item.instance_count, 0, 0)?;
+        }
+    }
+    stats.draws += 1;
+    Ok(())
+}
+
+#[derive(Debug, From)]
+pub enum DrawListError {
+    Draw(vulkano::DrawError),
+    DrawIndexed(vulkano::DrawIndexedError),
+    PushConstants(PushConstantsError),
+}
+
+impl fmt::Display for DrawListError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Draw(err) => write!(f, "failed to record draw: {}", err),
+            Self::DrawIndexed(err) => write!(f, "failed to record indexed draw: {}", err),
+            Self::PushConstants(err) => write!(f, "failed to push constants: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for DrawListError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(pipeline: usize, descriptor_sets: &[usize], vertex_buffers: &[usize]) -> StateKey {
+        StateKey {
+            pipeline,
+            descriptor_sets: descriptor_sets.to_vec(),
+            vertex_buffers: vertex_buffers.to_vec(),
+        }
+    }
+
+    #[test]
+    fn opaque_sorted_by_state_then_key() {
+        let mut items = vec![
+            (key(2, &[1], &[]), 0),
+            (key(1, &[2], &[]), 5),
+            (key(1, &[1], &[]), 9),
+            (key(2, &[1], &[]), 3),
+            (key(1, &[1], &[]), 4),
+        ];
+        sort_opaque(&mut items, |item| item.clone());
+        let order: Vec<_> = items
+            .iter()
+            .map(|(state, sort_key)| (state.pipeline, state.descriptor_sets[0], *sort_key))
+            .collect();
+        assert_eq!(
+            order,
+            vec![(1, 1, 4), (1, 1, 9), (1, 2, 5), (2, 1, 0), (2, 1, 3)]
+        );
+    }
+
+    #[test]
+    fn transparent_sorted_back_to_front() {
+        let mut items = vec![(3, 'a'), (7, 'b'), (3, 'c'), (1, 'd')];
+        sort_transparent(&mut items, |item| item.0);
+        // Items with the same key keep their order.
+        assert_eq!(items, vec![(7, 'b'), (3, 'a'), (3, 'c'), (1, 'd')]);
+    }
+
+    #[test]
+    fn redundant_binds_skipped() {
+        let mut stats = DrawListStats::default();
+        let mut state = BoundState::default();
+        let script = [
+            (key(1, &[1], &[1]), (true, true, true)),
+            (key(1, &[1], &[1]), (false, false, false)),
+            (key(1, &[2], &[1]), (false, true, false)),
+            // A new pipeline binds the sets again even if they did not change.
+            (key(2, &[2], &[2]), (true, true, true)),
+            // Items without sets or buffers leave the bound ones alone.
+            (key(2, &[], &[]), (false, false, false)),
+            (key(2, &[2], &[2]), (false, false, false)),
+        ];
+        for (step, (key, (pipeline, descriptor_sets, vertex_buffers))) in
+            script.into_iter().enumerate()
+        {
+            let expected = Rebinds {
+                pipeline,
+                descriptor_sets,
+                vertex_buffers,
+            };
+            assert_eq!(state.update(key, &mut stats), expected, "step {}", step);
+        }
+        assert_eq!(
+            stats,
+            DrawListStats {
+                draws: 0,
+                pipeline_binds: 2,
+                pipeline_binds_saved: 4,
+                descriptor_set_binds: 3,
+                descriptor_set_binds_saved: 2,
+                vertex_buffer_binds: 2,
+                vertex_buffer_binds_saved: 3,
+            }
+        );
+        assert_eq!(stats.binds_saved(), 9);
+        // Lists of the same frame add up in the frame stats.
+        let mut total = stats;
+        total += stats;
+        assert_eq!(total.pipeline_binds, 4);
+        assert_eq!(total.binds_saved(), 18);
+    }
+}
@@ -0,0 +1,730 @@
+use bytemuck::Pod;
+use std::fmt;
+use std::sync::Arc;
+
+use super::{
+    record_copy_buffer, record_fill_buffer, BeginRenderPassError, BindGroup, BufferCopy,
+    ClearValues, DebugGroups, DrawList, DrawListError, DrawListStats, IndexBuffer, InstanceBuffer,
+    Mesh, MeshDrawError, MisuseGuard, TransferError,
+};
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::buffer::*;
+    pub use vulkano::command_buffer::*;
+    pub use vulkano::descriptor_set::*;
+    pub use vulkano::device::DeviceOwned;
+    pub use vulkano::format::ClearValue;
+    pub use vulkano::image::immutable::SubImage;
+    pub use vulkano::image::*;
+    pub use vulkano::pipeline::graphics::vertex_input::*;
+    pub use vulkano::pipeline::graphics::viewport::*;
+    pub use vulkano::pipeline::*;
+    pub use vulkano::render_pass::*;
+    pub use vulkano::sampler::Filter;
+    pub use vulkano::shader::ShaderStages;
+    pub use vulkano::DeviceSize;
+}
+
+use vulkano::{DeviceOwned, Pipeline};
+
+/// Records a primary command buffer, created with `Device::create_command_encoder`.
+///
+/// Commands inside a render pass are recorded through the `RenderPassEncoder` returned by
+/// `begin_render_pass`, which borrows the encoder until the render pass ended. Forgetting to end
+/// it is therefore impossible, and so is recording e.g. a copy inside of it.
+pub struct CommandEncoder {
+    builder: vulkano::AutoCommandBufferBuilder<vulkano::PrimaryAutoCommandBuffer>,
+    debug: DebugGroups,
+    guard: MisuseGuard,
+}
+
+impl CommandEncoder {
+    #[track_caller]
+    pub fn from_builder(
+        builder: vulkano::AutoCommandBufferBuilder<vulkano::PrimaryAutoCommandBuffer>,
+    ) -> Self {
+        Self {
+            debug: DebugGroups::new(&builder),
+            builder,
+            guard: MisuseGuard::new(
+                "CommandEncoder",
+                "call `finish` and submit the command buffer, or `abandon` it",
+            ),
+        }
+    }
+    /// The wrapped builder, for commands outside of a render pass the encoder does not cover.
+    pub fn builder_mut(
+        &mut self,
+    ) -> &mut vulkano::AutoCommandBufferBuilder<vulkano::PrimaryAutoCommandBuffer> {
+        &mut self.builder
+    }
+    /// Begins the render pass of `framebuffer`, clearing the attachments with `clear_values`.
+    pub fn begin_render_pass(
+        &mut self,
+        framebuffer: Arc<vulkano::Framebuffer>,
+        clear_values: &ClearValues,
+    ) -> Result<RenderPassEncoder<'_>, BeginRenderPassError> {
+        clear_values.begin_render_pass(
+            &mut self.builder,
+            framebuffer,
+            vulkano::SubpassContents::Inline,
+        )?;
+        Ok(RenderPassEncoder::new(&mut self.builder, &mut self.debug))
+    }
+    /// Like `begin_render_pass`, but with the clear values in the order of the attachments, e.g.
+    /// from `DrawTarget::clear_values`.
+    pub fn begin_render_pass_with(
+        &mut self,
+        framebuffer: Arc<vulkano::Framebuffer>,
+        clear_values: Vec<vulkano::ClearValue>,
+    ) -> Result<RenderPassEncoder<'_>, BeginRenderPassError> {
+        self.builder.begin_render_pass(
+            framebuffer,
+            vulkano::SubpassContents::Inline,
+            clear_values,
+        )?;
+        Ok(RenderPassEncoder::new(&mut self.builder, &mut self.debug))
+    }
+    /// Records the copies of `regions` from `source` to `destination`, see `record_copy_buffer`.
+    pub fn copy_buffer(
+        &mut self,
+        source: &Arc<dyn vulkano::BufferAccess>,
+        destination: &Arc<dyn vulkano::BufferAccess>,
+        regions: &[BufferCopy],
+    ) -> Result<&mut Self, TransferError> {
+        record_copy_buffer(&mut self.builder, source, destination, regions)?;
+        Ok(self)
+    }
+    /// Records filling all of `destination`, whose size has to be a multiple of 4, with copies of
+    /// `value`.
+    pub fn fill_buffer(
+        &mut self,
+        destination: &Arc<dyn vulkano::BufferAccess>,
+        value: u32,
+    ) -> Result<&mut Self, TransferError> {
+        record_fill_buffer(&mut self.builder, destination, 0..destination.size(), value)?;
+        Ok(self)
+    }
+    /// Records blitting `source_region` of `source` into `destination_region` of `destination`,
+    /// scaling it with `filter`. Use `view.image()` to blit from or to an image view.
+    ///
+    /// Both regions need the same number of array layers. Blitting between mip levels of the same
+    /// image is supported, e.g. for `generate_mipmaps`. Blits need a queue with graphics support
+    /// and `Filter::Linear` a format that supports linear filtering.
+    pub fn blit_image(
+        &mut self,
+        source: Arc<dyn vulkano::ImageAccess>,
+        source_region: ImageRegion,
+        destination: Arc<dyn vulkano::ImageAccess>,
+        destination_region: ImageRegion,
+        filter: vulkano::Filter,
+    ) -> Result<&mut Self, TransferError> {
+        check_region(&*source, &source_region)?;
+        check_region(&*destination, &destination_region)?;
+        if source_region.layer_count != destination_region.layer_count {
+            return Err(TransferError::LayerCounts {
+                source: source_region.layer_count,
+                destination: destination_region.layer_count,
+            });
+        }
+        // vulkano tracks whole images, so the levels of the same image have to be narrowed down
+        // for the blit not to conflict with itself. The image keeps its layout outside of it.
+        let (source, destination) = if source.conflict_key() == destination.conflict_key() {
+            let layout = source.final_layout_requirement();
+            let narrow = |region: &ImageRegion| -> Arc<dyn vulkano::ImageAccess> {
+                vulkano::SubImage::new(
+                    source.clone(),
+                    region.mip_level,
+                    1,
+                    region.base_array_layer,
+                    region.layer_count,
+                    layout,
+                )
+            };
+            (narrow(&source_region), narrow(&destination_region))
+        } else {
+            (source, destination)
+        };
+        self.builder.blit_image(
+            source,
+            source_region.top_left(),
+            source_region.bottom_right(),
+            source_region.base_array_layer,
+            source_region.mip_level,
+            destination,
+            destination_region.top_left(),
+            destination_region.bottom_right(),
+            destination_region.base_array_layer,
+            destination_region.mip_level,
+            source_region.layer_count,
+            filter,
+        )?;
+        Ok(self)
+    }
+    /// Records filling the mip levels of `image` after the first one by blitting each level
+    /// into the next, for every array layer. See `blit_image` for the requirements.
+    pub fn generate_mipmaps(
+        &mut self,
+        image: Arc<dyn vulkano::ImageAccess>,
+    ) -> Result<&mut Self, TransferError> {
+        for level in 1..image.mip_levels() {
+            self.blit_image(
+                image.clone(),
+                ImageRegion::mip_level(&*image, level - 1),
+                image.clone(),
+                ImageRegion::mip_level(&*image, level),
+                vulkano::Filter::Linear,
+            )?;
+        }
+        Ok(self)
+    }
+    /// Records copying the tightly packed texels of `source` into `destination_region` of
+    /// `destination`.
+    pub fn copy_buffer_to_image(
+        &mut self,
+        source: Arc<dyn vulkano::BufferAccess>,
+        destination: Arc<dyn vulkano::ImageAccess>,
+        destination_region: ImageRegion,
+    ) -> Result<&mut Self, TransferError> {
+        check_region(&*destination, &destination_region)?;
+        check_buffer_size(&*destination, &destination_region, &*source)?;
+        self.builder.copy_buffer_to_image_dimensions(
+            source,
+            destination,
+            destination_region.offset,
+            destination_region.extent,
+            destination_region.base_array_layer,
+            destination_region.layer_count,
+            destination_region.mip_level,
+        )?;
+        Ok(self)
+    }
+    /// Records copying `source_region` of `source` into `destination`, tightly packed.
+    pub fn copy_image_to_buffer(
+        &mut self,
+        source: Arc<dyn vulkano::ImageAccess>,
+        source_region: ImageRegion,
+        destination: Arc<dyn vulkano::BufferAccess>,
+    ) -> Result<&mut Self, TransferError> {
+        check_region(&*source, &source_region)?;
+        check_buffer_size(&*source, &source_region, &*destination)?;
+        self.builder.copy_image_to_buffer_dimensions(
+            source,
+            destination,
+            source_region.offset,
+            source_region.extent,
+            source_region.base_array_layer,
+            source_region.layer_count,
+            source_region.mip_level,
+        )?;
+        Ok(self)
+    }
+    /// Records clearing every mip level and array layer of `image` to `color`, e.g. `[f32; 4]`
+    /// for float and normalized formats or `[u32; 4]` for unsigned integer formats.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if `color` is not a color value.
+    pub fn clear_color_image(
+        &mut self,
+        image: Arc<dyn vulkano::ImageAccess>,
+        color: impl Into<vulkano::ClearValue>,
+    ) -> Result<&mut Self, TransferError> {
+        self.builder.clear_color_image(image, color.into())?;
+        Ok(self)
+    }
+    /// Opens a group of commands named `label` in debuggers like RenderDoc, until the matching
+    /// `pop_debug_group`.
+    ///
+    /// Like the other debug helpers this does nothing without `ext_debug_utils`, see
+    /// `Device::debug_utils_enabled`.
+    pub fn push_debug_group(&mut self, label: &str, color: [f32; 4]) -> &mut Self {
+        self.debug.push(&mut self.builder, label, color);
+        self
+    }
+    pub fn pop_debug_group(&mut self) -> &mut Self {
+        self.debug.pop(&mut self.builder);
+        self
+    }
+    /// Marks the current position in the command buffer with `label`.
+    pub fn insert_debug_label(&mut self, label: &str, color: [f32; 4]) -> &mut Self {
+        self.debug.insert(&mut self.builder, label, color);
+        self
+    }
+    /// Builds the command buffer, ready to be submitted. Debug groups left open are closed.
+    pub fn finish(mut self) -> Result<vulkano::PrimaryAutoCommandBuffer, vulkano::BuildError> {
+        self.guard.consume();
+        self.debug.pop_all(&mut self.builder);
+        self.builder.build()
+    }
+    /// Drops the recorded commands without building them, e.g. after recording failed.
+    pub fn abandon(mut self) {
+        self.guard.consume();
+    }
+}
+
+/// Part of a single mip level of an image, for the image commands of `CommandEncoder`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ImageRegion {
+    pub mip_level: u32,
+    pub base_array_layer: u32,
+    pub layer_count: u32,
+    /// First texel of the region.
+    pub offset: [u32; 3],
+    pub extent: [u32; 3],
+}
+
+impl ImageRegion {
+    /// Every array layer of the first mip level of `image`.
+    pub fn whole(image: &dyn vulkano::ImageAccess) -> Self {
+        Self::mip_level(image, 0)
+    }
+    /// Every array layer of mip `level` of `image`, empty if the level does not exist.
+    pub fn mip_level(image: &dyn vulkano::ImageAccess, level: u32) -> Self {
+        let dimensions = image.dimensions();
+        Self {
+            mip_level: level,
+            base_array_layer: 0,
+            layer_count: dimensions.array_layers(),
+            offset: [0, 0, 0],
+            extent: dimensions
+                .mip_level_dimensions(level)
+                .map(|dimensions| dimensions.width_height_depth())
+                .unwrap_or([0, 0, 0]),
+        }
+    }
+    fn top_left(&self) -> [i32; 3] {
+        self.offset.map(|offset| offset as i32)
+    }
+    // Exclusive corner of the region, as blits take it.
+    fn bottom_right(&self) -> [i32; 3] {
+        [0, 1, 2].map(|i| (self.offset[i] + self.extent[i]) as i32)
+    }
+}
+
+// Checks that `region` lies within `image`, so failures are reported with the actual bounds
+// instead of vulkano's generic out of range errors.
+fn check_region(
+    image: &dyn vulkano::ImageAccess,
+    region: &ImageRegion,
+) -> Result<(), TransferError> {
+    let dimensions = image
+        .dimensions()
+        .mip_level_dimensions(region.mip_level)
+        .ok_or(TransferError::MipLevel {
+            level: region.mip_level,
+            levels: image.mip_levels(),
+        })?;
+    if region.layer_count == 0 || region.extent.contains(&0) {
+        return Err(TransferError::Empty);
+    }
+    let end_layer = region.base_array_layer.saturating_add(region.layer_count);
+    if end_layer > dimensions.array_layers() {
+        return Err(TransferError::ArrayLayers {
+            end: end_layer,
+            available: dimensions.array_layers(),
+        });
+    }
+    let available = dimensions.width_height_depth();
+    let end = [0, 1, 2].map(|i| region.offset[i].saturating_add(region.extent[i]));
+    if (0..3).any(|i| end[i] > available[i]) {
+        return Err(TransferError::ImageOutOfBounds {
+            mip_level: region.mip_level,
+            end,
+            available,
+        });
+    }
+    Ok(())
+}
+
+// Checks that `buffer` can hold the tightly packed texels of `region`. Compressed formats are
+// left to vulkano.
+fn check_buffer_size(
+    image: &dyn vulkano::ImageAccess,
+    region: &ImageRegion,
+    buffer: &dyn vulkano::BufferAccess,
+) -> Result<(), TransferError> {
+    let format = image.format();
+    let texel_size = match format.block_size() {
+        Some(texel_size) if format.block_extent() == [1, 1, 1] => texel_size,
+        _ => return Ok(()),
+    };
+    let size = region
+        .extent
+        .iter()
+        .map(|&extent| extent as vulkano::DeviceSize)
+        .product::<vulkano::DeviceSize>()
+        * region.layer_count as vulkano::DeviceSize
+        * texel_size;
+    if size > buffer.size() {
+        return Err(TransferError::OutOfBounds {
+            end: size,
+            available: buffer.size(),
+        });
+    }
+    Ok(())
+}
+
+/// Records the commands of a render pass begun with `CommandEncoder::begin_render_pass`.
+///
+/// The render pass ends with `finish`, or when the encoder is dropped. Ending it fails if the
+/// render pass has subpasses that were not reached with `next_subpass`, which `finish` returns
+/// and dropping only logs.
+pub struct RenderPassEncoder<'a> {
+    builder: &'a mut vulkano::AutoCommandBufferBuilder<vulkano::PrimaryAutoCommandBuffer>,
+    debug: &'a mut DebugGroups,
+    // Descriptor sets are bound for the layout of this pipeline.
+    pipeline: Option<Arc<vulkano::GraphicsPipeline>>,
+    ended: bool,
+}
+
+impl<'a> RenderPassEncoder<'a> {
+    fn new(
+        builder: &'a mut vulkano::AutoCommandBufferBuilder<vulkano::PrimaryAutoCommandBuffer>,
+        debug: &'a mut DebugGroups,
+    ) -> Self {
+        Self {
+            builder,
+            debug,
+            pipeline: None,
+            ended: false,
+        }
+    }
+    /// Sets the first viewport, e.g. to `Surface::viewport` for pipelines with a dynamic viewport.
+    pub fn set_viewport(&mut self, viewport: vulkano::Viewport) -> &mut Self {
+        self.builder.set_viewport(0, [viewport]);
+        self
+    }
+    /// Sets the depth bias for pipelines with `DynamicStates::depth_bias`, e.g. a bias per shadow
+    /// map. A `clamp` other than 0.0 needs the `depth_bias_clamp` feature.
+    ///
+    /// In debug builds this and the other dynamic state setters check that the bound pipeline
+    /// declared the state dynamic. Without a bound pipeline nothing is checked, the state applies
+    /// to the pipelines bound afterwards.
+    pub fn set_depth_bias(
+        &mut self,
+        constant_factor: f32,
+        clamp: f32,
+        slope_factor: f32,
+    ) -> Result<&mut Self, DynamicStateError> {
+        self.check_dynamic(vulkano::DynamicState::DepthBias)?;
+        if clamp != 0.0 && !self.builder.device().enabled_features().depth_bias_clamp {
+            return Err(DynamicStateError::FeatureNotEnabled("depth_bias_clamp"));
+        }
+        self.builder
+            .set_depth_bias(constant_factor, clamp, slope_factor);
+        Ok(self)
+    }
+    /// Sets the line width for pipelines with `DynamicStates::line_width`. Widths other than 1.0
+    /// need the `wide_lines` feature.
+    pub fn set_line_width(&mut self, line_width: f32) -> Result<&mut Self, DynamicStateError> {
+        self.check_dynamic(vulkano::DynamicState::LineWidth)?;
+        if line_width != 1.0 && !self.builder.device().enabled_features().wide_lines {
+            return Err(DynamicStateError::FeatureNotEnabled("wide_lines"));
+        }
+        self.builder.set_line_width(line_width);
+        Ok(self)
+    }
+    /// Sets the blend constants for pipelines with `DynamicStates::blend_constants`.
+    pub fn set_blend_constants(
+        &mut self,
+        constants: [f32; 4],
+    ) -> Result<&mut Self, DynamicStateError> {
+        self.check_dynamic(vulkano::DynamicState::BlendConstants)?;
+        self.builder.set_blend_constants(constants);
+        Ok(self)
+    }
+    fn check_dynamic(&self, state: vulkano::DynamicState) -> Result<(), DynamicStateError> {
+        match &self.pipeline {
+            Some(pipeline)
+                if cfg!(debug_assertions) && pipeline.dynamic_state(state) != Some(true) =>
+            {
+                Err(DynamicStateError::NotDynamic(state))
+            }
+            _ => Ok(()),
+        }
+    }
+    pub fn bind_pipeline(&mut self, pipeline: &Arc<vulkano::GraphicsPipeline>) -> &mut Self {
+        self.builder.bind_pipeline_graphics(pipeline.clone());
+        self.pipeline = Some(pipeline.clone());
+        self
+    }
+    /// Binds `buffers` starting at `first_binding`, e.g. `&buffer` for a `Buffer` or a tuple of
+    /// buffers for several bindings.
+    pub fn bind_vertex_buffers(
+        &mut self,
+        first_binding: u32,
+        buffers: impl vulkano::VertexBuffersCollection,
+    ) -> &mut Self {
+        self.builder.bind_vertex_buffers(first_binding, buffers);
+        self
+    }
+    /// Binds `buffer`, e.g. `&buffer` for a `Buffer<u16>` or `Buffer<u32>`.
+    pub fn bind_index_buffer(&mut self, buffer: impl Into<IndexBuffer>) -> &mut Self {
+        buffer.into().bind(self.builder);
+        self
+    }
+    /// Binds `sets` starting at `first_set` for the layout of the bound pipeline.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if no pipeline has been bound.
+    pub fn bind_descriptor_sets(
+        &mut self,
+        first_set: u32,
+        sets: impl vulkano::DescriptorSetsCollection,
+    ) -> &mut Self {
+        let layout = self
+            .pipeline
+            .as_ref()
+            .expect("a pipeline has to be bound before its descriptor sets")
+            .layout()
+            .clone();
+        self.builder.bind_descriptor_sets(
+            vulkano::PipelineBindPoint::Graphics,
+            layout,
+            first_set,
+            sets,
+        );
+        self
+    }
+    /// Binds `group` as set `set_index` for the layout of the bound pipeline, usually the set the
+    /// group was built for.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if no pipeline has been bound.
+    pub fn bind_group(&mut self, set_index: u32, group: &BindGroup) -> &mut Self {
+        self.bind_descriptor_sets(set_index, group.descriptor_set().clone())
+    }
+    /// Pushes `push_constants` for all push constant ranges of `pipeline`, e.g. of a pipeline
+    /// whose shaders declare a single push constant block.
+    ///
+    /// In debug builds the size of `Pc` is checked against the ranges reflected from the shaders.
+    pub fn push_constants<Pc: Pod>(
+        &mut self,
+        pipeline: &Arc<vulkano::GraphicsPipeline>,
+        push_constants: &Pc,
+    ) -> Result<&mut Self, PushConstantsError> {
+        let (offset, size) = push_constants_span(pipeline.layout())?;
+        self.push_constants_checked(pipeline, offset, size, push_constants)
+    }
+    /// Pushes `push_constants` for the push constant range of `pipeline` starting at `offset`,
+    /// e.g. for pipelines that split the push constants between the vertex and fragment stage.
+    pub fn push_constants_at<Pc: Pod>(
+        &mut self,
+        pipeline: &Arc<vulkano::GraphicsPipeline>,
+        offset: u32,
+        push_constants: &Pc,
+    ) -> Result<&mut Self, PushConstantsError> {
+        let range = pipeline
+            .layout()
+            .push_constant_ranges()
+            .iter()
+            .find(|range| range.offset == offset)
+            .ok_or(PushConstantsError::NoRangeAt(offset))?;
+        self.push_constants_checked(pipeline, range.offset, range.size, push_constants)
+    }
+    /// Like `push_constants_at`, but selects the range used by exactly `stages`.
+    pub fn push_constants_for<Pc: Pod>(
+        &mut self,
+        pipeline: &Arc<vulkano::GraphicsPipeline>,
+        stages: vulkano::ShaderStages,
+        push_constants: &Pc,
+    ) -> Result<&mut Self, PushConstantsError> {
+        let range = pipeline
+            .layout()
+            .push_constant_ranges()
+            .iter()
+            .find(|range| range.stages == stages)
+            .ok_or(PushConstantsError::NoRangeFor(stages))?;
+        self.push_constants_checked(pipeline, range.offset, range.size, push_constants)
+    }
+    fn push_constants_checked<Pc: Pod>(
+        &mut self,
+        pipeline: &Arc<vulkano::GraphicsPipeline>,
+        offset: u32,
+        size: u32,
+        push_constants: &Pc,
+    ) -> Result<&mut Self, PushConstantsError> {
+        check_push_constants_size::<Pc>(size)?;
+        self.builder
+            .push_constants(pipeline.layout().clone(), offset, *push_constants);
+        Ok(self)
+    }
+    pub fn draw(
+        &mut self,
+        vertex_count: u32,
+        instance_count: u32,
+        first_vertex: u32,
+        first_instance: u32,
+    ) -> Result<&mut Self, vulkano::DrawError> {
+        self.builder
+            .draw(vertex_count, instance_count, first_vertex, first_instance)?;
+        Ok(self)
+    }
+    pub fn draw_indexed(
+        &mut self,
+        index_count: u32,
+        instance_count: u32,
+        first_index: u32,
+        vertex_offset: i32,
+        first_instance: u32,
+    ) -> Result<&mut Self, vulkano::DrawIndexedError> {
+        self.builder.draw_indexed(
+            index_count,
+            instance_count,
+            first_index,
+            vertex_offset,
+            first_instance,
+        )?;
+        Ok(self)
+    }
+    /// Binds the buffers of `mesh` and draws it, see `Mesh::draw`.
+    pub fn draw_mesh<V: Pod + Send + Sync>(
+        &mut self,
+        mesh: &Mesh<V>,
+    ) -> Result<&mut Self, MeshDrawError> {
+        mesh.draw(self.builder)?;
+        Ok(self)
+    }
+    /// Binds the buffers of `mesh` and draws an instance per element of `instances`, see
+    /// `Mesh::draw_instanced`.
+    pub fn draw_mesh_instanced<V: Pod + Send + Sync, I: Pod + Send + Sync>(
+        &mut self,
+        mesh: &Mesh<V>,
+        instances: &InstanceBuffer<I>,
+    ) -> Result<&mut Self, MeshDrawError> {
+        mesh.draw_instanced(self.builder, instances)?;
+        Ok(self)
+    }
+    /// Records the draws of `list`, see `DrawList::record`.
+    ///
+    /// The list binds its own pipelines, so descriptor sets bound afterwards need `bind_pipeline`
+    /// first.
+    pub fn draw_list<Pc: Copy>(
+        &mut self,
+        list: &mut DrawList<Pc>,
+    ) -> Result<DrawListStats, DrawListError> {
+        self.pipeline = None;
+        list.record(self.builder)
+    }
+    /// Like `CommandEncoder::push_debug_group`, e.g. to group the draws of an object.
+    pub fn push_debug_group(&mut self, label: &str, color: [f32; 4]) -> &mut Self {
+        self.debug.push(self.builder, label, color);
+        self
+    }
+    pub fn pop_debug_group(&mut self) -> &mut Self {
+        self.debug.pop(self.builder);
+        self
+    }
+    pub fn insert_debug_label(&mut self, label: &str, color: [f32; 4]) -> &mut Self {
+        self.debug.insert(self.builder, label, color);
+        self
+    }
+    /// Continues with the next subpass of the render pass.
+    pub fn next_subpass(
+        &mut self,
+    ) -> Result<&mut Self, vulkano::AutoCommandBufferBuilderContextError> {
+        self.builder
+            .next_subpass(vulkano::SubpassContents::Inline)?;
+        self.pipeline = None;
+        Ok(self)
+    }
+    /// Ends the render pass, which has to be in its last subpass.
+    pub fn finish(mut self) -> Result<(), vulkano::AutoCommandBufferBuilderContextError> {
+        self.ended = true;
+        self.builder.end_render_pass()?;
+        Ok(())
+    }
+}
+
+impl<'a> Drop for RenderPassEncoder<'a> {
+    fn drop(&mut self) {
+        if self.ended {
+            return;
+        }
+        if let Err(err) = self.builder.end_render_pass() {
+            log::error!("Failed to end the render pass: {}", err);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PushConstantsError {
+    /// The shaders of the pipeline do not declare push constants.
+    NoRanges,
+    /// The pipeline has no push constant range starting at this offset.
+    NoRangeAt(u32),
+    /// The pipeline has no push constant range used by exactly these stages.
+    NoRangeFor(vulkano::ShaderStages),
+    /// The size of the pushed struct differs from the range reflected from the shaders.
+    SizeMismatch { size: usize, expected: usize },
+}
+
+impl fmt::Display for PushConstantsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoRanges => write!(f, "the pipeline has no push constants"),
+            Self::NoRangeAt(offset) => {
+                write!(f, "the pipeline has no push constants at offset {}", offset)
+            }
+            Self::NoRangeFor(stages) => {
+                write!(f, "the pipeline has no push constants for {:?}", stages)
+            }
+            Self::SizeMismatch { size, expected } => write!(
+                f,
+                "the push constants have {} bytes, but the shaders declare {}",
+                size, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PushConstantsError {}
+
+/// Offset and size of the span covering all push constant ranges of `layout`.
+pub(crate) fn push_constants_span(
+    layout: &vulkano::PipelineLayout,
+) -> Result<(u32, u32), PushConstantsError> {
+    let ranges = layout.push_constant_ranges();
+    let start = ranges.iter().map(|range| range.offset).min();
+    let end = ranges.iter().map(|range| range.offset + range.size).max();
+    let (start, end) = start.zip(end).ok_or(PushConstantsError::NoRanges)?;
+    Ok((start, end - start))
+}
+
+/// Checks the size of `Pc` against the `size` of the range it is pushed to, in debug builds only.
+pub(crate) fn check_push_constants_size<Pc>(size: u32) -> Result<(), PushConstantsError> {
+    if cfg!(debug_assertions) && std::mem::size_of::<Pc>() != size as usize {
+        return Err(PushConstantsError::SizeMismatch {
+            size: std::mem::size_of::<Pc>(),
+            expected: size as usize,
+        });
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum DynamicStateError {
+    /// The bound pipeline has the state baked in, see `PipelineDescriptor::dynamic_states`.
+    NotDynamic(vulkano::DynamicState),
+    /// The value needs this feature, which is not enabled on the device.
+    FeatureNotEnabled(&'static str),
+}
+
+impl fmt::Display for DynamicStateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotDynamic(state) => {
+                write!(f, "the bound pipeline does not have a dynamic {:?}", state)
+            }
+            Self::FeatureNotEnabled(feature) => {
+                write!(f, "the {} feature is not enabled", feature)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DynamicStateError {}
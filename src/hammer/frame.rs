@@ -0,0 +1,241 @@
+use std::sync::Arc;
+
+use super::{
+    then_execute_timer, CaptureError, CommandEncoder, Device, DrawListStats, FrameError,
+    MisuseGuard, Queue, Rect, RgbaImageData, Surface, SurfaceImage, VirtualBlitError,
+    VirtualResolution,
+};
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::command_buffer::*;
+    pub use vulkano::render_pass::*;
+    pub use vulkano::sync::*;
+    pub use vulkano::OomError;
+}
+
+use vulkano::GpuFuture;
+
+/// A frame of a `Surface` between `Surface::begin_frame` and `Frame::present`.
+///
+/// Covers the common case of a single frame in flight without a separate `FrameSync`: the frame
+/// starts out waiting for the acquired image and the previous frame, `submit` appends command
+/// buffers and `present` keeps the result in the surface for the next `begin_frame`.
+///
+/// A frame dropped without presenting submits the work it has recorded with a fence instead of
+/// waiting for it. The image is not presented then, so the swapchain is recreated with the next
+/// frame to get it back. Debug builds report dropping a frame this way as misuse unless it was
+/// skipped with `abandon`.
+pub struct Frame<'a, W: Send + Sync + 'static> {
+    surface: &'a mut Surface<W>,
+    device: &'a Device,
+    // Both are taken by `present`.
+    image: Option<SurfaceImage<W>>,
+    future: Option<Box<dyn GpuFuture>>,
+    // Queue of the first submission, which starts the GPU time of the frame stats.
+    timed_queue: Option<Queue>,
+    /// Parts of the image that changed since the last frame, see
+    /// `SurfaceImage::present_with_regions`. Empty means the whole image changed.
+    pub dirty_regions: Vec<Rect>,
+    guard: MisuseGuard,
+}
+
+impl<W: Send + Sync + 'static> Surface<W> {
+    /// Acquires the next image like `acquire` and begins a frame rendering into it.
+    ///
+    /// Fails with `FrameError::RecreateNeeded` if no image could be acquired because the swapchain
+    /// is out of date, the window is minimized or the surface has been invalidated. Skip the frame
+    /// in that case, the swapchain is recreated with the next one.
+    #[track_caller]
+    pub fn begin_frame<'a>(&'a mut self, device: &'a Device) -> Result<Frame<'a, W>, FrameError> {
+        if let Some(stats) = self.frame_stats_mut() {
+            stats.begin_frame();
+        }
+        let previous = self.take_previous_frame();
+        let mut image = match self.acquire() {
+            Ok(image) => image,
+            Err(err) => {
+                if let Some(previous) = previous {
+                    self.set_previous_frame(previous);
+                }
+                return Err(err.into());
+            }
+        };
+        if let Some(stats) = self.frame_stats_mut() {
+            stats.end_acquire();
+        }
+        let acquire_future = image
+            .take_acquire_future()
+            .expect("a newly acquired image has an acquire future");
+        let future = match previous {
+            Some(previous) => previous.join(acquire_future).boxed(),
+            None => vulkano::now(Arc::clone(device))
+                .join(acquire_future)
+                .boxed(),
+        };
+        Ok(Frame {
+            surface: self,
+            device,
+            image: Some(image),
+            future: Some(future),
+            timed_queue: None,
+            dirty_regions: Vec::new(),
+            guard: MisuseGuard::new("Frame", "present it or call `abandon` to skip it"),
+        })
+    }
+}
+
+impl<'a, W: Send + Sync + 'static> Frame<'a, W> {
+    pub fn image(&self) -> &SurfaceImage<W> {
+        self.image.as_ref().unwrap()
+    }
+    /// The surface the frame was begun on, e.g. for its viewport.
+    pub fn surface(&self) -> &Surface<W> {
+        self.surface
+    }
+    /// The cached framebuffer of the image for `render_pass`, see `SurfaceImage::framebuffer`.
+    pub fn framebuffer(
+        &self,
+        render_pass: &Arc<vulkano::RenderPass>,
+    ) -> Result<Arc<vulkano::Framebuffer>, vulkano::FramebufferCreationError> {
+        self.image().framebuffer(self.surface, render_pass)
+    }
+    /// Records scaling `virtual_resolution` onto the image into `encoder`, see
+    /// `VirtualResolution::blit`.
+    pub fn blit_virtual(
+        &self,
+        encoder: &mut CommandEncoder,
+        virtual_resolution: &VirtualResolution,
+    ) -> Result<(), VirtualBlitError> {
+        virtual_resolution.blit(encoder.builder_mut(), self.image().image.clone())
+    }
+    /// The device the frame was begun with, e.g. to record its command buffers with
+    /// `Device::create_command_encoder`.
+    pub fn device(&self) -> &'a Device {
+        self.device
+    }
+    /// Starts recording a command buffer that can be resubmitted in later frames, see
+    /// `Device::create_reusable_command_encoder` and `CommandBufferCache`.
+    #[track_caller]
+    pub fn reusable_command_encoder(
+        &self,
+        queue: &Queue,
+    ) -> Result<CommandEncoder, vulkano::OomError> {
+        self.device.create_reusable_command_encoder(queue)
+    }
+    /// Adds the stats returned by `RenderPassEncoder::draw_list` to the frame stats, if they are
+    /// enabled with `Surface::enable_frame_stats`.
+    pub fn add_draw_list_stats(&mut self, stats: DrawListStats) {
+        if let Some(frame_stats) = self.surface.frame_stats_mut() {
+            frame_stats.add_draw_list(stats);
+        }
+    }
+    /// Submits `command_buffer` to `queue` after the work of the frame so far.
+    pub fn submit<Cb>(&mut self, queue: &Queue, command_buffer: Cb) -> Result<(), FrameError>
+    where
+        Cb: vulkano::PrimaryCommandBuffer + 'static,
+    {
+        let mut future = self.future.take().unwrap();
+        if self.timed_queue.is_none() {
+            if let Some(stats) = self.surface.frame_stats_mut() {
+                let timer = stats.start_timer(queue);
+                self.timed_queue = Some(queue.clone());
+                future = match then_execute_timer(future, queue, timer) {
+                    Ok(future) => future,
+                    Err(err) => {
+                        self.future = Some(vulkano::now(Arc::clone(self.device)).boxed());
+                        return Err(FrameError::Submit(err));
+                    }
+                };
+            }
+        }
+        match queue.submit_after(future, command_buffer) {
+            Ok(submitted) => {
+                self.future = Some(submitted.into_future().boxed());
+                Ok(())
+            }
+            Err(err) => {
+                // After a failed submission nothing is left to wait for.
+                self.future = Some(vulkano::now(Arc::clone(self.device)).boxed());
+                Err(FrameError::Submit(err))
+            }
+        }
+    }
+    /// Copies the image to the host after the work submitted so far, see `Surface::capture`.
+    pub fn capture(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+    ) -> Result<RgbaImageData, CaptureError> {
+        let future = self.future.take().unwrap();
+        let capture = self.surface.capture(device, queue, self.image(), future);
+        // The capture waited for the rendering, so there is nothing left to wait for.
+        self.future = Some(vulkano::now(Arc::clone(self.device)).boxed());
+        capture
+    }
+    /// Presents the image on `queue` after the submitted work, limited to `dirty_regions` if
+    /// there are any.
+    ///
+    /// Fails with `FrameError::RecreateNeeded` if the swapchain turned out to be out of date, the
+    /// next `begin_frame` recreates it.
+    pub fn present(mut self, queue: &Queue) -> Result<(), FrameError> {
+        self.guard.consume();
+        let image = self.image.take().unwrap();
+        let mut future = self.future.take().unwrap();
+        if let (Some(timed_queue), Some(stats)) =
+            (&self.timed_queue, self.surface.frame_stats_mut())
+        {
+            let timer = stats.stop_timer(timed_queue);
+            future = match then_execute_timer(future, timed_queue, timer) {
+                Ok(future) => future,
+                Err(err) => {
+                    image.abandon();
+                    self.surface.mark_suboptimal();
+                    return Err(FrameError::Submit(err));
+                }
+            };
+        }
+        self.surface.pace_present();
+        let presented = if self.dirty_regions.is_empty() {
+            image.present(queue, future)
+        } else {
+            image.present_with_regions(queue, future, &self.dirty_regions)
+        };
+        match presented.map_err(FrameError::from) {
+            Ok(presented) => {
+                self.surface.set_previous_frame(presented);
+                Ok(())
+            }
+            Err(FrameError::RecreateNeeded) => {
+                self.surface.mark_suboptimal();
+                Err(FrameError::RecreateNeeded)
+            }
+            Err(err) => Err(err),
+        }
+    }
+    /// Skips presenting the frame, e.g. because nothing changed. The submitted work still runs,
+    /// see `Frame`.
+    pub fn abandon(mut self) {
+        self.guard.consume();
+    }
+}
+
+impl<'a, W: Send + Sync + 'static> Drop for Frame<'a, W> {
+    fn drop(&mut self) {
+        let (image, future) = match (self.image.take(), self.future.take()) {
+            (Some(image), Some(future)) => (image, future),
+            _ => return,
+        };
+        image.abandon();
+        // Without a queue nothing has been submitted that a fence could follow.
+        if future.queue().is_some() {
+            // Dropping the future would block until the work finished.
+            match future.then_signal_fence_and_flush() {
+                Ok(future) => self.surface.set_previous_frame(future),
+                Err(err) => log::warn!("Failed to submit a frame that was not presented: {}", err),
+            }
+        }
+        // Acquired images are only released by presenting them or recreating the swapchain.
+        self.surface.mark_suboptimal();
+    }
+}
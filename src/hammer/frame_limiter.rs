@@ -0,0 +1,93 @@
+use std::time::{Duration, Instant};
+
+// Sleeping overshoots by up to a scheduler tick, the rest of the wait is spent spinning.
+const SPIN_MARGIN: Duration = Duration::from_millis(2);
+
+/// Spaces presents at a target interval, e.g. so Immediate or Mailbox present modes do not render
+/// thousands of frames per second.
+///
+/// Used through `Surface::set_target_fps` and `FrameSync::set_target_fps`. The interval is
+/// measured from the previous present, so a present that already blocks long enough, e.g. in
+/// Fifo mode with a target above the refresh rate, is not delayed any further.
+#[derive(Debug, Default)]
+pub struct FrameLimiter {
+    interval: Option<Duration>,
+    // When the next present is due, advanced by the interval so the error of one wait does not
+    // accumulate.
+    deadline: Option<Instant>,
+}
+
+impl FrameLimiter {
+    /// Limits presents to `fps` frames per second, `None` or a non-positive value disables the
+    /// limit with the next present.
+    pub fn set_target_fps(&mut self, fps: Option<f32>) {
+        self.interval = fps
+            .filter(|&fps| fps > 0.0 && fps.is_finite())
+            .map(|fps| Duration::from_secs_f64(1.0 / fps as f64));
+        self.deadline = None;
+    }
+    pub fn target_fps(&self) -> Option<f32> {
+        Some((1.0 / self.interval?.as_secs_f64()) as f32)
+    }
+    /// Blocks until the next present is due, called right before presenting.
+    pub fn wait(&mut self) {
+        let interval = match self.interval {
+            Some(interval) => interval,
+            None => return,
+        };
+        let now = Instant::now();
+        let deadline = match self.deadline {
+            // A frame that took longer than the interval starts a new schedule instead of
+            // letting the following frames catch up.
+            Some(deadline) if deadline > now => {
+                sleep_until(deadline);
+                deadline
+            }
+            _ => now,
+        };
+        self.deadline = Some(deadline + interval);
+    }
+}
+
+// Sleeps for most of the time and spins for the rest, which is more accurate than sleeping alone.
+fn sleep_until(deadline: Instant) {
+    let now = Instant::now();
+    if deadline > now + SPIN_MARGIN {
+        std::thread::sleep(deadline - now - SPIN_MARGIN);
+    }
+    while Instant::now() < deadline {
+        std::hint::spin_loop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn waits_for_the_interval() {
+        let mut limiter = FrameLimiter::default();
+        limiter.set_target_fps(Some(200.0));
+        assert_eq!(limiter.target_fps(), Some(200.0));
+        let start = Instant::now();
+        for _ in 0..21 {
+            limiter.wait();
+        }
+        // The first wait only starts the schedule.
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn disabling_stops_waiting() {
+        let mut limiter = FrameLimiter::default();
+        limiter.set_target_fps(Some(1.0));
+        limiter.wait();
+        limiter.set_target_fps(None);
+        let start = Instant::now();
+        limiter.wait();
+        assert!(start.elapsed() < Duration::from_millis(500));
+        // Non-positive rates disable the limit as well.
+        limiter.set_target_fps(Some(0.0));
+        assert_eq!(limiter.target_fps(), None);
+    }
+}
@@ -0,0 +1,345 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::{Device, DrawListStats, Queue, SubmitError};
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::command_buffer::*;
+    pub use vulkano::query::*;
+    pub use vulkano::sync::*;
+}
+
+use vulkano::GpuFuture;
+
+/// Number of frames `FrameStats` averages over.
+pub const FRAME_STATS_SAMPLES: usize = 60;
+
+// Query pools used in turn, so reading the results of a frame does not have to wait for the
+// frames after it.
+const TIMER_POOLS: usize = 4;
+
+/// Times of a frame, or averages of them, see `FrameStats`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameTimes {
+    /// Time between the beginnings of two frames.
+    pub cpu: Duration,
+    /// Time `begin_frame` was blocked waiting for an earlier frame and the swapchain image.
+    pub acquire: Duration,
+    /// Time the GPU spent executing the command buffers of the frame, `None` if the queue has no
+    /// timestamp support or no results arrived yet.
+    pub gpu: Option<Duration>,
+    /// Time between two presents, the effective frame interval e.g. with a frame limiter.
+    pub present: Duration,
+}
+
+/// Collects frame times once enabled with `Surface::enable_frame_stats` or
+/// `FrameSync::enable_stats`.
+///
+/// GPU times are measured with timestamp queries written before the first and after the last
+/// command buffer of a frame. Their results are read without waiting, so they lag a few frames
+/// behind the CPU times.
+pub struct FrameStats {
+    cpu: VecDeque<Duration>,
+    acquire: VecDeque<Duration>,
+    present: VecDeque<Duration>,
+    gpu: VecDeque<Duration>,
+    last_begin: Option<Instant>,
+    acquire_start: Option<Instant>,
+    last_present: Option<Instant>,
+    timer: Option<GpuTimer>,
+    // Draw lists of the current frame and the last one.
+    frame_draw_list: DrawListStats,
+    last_draw_list: DrawListStats,
+}
+
+struct GpuTimer {
+    pools: Vec<TimerPool>,
+    next: usize,
+    // Index of the pool the current frame writes to, between `start_timer` and `stop_timer`.
+    recording: Option<usize>,
+    // Nanoseconds per timestamp tick.
+    period: f64,
+}
+
+struct TimerPool {
+    pool: Arc<vulkano::QueryPool>,
+    // Mask of the valid timestamp bits, set once both timestamps have been submitted and the
+    // results are not read yet.
+    pending: Option<u64>,
+}
+
+impl FrameStats {
+    /// Creates the query pools for the GPU times if the device supports timestamps.
+    pub fn new(device: &Device) -> Self {
+        let period = device.physical_device().properties().timestamp_period as f64;
+        let pools = (0..TIMER_POOLS)
+            .map(|_| {
+                let pool = vulkano::QueryPool::new(
+                    Arc::clone(device),
+                    vulkano::QueryPoolCreateInfo {
+                        query_count: 2,
+                        ..vulkano::QueryPoolCreateInfo::query_type(vulkano::QueryType::Timestamp)
+                    },
+                )?;
+                Ok(TimerPool {
+                    pool,
+                    pending: None,
+                })
+            })
+            .collect::<Result<Vec<_>, vulkano::QueryPoolCreationError>>();
+        let timer = match pools {
+            Ok(pools) if period > 0.0 => Some(GpuTimer {
+                pools,
+                next: 0,
+                recording: None,
+                period,
+            }),
+            Ok(_) => None,
+            Err(err) => {
+                log::warn!("Failed to create the queries for GPU frame times: {}", err);
+                None
+            }
+        };
+        Self {
+            cpu: VecDeque::with_capacity(FRAME_STATS_SAMPLES),
+            acquire: VecDeque::with_capacity(FRAME_STATS_SAMPLES),
+            present: VecDeque::with_capacity(FRAME_STATS_SAMPLES),
+            gpu: VecDeque::with_capacity(FRAME_STATS_SAMPLES),
+            last_begin: None,
+            acquire_start: None,
+            last_present: None,
+            timer,
+            frame_draw_list: DrawListStats::default(),
+            last_draw_list: DrawListStats::default(),
+        }
+    }
+    /// Whether GPU times can be measured at all, they still depend on the queue.
+    pub fn gpu_timing_supported(&self) -> bool {
+        self.timer.is_some()
+    }
+    /// Times of the last frame, the GPU time of the last frame whose results arrived.
+    pub fn last(&self) -> FrameTimes {
+        FrameTimes {
+            cpu: self.cpu.back().copied().unwrap_or_default(),
+            acquire: self.acquire.back().copied().unwrap_or_default(),
+            gpu: self.gpu.back().copied(),
+            present: self.present.back().copied().unwrap_or_default(),
+        }
+    }
+    /// Averages over the last `FRAME_STATS_SAMPLES` frames.
+    pub fn average(&self) -> FrameTimes {
+        FrameTimes {
+            cpu: average(&self.cpu).unwrap_or_default(),
+            acquire: average(&self.acquire).unwrap_or_default(),
+            gpu: average(&self.gpu),
+            present: average(&self.present).unwrap_or_default(),
+        }
+    }
+    /// Stats of the draw lists recorded for the last frame, summed over all of them.
+    pub fn draw_list(&self) -> DrawListStats {
+        self.last_draw_list
+    }
+    /// Adds the stats of a draw list recorded for the current frame.
+    pub fn add_draw_list(&mut self, stats: DrawListStats) {
+        self.frame_draw_list += stats;
+    }
+    /// Frames per second from the average CPU time, zero before the second frame.
+    pub fn fps(&self) -> f64 {
+        match self.average().cpu.as_secs_f64() {
+            secs if secs > 0.0 => 1.0 / secs,
+            _ => 0.0,
+        }
+    }
+
+    /// Called at the beginning of `begin_frame`, before waiting for anything.
+    pub(crate) fn begin_frame(&mut self) {
+        let now = Instant::now();
+        if let Some(last_begin) = self.last_begin.replace(now) {
+            push(&mut self.cpu, now - last_begin);
+        }
+        self.acquire_start = Some(now);
+        self.last_draw_list = std::mem::take(&mut self.frame_draw_list);
+        self.read_timers();
+    }
+    /// Called once `begin_frame` acquired the image.
+    pub(crate) fn end_acquire(&mut self) {
+        if let Some(start) = self.acquire_start.take() {
+            push(&mut self.acquire, start.elapsed());
+        }
+    }
+    /// Called right before presenting, after the frame limiter.
+    pub(crate) fn presented(&mut self) {
+        let now = Instant::now();
+        if let Some(last_present) = self.last_present.replace(now) {
+            push(&mut self.present, now - last_present);
+        }
+    }
+    /// Command buffer writing the timestamp the GPU time of the frame starts at, to be submitted
+    /// to `queue` before the first command buffer of the frame.
+    ///
+    /// `None` if the queue does not support timestamps.
+    pub(crate) fn start_timer(
+        &mut self,
+        queue: &Queue,
+    ) -> Option<vulkano::PrimaryAutoCommandBuffer> {
+        let timer = self.timer.as_mut()?;
+        queue.family().timestamp_valid_bits()?;
+        let index = timer.next;
+        timer.next = (timer.next + 1) % timer.pools.len();
+        // Unread results are lost, the next frames get the other pools.
+        let pool = &mut timer.pools[index];
+        pool.pending = None;
+        let command_buffer = timestamp_command_buffer(queue, &pool.pool, 0, true);
+        timer.recording = command_buffer.as_ref().map(|_| index);
+        command_buffer
+    }
+    /// Command buffer writing the timestamp the GPU time of the frame ends at, to be submitted
+    /// to the queue passed to `start_timer` after the last command buffer of the frame.
+    pub(crate) fn stop_timer(
+        &mut self,
+        queue: &Queue,
+    ) -> Option<vulkano::PrimaryAutoCommandBuffer> {
+        let timer = self.timer.as_mut()?;
+        let index = timer.recording.take()?;
+        let valid_bits = queue.family().timestamp_valid_bits()?;
+        let pool = &mut timer.pools[index];
+        let command_buffer = timestamp_command_buffer(queue, &pool.pool, 1, false)?;
+        pool.pending = Some(if valid_bits >= 64 {
+            u64::MAX
+        } else {
+            (1 << valid_bits) - 1
+        });
+        Some(command_buffer)
+    }
+    // Collects the GPU times of the frames that finished, oldest first.
+    fn read_timers(&mut self) {
+        let timer = match self.timer.as_mut() {
+            Some(timer) => timer,
+            None => return,
+        };
+        let count = timer.pools.len();
+        for offset in 0..count {
+            let pool = &mut timer.pools[(timer.next + offset) % count];
+            let mask = match pool.pending {
+                Some(mask) => mask,
+                None => continue,
+            };
+            let mut timestamps = [0u64; 2];
+            let available = pool
+                .pool
+                .queries_range(0..2)
+                .unwrap()
+                .get_results(&mut timestamps, vulkano::QueryResultFlags::default());
+            match available {
+                Ok(true) => {
+                    pool.pending = None;
+                    let ticks = timestamps[1].wrapping_sub(timestamps[0]) & mask;
+                    let nanos = ticks as f64 * timer.period;
+                    push(&mut self.gpu, Duration::from_nanos(nanos as u64));
+                }
+                // Still executing, read again with the next frame.
+                Ok(false) => {}
+                Err(err) => {
+                    pool.pending = None;
+                    log::warn!("Failed to read the GPU frame time: {}", err);
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for FrameStats {
+    /// Averages in a single line, e.g. for the title of a window.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let average = self.average();
+        write!(
+            f,
+            "{:.0} fps, cpu {:.2} ms, acquire {:.2} ms",
+            self.fps(),
+            average.cpu.as_secs_f64() * 1000.0,
+            average.acquire.as_secs_f64() * 1000.0,
+        )?;
+        if let Some(gpu) = average.gpu {
+            write!(f, ", gpu {:.2} ms", gpu.as_secs_f64() * 1000.0)?;
+        }
+        if self.last_draw_list.draws > 0 {
+            write!(
+                f,
+                ", {} draws, {} binds saved",
+                self.last_draw_list.draws,
+                self.last_draw_list.binds_saved(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Appends the timestamp `command_buffer` of `start_timer` or `stop_timer` to `future`, if there
+/// is one.
+pub(crate) fn then_execute_timer(
+    future: Box<dyn GpuFuture>,
+    queue: &Queue,
+    command_buffer: Option<vulkano::PrimaryAutoCommandBuffer>,
+) -> Result<Box<dyn GpuFuture>, SubmitError> {
+    Ok(match command_buffer {
+        Some(command_buffer) => future
+            .then_execute(queue.handle().clone(), command_buffer)?
+            .boxed(),
+        None => future,
+    })
+}
+
+fn push(samples: &mut VecDeque<Duration>, sample: Duration) {
+    if samples.len() == FRAME_STATS_SAMPLES {
+        samples.pop_front();
+    }
+    samples.push_back(sample);
+}
+
+fn average(samples: &VecDeque<Duration>) -> Option<Duration> {
+    if samples.is_empty() {
+        return None;
+    }
+    Some(samples.iter().sum::<Duration>() / samples.len() as u32)
+}
+
+// Writes timestamp `query` of `pool`, resetting the pool first if `reset` is set. Failures only
+// cost the GPU time of the frame, so they are logged instead of returned.
+fn timestamp_command_buffer(
+    queue: &Queue,
+    pool: &Arc<vulkano::QueryPool>,
+    query: u32,
+    reset: bool,
+) -> Option<vulkano::PrimaryAutoCommandBuffer> {
+    let record = || -> Result<_, Box<dyn std::error::Error>> {
+        let mut builder = vulkano::AutoCommandBufferBuilder::primary(
+            queue.device().clone(),
+            queue.family(),
+            vulkano::CommandBufferUsage::OneTimeSubmit,
+        )?;
+        let stage = if reset {
+            vulkano::PipelineStage::TopOfPipe
+        } else {
+            vulkano::PipelineStage::BottomOfPipe
+        };
+        // The pool is only used by the frames of one `FrameStats`, which are submitted one after
+        // another, so the reset cannot overlap with earlier writes.
+        unsafe {
+            if reset {
+                builder.reset_query_pool(pool.clone(), 0..2)?;
+            }
+            builder.write_timestamp(pool.clone(), query, stage)?;
+        }
+        Ok(builder.build()?)
+    };
+    match record() {
+        Ok(command_buffer) => Some(command_buffer),
+        Err(err) => {
+            log::warn!("Failed to record the GPU frame time: {}", err);
+            None
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use std::fmt;
+
+use super::{
+    then_execute_timer, AcquireImageError, Device, DrawListStats, FrameLimiter, FrameStats,
+    PresentError, Queue, Rect, RenderTarget, SubmitError, TargetImage, WaitIdleError,
+};
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::command_buffer::*;
+    pub use vulkano::sync::*;
+}
+
+use vulkano::GpuFuture;
+
+/// Limits how many frames the CPU can record ahead of the GPU.
+///
+/// Every frame gets a slot holding the fence of its submission. Beginning a frame waits for the
+/// frame that used the same slot `frames_in_flight` frames earlier, so resources indexed by
+/// `FrameContext::frame_index` can be reused safely.
+pub struct FrameSync {
+    present_queue: Queue,
+    frames: Vec<Option<vulkano::FenceSignalFuture<Box<dyn GpuFuture>>>>,
+    frame_index: usize,
+    // Set by `enable_stats`.
+    stats: Option<FrameStats>,
+    limiter: FrameLimiter,
+}
+
+/// A frame between `FrameSync::begin_frame` and `FrameSync::end_frame`.
+pub struct FrameContext<I> {
+    pub image: I,
+    /// Work rendering into the image has to wait for this, it starts out as the acquire future.
+    /// Join other futures, e.g. uploads, into it to make the frame wait for them as well.
+    pub future: Box<dyn GpuFuture>,
+    /// Slot of the frame in `0..frames_in_flight`.
+    pub frame_index: usize,
+    /// Parts of the image that changed since the last frame, passed to
+    /// `RenderTarget::present_with_regions`. Empty means the whole image changed.
+    pub dirty_regions: Vec<Rect>,
+}
+
+impl FrameSync {
+    /// Presents on the present queue of `device`, or the graphics queue if it has none, e.g.
+    /// when rendering to an `OffscreenTarget`.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if `frames_in_flight` is zero or `device` has neither a present nor a graphics
+    ///   queue.
+    pub fn new(device: &Device, frames_in_flight: usize) -> Self {
+        assert!(
+            frames_in_flight > 0,
+            "at least one frame has to be in flight"
+        );
+        let present_queue = device
+            .present_queue()
+            .or_else(|| device.graphics_queue())
+            .expect("the device has neither a present nor a graphics queue")
+            .clone();
+        Self {
+            present_queue,
+            frames: (0..frames_in_flight).map(|_| None).collect(),
+            frame_index: 0,
+            stats: None,
+            limiter: FrameLimiter::default(),
+        }
+    }
+    pub fn frames_in_flight(&self) -> usize {
+        self.frames.len()
+    }
+    /// Starts collecting the times of the frames, see `FrameStats`.
+    ///
+    /// GPU times are only measured for frames ended with `end_frame`.
+    pub fn enable_stats(&mut self, device: &Device) {
+        if self.stats.is_none() {
+            self.stats = Some(FrameStats::new(device));
+        }
+    }
+    pub fn stats(&self) -> Option<&FrameStats> {
+        self.stats.as_ref()
+    }
+    /// Adds the stats returned by `RenderPassEncoder::draw_list` to the stats of the current
+    /// frame, if they are enabled.
+    pub fn add_draw_list_stats(&mut self, stats: DrawListStats) {
+        if let Some(frame_stats) = &mut self.stats {
+            frame_stats.add_draw_list(stats);
+        }
+    }
+    /// Limits presents to `fps` frames per second, see `FrameLimiter`. `None` removes the limit.
+    pub fn set_target_fps(&mut self, fps: Option<f32>) {
+        self.limiter.set_target_fps(fps);
+    }
+    pub fn target_fps(&self) -> Option<f32> {
+        self.limiter.target_fps()
+    }
+    /// Waits until the slot of the next frame is free and acquires an image from `target`.
+    ///
+    /// Fails with `FrameError::RecreateNeeded` if no image could be acquired because the swapchain
+    /// is out of date, the window is minimized or the surface has been invalidated. Skip the frame
+    /// in that case, the swapchain is recreated with the next one.
+    #[track_caller]
+    pub fn begin_frame<T: RenderTarget>(
+        &mut self,
+        target: &mut T,
+    ) -> Result<FrameContext<T::Image>, FrameError> {
+        if let Some(stats) = &mut self.stats {
+            stats.begin_frame();
+        }
+        for frame in self.frames.iter_mut().flatten() {
+            frame.cleanup_finished();
+        }
+        if let Some(frame) = self.frames[self.frame_index].take() {
+            frame.wait(None).map_err(FrameError::Wait)?;
+        }
+
+        let mut image = target.acquire()?;
+        if let Some(stats) = &mut self.stats {
+            stats.end_acquire();
+        }
+        let future = image
+            .take_future()
+            .expect("a newly acquired image has an acquire future");
+        Ok(FrameContext {
+            image,
+            future,
+            frame_index: self.frame_index,
+            dirty_regions: Vec::new(),
+        })
+    }
+    /// Submits `command_buffer` to `queue` after the future of `frame` and presents the image on
+    /// `target`, which has to be the target the frame was begun with.
+    ///
+    /// Fails with `FrameError::RecreateNeeded` if the swapchain turned out to be out of date while
+    /// presenting, the next `begin_frame` recreates it.
+    pub fn end_frame<T, Cb>(
+        &mut self,
+        target: &mut T,
+        frame: FrameContext<T::Image>,
+        command_buffer: Cb,
+        queue: &Queue,
+    ) -> Result<(), FrameError>
+    where
+        T: RenderTarget,
+        Cb: vulkano::PrimaryCommandBuffer + 'static,
+    {
+        let FrameContext {
+            image,
+            future,
+            frame_index,
+            dirty_regions,
+        } = frame;
+        self.frame_index = (frame_index + 1) % self.frames.len();
+
+        let (start_timer, stop_timer) = match &mut self.stats {
+            Some(stats) => (stats.start_timer(queue), stats.stop_timer(queue)),
+            None => (None, None),
+        };
+        let rendered = then_execute_timer(future, queue, start_timer)
+            .and_then(|future| {
+                Ok(queue
+                    .submit_after(future, command_buffer)?
+                    .into_future()
+                    .boxed())
+            })
+            .and_then(|rendered| then_execute_timer(rendered, queue, stop_timer));
+        let rendered = match rendered {
+            Ok(rendered) => rendered,
+            Err(err) => {
+                image.abandon();
+                return Err(FrameError::Submit(err));
+            }
+        };
+        self.present_frame(
+            target,
+            FrameContext {
+                image,
+                future: rendered,
+                frame_index,
+                dirty_regions,
+            },
+        )
+    }
+    /// Presents the image of `frame` on `target` after the future of `frame`, without submitting
+    /// anything else.
+    ///
+    /// Used instead of `end_frame` if the rendering has been submitted already, e.g. to capture
+    /// the image with `Surface::capture` before presenting it.
+    pub fn present_frame<T: RenderTarget>(
+        &mut self,
+        target: &mut T,
+        frame: FrameContext<T::Image>,
+    ) -> Result<(), FrameError> {
+        let FrameContext {
+            image,
+            future,
+            frame_index,
+            dirty_regions,
+        } = frame;
+        self.frame_index = (frame_index + 1) % self.frames.len();
+
+        // The rendering has been submitted already, so the GPU keeps working while this waits.
+        self.limiter.wait();
+        if let Some(stats) = &mut self.stats {
+            stats.presented();
+        }
+        let presented = if dirty_regions.is_empty() {
+            target.present(&self.present_queue, image, future)
+        } else {
+            target.present_with_regions(&self.present_queue, image, future, &dirty_regions)
+        };
+        self.frames[frame_index] = Some(presented?);
+        Ok(())
+    }
+    /// Waits for all frames in flight, e.g. before destroying resources they use.
+    pub fn wait_idle(&mut self) -> Result<(), FrameError> {
+        for frame in self.frames.iter_mut() {
+            if let Some(frame) = frame.take() {
+                frame.wait(None).map_err(FrameError::Wait)?;
+            }
+        }
+        Ok(())
+    }
+    /// Waits for the frames in flight and then for all other work on `device`, call this before
+    /// dropping the target, its swapchain or the device when exiting.
+    pub fn shutdown(&mut self, device: &Device) -> Result<(), FrameError> {
+        self.wait_idle()?;
+        device.wait_idle().map_err(FrameError::WaitIdle)
+    }
+}
+
+#[derive(Debug)]
+pub enum FrameError {
+    /// The swapchain has to be recreated before a frame can be rendered, skip this frame.
+    RecreateNeeded,
+    Acquire(AcquireImageError),
+    Submit(SubmitError),
+    Present(PresentError),
+    /// Waiting for an earlier frame failed.
+    Wait(vulkano::FlushError),
+    /// Waiting for the device failed while shutting down.
+    WaitIdle(WaitIdleError),
+}
+
+impl From<AcquireImageError> for FrameError {
+    /// Errors that recreating the swapchain resolves become `RecreateNeeded`.
+    fn from(err: AcquireImageError) -> Self {
+        match err {
+            AcquireImageError::OutOfDate
+            | AcquireImageError::NotReady
+            | AcquireImageError::FullScreenExclusiveLost => Self::RecreateNeeded,
+            AcquireImageError::Swapchain(err) if err.is_retryable() => Self::RecreateNeeded,
+            err => Self::Acquire(err),
+        }
+    }
+}
+
+impl From<PresentError> for FrameError {
+    /// Errors that recreating the swapchain resolves become `RecreateNeeded`.
+    fn from(err: PresentError) -> Self {
+        match err {
+            PresentError::OutOfDate | PresentError::FullScreenExclusiveLost => Self::RecreateNeeded,
+            err => Self::Present(err),
+        }
+    }
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::RecreateNeeded => write!(f, "the swapchain has to be recreated"),
+            Self::Acquire(err) => write!(f, "failed to acquire the frame's image: {}", err),
+            Self::Submit(err) => write!(f, "failed to submit the frame: {}", err),
+            Self::Present(err) => write!(f, "failed to present the frame: {}", err),
+            Self::Wait(err) => write!(f, "failed to wait for an earlier frame: {}", err),
+            Self::WaitIdle(err) => write!(f, "failed to wait for the device: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for FrameError {}
@@ -0,0 +1,111 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::image::view::*;
+    pub use vulkano::render_pass::*;
+}
+
+/// Framebuffers created by `FramebufferCache::get_or_create`, keyed by the render pass and the
+/// attachment views.
+///
+/// The extent follows from the views, so new views, e.g. of a resized offscreen target, get new
+/// framebuffers. The entries keep their render pass and views alive, so their addresses cannot be
+/// reused while they are cached. The least recently used entry is dropped once the cache is full.
+pub struct FramebufferCache {
+    capacity: usize,
+    inner: Mutex<CacheInner>,
+}
+
+#[derive(Default)]
+struct CacheInner {
+    // Least recently used first.
+    entries: VecDeque<Arc<vulkano::Framebuffer>>,
+    hits: u64,
+    misses: u64,
+}
+
+/// Counters of a `FramebufferCache`, for debugging.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FramebufferCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+impl Default for FramebufferCache {
+    /// Room for a few render passes over the images of a swapchain and some offscreen targets.
+    fn default() -> Self {
+        Self::new(64)
+    }
+}
+
+impl FramebufferCache {
+    /// Keeps at most `capacity` framebuffers.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            inner: Mutex::new(CacheInner::default()),
+        }
+    }
+    /// The framebuffer for `render_pass` with `attachments`, created on the first call for them.
+    pub fn get_or_create(
+        &self,
+        render_pass: &Arc<vulkano::RenderPass>,
+        attachments: Vec<Arc<dyn vulkano::ImageViewAbstract>>,
+    ) -> Result<Arc<vulkano::Framebuffer>, vulkano::FramebufferCreationError> {
+        let mut inner = self.inner.lock().unwrap();
+        let position = inner.entries.iter().position(|framebuffer| {
+            Arc::ptr_eq(framebuffer.render_pass(), render_pass)
+                && framebuffer.attachments().len() == attachments.len()
+                && framebuffer
+                    .attachments()
+                    .iter()
+                    .zip(&attachments)
+                    .all(|(a, b)| same_view(a, b))
+        });
+        if let Some(position) = position {
+            inner.hits += 1;
+            let framebuffer = inner.entries.remove(position).unwrap();
+            inner.entries.push_back(framebuffer.clone());
+            return Ok(framebuffer);
+        }
+
+        inner.misses += 1;
+        let framebuffer = vulkano::Framebuffer::new(
+            render_pass.clone(),
+            vulkano::FramebufferCreateInfo {
+                attachments,
+                ..Default::default()
+            },
+        )?;
+        if inner.entries.len() == self.capacity {
+            inner.entries.pop_front();
+        }
+        inner.entries.push_back(framebuffer.clone());
+        Ok(framebuffer)
+    }
+    /// Drops all cached framebuffers, e.g. after the swapchain was recreated.
+    ///
+    /// Framebuffers still in use by command buffers stay alive until those are dropped.
+    pub fn invalidate(&self) {
+        self.inner.lock().unwrap().entries.clear();
+    }
+    pub fn stats(&self) -> FramebufferCacheStats {
+        let inner = self.inner.lock().unwrap();
+        FramebufferCacheStats {
+            hits: inner.hits,
+            misses: inner.misses,
+            entries: inner.entries.len(),
+        }
+    }
+}
+
+// Compares the addresses only, the vtables of the same view can differ between codegen units.
+fn same_view(
+    a: &Arc<dyn vulkano::ImageViewAbstract>,
+    b: &Arc<dyn vulkano::ImageViewAbstract>,
+) -> bool {
+    Arc::as_ptr(a) as *const () == Arc::as_ptr(b) as *const ()
+}
@@ -0,0 +1,99 @@
+#[cfg(any(debug_assertions, feature = "strict"))]
+use std::panic::Location;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Reports objects that are dropped without being consumed properly, naming where they were
+/// created.
+///
+/// The checks only exist in debug builds or with the `strict` feature, which also turns the
+/// reports into panics. Otherwise the guard is zero sized and does nothing.
+pub struct MisuseGuard {
+    #[cfg(any(debug_assertions, feature = "strict"))]
+    state: Option<GuardState>,
+}
+
+#[cfg(any(debug_assertions, feature = "strict"))]
+struct GuardState {
+    what: &'static str,
+    hint: &'static str,
+    location: &'static Location<'static>,
+    outstanding: Option<Arc<AtomicBool>>,
+}
+
+impl MisuseGuard {
+    /// `what` names the guarded object and `hint` explains how to consume it.
+    #[track_caller]
+    pub fn new(what: &'static str, hint: &'static str) -> Self {
+        Self::with_flag(what, hint, None)
+    }
+    /// Like `new`, but additionally keeps `outstanding` set until the guard is consumed or
+    /// dropped, so the owner of the flag can detect objects that are kept alive for too long.
+    #[track_caller]
+    pub fn with_flag(
+        what: &'static str,
+        hint: &'static str,
+        outstanding: Option<Arc<AtomicBool>>,
+    ) -> Self {
+        #[cfg(any(debug_assertions, feature = "strict"))]
+        {
+            if let Some(outstanding) = &outstanding {
+                outstanding.store(true, Ordering::SeqCst);
+            }
+            Self {
+                state: Some(GuardState {
+                    what,
+                    hint,
+                    location: Location::caller(),
+                    outstanding,
+                }),
+            }
+        }
+        #[cfg(not(any(debug_assertions, feature = "strict")))]
+        {
+            let _ = (what, hint, outstanding);
+            Self {}
+        }
+    }
+    /// Marks the guarded object as properly consumed.
+    pub fn consume(&mut self) {
+        #[cfg(any(debug_assertions, feature = "strict"))]
+        if let Some(state) = self.state.take() {
+            if let Some(outstanding) = state.outstanding {
+                outstanding.store(false, Ordering::SeqCst);
+            }
+        }
+    }
+}
+
+impl Drop for MisuseGuard {
+    fn drop(&mut self) {
+        #[cfg(any(debug_assertions, feature = "strict"))]
+        if let Some(state) = self.state.take() {
+            if let Some(outstanding) = &state.outstanding {
+                outstanding.store(false, Ordering::SeqCst);
+            }
+            report(&format!(
+                "{} created at {} was dropped without being consumed; {}",
+                state.what, state.location, state.hint
+            ));
+        }
+    }
+}
+
+/// Logs misuse of the API, or panics with the `strict` feature.
+#[cfg(any(debug_assertions, feature = "strict"))]
+pub(crate) fn report(message: &str) {
+    if cfg!(feature = "strict") {
+        if !std::thread::panicking() {
+            panic!("{}", message);
+        }
+    } else {
+        log::error!("{}", message);
+    }
+}
+
+/// Returns whether an object guarded with `flag` is still alive and unconsumed.
+pub(crate) fn is_outstanding(flag: &AtomicBool) -> bool {
+    cfg!(any(debug_assertions, feature = "strict")) && flag.load(Ordering::SeqCst)
+}
@@ -0,0 +1,262 @@
+use derive_more::*;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+
+use notify::Watcher;
+
+use super::{Device, GlslError, PipelineDescriptor, PipelineError, ShaderKind};
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::pipeline::GraphicsPipeline;
+    pub use vulkano::render_pass::Subpass;
+    pub use vulkano::shader::*;
+}
+
+/// GLSL file of a stage of a pipeline watched by a `ShaderWatcher`.
+#[derive(Clone, Debug)]
+pub struct ShaderSource {
+    pub path: PathBuf,
+    pub kind: ShaderKind,
+}
+
+impl ShaderSource {
+    pub fn new(path: impl Into<PathBuf>, kind: ShaderKind) -> Self {
+        Self {
+            path: path.into(),
+            kind,
+        }
+    }
+    pub fn vertex(path: impl Into<PathBuf>) -> Self {
+        Self::new(path, ShaderKind::Vertex)
+    }
+    pub fn fragment(path: impl Into<PathBuf>) -> Self {
+        Self::new(path, ShaderKind::Fragment)
+    }
+}
+
+/// A pipeline that is rebuilt whenever its shaders change on disk, see
+/// `ShaderWatcher::watch_pipeline`.
+///
+/// Clones share the pipeline, so the render loop can keep one and call `get` every frame.
+#[derive(Clone)]
+pub struct HotPipeline {
+    current: Arc<Mutex<Arc<vulkano::GraphicsPipeline>>>,
+}
+
+impl HotPipeline {
+    /// The pipeline built from the last shaders that compiled.
+    pub fn get(&self) -> Arc<vulkano::GraphicsPipeline> {
+        self.current.lock().unwrap().clone()
+    }
+}
+
+type Configure = Box<dyn for<'a> Fn(PipelineDescriptor<'a>) -> PipelineDescriptor<'a>>;
+
+// What a watched pipeline is built from.
+struct PipelineSource {
+    vertex: ShaderSource,
+    fragment: ShaderSource,
+    subpass: vulkano::Subpass,
+    configure: Configure,
+}
+
+impl PipelineSource {
+    fn build(&self, device: &Device) -> Result<Arc<vulkano::GraphicsPipeline>, HotReloadError> {
+        let vertex = compile(device, &self.vertex)?;
+        let fragment = compile(device, &self.fragment)?;
+        let desc = (self.configure)(PipelineDescriptor::new(
+            entry_point(&vertex, &self.vertex)?,
+            entry_point(&fragment, &self.fragment)?,
+        ));
+        Ok(device.create_graphics_pipeline(&desc, self.subpass.clone())?)
+    }
+    fn depends_on(&self, path: &Path) -> bool {
+        self.vertex.path == path || self.fragment.path == path
+    }
+}
+
+/// Rebuilds pipelines when the GLSL files of their shaders change, so shaders can be edited
+/// while the app is running.
+///
+/// Changes are only picked up by `poll_updates`, which is meant to be called at the start of a
+/// frame, so a pipeline never changes while a frame is recorded. A shader that fails to compile
+/// keeps the pipeline built before and is reported to the `on_error` callback.
+///
+/// Files included by the shaders are resolved relative to the including file, but only changes
+/// to the stage files themselves trigger a rebuild.
+pub struct ShaderWatcher {
+    watcher: notify::RecommendedWatcher,
+    events: mpsc::Receiver<notify::Result<notify::Event>>,
+    directories: HashSet<PathBuf>,
+    pipelines: Vec<(PipelineSource, HotPipeline)>,
+    on_error: Box<dyn FnMut(&HotReloadError)>,
+}
+
+impl ShaderWatcher {
+    pub fn new() -> Result<Self, HotReloadError> {
+        let (sender, events) = mpsc::channel();
+        Ok(Self {
+            watcher: notify::recommended_watcher(sender)?,
+            events,
+            directories: HashSet::new(),
+            pipelines: Vec::new(),
+            on_error: Box::new(|err| log::error!("Failed to reload shaders: {}", err)),
+        })
+    }
+    /// Replaces the callback errors while reloading are reported to, which logs them by default.
+    pub fn on_error(&mut self, on_error: impl FnMut(&HotReloadError) + 'static) {
+        self.on_error = Box::new(on_error);
+    }
+    /// Builds a pipeline from the GLSL files `vertex` and `fragment` and rebuilds it whenever
+    /// one of them changes.
+    ///
+    /// The descriptor the pipeline is built from starts out as `PipelineDescriptor::new` with
+    /// the compiled shaders and is then passed through `configure`, every time the pipeline is
+    /// rebuilt. Errors building the first pipeline are returned, as there is none to keep.
+    pub fn watch_pipeline<F>(
+        &mut self,
+        device: &Device,
+        vertex: ShaderSource,
+        fragment: ShaderSource,
+        subpass: vulkano::Subpass,
+        configure: F,
+    ) -> Result<HotPipeline, HotReloadError>
+    where
+        F: for<'a> Fn(PipelineDescriptor<'a>) -> PipelineDescriptor<'a> + 'static,
+    {
+        let source = PipelineSource {
+            vertex: self.watch_source(vertex)?,
+            fragment: self.watch_source(fragment)?,
+            subpass,
+            configure: Box::new(configure),
+        };
+        let pipeline = HotPipeline {
+            current: Arc::new(Mutex::new(source.build(device)?)),
+        };
+        self.pipelines.push((source, pipeline.clone()));
+        Ok(pipeline)
+    }
+    /// Rebuilds the pipelines whose shaders changed since the last call and swaps them in.
+    /// Returns the number of pipelines that were rebuilt.
+    pub fn poll_updates(&mut self, device: &Device) -> usize {
+        let mut changed = HashSet::new();
+        for event in self.events.try_iter() {
+            match event {
+                Ok(event) if event.kind.is_modify() || event.kind.is_create() => {
+                    changed.extend(event.paths);
+                }
+                Ok(_) => {}
+                Err(err) => (self.on_error)(&HotReloadError::Watch(err)),
+            }
+        }
+        if changed.is_empty() {
+            return 0;
+        }
+
+        let mut rebuilt = 0;
+        for (source, pipeline) in &self.pipelines {
+            if !changed.iter().any(|path| source.depends_on(path)) {
+                continue;
+            }
+            match source.build(device) {
+                Ok(rebuilt_pipeline) => {
+                    *pipeline.current.lock().unwrap() = rebuilt_pipeline;
+                    rebuilt += 1;
+                }
+                Err(err) => (self.on_error)(&err),
+            }
+        }
+        if rebuilt > 0 {
+            log::info!("Reloaded {} pipelines", rebuilt);
+        }
+        rebuilt
+    }
+    // Watches the directory of `source` rather than the file, as many editors save by replacing
+    // the file, which ends watches on the file itself.
+    fn watch_source(&mut self, source: ShaderSource) -> Result<ShaderSource, HotReloadError> {
+        let path = fs::canonicalize(&source.path).map_err(|err| HotReloadError::Io {
+            path: source.path.clone(),
+            err,
+        })?;
+        let directory = path.parent().unwrap_or(&path).to_path_buf();
+        if !self.directories.contains(&directory) {
+            self.watcher
+                .watch(&directory, notify::RecursiveMode::NonRecursive)?;
+            self.directories.insert(directory);
+        }
+        Ok(ShaderSource { path, ..source })
+    }
+}
+
+fn compile(
+    device: &Device,
+    source: &ShaderSource,
+) -> Result<Arc<vulkano::ShaderModule>, HotReloadError> {
+    let path = &source.path;
+    let code = fs::read_to_string(path).map_err(|err| HotReloadError::Io {
+        path: path.clone(),
+        err,
+    })?;
+    let file_name = path.to_string_lossy();
+    device
+        .create_shader_glsl_with_includes(
+            &code,
+            source.kind,
+            "main",
+            &file_name,
+            |requested, requesting| {
+                let directory = Path::new(requesting).parent().unwrap_or(Path::new(""));
+                fs::read_to_string(directory.join(requested))
+                    .map_err(|err| format!("cannot include {}: {}", requested, err))
+            },
+        )
+        .map_err(|err| HotReloadError::Glsl {
+            path: path.clone(),
+            err,
+        })
+}
+
+fn entry_point<'a>(
+    module: &'a vulkano::ShaderModule,
+    source: &ShaderSource,
+) -> Result<vulkano::EntryPoint<'a>, HotReloadError> {
+    module
+        .entry_point("main")
+        .ok_or_else(|| HotReloadError::NoEntryPoint(source.path.clone()))
+}
+
+#[derive(Debug, From)]
+pub enum HotReloadError {
+    /// Watching the files failed.
+    Watch(notify::Error),
+    /// A shader file could not be read.
+    #[from(ignore)]
+    Io { path: PathBuf, err: io::Error },
+    /// A shader failed to compile.
+    #[from(ignore)]
+    Glsl { path: PathBuf, err: GlslError },
+    /// A shader has no `main` entry point.
+    #[from(ignore)]
+    NoEntryPoint(PathBuf),
+    /// The shaders compiled, but the pipeline could not be built from them.
+    Pipeline(PipelineError),
+}
+
+impl fmt::Display for HotReloadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Watch(err) => write!(f, "failed to watch the shader files: {}", err),
+            Self::Io { path, err } => write!(f, "failed to read {:?}: {}", path, err),
+            Self::Glsl { path, err } => write!(f, "{:?}: {}", path, err),
+            Self::NoEntryPoint(path) => write!(f, "{:?} has no `main` entry point", path),
+            Self::Pipeline(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for HotReloadError {}
@@ -1,41 +1,160 @@
 use derive_more::*;
+use std::fmt;
 use std::sync::Arc;
 
+use super::{Device, DeviceDescriptor, Queue};
+
 // Getting rust analyzer problems when not defining the module here again.
 mod vulkano {
     pub use vulkano::device::physical::*;
     pub use vulkano::device::*;
-    pub use vulkano::image::view::*;
     pub use vulkano::image::*;
     pub use vulkano::instance::*;
-    pub use vulkano::pipeline::graphics::viewport::*;
-    pub use vulkano::render_pass::*;
     pub use vulkano::swapchain::*;
     pub use vulkano::*;
 }
 
-#[derive(Deref, DerefMut)]
+/// Cheap to clone, all clones share the same vulkano instance.
+#[derive(Clone, Deref, DerefMut)]
 pub struct Instance {
     instance: Arc<vulkano::Instance>,
 }
 
+impl From<Instance> for Arc<vulkano::Instance> {
+    fn from(instance: Instance) -> Self {
+        instance.instance
+    }
+}
+
+impl From<&Instance> for Arc<vulkano::Instance> {
+    fn from(instance: &Instance) -> Self {
+        instance.instance.clone()
+    }
+}
+
 impl Instance {
     pub fn new(info: vulkano::InstanceCreateInfo) -> Self{
         Self{
             instance: vulkano::Instance::new(info).unwrap(),
         }
     }
-    pub fn request_adapter<'a, 'ad, W>(&'a self, desc: &AdapterDescriptor<'ad, W>) -> Adapter<'a> {
-        let (physical_device, queue_family) = vulkano::PhysicalDevice::enumerate(&self.instance)
-            .filter(|&p| {
-                p.supported_extensions()
-                    .is_superset_of(&desc.device_extensions)
+    pub fn request_adapter<'ad, W>(
+        &self,
+        desc: &AdapterDescriptor<'ad, W>,
+    ) -> Result<Adapter, RequestAdapterError> {
+        self.select_adapter(desc, false)
+    }
+    /// Like `request_adapter`, but logs every candidate device and which of the filters it passed
+    /// at debug level.
+    pub fn request_adapter_verbose<'ad, W>(
+        &self,
+        desc: &AdapterDescriptor<'ad, W>,
+    ) -> Result<Adapter, RequestAdapterError> {
+        self.select_adapter(desc, true)
+    }
+    fn select_adapter<'ad, W>(
+        &self,
+        desc: &AdapterDescriptor<'ad, W>,
+        verbose: bool,
+    ) -> Result<Adapter, RequestAdapterError> {
+        let report = |p: &vulkano::PhysicalDevice, filter: &str, passed: bool| {
+            if verbose {
+                log::debug!(
+                    "Device {} {} the {} filter",
+                    p.properties().device_name,
+                    if passed { "passed" } else { "failed" },
+                    filter,
+                );
+            }
+            passed
+        };
+
+        let candidates = vulkano::PhysicalDevice::enumerate(&self.instance).collect::<Vec<_>>();
+        if candidates.is_empty() {
+            return Err(RequestAdapterError::NoAdapters);
+        }
+        if verbose {
+            for p in &candidates {
+                log::debug!(
+                    "Candidate device: {} (type: {:?})",
+                    p.properties().device_name,
+                    p.properties().device_type,
+                );
+            }
+        }
+
+        let (candidates, rejected) = candidates
+            .into_iter()
+            .partition::<Vec<_>, _>(|p| {
+                report(p, "device type", desc.allowed_device_type(p.properties().device_type))
+            });
+        if candidates.is_empty() {
+            return Err(RequestAdapterError::RejectedDeviceTypes(
+                rejected
+                    .iter()
+                    .map(|p| p.properties().device_type)
+                    .collect(),
+            ));
+        }
+
+        let candidates = candidates
+            .into_iter()
+            .filter(|p| {
+                report(
+                    p,
+                    "extension",
+                    p.supported_extensions()
+                        .is_superset_of(&desc.device_extensions),
+                )
             })
+            .collect::<Vec<_>>();
+        if candidates.is_empty() && desc.requires_ray_tracing() {
+            return Err(RequestAdapterError::NoRayTracingAdapter);
+        }
+        if candidates.is_empty() {
+            return Err(RequestAdapterError::MissingExtensions(Box::new(
+                desc.device_extensions,
+            )));
+        }
+
+        let candidates = candidates
+            .into_iter()
+            .filter(|p| {
+                report(
+                    p,
+                    "feature",
+                    p.supported_features().is_superset_of(&desc.required_features),
+                )
+            })
+            .collect::<Vec<_>>();
+        if candidates.is_empty() && desc.requires_ray_tracing() {
+            return Err(RequestAdapterError::NoRayTracingAdapter);
+        }
+        if candidates.is_empty() {
+            return Err(RequestAdapterError::MissingFeatures(Box::new(
+                desc.required_features.clone(),
+            )));
+        }
+
+        let candidates = candidates
+            .into_iter()
             .filter_map(|p| {
-                p.queue_families()
-                    .find(|&q| desc.compatible(&q))
-                    .map(|q| (p, q))
+                let queue_families = desc.queue_families(&p);
+                report(
+                    &p,
+                    "queue family and surface support",
+                    queue_families.is_some(),
+                );
+                queue_families.map(|q| (p, q))
             })
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            return Err(RequestAdapterError::NoCompatibleQueueFamily);
+        }
+
+        let (physical_device, (queue_family, present_family)) = candidates
+            .into_iter()
+            .filter(|(p, _)| report(p, "vendor", desc.vendor_filter.allows(p)))
             .min_by_key(|(p, _)| match p.properties().device_type {
                 vulkano::PhysicalDeviceType::DiscreteGpu => 0,
                 vulkano::PhysicalDeviceType::IntegratedGpu => 1,
@@ -43,27 +162,274 @@ impl Instance {
                 vulkano::PhysicalDeviceType::Cpu => 3,
                 vulkano::PhysicalDeviceType::Other => 4,
             })
-            .unwrap();
-        println!(
+            .ok_or(RequestAdapterError::RejectedByVendorFilter)?;
+        log::info!(
             "Using device: {} (type: {:?})",
             physical_device.properties().device_name,
             physical_device.properties().device_type,
         );
+        if let Some(present_family) = present_family {
+            log::info!(
+                "Using queue family {} for rendering and {} for presentation",
+                queue_family.id(),
+                present_family.id(),
+            );
+        }
 
-        Adapter {
-            physical_device,
-            queue_family,
-            device_extensions: desc.device_extensions,
+        Ok(Adapter {
+            instance: self.instance.clone(),
+            physical_device_index: physical_device.index(),
+            queue_family_id: queue_family.id(),
+            device_extensions: desc.device_extensions.union(
+                &desc
+                    .optional_extensions
+                    .intersection(physical_device.supported_extensions()),
+            ),
+            required_features: desc.required_features.clone(),
+            present_family_id: desc
+                .supports_surface
+                .map(|_| present_family.unwrap_or(queue_family).id()),
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum RequestAdapterError {
+    /// The instance does not expose any physical devices.
+    NoAdapters,
+    /// All physical devices were rejected because of their device type.
+    RejectedDeviceTypes(Vec<vulkano::PhysicalDeviceType>),
+    /// No physical device supports all of the requested device extensions.
+    ///
+    /// Boxed like the features, both are large enough to bloat every `Result` otherwise.
+    MissingExtensions(Box<vulkano::DeviceExtensions>),
+    /// No physical device supports all of the required features.
+    MissingFeatures(Box<vulkano::Features>),
+    /// The descriptor asks for ray tracing, e.g. `AdapterDescriptor::ray_tracing`, but no
+    /// physical device supports its extensions and features.
+    NoRayTracingAdapter,
+    /// No physical device has a queue family matching the descriptor.
+    NoCompatibleQueueFamily,
+    /// All remaining physical devices were rejected by the vendor filter.
+    RejectedByVendorFilter,
+}
+
+impl fmt::Display for RequestAdapterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoAdapters => write!(f, "no physical devices are available"),
+            Self::RejectedDeviceTypes(types) => write!(
+                f,
+                "all adapters were rejected because of their device type {:?}",
+                types
+            ),
+            Self::MissingExtensions(extensions) => write!(
+                f,
+                "no adapter supports the required device extensions {:?}",
+                extensions
+            ),
+            Self::MissingFeatures(features) => write!(
+                f,
+                "no adapter supports the required features {:?}",
+                feature_names(features)
+            ),
+            Self::NoRayTracingAdapter => write!(f, "no ray tracing capable adapter is available"),
+            Self::NoCompatibleQueueFamily => {
+                write!(f, "no adapter has a queue family compatible with the descriptor")
+            }
+            Self::RejectedByVendorFilter => {
+                write!(f, "all compatible adapters were rejected by the vendor filter")
+            }
         }
     }
 }
 
+impl std::error::Error for RequestAdapterError {}
+
+// vulkano does not expose the names of the features, so the fields of `Features` are listed here.
+macro_rules! feature_names {
+    ($($name:ident),* $(,)?) => {
+        /// Lists the names of all features that are enabled in `features`.
+        pub(crate) fn feature_names(features: &vulkano::Features) -> Vec<String> {
+            let mut names = Vec::new();
+            $(
+                if features.$name {
+                    names.push(stringify!($name).to_string());
+                }
+            )*
+            names
+        }
+    };
+}
+
+feature_names!(
+    acceleration_structure, acceleration_structure_capture_replay,
+    acceleration_structure_host_commands, acceleration_structure_indirect_build,
+    advanced_blend_coherent_operations, alpha_to_one, attachment_fragment_shading_rate,
+    border_color_swizzle, border_color_swizzle_from_image, bresenham_lines, buffer_device_address,
+    buffer_device_address_capture_replay, buffer_device_address_multi_device, color_write_enable,
+    compute_derivative_group_linear, compute_derivative_group_quads, compute_full_subgroups,
+    conditional_rendering, constant_alpha_color_blend_factors, cooperative_matrix,
+    cooperative_matrix_robust_buffer_access, corner_sampled_image, coverage_reduction_mode,
+    custom_border_color_without_format, custom_border_colors, decode_mode_shared_exponent,
+    dedicated_allocation_image_aliasing, depth_bias_clamp, depth_bounds, depth_clamp,
+    depth_clip_control, depth_clip_enable,
+    descriptor_binding_acceleration_structure_update_after_bind,
+    descriptor_binding_inline_uniform_block_update_after_bind, descriptor_binding_partially_bound,
+    descriptor_binding_sampled_image_update_after_bind,
+    descriptor_binding_storage_buffer_update_after_bind,
+    descriptor_binding_storage_image_update_after_bind,
+    descriptor_binding_storage_texel_buffer_update_after_bind,
+    descriptor_binding_uniform_buffer_update_after_bind,
+    descriptor_binding_uniform_texel_buffer_update_after_bind,
+    descriptor_binding_update_unused_while_pending, descriptor_binding_variable_descriptor_count,
+    descriptor_indexing, device_coherent_memory, device_generated_commands, device_memory_report,
+    diagnostics_config, draw_indirect_count, draw_indirect_first_instance, dual_src_blend,
+    dynamic_rendering, events, exclusive_scissor, extended_dynamic_state, extended_dynamic_state2,
+    extended_dynamic_state2_logic_op, extended_dynamic_state2_patch_control_points,
+    external_memory_rdma, fill_mode_non_solid, format_a4b4g4r4, format_a4r4g4b4,
+    format_rgba10x6_without_y_cb_cr_sampler, fragment_density_map, fragment_density_map_deferred,
+    fragment_density_map_dynamic, fragment_density_map_non_subsampled_images,
+    fragment_density_map_offset, fragment_shader_barycentric, fragment_shader_pixel_interlock,
+    fragment_shader_sample_interlock, fragment_shader_shading_rate_interlock,
+    fragment_shading_rate_enums, fragment_stores_and_atomics, full_draw_index_uint32,
+    geometry_shader, geometry_streams, global_priority_query, host_query_reset, image_cube_array,
+    image_footprint, image_view2_d_on3_d_image, image_view_format_reinterpretation,
+    image_view_format_swizzle, imageless_framebuffer, independent_blend, index_type_uint8,
+    inherited_conditional_rendering, inherited_queries, inherited_viewport_scissor2_d,
+    inline_uniform_block, invocation_mask, large_points, linear_color_attachment, logic_op,
+    maintenance4, memory_priority, mesh_shader, min_lod, multi_draw, multi_draw_indirect,
+    multi_viewport, multisample_array_image, multiview, multiview_geometry_shader,
+    multiview_tessellation_shader, mutable_comparison_samplers, mutable_descriptor_type,
+    no_invocation_fragment_shading_rates, null_descriptor, occlusion_query_precise,
+    pageable_device_local_memory, performance_counter_multiple_query_pools,
+    performance_counter_query_pools, pipeline_creation_cache_control, pipeline_executable_info,
+    pipeline_fragment_shading_rate, pipeline_statistics_query, point_polygons, present_id,
+    present_wait, primitive_fragment_shading_rate, primitive_topology_list_restart,
+    primitive_topology_patch_list_restart, private_data, protected_memory, provoking_vertex_last,
+    rasterization_order_color_attachment_access, rasterization_order_depth_attachment_access,
+    rasterization_order_stencil_attachment_access, ray_query, ray_tracing_motion_blur,
+    ray_tracing_motion_blur_pipeline_trace_rays_indirect, ray_tracing_pipeline,
+    ray_tracing_pipeline_shader_group_handle_capture_replay,
+    ray_tracing_pipeline_shader_group_handle_capture_replay_mixed,
+    ray_tracing_pipeline_trace_rays_indirect, ray_traversal_primitive_culling, rectangular_lines,
+    representative_fragment_test, robust_buffer_access, robust_buffer_access2, robust_image_access,
+    robust_image_access2, runtime_descriptor_array, sample_rate_shading, sampler_anisotropy,
+    sampler_filter_minmax, sampler_mip_lod_bias, sampler_mirror_clamp_to_edge,
+    sampler_ycbcr_conversion, scalar_block_layout, separate_depth_stencil_layouts,
+    separate_stencil_mask_ref, shader_buffer_float16_atomic_add,
+    shader_buffer_float16_atomic_min_max, shader_buffer_float16_atomics,
+    shader_buffer_float32_atomic_add, shader_buffer_float32_atomic_min_max,
+    shader_buffer_float32_atomics, shader_buffer_float64_atomic_add,
+    shader_buffer_float64_atomic_min_max, shader_buffer_float64_atomics,
+    shader_buffer_int64_atomics, shader_clip_distance, shader_cull_distance,
+    shader_demote_to_helper_invocation, shader_device_clock, shader_draw_parameters,
+    shader_float16, shader_float64, shader_image_float32_atomic_add,
+    shader_image_float32_atomic_min_max, shader_image_float32_atomics,
+    shader_image_gather_extended, shader_image_int64_atomics,
+    shader_input_attachment_array_dynamic_indexing,
+    shader_input_attachment_array_non_uniform_indexing, shader_int16, shader_int64, shader_int8,
+    shader_integer_dot_product, shader_integer_functions2, shader_output_layer,
+    shader_output_viewport_index, shader_resource_min_lod, shader_resource_residency,
+    shader_sample_rate_interpolation_functions, shader_sampled_image_array_dynamic_indexing,
+    shader_sampled_image_array_non_uniform_indexing, shader_shared_float16_atomic_add,
+    shader_shared_float16_atomic_min_max, shader_shared_float16_atomics,
+    shader_shared_float32_atomic_add, shader_shared_float32_atomic_min_max,
+    shader_shared_float32_atomics, shader_shared_float64_atomic_add,
+    shader_shared_float64_atomic_min_max, shader_shared_float64_atomics,
+    shader_shared_int64_atomics, shader_sm_builtins, shader_storage_buffer_array_dynamic_indexing,
+    shader_storage_buffer_array_non_uniform_indexing, shader_storage_image_array_dynamic_indexing,
+    shader_storage_image_array_non_uniform_indexing, shader_storage_image_extended_formats,
+    shader_storage_image_multisample, shader_storage_image_read_without_format,
+    shader_storage_image_write_without_format, shader_storage_texel_buffer_array_dynamic_indexing,
+    shader_storage_texel_buffer_array_non_uniform_indexing, shader_subgroup_clock,
+    shader_subgroup_extended_types, shader_subgroup_uniform_control_flow,
+    shader_terminate_invocation, shader_tessellation_and_geometry_point_size,
+    shader_uniform_buffer_array_dynamic_indexing, shader_uniform_buffer_array_non_uniform_indexing,
+    shader_uniform_texel_buffer_array_dynamic_indexing,
+    shader_uniform_texel_buffer_array_non_uniform_indexing,
+    shader_zero_initialize_workgroup_memory, shading_rate_coarse_sample_order, shading_rate_image,
+    smooth_lines, sparse_binding, sparse_image_float32_atomic_add,
+    sparse_image_float32_atomic_min_max, sparse_image_float32_atomics, sparse_image_int64_atomics,
+    sparse_residency16_samples, sparse_residency2_samples, sparse_residency4_samples,
+    sparse_residency8_samples, sparse_residency_aliased, sparse_residency_buffer,
+    sparse_residency_image2_d, sparse_residency_image3_d, stippled_bresenham_lines,
+    stippled_rectangular_lines, stippled_smooth_lines, storage_buffer16_bit_access,
+    storage_buffer8_bit_access, storage_input_output16, storage_push_constant16,
+    storage_push_constant8, subgroup_broadcast_dynamic_id, subgroup_size_control, subpass_shading,
+    supersample_fragment_shading_rates, synchronization2, task_shader, tessellation_isolines,
+    tessellation_point_mode, tessellation_shader, texel_buffer_alignment,
+    texture_compression_astc_hdr, texture_compression_astc_ldr, texture_compression_bc,
+    texture_compression_etc2, timeline_semaphore, transform_feedback,
+    transform_feedback_preserves_provoking_vertex, triangle_fans,
+    uniform_and_storage_buffer16_bit_access, uniform_and_storage_buffer8_bit_access,
+    uniform_buffer_standard_layout, variable_multisample_rate, variable_pointers,
+    variable_pointers_storage_buffer, vertex_attribute_access_beyond_stride,
+    vertex_attribute_instance_rate_divisor, vertex_attribute_instance_rate_zero_divisor,
+    vertex_input_dynamic_state, vertex_pipeline_stores_and_atomics, vulkan_memory_model,
+    vulkan_memory_model_availability_visibility_chains, vulkan_memory_model_device_scope,
+    wide_lines, workgroup_memory_explicit_layout, workgroup_memory_explicit_layout16_bit_access,
+    workgroup_memory_explicit_layout8_bit_access,
+    workgroup_memory_explicit_layout_scalar_block_layout, ycbcr2plane444_formats,
+    ycbcr_image_arrays,
+);
+
+/// Returns the features enabled in either `a` or `b`.
+pub(crate) fn features_union(a: &vulkano::Features, b: &vulkano::Features) -> vulkano::Features {
+    let all = vulkano::Features::all();
+    all.difference(&all.difference(a).intersection(&all.difference(b)))
+}
 
 pub struct AdapterDescriptor<'ad, W> {
     pub device_extensions: vulkano::DeviceExtensions,
+    /// Extensions enabled on the device if the adapter supports them, without rejecting adapters
+    /// that do not.
+    pub optional_extensions: vulkano::DeviceExtensions,
+    /// Features the adapter has to support. They are enabled automatically by
+    /// `Adapter::request_device`.
+    pub required_features: vulkano::Features,
     pub supports_graphics: bool,
     pub supports_compute: bool,
     pub supports_surface: Option<&'ad vulkano::Surface<W>>,
+    /// Fall back to a separate queue family for presentation if no family supports both the
+    /// surface and the other requirements.
+    pub allow_separate_present_queue: bool,
+    /// Skip CPU (software) implementations such as llvmpipe.
+    pub forbid_cpu_devices: bool,
+    /// Only consider CPU (software) implementations such as lavapipe.
+    pub allow_only_cpu: bool,
+    /// Applied after the extension, feature and queue family checks.
+    pub vendor_filter: VendorFilter,
+}
+
+/// Filters adapters by their vendor and driver version, e.g. to blacklist broken drivers.
+#[derive(Clone, Debug, Default)]
+pub struct VendorFilter {
+    /// If not empty, only adapters of these vendors are considered.
+    pub allowed_vendor_ids: Vec<u32>,
+    pub denied_vendor_ids: Vec<u32>,
+    /// Minimum driver version, encoded the way the vendor reports it.
+    pub min_driver_version: Option<u32>,
+}
+
+impl VendorFilter {
+    pub fn allows(&self, physical_device: &vulkano::PhysicalDevice) -> bool {
+        let properties = physical_device.properties();
+        if !self.allowed_vendor_ids.is_empty()
+            && !self.allowed_vendor_ids.contains(&properties.vendor_id)
+        {
+            return false;
+        }
+        if self.denied_vendor_ids.contains(&properties.vendor_id) {
+            return false;
+        }
+        if let Some(min_driver_version) = self.min_driver_version {
+            if properties.driver_version < min_driver_version {
+                return false;
+            }
+        }
+        true
+    }
 }
 
 impl<'ad, W> AdapterDescriptor<'ad, W> {
@@ -74,13 +440,46 @@ impl<'ad, W> AdapterDescriptor<'ad, W> {
         if self.supports_compute && !queue_family.supports_compute() {
             return false;
         }
-        if let Some(surface) = self.supports_surface {
-            if !queue_family.supports_surface(&surface).unwrap_or(false) {
-                return false;
-            }
-        }
 
-        return true;
+        true
+    }
+    fn presents(&self, queue_family: &vulkano::QueueFamily) -> bool {
+        match self.supports_surface {
+            Some(surface) => queue_family.supports_surface(surface).unwrap_or(false),
+            None => true,
+        }
+    }
+    /// Queue family for the work and, if it cannot present, a separate family for presentation.
+    fn queue_families<'p>(
+        &self,
+        physical_device: &vulkano::PhysicalDevice<'p>,
+    ) -> Option<(vulkano::QueueFamily<'p>, Option<vulkano::QueueFamily<'p>>)> {
+        let single = physical_device
+            .queue_families()
+            .find(|q| self.compatible(q) && self.presents(q));
+        if let Some(queue_family) = single {
+            return Some((queue_family, None));
+        }
+        if !self.allow_separate_present_queue {
+            return None;
+        }
+        let queue_family = physical_device.queue_families().find(|q| self.compatible(q))?;
+        let present_family = physical_device.queue_families().find(|q| self.presents(q))?;
+        Some((queue_family, Some(present_family)))
+    }
+    // Missing extensions or features are reported as `NoRayTracingAdapter` then.
+    fn requires_ray_tracing(&self) -> bool {
+        self.device_extensions.khr_ray_tracing_pipeline || self.required_features.ray_tracing_pipeline
+    }
+    fn allowed_device_type(&self, device_type: vulkano::PhysicalDeviceType) -> bool {
+        let is_cpu = device_type == vulkano::PhysicalDeviceType::Cpu;
+        if self.forbid_cpu_devices && is_cpu {
+            return false;
+        }
+        if self.allow_only_cpu && !is_cpu {
+            return false;
+        }
+        true
     }
     pub fn graphics() -> Self{
         AdapterDescriptor{
@@ -88,70 +487,518 @@ impl<'ad, W> AdapterDescriptor<'ad, W> {
                 khr_swapchain: true,
                 ..vulkano::DeviceExtensions::none()
             },
+            // Lets `SurfaceImage::present_with_regions` pass the changed regions on.
+            optional_extensions: vulkano::DeviceExtensions{
+                khr_incremental_present: true,
+                ..vulkano::DeviceExtensions::none()
+            },
+            required_features: vulkano::Features::none(),
             supports_graphics: true,
             supports_surface: None,
+            allow_separate_present_queue: true,
             supports_compute: false,
+            forbid_cpu_devices: false,
+            allow_only_cpu: false,
+            vendor_filter: VendorFilter::default(),
+        }
+    }
+    /// Graphics adapter that additionally supports hardware ray tracing.
+    pub fn ray_tracing() -> Self {
+        let graphics = Self::graphics();
+        AdapterDescriptor {
+            device_extensions: vulkano::DeviceExtensions {
+                khr_acceleration_structure: true,
+                khr_ray_tracing_pipeline: true,
+                khr_deferred_host_operations: true,
+                ..graphics.device_extensions
+            },
+            required_features: vulkano::Features {
+                buffer_device_address: true,
+                acceleration_structure: true,
+                ray_tracing_pipeline: true,
+                ..vulkano::Features::none()
+            },
+            ..graphics
         }
     }
 }
 
-pub struct Adapter<'a> {
-    pub physical_device: vulkano::PhysicalDevice<'a>,
-    pub queue_family: vulkano::QueueFamily<'a>,
+/// Physical device and queue family selected by `Instance::request_adapter`.
+///
+/// Only the indices are stored, so the adapter does not borrow from the instance.
+#[derive(Clone)]
+pub struct Adapter {
+    instance: Arc<vulkano::Instance>,
+    physical_device_index: usize,
+    queue_family_id: u32,
     device_extensions: vulkano::DeviceExtensions,
+    required_features: vulkano::Features,
+    /// Family checked against the surface during selection. Usually the same as
+    /// `queue_family_id`.
+    present_family_id: Option<u32>,
 }
 
-impl<'a> Adapter<'a> {
+impl Adapter {
+    pub fn physical_device(&self) -> vulkano::PhysicalDevice<'_> {
+        vulkano::PhysicalDevice::from_index(&self.instance, self.physical_device_index).unwrap()
+    }
+    pub fn queue_family(&self) -> vulkano::QueueFamily<'_> {
+        self.physical_device()
+            .queue_family_by_id(self.queue_family_id)
+            .unwrap()
+    }
+    /// Queue family used for presentation, `None` if the adapter was not selected for a surface.
+    pub fn present_queue_family(&self) -> Option<vulkano::QueueFamily<'_>> {
+        self.physical_device()
+            .queue_family_by_id(self.present_family_id?)
+    }
+    /// Whether presentation needs a queue from a different family than `queue_family`.
+    pub fn has_separate_present_queue(&self) -> bool {
+        self.present_family_id
+            .is_some_and(|id| id != self.queue_family_id)
+    }
     pub fn request_device(
         &self,
         features: vulkano::Features,
-    ) -> (Arc<vulkano::Device>, Arc<vulkano::Queue>) {
-        let (device, mut queues) = vulkano::Device::new(
+    ) -> Result<Device, RequestDeviceError> {
+        self.request_device_with(&DeviceDescriptor {
+            features,
+            ..Default::default()
+        })
+    }
+    /// Creates a device with `desc.queue_count` queues from the adapter's queue family, plus a
+    /// queue from the present family if the adapter uses a separate one.
+    pub fn request_device_with(
+        &self,
+        desc: &DeviceDescriptor,
+    ) -> Result<Device, RequestDeviceError> {
+        let physical_device = self.physical_device();
+        let enabled_features = features_union(&desc.features, &self.required_features);
+        let missing_features = enabled_features.difference(physical_device.supported_features());
+        if missing_features != vulkano::Features::none() {
+            return Err(RequestDeviceError::FeatureNotPresent(feature_names(
+                &missing_features,
+            )));
+        }
+        let extensions = self.device_extensions.union(&desc.extra_extensions);
+        let missing_extensions = extensions.difference(physical_device.supported_extensions());
+        if missing_extensions != vulkano::DeviceExtensions::none() {
+            return Err(RequestDeviceError::ExtensionNotPresent(Box::new(
+                missing_extensions,
+            )));
+        }
+        let available = self.queue_family().queues_count() as u32;
+        if desc.queue_count == 0 || desc.queue_count > available {
+            return Err(RequestDeviceError::TooManyQueues {
+                requested: desc.queue_count,
+                available,
+            });
+        }
+
+        let mut queue_create_infos = vec![vulkano::QueueCreateInfo {
+            queues: desc.priorities(),
+            ..vulkano::QueueCreateInfo::family(self.queue_family())
+        }];
+        if self.has_separate_present_queue() {
+            queue_create_infos.push(vulkano::QueueCreateInfo::family(
+                self.present_queue_family().unwrap(),
+            ));
+        }
+
+        let (device, queues) = vulkano::Device::new(
             // Which physical device to connect to.
-            self.physical_device,
+            physical_device,
             vulkano::DeviceCreateInfo {
                 // A list of optional features and extensions that our program needs to work correctly.
                 // Some parts of the Vulkan specs are optional and must be enabled manually at device
                 // creation. In this example the only thing we are going to need is the `khr_swapchain`
                 // extension that allows us to draw to a window.
-                enabled_extensions: self
-                    .physical_device
+                enabled_extensions: physical_device
                     // Some devices require certain extensions to be enabled if they are present
                     // (e.g. `khr_portability_subset`). We add them to the device extensions that we're
                     // going to enable.
                     .required_extensions()
-                    .union(&self.device_extensions),
+                    .union(&extensions),
 
-                // The list of queues that we are going to use. Here we only use one queue, from the
-                // previously chosen queue family.
-                queue_create_infos: vec![vulkano::QueueCreateInfo::family(self.queue_family)],
+                // The list of queues that we are going to use, from the previously chosen queue
+                // family and the present family if it differs.
+                queue_create_infos,
 
-                enabled_features: features,
+                enabled_features,
 
                 ..Default::default()
             },
+        )?;
+        let device = Device::new(
+            device,
+            queues.map(Queue::new).collect(),
+            self.present_family_id,
+        );
+        if let Some(label) = &desc.label {
+            log::info!("Created device {}", label);
+            device.set_device_name(label);
+        }
+        Ok(device)
+    }
+    /// Plain description of the adapter, e.g. for about dialogs or crash reports.
+    pub fn info(&self) -> AdapterInfo {
+        let properties = self.physical_device().properties();
+        AdapterInfo {
+            name: properties.device_name.clone(),
+            device_type: properties.device_type,
+            vendor_id: properties.vendor_id,
+            device_id: properties.device_id,
+            driver_version: properties.driver_version,
+            api_version: self.physical_device().api_version(),
+        }
+    }
+    /// Whether the adapter's queue family can present to `surface` and the surface reports at
+    /// least one supported format.
+    pub fn is_surface_supported<W>(&self, surface: &vulkano::Surface<W>) -> bool {
+        let family = self.present_queue_family().unwrap_or_else(|| self.queue_family());
+        if !family.supports_surface(surface).unwrap_or(false) {
+            return false;
+        }
+        self.surface_formats(surface)
+            .map(|formats| !formats.is_empty())
+            .unwrap_or(false)
+    }
+    /// Formats and color spaces the surface supports on this adapter, in the order the driver
+    /// reports them.
+    pub fn surface_formats<W>(
+        &self,
+        surface: &vulkano::Surface<W>,
+    ) -> Result<Vec<(vulkano::format::Format, vulkano::ColorSpace)>, vulkano::SurfacePropertiesError>
+    {
+        self.physical_device()
+            .surface_formats(surface, Default::default())
+    }
+    pub fn surface_present_modes<W>(
+        &self,
+        surface: &vulkano::Surface<W>,
+    ) -> Result<Vec<vulkano::PresentMode>, vulkano::SurfacePropertiesError> {
+        Ok(self
+            .physical_device()
+            .surface_present_modes(surface)?
+            .collect())
+    }
+    /// Image count limits, current extent and supported usage of swapchains for the surface.
+    pub fn surface_capabilities<W>(
+        &self,
+        surface: &vulkano::Surface<W>,
+    ) -> Result<vulkano::SurfaceCapabilities, vulkano::SurfacePropertiesError> {
+        self.physical_device()
+            .surface_capabilities(surface, Default::default())
+    }
+    /// Whether optimally tiled images of `format` can be created with all of the given `usage`
+    /// flags.
+    pub fn supports_format(&self, format: vulkano::format::Format, usage: vulkano::ImageUsage) -> bool {
+        let features = self
+            .physical_device()
+            .format_properties(format)
+            .optimal_tiling_features;
+        let required = [
+            (usage.transfer_source, features.transfer_src),
+            (usage.transfer_destination, features.transfer_dst),
+            (usage.sampled, features.sampled_image),
+            (usage.storage, features.storage_image),
+            (usage.color_attachment, features.color_attachment),
+            (usage.depth_stencil_attachment, features.depth_stencil_attachment),
+            (
+                usage.input_attachment,
+                features.color_attachment || features.depth_stencil_attachment,
+            ),
+        ];
+        required
+            .iter()
+            .all(|&(requested, supported)| !requested || supported)
+    }
+    /// Returns the first of `candidates` that supports `usage`, e.g. to pick a depth format from
+    /// `[D32_SFLOAT, D24_UNORM_S8_UINT, D16_UNORM]`.
+    pub fn find_supported_format(
+        &self,
+        candidates: &[vulkano::format::Format],
+        usage: vulkano::ImageUsage,
+    ) -> Option<vulkano::format::Format> {
+        candidates
+            .iter()
+            .copied()
+            .find(|&format| self.supports_format(format, usage))
+    }
+    pub fn memory_info(&self) -> Vec<MemoryHeapInfo> {
+        let physical_device = self.physical_device();
+        physical_device
+            .memory_heaps()
+            .map(|heap| MemoryHeapInfo {
+                size: heap.size(),
+                device_local: heap.is_device_local(),
+                host_visible: physical_device
+                    .memory_types()
+                    .any(|ty| ty.heap().id() == heap.id() && ty.is_host_visible()),
+            })
+            .collect()
+    }
+    /// Total size of all device local heaps in bytes.
+    pub fn device_local_memory(&self) -> u64 {
+        self.memory_info()
+            .iter()
+            .filter(|heap| heap.device_local)
+            .map(|heap| heap.size)
+            .sum()
+    }
+    /// Sample counts usable for both color and depth attachments.
+    pub fn supported_sample_counts(&self) -> vulkano::SampleCounts {
+        framebuffer_sample_counts(self.physical_device().properties())
+    }
+    /// Highest sample count usable for color and depth attachments, e.g. to clamp the MSAA
+    /// setting.
+    pub fn max_supported_sample_count(&self) -> vulkano::SampleCount {
+        clamp_sample_count(
+            self.supported_sample_counts(),
+            vulkano::SampleCount::Sample64,
         )
-        .unwrap();
-        let queue = queues.next().unwrap();
+    }
+    /// `requested` rounded down to the nearest of `supported_sample_counts`, logging a warning if
+    /// it has to be rounded.
+    pub fn clamp_samples(&self, requested: vulkano::SampleCount) -> vulkano::SampleCount {
+        let samples = clamp_sample_count(self.supported_sample_counts(), requested);
+        if samples != requested {
+            log::warn!(
+                "Sample count {:?} is not supported, using {:?}",
+                requested,
+                samples
+            );
+        }
+        samples
+    }
+    pub fn vendor_id(&self) -> u32 {
+        self.physical_device().properties().vendor_id
+    }
+    pub fn driver_version(&self) -> u32 {
+        self.physical_device().properties().driver_version
+    }
+    /// Whether the device supports `VK_EXT_full_screen_exclusive`, which has to be enabled
+    /// through `DeviceDescriptor::extra_extensions` for
+    /// `SwapchainDescriptor::full_screen_exclusive`.
+    pub fn supports_full_screen_exclusive(&self) -> bool {
+        self.physical_device()
+            .supported_extensions()
+            .ext_full_screen_exclusive
+    }
+}
+
 
-        (device, queue)
+
+#[derive(Clone, Debug)]
+pub struct AdapterInfo {
+    pub name: String,
+    pub device_type: vulkano::PhysicalDeviceType,
+    pub vendor_id: u32,
+    pub device_id: u32,
+    /// Driver version, encoded the way the vendor reports it.
+    pub driver_version: u32,
+    pub api_version: vulkano::Version,
+}
+
+impl fmt::Display for AdapterInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} ({:?}), driver {}, Vulkan {}",
+            self.name, self.device_type, self.driver_version, self.api_version
+        )
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for AdapterInfo {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        // The vulkano types are not serializable, so they are written as strings.
+        let mut state = serializer.serialize_struct("AdapterInfo", 6)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("device_type", &format!("{:?}", self.device_type))?;
+        state.serialize_field("vendor_id", &self.vendor_id)?;
+        state.serialize_field("device_id", &self.device_id)?;
+        state.serialize_field("driver_version", &self.driver_version)?;
+        state.serialize_field("api_version", &self.api_version.to_string())?;
+        state.end()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum RequestDeviceError {
+    /// The adapter does not support these features.
+    FeatureNotPresent(Vec<String>),
+    /// The adapter does not support these extensions.
+    ExtensionNotPresent(Box<vulkano::DeviceExtensions>),
+    TooManyObjects,
+    /// The queue family does not have the requested number of queues.
+    TooManyQueues { requested: u32, available: u32 },
+    /// Any other error reported by vulkano.
+    DeviceCreation(vulkano::DeviceCreationError),
+}
+
+impl From<vulkano::DeviceCreationError> for RequestDeviceError {
+    fn from(err: vulkano::DeviceCreationError) -> Self {
+        match err {
+            vulkano::DeviceCreationError::TooManyObjects => Self::TooManyObjects,
+            err => Self::DeviceCreation(err),
+        }
+    }
+}
+
+impl fmt::Display for RequestDeviceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::FeatureNotPresent(features) => {
+                write!(f, "the adapter does not support the features {:?}", features)
+            }
+            Self::ExtensionNotPresent(extensions) => write!(
+                f,
+                "the adapter does not support the extensions {:?}",
+                extensions
+            ),
+            Self::TooManyObjects => write!(f, "too many devices have been created already"),
+            Self::TooManyQueues {
+                requested,
+                available,
+            } => write!(
+                f,
+                "requested {} queues but the queue family only has {}",
+                requested, available
+            ),
+            Self::DeviceCreation(err) => write!(f, "failed to create the device: {}", err),
+        }
     }
 }
 
+impl std::error::Error for RequestDeviceError {}
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryHeapInfo {
+    /// Size in bytes.
+    pub size: u64,
+    pub device_local: bool,
+    /// Whether the heap has a memory type the host can map.
+    pub host_visible: bool,
+}
+
+impl MemoryHeapInfo {
+    /// Whether this is device local memory the host can write to directly (the PCIe BAR).
+    ///
+    /// Without resizable BAR this is usually a small extra heap of 256 MiB, with it the heap
+    /// covers the whole VRAM.
+    pub fn is_bar(&self) -> bool {
+        self.device_local && self.host_visible
+    }
+}
 
 pub trait GetPhysicalDevice{
-    fn get_physical_device(&self) -> &vulkano::PhysicalDevice;
+    fn get_physical_device(&self) -> vulkano::PhysicalDevice<'_>;
 }
 
-impl<'a> GetPhysicalDevice for &Adapter<'a>{
-    fn get_physical_device(&self) -> &vulkano::PhysicalDevice {
-        &self.physical_device
+impl GetPhysicalDevice for &Adapter{
+    fn get_physical_device(&self) -> vulkano::PhysicalDevice<'_> {
+        self.physical_device()
     }
 }
 
 impl<'p> GetPhysicalDevice for &vulkano::PhysicalDevice<'p>{
-    fn get_physical_device(&self) -> &vulkano::PhysicalDevice {
-        self
+    fn get_physical_device(&self) -> vulkano::PhysicalDevice<'_> {
+        **self
+    }
+}
+
+/// Sample counts the device allows for framebuffers with both color and depth attachments.
+pub fn framebuffer_sample_counts(properties: &vulkano::Properties) -> vulkano::SampleCounts {
+    (ash::vk::SampleCountFlags::from(properties.framebuffer_color_sample_counts)
+        & ash::vk::SampleCountFlags::from(properties.framebuffer_depth_sample_counts))
+    .into()
+}
+
+/// The highest of `supported` that is not above `requested`, `Sample1` if there is none.
+pub fn clamp_sample_count(
+    supported: vulkano::SampleCounts,
+    requested: vulkano::SampleCount,
+) -> vulkano::SampleCount {
+    [
+        vulkano::SampleCount::Sample64,
+        vulkano::SampleCount::Sample32,
+        vulkano::SampleCount::Sample16,
+        vulkano::SampleCount::Sample8,
+        vulkano::SampleCount::Sample4,
+        vulkano::SampleCount::Sample2,
+    ]
+    .into_iter()
+    .filter(|&samples| samples as u32 <= requested as u32)
+    .find(|&samples| supported.contains(samples))
+    .unwrap_or(vulkano::SampleCount::Sample1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn feature_names_lists_enabled_features() {
+        let features = vulkano::Features {
+            ray_tracing_pipeline: true,
+            wide_lines: true,
+            ..vulkano::Features::none()
+        };
+        assert_eq!(feature_names(&features), ["ray_tracing_pipeline", "wide_lines"]);
+        assert!(feature_names(&vulkano::Features::none()).is_empty());
+    }
+
+    #[test]
+    fn ray_tracing_preset_reports_missing_ray_tracing() {
+        assert!(AdapterDescriptor::<()>::ray_tracing().requires_ray_tracing());
+        assert!(!AdapterDescriptor::<()>::graphics().requires_ray_tracing());
+        assert_eq!(
+            RequestAdapterError::NoRayTracingAdapter.to_string(),
+            "no ray tracing capable adapter is available"
+        );
+    }
+
+    fn sample_counts(counts: &[vulkano::SampleCount]) -> vulkano::SampleCounts {
+        let mut supported = vulkano::SampleCounts::default();
+        for &samples in counts {
+            match samples {
+                vulkano::SampleCount::Sample1 => supported.sample1 = true,
+                vulkano::SampleCount::Sample2 => supported.sample2 = true,
+                vulkano::SampleCount::Sample4 => supported.sample4 = true,
+                vulkano::SampleCount::Sample8 => supported.sample8 = true,
+                vulkano::SampleCount::Sample16 => supported.sample16 = true,
+                vulkano::SampleCount::Sample32 => supported.sample32 = true,
+                vulkano::SampleCount::Sample64 => supported.sample64 = true,
+            }
+        }
+        supported
+    }
+
+    #[test]
+    fn framebuffer_sample_counts_intersects_color_and_depth() {
+        use super::vulkano::SampleCount::*;
+        let properties = vulkano::Properties {
+            framebuffer_color_sample_counts: sample_counts(&[Sample1, Sample2, Sample4, Sample8]),
+            framebuffer_depth_sample_counts: sample_counts(&[Sample1, Sample4, Sample16]),
+            ..Default::default()
+        };
+        let supported = framebuffer_sample_counts(&properties);
+        assert_eq!(supported, sample_counts(&[Sample1, Sample4]));
+    }
+
+    #[test]
+    fn clamp_sample_count_rounds_down() {
+        use super::vulkano::SampleCount::*;
+        let supported = sample_counts(&[Sample1, Sample2, Sample8]);
+        assert_eq!(clamp_sample_count(supported, Sample8), Sample8);
+        assert_eq!(clamp_sample_count(supported, Sample4), Sample2);
+        assert_eq!(clamp_sample_count(supported, Sample64), Sample8);
+        assert_eq!(clamp_sample_count(supported, Sample1), Sample1);
+        // Every device supports one sample per pixel.
+        assert_eq!(clamp_sample_count(sample_counts(&[]), Sample4), Sample1);
     }
 }
@@ -0,0 +1,195 @@
+use derive_more::*;
+use std::collections::VecDeque;
+use std::fmt;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::command_buffer::*;
+    pub use vulkano::device::*;
+    pub use vulkano::sync::*;
+}
+
+use vulkano::{GpuFuture, PrimaryCommandBuffer};
+
+/// Shared flag used to stop a `CancellableJob` from submitting further chunks.
+#[derive(Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// Number of finished chunks of a `CancellableJob`, which can be polled from another thread.
+#[derive(Clone)]
+pub struct JobProgress {
+    completed: Arc<AtomicUsize>,
+    total: usize,
+}
+
+impl JobProgress {
+    pub fn completed(&self) -> usize {
+        self.completed.load(Ordering::SeqCst)
+    }
+    pub fn total(&self) -> usize {
+        self.total
+    }
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            return 1.0;
+        }
+        self.completed() as f32 / self.total as f32
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobStatus {
+    Completed,
+    /// The job was cancelled, only the chunks that were already submitted have been executed.
+    Cancelled,
+}
+
+#[derive(Clone, Debug, From)]
+pub enum JobError {
+    Exec(vulkano::CommandBufferExecError),
+    Flush(vulkano::FlushError),
+}
+
+impl fmt::Display for JobError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Exec(err) => write!(f, "failed to execute job chunk: {}", err),
+            Self::Flush(err) => write!(f, "failed to flush job chunk: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for JobError {}
+
+/// Splits a long running workload into chunks that are submitted one after another, so that the
+/// work can be cancelled in between.
+///
+/// `record` is called with the range of work items of every chunk and has to return the command
+/// buffer executing them (usually a single dispatch).
+pub struct CancellableJob<F> {
+    queue: Arc<vulkano::Queue>,
+    work_size: u32,
+    chunk_size: u32,
+    max_in_flight: usize,
+    record: F,
+    token: CancelToken,
+    progress: JobProgress,
+}
+
+impl<F> CancellableJob<F>
+where
+    F: FnMut(Range<u32>) -> vulkano::PrimaryAutoCommandBuffer,
+{
+    pub fn new(queue: Arc<vulkano::Queue>, work_size: u32, chunk_size: u32, record: F) -> Self {
+        let chunk_size = chunk_size.max(1);
+        let chunk_count = (work_size as usize).div_ceil(chunk_size as usize);
+        Self {
+            queue,
+            work_size,
+            chunk_size,
+            max_in_flight: 2,
+            record,
+            token: CancelToken::new(),
+            progress: JobProgress {
+                completed: Arc::new(AtomicUsize::new(0)),
+                total: chunk_count,
+            },
+        }
+    }
+    /// Number of chunks that may be queued on the GPU at the same time.
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight.max(1);
+        self
+    }
+    /// Uses `token` instead of a new token, e.g. to cancel from the closure recording the chunks.
+    pub fn with_cancel_token(mut self, token: CancelToken) -> Self {
+        self.token = token;
+        self
+    }
+    pub fn cancel_token(&self) -> CancelToken {
+        self.token.clone()
+    }
+    pub fn progress(&self) -> JobProgress {
+        self.progress.clone()
+    }
+    /// Submits the chunks and blocks until the job finished or was cancelled.
+    ///
+    /// After a cancellation only the chunks that are already in flight are waited for.
+    pub fn wait(mut self) -> Result<JobStatus, JobError> {
+        let mut in_flight = VecDeque::new();
+        let mut status = JobStatus::Completed;
+
+        let mut start = 0;
+        while start < self.work_size {
+            if self.token.is_cancelled() {
+                status = JobStatus::Cancelled;
+                break;
+            }
+            if in_flight.len() >= self.max_in_flight {
+                let future: vulkano::FenceSignalFuture<_> = in_flight.pop_front().unwrap();
+                future.wait(None)?;
+                self.progress.completed.fetch_add(1, Ordering::SeqCst);
+            }
+
+            let end = start.saturating_add(self.chunk_size).min(self.work_size);
+            let command_buffer = (self.record)(start..end);
+            let future = command_buffer
+                .execute(self.queue.clone())?
+                .then_signal_fence_and_flush()?;
+            in_flight.push_back(future);
+            start = end;
+        }
+
+        for future in in_flight {
+            future.wait(None)?;
+            self.progress.completed.fetch_add(1, Ordering::SeqCst);
+        }
+
+        Ok(status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancel_token_is_shared_by_clones() {
+        let token = CancelToken::new();
+        let clone = token.clone();
+        assert!(!token.is_cancelled());
+        clone.cancel();
+        assert!(token.is_cancelled());
+    }
+
+    #[test]
+    fn progress_fraction() {
+        let progress = JobProgress {
+            completed: Arc::new(AtomicUsize::new(1)),
+            total: 4,
+        };
+        assert_eq!(progress.fraction(), 0.25);
+        let empty = JobProgress {
+            completed: Arc::new(AtomicUsize::new(0)),
+            total: 0,
+        };
+        assert_eq!(empty.fraction(), 1.0);
+    }
+}
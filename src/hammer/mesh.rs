@@ -0,0 +1,182 @@
+use bytemuck::Pod;
+use derive_more::*;
+use std::fmt;
+use std::sync::Arc;
+
+use super::{Buffer, Device, IndexBuffer, TransferError};
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::buffer::cpu_access::*;
+    pub use vulkano::buffer::*;
+    pub use vulkano::command_buffer::*;
+    pub use vulkano::device::*;
+    pub use vulkano::memory::*;
+    pub use vulkano::pipeline::graphics::vertex_input::*;
+}
+
+/// Vertex input with `V` advancing per vertex in binding 0 and `I` per instance in binding 1, as
+/// used by `Mesh::draw_instanced`.
+pub fn instanced_vertex_input<V, I>() -> vulkano::BuffersDefinition
+where
+    V: vulkano::Vertex,
+    I: vulkano::Vertex,
+{
+    vulkano::BuffersDefinition::new()
+        .vertex::<V>()
+        .instance::<I>()
+}
+
+/// Index data for `Mesh::new`.
+#[derive(Clone, Copy, Debug)]
+pub enum Indices<'a> {
+    U16(&'a [u16]),
+    U32(&'a [u32]),
+}
+
+impl<'a> From<&'a [u16]> for Indices<'a> {
+    fn from(indices: &'a [u16]) -> Self {
+        Self::U16(indices)
+    }
+}
+
+impl<'a> From<&'a [u32]> for Indices<'a> {
+    fn from(indices: &'a [u32]) -> Self {
+        Self::U32(indices)
+    }
+}
+
+/// Vertex and optional index buffer in device local memory.
+pub struct Mesh<V: Pod + Send + Sync> {
+    pub vertex_buffer: Arc<vulkano::DeviceLocalBuffer<[V]>>,
+    pub index_buffer: Option<IndexBuffer>,
+    pub vertex_count: u32,
+    /// Zero if the mesh is not indexed.
+    pub index_count: u32,
+}
+
+impl<V: Pod + Send + Sync> Mesh<V> {
+    /// Uploads `vertices` and `indices`, blocking until the upload finished.
+    pub fn new(
+        device: &Device,
+        vertices: &[V],
+        indices: Option<Indices>,
+    ) -> Result<Self, TransferError> {
+        let vertex_buffer =
+            device.create_buffer_init(vertices, vulkano::BufferUsage::vertex_buffer())?;
+        let usage = vulkano::BufferUsage::index_buffer();
+        let (index_buffer, index_count) = match indices {
+            Some(Indices::U16(indices)) => (
+                Some(IndexBuffer::u16(device.create_buffer_init(indices, usage)?)),
+                indices.len(),
+            ),
+            Some(Indices::U32(indices)) => (
+                Some(IndexBuffer::u32(device.create_buffer_init(indices, usage)?)),
+                indices.len(),
+            ),
+            None => (None, 0),
+        };
+        Ok(Self {
+            vertex_buffer,
+            index_buffer,
+            vertex_count: vertices.len() as u32,
+            index_count: index_count as u32,
+        })
+    }
+    /// Binds the buffers and draws the mesh. The pipeline has to be bound already.
+    pub fn draw<L, P>(
+        &self,
+        builder: &mut vulkano::AutoCommandBufferBuilder<L, P>,
+    ) -> Result<(), MeshDrawError> {
+        builder.bind_vertex_buffers(0, self.vertex_buffer.clone());
+        match &self.index_buffer {
+            Some(index_buffer) => {
+                index_buffer.bind(builder);
+                builder.draw_indexed(self.index_count, 1, 0, 0, 0)?;
+            }
+            None => {
+                builder.draw(self.vertex_count, 1, 0, 0)?;
+            }
+        }
+        Ok(())
+    }
+    /// Like `draw`, but draws one instance per element of `instances`.
+    pub fn draw_instanced<L, P, I: Pod + Send + Sync>(
+        &self,
+        builder: &mut vulkano::AutoCommandBufferBuilder<L, P>,
+        instances: &InstanceBuffer<I>,
+    ) -> Result<(), MeshDrawError> {
+        let instance_count = instances.len() as u32;
+        builder.bind_vertex_buffers(0, (self.vertex_buffer.clone(), instances.buffer().clone()));
+        match &self.index_buffer {
+            Some(index_buffer) => {
+                index_buffer.bind(builder);
+                builder.draw_indexed(self.index_count, instance_count, 0, 0, 0)?;
+            }
+            None => {
+                builder.draw(self.vertex_count, instance_count, 0, 0)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Host visible per-instance data for `Mesh::draw_instanced`.
+pub struct InstanceBuffer<I: Pod + Send + Sync> {
+    buffer: Buffer<I>,
+}
+
+impl<I: Pod + Send + Sync> InstanceBuffer<I> {
+    pub fn new(
+        device: Arc<vulkano::Device>,
+        instances: &[I],
+    ) -> Result<Self, vulkano::DeviceMemoryAllocationError> {
+        Ok(Self {
+            buffer: Buffer::vertex(device, instances)?,
+        })
+    }
+    pub fn buffer(&self) -> &Arc<vulkano::CpuAccessibleBuffer<[I]>> {
+        self.buffer.buffer()
+    }
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+    /// Updates the instances in `range`, failing instead of blocking while the device uses them.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if `range` is out of bounds or its length does not match `instances`.
+    pub fn write(
+        &self,
+        range: std::ops::Range<usize>,
+        instances: &[I],
+    ) -> Result<(), vulkano::WriteLockError> {
+        self.buffer.write(range, instances)
+    }
+}
+
+impl<I: Pod + Send + Sync> vulkano::BufferAccessObject for &InstanceBuffer<I> {
+    fn as_buffer_access_object(&self) -> Arc<dyn vulkano::BufferAccess> {
+        self.buffer().clone()
+    }
+}
+
+#[derive(Debug, From)]
+pub enum MeshDrawError {
+    Draw(vulkano::DrawError),
+    DrawIndexed(vulkano::DrawIndexedError),
+}
+
+impl fmt::Display for MeshDrawError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Draw(err) => write!(f, "failed to draw the mesh: {}", err),
+            Self::DrawIndexed(err) => write!(f, "failed to draw the indexed mesh: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for MeshDrawError {}
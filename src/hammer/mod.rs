@@ -10,12 +10,90 @@ pub mod vulkano{
     pub use vulkano::swapchain::*;
     pub use vulkano::render_pass::*;
     pub use vulkano::pipeline::graphics::viewport::*;
+    // `image` has a `swapchain` module as well.
+    pub use vulkano::swapchain;
 }
 
 pub mod surface;
 pub mod instance;
 pub mod device;
+pub mod queue;
+pub mod job;
+pub mod deferred;
+pub mod buffer;
+pub mod virtual_resolution;
+pub mod draw_list;
+pub mod guard;
+pub mod shader;
+pub mod pipeline;
+pub mod pipeline_cache;
+pub mod compute;
+#[cfg(feature = "reflect")]
+pub mod reflect;
+pub mod specialization;
+pub mod uniform;
+pub mod uniform_ring;
+pub mod bind_group;
+pub mod descriptor_cache;
+pub mod transfer;
+pub mod upload;
+pub mod mesh;
+pub mod encoder;
+pub mod command_cache;
+pub mod debug;
+pub mod frame_sync;
+pub mod frame;
+pub mod frame_stats;
+pub mod frame_limiter;
+pub mod app;
+pub mod render_target;
+pub mod render_pass;
+pub mod framebuffer_cache;
+pub mod presets;
+pub mod offscreen;
+pub mod capture;
+pub mod multi_surface;
+#[cfg(feature = "assets")]
+pub mod asset;
+#[cfg(feature = "hot-reload")]
+pub mod hot_reload;
 
 pub use surface::*;
 pub use instance::*;
 pub use device::*;
+pub use queue::*;
+pub use job::*;
+pub use deferred::*;
+pub use buffer::*;
+pub use virtual_resolution::*;
+pub use draw_list::*;
+pub use guard::*;
+pub use shader::*;
+pub use pipeline::*;
+pub use pipeline_cache::*;
+pub use compute::*;
+#[cfg(feature = "reflect")]
+pub use reflect::*;
+pub use specialization::*;
+pub use uniform::*;
+pub use uniform_ring::*;
+pub use bind_group::*;
+pub use descriptor_cache::*;
+pub use transfer::*;
+pub use upload::*;
+pub use mesh::*;
+pub use encoder::*;
+pub use command_cache::*;
+pub(crate) use debug::DebugGroups;
+pub use frame_sync::*;
+pub use frame::*;
+pub use frame_stats::*;
+pub use frame_limiter::*;
+pub use app::*;
+pub use render_target::*;
+pub use render_pass::*;
+pub use framebuffer_cache::*;
+pub use presets::*;
+pub use offscreen::*;
+pub use capture::*;
+pub use multi_surface::*;
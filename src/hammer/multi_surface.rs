@@ -0,0 +1,133 @@
+use winit::event::WindowEvent;
+use winit::window::{Window, WindowId};
+
+use super::{Device, FrameContext, FrameError, FrameSync, Queue, Surface, SurfaceImage};
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::command_buffer::*;
+}
+
+/// Several windows rendered with the same device, e.g. by a tiling window tool.
+///
+/// Every window has its own swapchain and `FrameSync`, so the frames in flight of one window
+/// never wait for another one and resizing a window only recreates its own swapchain. The device
+/// and its queues are shared. Pass the window events to `handle_window_event`, then render all
+/// windows one after another with `begin_frame` and `end_frame` in the same
+/// `RedrawEventsCleared` pass.
+pub struct MultiSurface {
+    frames_in_flight: usize,
+    windows: Vec<WindowSurface>,
+}
+
+struct WindowSurface {
+    id: WindowId,
+    surface: Surface<Window>,
+    frame_sync: FrameSync,
+}
+
+impl MultiSurface {
+    pub fn new(frames_in_flight: usize) -> Self {
+        Self {
+            frames_in_flight,
+            windows: Vec::new(),
+        }
+    }
+    /// Adds the window of `surface`, presenting on the present queue of `device`.
+    ///
+    /// The device has to be able to present to the surface, see `Adapter::is_surface_supported`.
+    pub fn add(&mut self, device: &Device, surface: Surface<Window>) -> WindowId {
+        let id = surface.window().id();
+        self.windows.push(WindowSurface {
+            id,
+            surface,
+            frame_sync: FrameSync::new(device, self.frames_in_flight),
+        });
+        id
+    }
+    /// Removes the window `id` after waiting for its frames in flight, e.g. once it was closed.
+    pub fn remove(&mut self, id: WindowId) -> Result<Option<Surface<Window>>, FrameError> {
+        let index = match self.windows.iter().position(|window| window.id == id) {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+        self.windows[index].frame_sync.wait_idle()?;
+        Ok(Some(self.windows.remove(index).surface))
+    }
+    pub fn len(&self) -> usize {
+        self.windows.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+    /// Windows in the order they were added.
+    pub fn window_ids(&self) -> impl Iterator<Item = WindowId> + '_ {
+        self.windows.iter().map(|window| window.id)
+    }
+    pub fn get(&self, id: WindowId) -> Option<&Surface<Window>> {
+        Some(&self.window(id)?.surface)
+    }
+    pub fn get_mut(&mut self, id: WindowId) -> Option<&mut Surface<Window>> {
+        Some(&mut self.window_mut(id)?.surface)
+    }
+    /// Passes `event` to the surface of window `id`, see `Surface::handle_window_event`. Events
+    /// of other windows are ignored.
+    pub fn handle_window_event(&mut self, id: WindowId, event: &WindowEvent) {
+        if let Some(window) = self.window_mut(id) {
+            window.surface.handle_window_event(event);
+        }
+    }
+    /// Begins a frame of window `id`, see `FrameSync::begin_frame`.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if there is no window `id`.
+    #[track_caller]
+    pub fn begin_frame(
+        &mut self,
+        id: WindowId,
+    ) -> Result<FrameContext<SurfaceImage<Window>>, FrameError> {
+        let window = self.window_mut(id).expect("no window with this id");
+        window.frame_sync.begin_frame(&mut window.surface)
+    }
+    /// Submits `command_buffer` and presents the frame of window `id`, see
+    /// `FrameSync::end_frame`.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if there is no window `id`.
+    pub fn end_frame<Cb>(
+        &mut self,
+        id: WindowId,
+        frame: FrameContext<SurfaceImage<Window>>,
+        command_buffer: Cb,
+        queue: &Queue,
+    ) -> Result<(), FrameError>
+    where
+        Cb: vulkano::PrimaryCommandBuffer + 'static,
+    {
+        let window = self.window_mut(id).expect("no window with this id");
+        window
+            .frame_sync
+            .end_frame(&mut window.surface, frame, command_buffer, queue)
+    }
+    /// Waits for the frames in flight of all windows.
+    pub fn wait_idle(&mut self) -> Result<(), FrameError> {
+        for window in &mut self.windows {
+            window.frame_sync.wait_idle()?;
+        }
+        Ok(())
+    }
+    /// Waits for the frames in flight of all windows and then for `device`, see
+    /// `FrameSync::shutdown`.
+    pub fn shutdown(&mut self, device: &Device) -> Result<(), FrameError> {
+        self.wait_idle()?;
+        device.wait_idle().map_err(FrameError::WaitIdle)
+    }
+    fn window(&self, id: WindowId) -> Option<&WindowSurface> {
+        self.windows.iter().find(|window| window.id == id)
+    }
+    fn window_mut(&mut self, id: WindowId) -> Option<&mut WindowSurface> {
+        self.windows.iter_mut().find(|window| window.id == id)
+    }
+}
@@ -0,0 +1,470 @@
+use std::sync::Arc;
+
+use super::{
+    AcquireImageError, AttachmentError, Attachments, DrawTarget, FramebufferCache, PresentError,
+    Queue, RenderTarget, SwapchainError, TargetImage,
+};
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::descriptor_set::*;
+    pub use vulkano::device::*;
+    pub use vulkano::format::*;
+    pub use vulkano::image::view::*;
+    pub use vulkano::image::*;
+    pub use vulkano::pipeline::graphics::viewport::*;
+    pub use vulkano::render_pass::*;
+    pub use vulkano::sampler::*;
+    pub use vulkano::sync::*;
+}
+
+use vulkano::GpuFuture;
+
+/// Settings for `OffscreenTarget::new`.
+#[derive(Clone, Debug)]
+pub struct OffscreenDescriptor {
+    /// Defaults to `R8G8B8A8_SRGB`, so read back images are in RGBA order.
+    pub format: vulkano::Format,
+    pub image_count: u32,
+    /// Color attachment and transfer source usage are always added.
+    pub image_usage: vulkano::ImageUsage,
+    /// See `SwapchainDescriptor::depth_format`.
+    pub depth_format: Option<vulkano::Format>,
+    /// See `SwapchainDescriptor::sample_count`.
+    pub sample_count: vulkano::SampleCount,
+}
+
+impl Default for OffscreenDescriptor {
+    fn default() -> Self {
+        Self {
+            format: vulkano::Format::R8G8B8A8_SRGB,
+            image_count: 2,
+            image_usage: vulkano::ImageUsage::none(),
+            depth_format: None,
+            sample_count: vulkano::SampleCount::Sample1,
+        }
+    }
+}
+
+/// Render target without a window, e.g. to run a render loop in CI.
+///
+/// Behaves like a `Surface` whose swapchain never goes out of date: `acquire` hands out the
+/// images in turn and presenting just submits the work. Read the presented image back with
+/// `Device::read_image`.
+pub struct OffscreenTarget {
+    device: Arc<vulkano::Device>,
+    descriptor: OffscreenDescriptor,
+    extent: [u32; 2],
+    images: Vec<Arc<vulkano::AttachmentImage>>,
+    attachments: Attachments,
+    framebuffer_cache: Arc<FramebufferCache>,
+    next_image: usize,
+    presented: Option<usize>,
+}
+
+impl OffscreenTarget {
+    pub fn new(
+        device: Arc<vulkano::Device>,
+        extent: [u32; 2],
+        descriptor: &OffscreenDescriptor,
+    ) -> Result<Self, AttachmentError> {
+        let framebuffer_cache = Arc::new(FramebufferCache::default());
+        let (images, attachments) =
+            Self::create_images(&device, extent, descriptor, &framebuffer_cache)?;
+        Ok(Self {
+            device,
+            descriptor: descriptor.clone(),
+            extent,
+            images,
+            attachments,
+            framebuffer_cache,
+            next_image: 0,
+            presented: None,
+        })
+    }
+    /// Creates the images again with `extent`, like recreating a swapchain after a resize.
+    ///
+    /// Images acquired before cannot be used afterwards.
+    pub fn recreate(&mut self, extent: [u32; 2]) -> Result<(), AttachmentError> {
+        let (images, attachments) = Self::create_images(
+            &self.device,
+            extent,
+            &self.descriptor,
+            &self.framebuffer_cache,
+        )?;
+        self.framebuffer_cache.invalidate();
+        self.extent = extent;
+        self.images = images;
+        self.attachments = attachments;
+        self.next_image = 0;
+        self.presented = None;
+        Ok(())
+    }
+    pub fn image_format(&self) -> vulkano::Format {
+        self.descriptor.format
+    }
+    pub fn device(&self) -> &Arc<vulkano::Device> {
+        &self.device
+    }
+    pub fn extent(&self) -> [u32; 2] {
+        self.extent
+    }
+    pub fn images(&self) -> &[Arc<vulkano::AttachmentImage>] {
+        &self.images
+    }
+    pub fn framebuffer_cache(&self) -> &Arc<FramebufferCache> {
+        &self.framebuffer_cache
+    }
+    /// The image presented last, `None` until the first image is presented.
+    pub fn presented_image(&self) -> Option<&Arc<vulkano::AttachmentImage>> {
+        Some(&self.images[self.presented?])
+    }
+    fn create_images(
+        device: &Arc<vulkano::Device>,
+        extent: [u32; 2],
+        descriptor: &OffscreenDescriptor,
+        framebuffer_cache: &Arc<FramebufferCache>,
+    ) -> Result<(Vec<Arc<vulkano::AttachmentImage>>, Attachments), AttachmentError> {
+        let usage = vulkano::ImageUsage {
+            color_attachment: true,
+            transfer_source: true,
+            ..descriptor.image_usage
+        };
+        let images = (0..descriptor.image_count.max(1))
+            .map(|_| {
+                vulkano::AttachmentImage::with_usage(
+                    device.clone(),
+                    extent,
+                    descriptor.format,
+                    usage,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let views = images
+            .iter()
+            .map(|image| {
+                let view: Arc<dyn vulkano::ImageViewAbstract> =
+                    vulkano::ImageView::new_default(image.clone())?;
+                Ok(view)
+            })
+            .collect::<Result<Vec<_>, vulkano::ImageViewCreationError>>()?;
+        let attachments = Attachments::new(
+            device.clone(),
+            views,
+            extent,
+            descriptor.format,
+            descriptor.sample_count,
+            descriptor.depth_format,
+            framebuffer_cache.clone(),
+        )?;
+        Ok((images, attachments))
+    }
+}
+
+impl RenderTarget for OffscreenTarget {
+    type Image = OffscreenImage;
+
+    fn acquire(&mut self) -> Result<OffscreenImage, AcquireImageError> {
+        let image_num = self.next_image;
+        self.next_image = (image_num + 1) % self.images.len();
+        Ok(OffscreenImage {
+            image: self.images[image_num].clone(),
+            image_num,
+            future: Some(vulkano::now(self.device.clone()).boxed()),
+        })
+    }
+    fn image_format(&self) -> Option<vulkano::Format> {
+        Some(self.descriptor.format)
+    }
+    fn extent(&self) -> Option<[u32; 2]> {
+        Some(self.extent)
+    }
+    fn sample_count(&self) -> Option<vulkano::SampleCount> {
+        Some(self.attachments.sample_count())
+    }
+    fn create_render_pass(&self) -> Result<Arc<vulkano::RenderPass>, SwapchainError> {
+        Ok(self.attachments.create_render_pass()?)
+    }
+    fn clear_values(&self, color: [f32; 4]) -> Option<Vec<vulkano::ClearValue>> {
+        Some(self.attachments.clear_values(color))
+    }
+    fn framebuffer(
+        &self,
+        image: &OffscreenImage,
+        render_pass: &Arc<vulkano::RenderPass>,
+    ) -> Result<Arc<vulkano::Framebuffer>, vulkano::FramebufferCreationError> {
+        debug_assert!(
+            Arc::ptr_eq(&image.image, &self.images[image.image_num]),
+            "the target has been recreated since the image was acquired",
+        );
+        Ok(self.attachments.framebuffers(render_pass)?[image.image_num].clone())
+    }
+    /// Only submits `after`, there is nothing to present on.
+    fn present(
+        &mut self,
+        queue: &Queue,
+        image: OffscreenImage,
+        after: Box<dyn GpuFuture>,
+    ) -> Result<vulkano::FenceSignalFuture<Box<dyn GpuFuture>>, PresentError> {
+        let future = {
+            let _lock = queue.lock();
+            after.then_signal_fence_and_flush()?
+        };
+        self.presented = Some(image.image_num);
+        Ok(future)
+    }
+}
+
+/// An image acquired from an `OffscreenTarget`.
+pub struct OffscreenImage {
+    pub image: Arc<vulkano::AttachmentImage>,
+    pub image_num: usize,
+    /// Already signaled, kept so the image can be used like a `SurfaceImage`.
+    pub future: Option<Box<dyn GpuFuture>>,
+}
+
+impl TargetImage for OffscreenImage {
+    fn take_future(&mut self) -> Option<Box<dyn GpuFuture>> {
+        self.future.take()
+    }
+    fn viewport(&self) -> vulkano::Viewport {
+        let dimensions = vulkano::ImageAccess::dimensions(&*self.image).width_height();
+        vulkano::Viewport {
+            origin: [0.0, 0.0],
+            dimensions: [dimensions[0] as f32, dimensions[1] as f32],
+            depth_range: 0.0..1.0,
+        }
+    }
+    fn abandon(self) {}
+}
+
+/// Settings for `OffscreenTexture::new_with`.
+#[derive(Clone, Debug)]
+pub struct OffscreenTextureDescriptor {
+    pub format: vulkano::Format,
+    /// Color attachment and sampled usage are always added, e.g. add `storage` to write the
+    /// image from a compute shader as well.
+    pub image_usage: vulkano::ImageUsage,
+    /// Depth buffer attached to the framebuffers, see `SwapchainDescriptor::depth_format`.
+    pub depth_format: Option<vulkano::Format>,
+}
+
+impl OffscreenTextureDescriptor {
+    pub fn new(format: vulkano::Format) -> Self {
+        Self {
+            format,
+            image_usage: vulkano::ImageUsage::none(),
+            depth_format: None,
+        }
+    }
+}
+
+/// Intermediate color image that is rendered into and sampled afterwards, e.g. by a post effect
+/// that composites it onto the window.
+///
+/// Unlike an `OffscreenTarget` there is a single image without multisampling, which can be
+/// resized, e.g. to follow the size of the window. As a `RenderTarget`, `acquire` always hands
+/// out this image and presenting just submits the work.
+pub struct OffscreenTexture {
+    device: Arc<vulkano::Device>,
+    descriptor: OffscreenTextureDescriptor,
+    image: Arc<vulkano::AttachmentImage>,
+    attachments: Attachments,
+    framebuffer_cache: Arc<FramebufferCache>,
+}
+
+impl OffscreenTexture {
+    /// Creates the image with color attachment and sampled usage.
+    pub fn new(
+        device: Arc<vulkano::Device>,
+        extent: [u32; 2],
+        format: vulkano::Format,
+    ) -> Result<Self, AttachmentError> {
+        Self::new_with(device, extent, &OffscreenTextureDescriptor::new(format))
+    }
+    pub fn new_with(
+        device: Arc<vulkano::Device>,
+        extent: [u32; 2],
+        descriptor: &OffscreenTextureDescriptor,
+    ) -> Result<Self, AttachmentError> {
+        // A texture has a single framebuffer per render pass.
+        let framebuffer_cache = Arc::new(FramebufferCache::new(4));
+        let (image, attachments) =
+            Self::create_image(&device, extent, descriptor, &framebuffer_cache)?;
+        Ok(Self {
+            device,
+            descriptor: descriptor.clone(),
+            image,
+            attachments,
+            framebuffer_cache,
+        })
+    }
+    /// Creates the image and the depth buffer again with `extent`, if it changed.
+    ///
+    /// The view changes as well, so descriptor sets sampling the texture have to be created
+    /// again. Render passes stay valid.
+    pub fn resize(&mut self, extent: [u32; 2]) -> Result<(), AttachmentError> {
+        if extent == self.extent() {
+            return Ok(());
+        }
+        let (image, attachments) = Self::create_image(
+            &self.device,
+            extent,
+            &self.descriptor,
+            &self.framebuffer_cache,
+        )?;
+        self.framebuffer_cache.invalidate();
+        self.image = image;
+        self.attachments = attachments;
+        Ok(())
+    }
+    pub fn device(&self) -> &Arc<vulkano::Device> {
+        &self.device
+    }
+    pub fn image(&self) -> &Arc<vulkano::AttachmentImage> {
+        &self.image
+    }
+    pub fn extent(&self) -> [u32; 2] {
+        vulkano::ImageAccess::dimensions(&*self.image).width_height()
+    }
+    pub fn format(&self) -> vulkano::Format {
+        self.descriptor.format
+    }
+    /// View of the image for sampling it in a later pass.
+    pub fn sampled_view(&self) -> Arc<dyn vulkano::ImageViewAbstract> {
+        self.attachments.views()[0].clone()
+    }
+    pub fn depth_view(&self) -> Option<&Arc<dyn vulkano::ImageViewAbstract>> {
+        self.attachments.depth_view(0)
+    }
+    /// The descriptor write binding the texture with `sampler` at `binding`.
+    pub fn write_descriptor(
+        &self,
+        binding: u32,
+        sampler: Arc<vulkano::Sampler>,
+    ) -> vulkano::WriteDescriptorSet {
+        vulkano::WriteDescriptorSet::image_view_sampler(binding, self.sampled_view(), sampler)
+    }
+    /// Creates a render pass whose framebuffers `framebuffer` can create, see
+    /// `Swapchain::create_render_pass`.
+    pub fn create_render_pass(
+        &self,
+    ) -> Result<Arc<vulkano::RenderPass>, vulkano::RenderPassCreationError> {
+        self.attachments.create_render_pass()
+    }
+    /// Clear values for the attachments of `create_render_pass`.
+    pub fn clear_values(&self, color: [f32; 4]) -> Vec<vulkano::ClearValue> {
+        self.attachments.clear_values(color)
+    }
+    /// The cached framebuffer of the texture for `render_pass`.
+    pub fn framebuffer(
+        &self,
+        render_pass: &Arc<vulkano::RenderPass>,
+    ) -> Result<Arc<vulkano::Framebuffer>, vulkano::FramebufferCreationError> {
+        Ok(self.attachments.framebuffers(render_pass)?[0].clone())
+    }
+    pub fn framebuffer_cache(&self) -> &Arc<FramebufferCache> {
+        &self.framebuffer_cache
+    }
+    fn create_image(
+        device: &Arc<vulkano::Device>,
+        extent: [u32; 2],
+        descriptor: &OffscreenTextureDescriptor,
+        framebuffer_cache: &Arc<FramebufferCache>,
+    ) -> Result<(Arc<vulkano::AttachmentImage>, Attachments), AttachmentError> {
+        let image = vulkano::AttachmentImage::with_usage(
+            device.clone(),
+            extent,
+            descriptor.format,
+            vulkano::ImageUsage {
+                color_attachment: true,
+                sampled: true,
+                ..descriptor.image_usage
+            },
+        )?;
+        let view: Arc<dyn vulkano::ImageViewAbstract> =
+            vulkano::ImageView::new_default(image.clone())?;
+        let attachments = Attachments::new(
+            device.clone(),
+            vec![view],
+            extent,
+            descriptor.format,
+            vulkano::SampleCount::Sample1,
+            descriptor.depth_format,
+            framebuffer_cache.clone(),
+        )?;
+        Ok((image, attachments))
+    }
+}
+
+impl DrawTarget for OffscreenTexture {
+    fn extent(&self) -> [u32; 2] {
+        OffscreenTexture::extent(self)
+    }
+    fn format(&self) -> vulkano::Format {
+        OffscreenTexture::format(self)
+    }
+    fn view(&self) -> Arc<dyn vulkano::ImageViewAbstract> {
+        self.sampled_view()
+    }
+    fn create_render_pass(&self) -> Result<Arc<vulkano::RenderPass>, SwapchainError> {
+        Ok(OffscreenTexture::create_render_pass(self)?)
+    }
+    fn clear_values(&self, color: [f32; 4]) -> Vec<vulkano::ClearValue> {
+        OffscreenTexture::clear_values(self, color)
+    }
+    fn framebuffer(
+        &self,
+        render_pass: &Arc<vulkano::RenderPass>,
+    ) -> Result<Arc<vulkano::Framebuffer>, vulkano::FramebufferCreationError> {
+        OffscreenTexture::framebuffer(self, render_pass)
+    }
+}
+
+impl RenderTarget for OffscreenTexture {
+    type Image = OffscreenImage;
+
+    fn acquire(&mut self) -> Result<OffscreenImage, AcquireImageError> {
+        Ok(OffscreenImage {
+            image: self.image.clone(),
+            image_num: 0,
+            future: Some(vulkano::now(self.device.clone()).boxed()),
+        })
+    }
+    fn image_format(&self) -> Option<vulkano::Format> {
+        Some(self.format())
+    }
+    fn extent(&self) -> Option<[u32; 2]> {
+        Some(OffscreenTexture::extent(self))
+    }
+    fn sample_count(&self) -> Option<vulkano::SampleCount> {
+        Some(vulkano::SampleCount::Sample1)
+    }
+    fn create_render_pass(&self) -> Result<Arc<vulkano::RenderPass>, SwapchainError> {
+        Ok(OffscreenTexture::create_render_pass(self)?)
+    }
+    fn clear_values(&self, color: [f32; 4]) -> Option<Vec<vulkano::ClearValue>> {
+        Some(OffscreenTexture::clear_values(self, color))
+    }
+    fn framebuffer(
+        &self,
+        image: &OffscreenImage,
+        render_pass: &Arc<vulkano::RenderPass>,
+    ) -> Result<Arc<vulkano::Framebuffer>, vulkano::FramebufferCreationError> {
+        debug_assert!(
+            Arc::ptr_eq(&image.image, &self.image),
+            "the texture has been resized since the image was acquired",
+        );
+        OffscreenTexture::framebuffer(self, render_pass)
+    }
+    /// Only submits `after`, there is nothing to present on.
+    fn present(
+        &mut self,
+        queue: &Queue,
+        _image: OffscreenImage,
+        after: Box<dyn GpuFuture>,
+    ) -> Result<vulkano::FenceSignalFuture<Box<dyn GpuFuture>>, PresentError> {
+        let _lock = queue.lock();
+        Ok(after.then_signal_fence_and_flush()?)
+    }
+}
@@ -0,0 +1,778 @@
+use std::fmt;
+use std::sync::Arc;
+
+use super::{
+    instanced_vertex_input, Device, EntryPointError, Shader, SpecializationError, SpecializationMap,
+};
+#[cfg(feature = "reflect")]
+use super::{ShaderInterface, VertexInputMismatch, VertexLocations};
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::device::Features;
+    pub use vulkano::format::*;
+    pub use vulkano::image::*;
+    pub use vulkano::pipeline::graphics::color_blend::*;
+    pub use vulkano::pipeline::graphics::depth_stencil::*;
+    pub use vulkano::pipeline::graphics::input_assembly::*;
+    pub use vulkano::pipeline::graphics::rasterization::*;
+    pub use vulkano::pipeline::graphics::tessellation::*;
+    pub use vulkano::pipeline::graphics::vertex_input::*;
+    pub use vulkano::pipeline::graphics::viewport::*;
+    pub use vulkano::pipeline::graphics::*;
+    pub use vulkano::pipeline::StateMode;
+    pub use vulkano::render_pass::*;
+    pub use vulkano::shader::spirv::ExecutionModel;
+    pub use vulkano::shader::*;
+}
+
+/// Vertex buffers a pipeline reads, in binding order.
+///
+/// The default has no vertex buffers, for shaders that generate their vertices, e.g. from
+/// `gl_VertexIndex`.
+#[derive(Clone, Debug, Default)]
+pub struct VertexLayout {
+    definition: vulkano::BuffersDefinition,
+    // Replaces `definition` with the members by location, see `from_reflection`.
+    #[cfg(feature = "reflect")]
+    locations: Option<VertexLocations>,
+}
+
+impl VertexLayout {
+    /// A single buffer of `V` advancing per vertex.
+    pub fn of<V: vulkano::Vertex>() -> Self {
+        Self::default().vertex::<V>()
+    }
+    /// `V` per vertex in binding 0 and `I` per instance in binding 1, see
+    /// `instanced_vertex_input`.
+    pub fn instanced<V: vulkano::Vertex, I: vulkano::Vertex>() -> Self {
+        Self {
+            definition: instanced_vertex_input::<V, I>(),
+            #[cfg(feature = "reflect")]
+            locations: None,
+        }
+    }
+    /// A single buffer of `V` advancing per vertex, whose `members` are given by the shader
+    /// location they feed instead of matched by name, e.g. `&[(0, "position"), (1, "uv")]`.
+    ///
+    /// The formats are reflected from the vertex shader when creating the pipeline and checked
+    /// against the members, so a shader out of sync with `V` is reported with the offending
+    /// locations instead of failing at draw time. Members spanning several locations, e.g.
+    /// matrices, are given by their first one.
+    #[cfg(feature = "reflect")]
+    pub fn from_reflection<V: vulkano::Vertex>(members: &[(u32, &str)]) -> Self {
+        Self {
+            locations: Some(VertexLocations::of::<V>(members)),
+            ..Self::of::<V>()
+        }
+    }
+    /// Adds a binding of `V` advancing per vertex.
+    pub fn vertex<V: vulkano::Vertex>(mut self) -> Self {
+        self.definition = self.definition.vertex::<V>();
+        self
+    }
+    /// Adds a binding of `I` advancing per instance.
+    pub fn instance<I: vulkano::Vertex>(mut self) -> Self {
+        self.definition = self.definition.instance::<I>();
+        self
+    }
+    // The vertex input of a pipeline with `vertex_shader`.
+    fn vertex_input_state(
+        &self,
+        vertex_shader: &vulkano::EntryPoint,
+    ) -> Result<vulkano::VertexInputState, PipelineError> {
+        use vulkano::VertexDefinition;
+        #[cfg(feature = "reflect")]
+        if let Some(locations) = &self.locations {
+            let inputs = ShaderInterface::of(vertex_shader).vertex_inputs;
+            return locations
+                .vertex_input_state(&inputs)
+                .map_err(PipelineError::VertexInputs);
+        }
+        self.definition
+            .definition(vertex_shader.input_interface())
+            .map_err(|err| {
+                PipelineError::VertexLayout(
+                    vulkano::GraphicsPipelineCreationError::IncompatibleVertexDefinition(err),
+                )
+            })
+    }
+}
+
+/// Depth test of a `PipelineDescriptor`. The default passes fragments in front of the depth
+/// buffer and writes their depth.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DepthState {
+    pub compare: vulkano::CompareOp,
+    pub write: bool,
+}
+
+impl Default for DepthState {
+    fn default() -> Self {
+        Self {
+            compare: vulkano::CompareOp::Less,
+            write: true,
+        }
+    }
+}
+
+/// States of a `PipelineDescriptor` that are set while recording, with the setters of
+/// `RenderPassEncoder`, instead of being baked into the pipeline. The viewport always is.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DynamicStates {
+    /// Enables depth bias, set with `RenderPassEncoder::set_depth_bias`, e.g. per shadow map.
+    pub depth_bias: bool,
+    /// Replaces `PipelineDescriptor::line_width`, set with `RenderPassEncoder::set_line_width`.
+    pub line_width: bool,
+    /// Set with `RenderPassEncoder::set_blend_constants`, for `BlendPreset::Custom` blending
+    /// with constant factors.
+    pub blend_constants: bool,
+}
+
+/// How fragments are combined with a color attachment.
+///
+/// `AlphaBlend` and `PremultipliedAlpha` differ in what the shader outputs: a straight color
+/// still has to be multiplied with its alpha, a premultiplied one already was. Both leave
+/// premultiplied colors in the attachment, and `src.a + dst.a * (1 - src.a)` as its alpha.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlendPreset {
+    /// Fragments replace the attachment.
+    #[default]
+    Opaque,
+    /// Straight alpha: `src.rgb * src.a + dst.rgb * (1 - src.a)`, e.g. for sprites.
+    AlphaBlend,
+    /// Premultiplied alpha: `src.rgb + dst.rgb * (1 - src.a)`.
+    PremultipliedAlpha,
+    /// Fragments are added to the attachment, e.g. for particles or glow. The alpha becomes the
+    /// larger of both.
+    Additive,
+    Custom(vulkano::AttachmentBlend),
+}
+
+impl BlendPreset {
+    /// The blend equation of the preset, `None` for `Opaque`.
+    pub fn attachment_blend(self) -> Option<vulkano::AttachmentBlend> {
+        use vulkano::BlendFactor::{One, OneMinusSrcAlpha, SrcAlpha};
+        let over = |color_source| vulkano::AttachmentBlend {
+            color_op: vulkano::BlendOp::Add,
+            color_source,
+            color_destination: OneMinusSrcAlpha,
+            alpha_op: vulkano::BlendOp::Add,
+            alpha_source: One,
+            alpha_destination: OneMinusSrcAlpha,
+        };
+        match self {
+            Self::Opaque => None,
+            // vulkano's `AttachmentBlend::alpha` multiplies the alpha with itself as well.
+            Self::AlphaBlend => Some(over(SrcAlpha)),
+            Self::PremultipliedAlpha => Some(over(One)),
+            Self::Additive => Some(vulkano::AttachmentBlend::additive()),
+            Self::Custom(blend) => Some(blend),
+        }
+    }
+}
+
+/// Tessellation stages of a `PipelineDescriptor`, which need the `tessellation_shader` feature.
+#[derive(Clone)]
+pub struct TessellationStages<'a> {
+    pub control: vulkano::EntryPoint<'a>,
+    pub evaluation: vulkano::EntryPoint<'a>,
+    /// Vertices per patch, at most the `max_tessellation_patch_size` limit of the device.
+    pub patch_control_points: u32,
+    /// Values of the specialization constants of `control`.
+    pub control_specialization: SpecializationMap,
+    /// Values of the specialization constants of `evaluation`.
+    pub evaluation_specialization: SpecializationMap,
+}
+
+impl<'a> TessellationStages<'a> {
+    pub fn new(
+        control: vulkano::EntryPoint<'a>,
+        evaluation: vulkano::EntryPoint<'a>,
+        patch_control_points: u32,
+    ) -> Self {
+        Self {
+            control,
+            evaluation,
+            patch_control_points,
+            control_specialization: SpecializationMap::new(),
+            evaluation_specialization: SpecializationMap::new(),
+        }
+    }
+}
+
+/// Description of a graphics pipeline for `Device::create_graphics_pipeline`.
+///
+/// `new` sets up a pipeline drawing triangle lists without culling, depth test or blending, with
+/// a dynamic viewport. Change the fields that differ with struct update syntax.
+#[derive(Clone)]
+pub struct PipelineDescriptor<'a> {
+    pub vertex_shader: vulkano::EntryPoint<'a>,
+    pub fragment_shader: vulkano::EntryPoint<'a>,
+    /// Values of the specialization constants of `vertex_shader`.
+    pub vertex_specialization: SpecializationMap,
+    /// Values of the specialization constants of `fragment_shader`.
+    pub fragment_specialization: SpecializationMap,
+    /// Tessellates patches of the vertices between the vertex and geometry or fragment stage.
+    pub tessellation: Option<TessellationStages<'a>>,
+    /// Needs the `geometry_shader` feature.
+    pub geometry_shader: Option<vulkano::EntryPoint<'a>>,
+    /// Values of the specialization constants of `geometry_shader`.
+    pub geometry_specialization: SpecializationMap,
+    pub vertex_layout: VertexLayout,
+    /// Ignored with `tessellation`, which always draws patch lists.
+    pub topology: vulkano::PrimitiveTopology,
+    pub cull_mode: vulkano::CullMode,
+    pub front_face: vulkano::FrontFace,
+    /// Modes other than `Fill` need the `fill_mode_non_solid` feature.
+    pub polygon_mode: vulkano::PolygonMode,
+    /// Widths other than 1.0 need the `wide_lines` feature.
+    pub line_width: f32,
+    /// Needs a subpass with a depth attachment.
+    pub depth: Option<DepthState>,
+    /// Blending of all color attachments that `attachment_blend` does not override.
+    pub blend: BlendPreset,
+    /// Blending of the color attachment at the same index instead of `blend`, `None` keeps
+    /// `blend`. Can be shorter than the number of color attachments.
+    pub attachment_blend: Vec<Option<BlendPreset>>,
+    /// States set while recording instead of by the pipeline.
+    pub dynamic_states: DynamicStates,
+    /// Sample count the subpass is expected to have, `None` accepts any.
+    pub samples: Option<vulkano::SampleCount>,
+}
+
+impl<'a> PipelineDescriptor<'a> {
+    pub fn new(
+        vertex_shader: vulkano::EntryPoint<'a>,
+        fragment_shader: vulkano::EntryPoint<'a>,
+    ) -> Self {
+        Self {
+            vertex_shader,
+            fragment_shader,
+            vertex_specialization: SpecializationMap::new(),
+            fragment_specialization: SpecializationMap::new(),
+            tessellation: None,
+            geometry_shader: None,
+            geometry_specialization: SpecializationMap::new(),
+            vertex_layout: VertexLayout::default(),
+            topology: vulkano::PrimitiveTopology::TriangleList,
+            cull_mode: vulkano::CullMode::None,
+            front_face: vulkano::FrontFace::CounterClockwise,
+            polygon_mode: vulkano::PolygonMode::Fill,
+            line_width: 1.0,
+            depth: None,
+            blend: BlendPreset::Opaque,
+            attachment_blend: Vec::new(),
+            dynamic_states: DynamicStates::default(),
+            samples: None,
+        }
+    }
+    /// Like `new`, with the entry points `vertex` and `fragment` of a module declaring several,
+    /// e.g. `"vs_main"` and `"fs_main"`.
+    pub fn from_shader(
+        shader: &'a Shader,
+        vertex: &str,
+        fragment: &str,
+    ) -> Result<Self, EntryPointError> {
+        Ok(Self::new(
+            shader.entry_point(vertex, vulkano::ExecutionModel::Vertex)?,
+            shader.entry_point(fragment, vulkano::ExecutionModel::Fragment)?,
+        ))
+    }
+    /// Blending of color attachment `index`.
+    fn attachment_blend(&self, index: usize) -> BlendPreset {
+        self.attachment_blend
+            .get(index)
+            .copied()
+            .flatten()
+            .unwrap_or(self.blend)
+    }
+}
+
+impl Device {
+    /// Creates a graphics pipeline described by `desc` rendering in `subpass`, through the
+    /// pipeline cache of the device.
+    ///
+    /// The descriptor is checked against the subpass first, so mistakes are reported with the
+    /// field of the descriptor causing them.
+    pub fn create_graphics_pipeline(
+        &self,
+        desc: &PipelineDescriptor,
+        subpass: vulkano::Subpass,
+    ) -> Result<Arc<vulkano::GraphicsPipeline>, PipelineError> {
+        check_features(desc, self.enabled_features())?;
+        check_tessellation(
+            desc,
+            self.physical_device()
+                .properties()
+                .max_tessellation_patch_size,
+        )?;
+        check_subpass(desc, &subpass)?;
+        check_specialization(desc)?;
+        let vertex_input = desc.vertex_layout.vertex_input_state(&desc.vertex_shader)?;
+
+        let topology = match desc.tessellation {
+            Some(_) => vulkano::PrimitiveTopology::PatchList,
+            None => desc.topology,
+        };
+        let patch_control_points = desc
+            .tessellation
+            .as_ref()
+            .map_or(3, |tessellation| tessellation.patch_control_points);
+        let builder = vulkano::GraphicsPipeline::start()
+            .vertex_input_state(vertex_input)
+            .vertex_shader(
+                desc.vertex_shader.clone(),
+                desc.vertex_specialization.constants(),
+            )
+            .input_assembly_state(vulkano::InputAssemblyState::new().topology(topology))
+            // Only used with tessellation shaders.
+            .tessellation_state(
+                vulkano::TessellationState::new().patch_control_points(patch_control_points),
+            )
+            .viewport_state(vulkano::ViewportState::viewport_dynamic_scissor_irrelevant())
+            .rasterization_state(vulkano::RasterizationState {
+                depth_bias: desc
+                    .dynamic_states
+                    .depth_bias
+                    .then_some(vulkano::DepthBiasState {
+                        enable_dynamic: false,
+                        bias: vulkano::StateMode::Dynamic,
+                    }),
+                line_width: match desc.dynamic_states.line_width {
+                    true => vulkano::StateMode::Dynamic,
+                    false => vulkano::StateMode::Fixed(desc.line_width),
+                },
+                ..vulkano::RasterizationState::new()
+                    .cull_mode(desc.cull_mode)
+                    .front_face(desc.front_face)
+                    .polygon_mode(desc.polygon_mode)
+            })
+            .fragment_shader(
+                desc.fragment_shader.clone(),
+                desc.fragment_specialization.constants(),
+            );
+
+        // Every combination of stages is a different builder type.
+        match (&desc.tessellation, &desc.geometry_shader) {
+            (None, None) => self.build_graphics_pipeline(desc, subpass, builder),
+            (Some(tessellation), None) => self.build_graphics_pipeline(
+                desc,
+                subpass,
+                builder.tessellation_shaders(
+                    tessellation.control.clone(),
+                    tessellation.control_specialization.constants(),
+                    tessellation.evaluation.clone(),
+                    tessellation.evaluation_specialization.constants(),
+                ),
+            ),
+            (None, Some(geometry_shader)) => self.build_graphics_pipeline(
+                desc,
+                subpass,
+                builder.geometry_shader(
+                    geometry_shader.clone(),
+                    desc.geometry_specialization.constants(),
+                ),
+            ),
+            (Some(tessellation), Some(geometry_shader)) => self.build_graphics_pipeline(
+                desc,
+                subpass,
+                builder
+                    .tessellation_shaders(
+                        tessellation.control.clone(),
+                        tessellation.control_specialization.constants(),
+                        tessellation.evaluation.clone(),
+                        tessellation.evaluation_specialization.constants(),
+                    )
+                    .geometry_shader(
+                        geometry_shader.clone(),
+                        desc.geometry_specialization.constants(),
+                    ),
+            ),
+        }
+    }
+    // Sets the states that do not depend on the stages and builds the pipeline.
+    fn build_graphics_pipeline<Vdef, Vss, Tcss, Tess, Gss, Fss>(
+        &self,
+        desc: &PipelineDescriptor,
+        subpass: vulkano::Subpass,
+        mut builder: vulkano::GraphicsPipelineBuilder<
+            '_,
+            '_,
+            '_,
+            '_,
+            '_,
+            Vdef,
+            Vss,
+            Tcss,
+            Tess,
+            Gss,
+            Fss,
+        >,
+    ) -> Result<Arc<vulkano::GraphicsPipeline>, PipelineError>
+    where
+        Vdef: vulkano::VertexDefinition,
+        Vss: vulkano::SpecializationConstants,
+        Tcss: vulkano::SpecializationConstants,
+        Tess: vulkano::SpecializationConstants,
+        Gss: vulkano::SpecializationConstants,
+        Fss: vulkano::SpecializationConstants,
+    {
+        if let Some(depth) = desc.depth {
+            builder = builder.depth_stencil_state(vulkano::DepthStencilState {
+                depth: Some(vulkano::DepthState {
+                    enable_dynamic: false,
+                    compare_op: vulkano::StateMode::Fixed(depth.compare),
+                    write_enable: vulkano::StateMode::Fixed(depth.write),
+                }),
+                ..Default::default()
+            });
+        }
+        let mut color_blend = vulkano::ColorBlendState::new(subpass.num_color_attachments());
+        for (index, attachment) in color_blend.attachments.iter_mut().enumerate() {
+            attachment.blend = desc.attachment_blend(index).attachment_blend();
+        }
+        if desc.dynamic_states.blend_constants {
+            color_blend.blend_constants = vulkano::StateMode::Dynamic;
+        }
+        builder = builder.color_blend_state(color_blend);
+        if let Some(cache) = self.pipeline_cache() {
+            builder = builder.build_with_cache(cache.clone());
+        }
+        builder
+            .render_pass(subpass)
+            .build(self.handle().clone())
+            .map_err(PipelineError::from)
+    }
+}
+
+/// A pipeline and its wireframe variant, see `Device::create_wireframe_pipelines`.
+#[derive(Clone)]
+pub struct WireframePipelines {
+    pub fill: Arc<vulkano::GraphicsPipeline>,
+    /// `None` if the device does not have the `fill_mode_non_solid` feature enabled.
+    pub wireframe: Option<Arc<vulkano::GraphicsPipeline>>,
+}
+
+impl WireframePipelines {
+    /// The wireframe pipeline if `wireframe` is set and there is one, the filled one otherwise.
+    pub fn select(&self, wireframe: bool) -> &Arc<vulkano::GraphicsPipeline> {
+        match &self.wireframe {
+            Some(pipeline) if wireframe => pipeline,
+            _ => &self.fill,
+        }
+    }
+}
+
+impl Device {
+    /// Creates the pipeline of `desc` with `PolygonMode::Fill` and `PolygonMode::Line`, e.g. to
+    /// toggle a wireframe view of meshes with a key.
+    ///
+    /// Without the `fill_mode_non_solid` feature only the filled pipeline is created, enable it
+    /// with `AdapterDescriptor::required_features` if the adapter supports it.
+    pub fn create_wireframe_pipelines(
+        &self,
+        desc: &PipelineDescriptor,
+        subpass: vulkano::Subpass,
+    ) -> Result<WireframePipelines, PipelineError> {
+        let fill = PipelineDescriptor {
+            polygon_mode: vulkano::PolygonMode::Fill,
+            ..desc.clone()
+        };
+        let wireframe = PipelineDescriptor {
+            polygon_mode: vulkano::PolygonMode::Line,
+            ..desc.clone()
+        };
+        Ok(WireframePipelines {
+            fill: self.create_graphics_pipeline(&fill, subpass.clone())?,
+            wireframe: match self.enabled_features().fill_mode_non_solid {
+                true => Some(self.create_graphics_pipeline(&wireframe, subpass)?),
+                false => None,
+            },
+        })
+    }
+}
+
+fn check_features(
+    desc: &PipelineDescriptor,
+    features: &vulkano::Features,
+) -> Result<(), PipelineError> {
+    if desc.polygon_mode != vulkano::PolygonMode::Fill && !features.fill_mode_non_solid {
+        return Err(PipelineError::FeatureNotEnabled {
+            field: "polygon_mode",
+            feature: "fill_mode_non_solid",
+        });
+    }
+    if desc.line_width != 1.0 && !desc.dynamic_states.line_width && !features.wide_lines {
+        return Err(PipelineError::FeatureNotEnabled {
+            field: "line_width",
+            feature: "wide_lines",
+        });
+    }
+    if desc.tessellation.is_some() && !features.tessellation_shader {
+        return Err(PipelineError::FeatureNotEnabled {
+            field: "tessellation",
+            feature: "tessellation_shader",
+        });
+    }
+    if desc.geometry_shader.is_some() && !features.geometry_shader {
+        return Err(PipelineError::FeatureNotEnabled {
+            field: "geometry_shader",
+            feature: "geometry_shader",
+        });
+    }
+    Ok(())
+}
+
+fn check_tessellation(desc: &PipelineDescriptor, max: u32) -> Result<(), PipelineError> {
+    match &desc.tessellation {
+        Some(tessellation)
+            if tessellation.patch_control_points == 0
+                || tessellation.patch_control_points > max =>
+        {
+            Err(PipelineError::PatchControlPoints {
+                count: tessellation.patch_control_points,
+                max,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+fn check_specialization(desc: &PipelineDescriptor) -> Result<(), PipelineError> {
+    let stages = [
+        (
+            "vertex_specialization",
+            &desc.vertex_specialization,
+            &desc.vertex_shader,
+        ),
+        (
+            "fragment_specialization",
+            &desc.fragment_specialization,
+            &desc.fragment_shader,
+        ),
+    ];
+    let tessellation = desc.tessellation.iter().flat_map(|tessellation| {
+        [
+            (
+                "tessellation.control_specialization",
+                &tessellation.control_specialization,
+                &tessellation.control,
+            ),
+            (
+                "tessellation.evaluation_specialization",
+                &tessellation.evaluation_specialization,
+                &tessellation.evaluation,
+            ),
+        ]
+    });
+    let geometry = desc.geometry_shader.iter().map(|geometry_shader| {
+        (
+            "geometry_specialization",
+            &desc.geometry_specialization,
+            geometry_shader,
+        )
+    });
+    for (field, specialization, entry_point) in
+        stages.into_iter().chain(tessellation).chain(geometry)
+    {
+        specialization
+            .check(entry_point)
+            .map_err(|error| PipelineError::Specialization { field, error })?;
+    }
+    Ok(())
+}
+
+fn check_subpass(
+    desc: &PipelineDescriptor,
+    subpass: &vulkano::Subpass,
+) -> Result<(), PipelineError> {
+    if desc.depth.is_some() && !subpass.has_depth() {
+        return Err(PipelineError::NoDepthAttachment);
+    }
+    if let (Some(samples), Some(available)) = (desc.samples, subpass.num_samples()) {
+        if samples != available {
+            return Err(PipelineError::SampleCountMismatch { samples, available });
+        }
+    }
+    let color_attachments = &subpass.subpass_desc().color_attachments;
+    if desc.attachment_blend.len() > color_attachments.len() {
+        return Err(PipelineError::AttachmentBlendCount {
+            count: desc.attachment_blend.len(),
+            attachments: color_attachments.len(),
+        });
+    }
+    let attachments = subpass.render_pass().attachments();
+    for (index, reference) in color_attachments.iter().enumerate() {
+        if desc.attachment_blend(index) == BlendPreset::Opaque {
+            continue;
+        }
+        let format = reference
+            .as_ref()
+            .and_then(|reference| attachments[reference.attachment as usize].format);
+        if let Some(format) = format.filter(|format| is_integer(*format)) {
+            return Err(PipelineError::BlendIntegerFormat {
+                attachment: index as u32,
+                format,
+                overridden: matches!(desc.attachment_blend.get(index), Some(Some(_))),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn is_integer(format: vulkano::Format) -> bool {
+    matches!(
+        format.type_color(),
+        Some(vulkano::NumericType::UINT | vulkano::NumericType::SINT)
+    )
+}
+
+#[derive(Debug)]
+pub enum PipelineError {
+    /// `field` needs `feature`, which was not enabled when creating the device.
+    FeatureNotEnabled {
+        field: &'static str,
+        feature: &'static str,
+    },
+    /// `depth` is set, but the subpass has no depth attachment.
+    NoDepthAttachment,
+    /// `samples` differs from the sample count of the subpass.
+    SampleCountMismatch {
+        samples: vulkano::SampleCount,
+        available: vulkano::SampleCount,
+    },
+    /// `blend`, or `attachment_blend` if `overridden`, blends a color attachment of an integer
+    /// format, which cannot be blended.
+    BlendIntegerFormat {
+        attachment: u32,
+        format: vulkano::Format,
+        overridden: bool,
+    },
+    /// `attachment_blend` has more entries than the subpass has color attachments.
+    AttachmentBlendCount {
+        count: usize,
+        attachments: usize,
+    },
+    /// `patch_control_points` of `tessellation` is zero or above the `max_tessellation_patch_size`
+    /// limit of the device.
+    PatchControlPoints {
+        count: u32,
+        max: u32,
+    },
+    /// `vertex_layout` does not match the inputs of the vertex shader.
+    VertexLayout(vulkano::GraphicsPipelineCreationError),
+    /// The inputs of the vertex shader do not match the vertex type of a `vertex_layout` from
+    /// `VertexLayout::from_reflection`.
+    #[cfg(feature = "reflect")]
+    VertexInputs(Vec<VertexInputMismatch>),
+    /// `field`, the specialization of a stage, does not fit the constants its shader declares.
+    Specialization {
+        field: &'static str,
+        error: SpecializationError,
+    },
+    Creation(vulkano::GraphicsPipelineCreationError),
+}
+
+impl PipelineError {
+    /// The field of the `PipelineDescriptor` the error was caused by, if it is known.
+    pub fn field(&self) -> Option<&'static str> {
+        match self {
+            Self::FeatureNotEnabled { field, .. } | Self::Specialization { field, .. } => {
+                Some(*field)
+            }
+            Self::NoDepthAttachment => Some("depth"),
+            Self::SampleCountMismatch { .. } => Some("samples"),
+            Self::BlendIntegerFormat {
+                overridden: false, ..
+            } => Some("blend"),
+            Self::BlendIntegerFormat { .. } | Self::AttachmentBlendCount { .. } => {
+                Some("attachment_blend")
+            }
+            Self::PatchControlPoints { .. } => Some("tessellation"),
+            Self::VertexLayout(_) => Some("vertex_layout"),
+            #[cfg(feature = "reflect")]
+            Self::VertexInputs(_) => Some("vertex_layout"),
+            Self::Creation(_) => None,
+        }
+    }
+}
+
+impl From<vulkano::GraphicsPipelineCreationError> for PipelineError {
+    /// Errors of the vertex input become `VertexLayout`.
+    fn from(err: vulkano::GraphicsPipelineCreationError) -> Self {
+        use vulkano::GraphicsPipelineCreationError as E;
+        match err {
+            E::IncompatibleVertexDefinition(_)
+            | E::VertexInputAttributeIncompatibleFormat { .. }
+            | E::VertexInputAttributeInvalidBinding { .. }
+            | E::VertexInputAttributeMissing { .. }
+            | E::VertexInputAttributeUnsupportedFormat { .. }
+            | E::MaxVertexInputAttributesExceeded { .. }
+            | E::MaxVertexInputAttributeOffsetExceeded { .. }
+            | E::MaxVertexInputBindingsExceeded { .. }
+            | E::MaxVertexInputBindingStrideExceeded { .. } => Self::VertexLayout(err),
+            err => Self::Creation(err),
+        }
+    }
+}
+
+impl fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::FeatureNotEnabled { field, feature } => write!(
+                f,
+                "`{}` needs the `{}` feature, add it to `AdapterDescriptor::required_features`",
+                field, feature
+            ),
+            Self::NoDepthAttachment => {
+                write!(f, "`depth` is set, but the subpass has no depth attachment")
+            }
+            Self::SampleCountMismatch { samples, available } => write!(
+                f,
+                "`samples` is {:?}, but the subpass has {:?}",
+                samples, available
+            ),
+            Self::BlendIntegerFormat {
+                attachment,
+                format,
+                overridden,
+            } => write!(
+                f,
+                "`{}` cannot blend color attachment {} of the integer format {:?}",
+                if *overridden {
+                    "attachment_blend"
+                } else {
+                    "blend"
+                },
+                attachment,
+                format
+            ),
+            Self::AttachmentBlendCount { count, attachments } => write!(
+                f,
+                "`attachment_blend` has {} entries, but the subpass only {} color attachments",
+                count, attachments
+            ),
+            Self::PatchControlPoints { count, max } => write!(
+                f,
+                "`tessellation` has {} patch control points, but the device supports 1 to {}",
+                count, max
+            ),
+            Self::VertexLayout(err) => {
+                write!(f, "`vertex_layout` does not fit the vertex shader: {}", err)
+            }
+            #[cfg(feature = "reflect")]
+            Self::VertexInputs(mismatches) => {
+                write!(f, "`vertex_layout` does not fit the vertex shader inputs")?;
+                for mismatch in mismatches {
+                    write!(f, "\n  {}", mismatch)?;
+                }
+                Ok(())
+            }
+            Self::Specialization { field, error } => write!(f, "`{}`: {}", field, error),
+            Self::Creation(err) => write!(f, "failed to create the pipeline: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for PipelineError {}
@@ -0,0 +1,124 @@
+use derive_more::*;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use super::Device;
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::pipeline::cache::*;
+    pub use vulkano::OomError;
+}
+
+const MAGIC: [u8; 8] = *b"HMRPCACH";
+const VERSION: u32 = 1;
+// Magic, version, vendor id, device id, driver version, cache uuid, data length and checksum.
+const HEADER_LEN: usize = 8 + 4 * 4 + 16 + 8 + 8;
+
+impl Device {
+    /// Cache shared by all pipelines created through the device, `None` if it could not be
+    /// created.
+    pub fn pipeline_cache(&self) -> Option<&Arc<vulkano::PipelineCache>> {
+        self.pipeline_cache.as_ref()
+    }
+    /// Writes the pipeline cache to `path`, so the next launch can `load_pipeline_cache` it.
+    ///
+    /// The file is written next to `path` with `.tmp` appended to its name first and then
+    /// renamed, so an interrupted save does not leave a truncated cache behind.
+    pub fn save_pipeline_cache(&self, path: impl AsRef<Path>) -> Result<(), PipelineCacheError> {
+        let path = path.as_ref();
+        let cache = self.pipeline_cache().ok_or(PipelineCacheError::NoCache)?;
+        let data = cache.get_data()?;
+
+        let mut file = self.pipeline_cache_header(&data);
+        file.extend_from_slice(&data);
+        // Appended instead of replacing the extension, which could be the one of another file.
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        fs::write(&tmp, &file)?;
+        fs::rename(&tmp, path)?;
+        log::debug!("Saved {} bytes of pipeline cache to {:?}", data.len(), path);
+        Ok(())
+    }
+    /// Merges the pipeline cache saved at `path` into the cache of the device.
+    ///
+    /// Returns whether anything was loaded. A missing file, a file saved for another device or
+    /// driver and a corrupt file are skipped, as feeding them to the driver is not safe.
+    pub fn load_pipeline_cache(&self, path: impl AsRef<Path>) -> Result<bool, PipelineCacheError> {
+        let path = path.as_ref();
+        let cache = self.pipeline_cache().ok_or(PipelineCacheError::NoCache)?;
+        let file = match fs::read(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(err) => return Err(err.into()),
+        };
+
+        if file.len() < HEADER_LEN {
+            log::warn!("Ignoring truncated pipeline cache {:?}", path);
+            return Ok(false);
+        }
+        let (header, data) = file.split_at(HEADER_LEN);
+        if header != self.pipeline_cache_header(data) {
+            log::warn!(
+                "Ignoring pipeline cache {:?}, it is corrupt or from another device or driver",
+                path
+            );
+            return Ok(false);
+        }
+
+        // Safe as the header matches this device and driver and the checksum the data.
+        let loaded = unsafe { vulkano::PipelineCache::with_data(self.handle().clone(), data)? };
+        cache.merge(&[&loaded])?;
+        log::debug!(
+            "Loaded {} bytes of pipeline cache from {:?}",
+            data.len(),
+            path
+        );
+        Ok(true)
+    }
+    // Header of a file holding `data` saved for this device.
+    fn pipeline_cache_header(&self, data: &[u8]) -> Vec<u8> {
+        let properties = self.physical_device().properties();
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(&MAGIC);
+        header.extend_from_slice(&VERSION.to_le_bytes());
+        header.extend_from_slice(&properties.vendor_id.to_le_bytes());
+        header.extend_from_slice(&properties.device_id.to_le_bytes());
+        header.extend_from_slice(&properties.driver_version.to_le_bytes());
+        header.extend_from_slice(&properties.pipeline_cache_uuid);
+        header.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        header.extend_from_slice(&checksum(data).to_le_bytes());
+        header
+    }
+}
+
+// FNV-1a, only to detect files damaged on disk.
+fn checksum(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[derive(Debug, From)]
+pub enum PipelineCacheError {
+    /// The device could not create its pipeline cache.
+    #[from(ignore)]
+    NoCache,
+    Io(io::Error),
+    OutOfMemory(vulkano::OomError),
+}
+
+impl fmt::Display for PipelineCacheError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoCache => write!(f, "the device has no pipeline cache"),
+            Self::Io(err) => write!(f, "failed to access the pipeline cache file: {}", err),
+            Self::OutOfMemory(err) => write!(f, "out of memory: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for PipelineCacheError {}
@@ -0,0 +1,57 @@
+use std::sync::Arc;
+
+use super::{Adapter, ClearValues, Surface, SwapchainError};
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::format::*;
+    pub use vulkano::image::*;
+    pub use vulkano::render_pass::*;
+}
+
+/// Depth formats in order of preference. Every device supports `D16_UNORM` for depth buffers.
+pub const DEPTH_FORMATS: [vulkano::Format; 3] = [
+    vulkano::Format::D32_SFLOAT,
+    vulkano::Format::D24_UNORM_S8_UINT,
+    vulkano::Format::D16_UNORM,
+];
+
+/// The first of `DEPTH_FORMATS` the adapter supports for depth buffers.
+pub fn depth_format(adapter: &Adapter) -> vulkano::Format {
+    adapter
+        .find_supported_format(
+            &DEPTH_FORMATS,
+            vulkano::ImageUsage {
+                depth_stencil_attachment: true,
+                ..vulkano::ImageUsage::none()
+            },
+        )
+        .unwrap_or(vulkano::Format::D16_UNORM)
+}
+
+/// Render pass for forward rendering into the swapchain of `surface`: the color attachment is
+/// cleared and stored, the depth buffer cleared and discarded.
+///
+/// The swapchain manages the depth buffer, so `Surface::framebuffer` works with the render pass.
+/// Without `depth_format`, the first of `DEPTH_FORMATS` the adapter supports is used. If the
+/// swapchain has another depth format it is recreated with this one. The clear values clear the
+/// `"color"` attachment to black and the `"depth"` attachment to the far plane.
+pub fn forward_render_pass<W: Send + Sync + 'static>(
+    adapter: &Adapter,
+    surface: &mut Surface<W>,
+    depth_format: Option<vulkano::Format>,
+) -> Result<(Arc<vulkano::RenderPass>, ClearValues), SwapchainError> {
+    let depth_format = depth_format.unwrap_or_else(|| self::depth_format(adapter));
+    if surface.depth_format() != Some(depth_format) {
+        surface.set_depth_format(Some(depth_format))?;
+        surface.recreate_swapchain()?;
+    }
+    let render_pass = surface.create_render_pass()?;
+    let clear_values = surface
+        .render_pass_builder()
+        .ok_or(SwapchainError::NoSwapchain)?
+        .clear_values()
+        .color("color", [0.0, 0.0, 0.0, 1.0])
+        .depth("depth", 1.0);
+    Ok((render_pass, clear_values))
+}
@@ -0,0 +1,165 @@
+use derive_more::*;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use super::{PresentError, Surface, SurfaceImage, WaitIdleError};
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::command_buffer::*;
+    pub use vulkano::device::*;
+    pub use vulkano::sync::*;
+    pub use vulkano::*;
+}
+
+use vulkano::GpuFuture;
+
+/// Queue that serializes submissions made through it, so work submitted from different threads
+/// reaches the GPU in the order the submit calls returned.
+///
+/// Clones share the same lock. Derefs to the `Arc<vulkano::Queue>`.
+#[derive(Clone, Deref)]
+pub struct Queue {
+    #[deref]
+    queue: Arc<vulkano::Queue>,
+    lock: Arc<Mutex<()>>,
+}
+
+impl Queue {
+    pub fn new(queue: Arc<vulkano::Queue>) -> Self {
+        Self {
+            queue,
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+    pub fn handle(&self) -> &Arc<vulkano::Queue> {
+        &self.queue
+    }
+    /// Blocks other submissions through this queue while the guard is alive.
+    pub(crate) fn lock(&self) -> MutexGuard<'_, ()> {
+        // The lock does not protect any data, so a panic while holding it does no harm.
+        self.lock.lock().unwrap_or_else(|err| err.into_inner())
+    }
+    /// Submits `command_buffer` and returns a future that is signaled once it finished executing.
+    pub fn submit<Cb>(&self, command_buffer: Cb) -> Result<SubmitFuture, SubmitError>
+    where
+        Cb: vulkano::PrimaryCommandBuffer + 'static,
+    {
+        self.submit_after(vulkano::now(self.queue.device().clone()), command_buffer)
+    }
+    /// Submits `command_buffer` to be executed after `future`.
+    pub fn submit_after<F, Cb>(
+        &self,
+        future: F,
+        command_buffer: Cb,
+    ) -> Result<SubmitFuture, SubmitError>
+    where
+        F: GpuFuture + 'static,
+        Cb: vulkano::PrimaryCommandBuffer + 'static,
+    {
+        let _lock = self.lock();
+        let future = future
+            .then_execute(self.queue.clone(), command_buffer)?
+            .boxed()
+            .then_signal_fence_and_flush()?;
+        Ok(SubmitFuture {
+            queue: self.clone(),
+            future,
+        })
+    }
+    /// Blocks until all work submitted to this queue has finished.
+    ///
+    /// A lost device is reported as an error instead of panicking.
+    pub fn wait_idle(&self) -> Result<(), WaitIdleError> {
+        let _lock = self.lock();
+        let result = {
+            let handle = vulkano::SynchronizedVulkanObject::internal_object_guard(&*self.queue);
+            let fns = self.queue.device().fns();
+            unsafe { (fns.v1_0.queue_wait_idle)(*handle) }
+        };
+        match result {
+            ash::vk::Result::SUCCESS => Ok(()),
+            ash::vk::Result::ERROR_OUT_OF_HOST_MEMORY => {
+                Err(vulkano::OomError::OutOfHostMemory.into())
+            }
+            ash::vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => {
+                Err(vulkano::OomError::OutOfDeviceMemory.into())
+            }
+            _ => Err(WaitIdleError::DeviceLost),
+        }
+    }
+}
+
+/// Work submitted with `Queue::submit`, whose fence is signaled once it finished executing.
+///
+/// Dropping the future blocks until the work finished, since the resources it uses have to stay
+/// alive until then. `into_future` gives access to the vulkano future, e.g. to chain more work
+/// after it without waiting.
+pub struct SubmitFuture {
+    queue: Queue,
+    future: vulkano::FenceSignalFuture<Box<dyn GpuFuture>>,
+}
+
+impl SubmitFuture {
+    /// The queue the work was submitted to.
+    pub fn queue(&self) -> &Queue {
+        &self.queue
+    }
+    /// Whether the work finished, without blocking.
+    ///
+    /// `false` if the fence could not be queried, `wait` reports the error then.
+    pub fn is_complete(&self) -> bool {
+        self.wait(Some(Duration::ZERO)).unwrap_or(false)
+    }
+    /// Blocks until the work finished or `timeout` passed, returning whether it finished.
+    ///
+    /// `None` waits without a timeout.
+    pub fn wait(&self, timeout: Option<Duration>) -> Result<bool, vulkano::FlushError> {
+        match self.future.wait(timeout) {
+            Ok(()) => Ok(true),
+            Err(vulkano::FlushError::Timeout) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+    /// Presents `image` of `surface` on the queue of the work once it finished, see
+    /// `Surface::present`.
+    pub fn and_then_present<W>(
+        self,
+        surface: &Surface<W>,
+        image: SurfaceImage<W>,
+    ) -> Result<SubmitFuture, PresentError>
+    where
+        W: Send + Sync + 'static,
+    {
+        let future = surface.present(&self.queue, image, self.future)?;
+        Ok(SubmitFuture {
+            queue: self.queue,
+            future,
+        })
+    }
+    pub fn future(&self) -> &vulkano::FenceSignalFuture<Box<dyn GpuFuture>> {
+        &self.future
+    }
+    /// The vulkano future, for chaining with `GpuFuture` directly.
+    pub fn into_future(self) -> vulkano::FenceSignalFuture<Box<dyn GpuFuture>> {
+        self.future
+    }
+}
+
+#[derive(Debug, From)]
+pub enum SubmitError {
+    Exec(vulkano::CommandBufferExecError),
+    Flush(vulkano::FlushError),
+}
+
+impl fmt::Display for SubmitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Exec(err) => write!(f, "failed to execute command buffer: {}", err),
+            Self::Flush(err) => write!(f, "failed to submit command buffer: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for SubmitError {}
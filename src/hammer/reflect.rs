@@ -0,0 +1,434 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::Device;
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::descriptor_set::layout::DescriptorType;
+    pub use vulkano::format::Format;
+    pub use vulkano::pipeline::graphics::vertex_input::*;
+    pub use vulkano::pipeline::layout::PushConstantRange;
+    pub use vulkano::shader::*;
+}
+
+/// What a shader entry point expects from a pipeline, reflected from its SPIR-V by vulkano.
+#[derive(Clone, Debug, Default)]
+pub struct ShaderInterface {
+    /// Descriptor bindings by set, ordered by binding.
+    pub descriptor_bindings: BTreeMap<u32, Vec<DescriptorBinding>>,
+    pub push_constant_ranges: Vec<vulkano::PushConstantRange>,
+    /// Inputs of a vertex shader ordered by location, empty for other stages.
+    pub vertex_inputs: Vec<VertexInput>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DescriptorBinding {
+    pub binding: u32,
+    /// Types the shader can use the binding as, usually one.
+    pub descriptor_types: Vec<vulkano::DescriptorType>,
+    pub descriptor_count: u32,
+    pub stages: vulkano::ShaderStages,
+}
+
+/// An input of a vertex shader. Arrays and matrices have an input per element or column.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VertexInput {
+    pub location: u32,
+    pub name: Option<String>,
+    pub format: vulkano::Format,
+}
+
+impl ShaderInterface {
+    pub fn of(entry_point: &vulkano::EntryPoint) -> Self {
+        let stages =
+            vulkano::ShaderStages::from(vulkano::ShaderStage::from(*entry_point.execution()));
+        let mut descriptor_bindings = BTreeMap::<u32, Vec<DescriptorBinding>>::new();
+        for ((set, binding), requirements) in entry_point.descriptor_requirements() {
+            descriptor_bindings
+                .entry(set)
+                .or_default()
+                .push(DescriptorBinding {
+                    binding,
+                    descriptor_types: requirements.descriptor_types.clone(),
+                    descriptor_count: requirements.descriptor_count,
+                    stages,
+                });
+        }
+        for bindings in descriptor_bindings.values_mut() {
+            bindings.sort_by_key(|binding| binding.binding);
+        }
+
+        let mut vertex_inputs = Vec::new();
+        if *entry_point.execution() == vulkano::ShaderExecution::Vertex {
+            for element in entry_point.input_interface().elements() {
+                let format = input_format(&element.ty);
+                let locations = if element.ty.is_64bit && element.ty.num_components > 2 {
+                    2
+                } else {
+                    1
+                };
+                for index in 0..element.ty.num_elements {
+                    vertex_inputs.push(VertexInput {
+                        location: element.location + index * locations,
+                        name: element.name.as_ref().map(|name| name.to_string()),
+                        format,
+                    });
+                }
+            }
+            vertex_inputs.sort_by_key(|input| input.location);
+        }
+
+        Self {
+            descriptor_bindings,
+            push_constant_ranges: entry_point
+                .push_constant_requirements()
+                .cloned()
+                .into_iter()
+                .collect(),
+            vertex_inputs,
+        }
+    }
+}
+
+impl Device {
+    /// Reflects the interface of the `main` entry point of `module`.
+    pub fn reflect_shader(
+        &self,
+        module: &vulkano::ShaderModule,
+    ) -> Result<ShaderInterface, ReflectError> {
+        let entry_point = module
+            .entry_point("main")
+            .ok_or(ReflectError::NoEntryPoint("main"))?;
+        Ok(ShaderInterface::of(&entry_point))
+    }
+}
+
+// Format of a single element of a shader input of type `ty`.
+fn input_format(ty: &vulkano::ShaderInterfaceEntryType) -> vulkano::Format {
+    use vulkano::Format as F;
+    use vulkano::ShaderScalarType as T;
+    let formats = match (ty.base_type, ty.is_64bit) {
+        (T::Float, false) => [
+            F::R32_SFLOAT,
+            F::R32G32_SFLOAT,
+            F::R32G32B32_SFLOAT,
+            F::R32G32B32A32_SFLOAT,
+        ],
+        (T::Sint, false) => [
+            F::R32_SINT,
+            F::R32G32_SINT,
+            F::R32G32B32_SINT,
+            F::R32G32B32A32_SINT,
+        ],
+        (T::Uint, false) => [
+            F::R32_UINT,
+            F::R32G32_UINT,
+            F::R32G32B32_UINT,
+            F::R32G32B32A32_UINT,
+        ],
+        (T::Float, true) => [
+            F::R64_SFLOAT,
+            F::R64G64_SFLOAT,
+            F::R64G64B64_SFLOAT,
+            F::R64G64B64A64_SFLOAT,
+        ],
+        (T::Sint, true) => [
+            F::R64_SINT,
+            F::R64G64_SINT,
+            F::R64G64B64_SINT,
+            F::R64G64B64A64_SINT,
+        ],
+        (T::Uint, true) => [
+            F::R64_UINT,
+            F::R64G64_UINT,
+            F::R64G64B64_UINT,
+            F::R64G64B64A64_UINT,
+        ],
+    };
+    formats[ty.num_components.clamp(1, 4) as usize - 1]
+}
+
+/// Members of a vertex type by the first shader location they feed, see
+/// `VertexLayout::from_reflection`.
+#[derive(Clone, Debug)]
+pub(crate) struct VertexLocations {
+    stride: u32,
+    // Ordered by location.
+    members: Vec<LocatedMember>,
+}
+
+#[derive(Clone, Debug)]
+struct LocatedMember {
+    location: u32,
+    // Offset, type and array size, `None` if the vertex type has no member of the name.
+    info: Option<(usize, vulkano::VertexMemberTy, usize)>,
+}
+
+impl VertexLocations {
+    pub(crate) fn of<V: vulkano::Vertex>(members: &[(u32, &str)]) -> Self {
+        let mut members: Vec<_> = members
+            .iter()
+            .map(|&(location, name)| LocatedMember {
+                location,
+                info: V::member(name).map(|info| (info.offset, info.ty, info.array_size)),
+            })
+            .collect();
+        members.sort_by_key(|member| member.location);
+        Self {
+            stride: std::mem::size_of::<V>() as u32,
+            members,
+        }
+    }
+    /// The vertex input reading the reflected `inputs` of a vertex shader from a single buffer
+    /// of the vertex type, or the inputs that do not match their member.
+    ///
+    /// A member feeds the consecutive locations starting at its own until its size is used up, so
+    /// a matrix feeds one location per column. Members at locations the shader does not read
+    /// are ignored.
+    pub(crate) fn vertex_input_state(
+        &self,
+        inputs: &[VertexInput],
+    ) -> Result<vulkano::VertexInputState, Vec<VertexInputMismatch>> {
+        let mut mismatches = Vec::new();
+        let mut attributes = Vec::new();
+        let mut fed_locations = Vec::new();
+        for member in &self.members {
+            let size = member
+                .info
+                .map_or(0, |(_, ty, array_size)| member_size(ty) * array_size);
+            let mut fed = Vec::new();
+            let mut fed_size = 0;
+            while let Some(input) = inputs
+                .iter()
+                .find(|input| input.location == member.location + fed.len() as u32)
+            {
+                fed.push(input);
+                fed_size += input.format.block_size().unwrap_or(0) as usize;
+                if fed_size >= size {
+                    break;
+                }
+            }
+            fed_locations.extend(fed.iter().map(|input| input.location));
+            let first_input = match fed.first() {
+                Some(input) => input,
+                None => continue,
+            };
+            let (offset, ty, array_size) = match member.info {
+                Some(info) => info,
+                None => {
+                    mismatches.push(mismatch(first_input, None));
+                    continue;
+                }
+            };
+            let same_type = fed.iter().all(|input| {
+                input
+                    .format
+                    .type_color()
+                    .map(vulkano::ShaderScalarType::from)
+                    == Some(member_scalar_type(ty))
+            });
+            if fed_size != size || !same_type {
+                mismatches.push(mismatch(first_input, Some((ty, array_size))));
+                continue;
+            }
+            let mut offset = offset as u32;
+            for input in fed {
+                attributes.push((
+                    input.location,
+                    vulkano::VertexInputAttributeDescription {
+                        binding: 0,
+                        format: input.format,
+                        offset,
+                    },
+                ));
+                offset += input.format.block_size().unwrap_or(0) as u32;
+            }
+        }
+        for input in inputs {
+            if !fed_locations.contains(&input.location) {
+                mismatches.push(mismatch(input, None));
+            }
+        }
+        if !mismatches.is_empty() {
+            mismatches.sort_by_key(|mismatch| mismatch.location);
+            return Err(mismatches);
+        }
+        Ok(vulkano::VertexInputState::new()
+            .binding(
+                0,
+                vulkano::VertexInputBindingDescription {
+                    stride: self.stride,
+                    input_rate: vulkano::VertexInputRate::Vertex,
+                },
+            )
+            .attributes(attributes))
+    }
+}
+
+/// A vertex shader input that does not match its member of the Rust vertex type, see
+/// `VertexLayout::from_reflection`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VertexInputMismatch {
+    pub location: u32,
+    pub name: Option<String>,
+    /// Format of the shader input.
+    pub format: vulkano::Format,
+    /// Type and array size of the member given for the location, `None` if there is none or the
+    /// vertex type has no member of its name.
+    pub member: Option<(vulkano::VertexMemberTy, usize)>,
+}
+
+impl fmt::Display for VertexInputMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = self.name.as_deref().unwrap_or("<unnamed>");
+        write!(
+            f,
+            "location {} `{}` is {:?}",
+            self.location, name, self.format
+        )?;
+        match self.member {
+            Some((ty, array_size)) => write!(f, " but the member is {:?} x {}", ty, array_size),
+            None => write!(f, " but no member of the vertex type is given for it"),
+        }
+    }
+}
+
+fn mismatch(
+    input: &VertexInput,
+    member: Option<(vulkano::VertexMemberTy, usize)>,
+) -> VertexInputMismatch {
+    VertexInputMismatch {
+        location: input.location,
+        name: input.name.clone(),
+        format: input.format,
+        member,
+    }
+}
+
+fn member_size(ty: vulkano::VertexMemberTy) -> usize {
+    use vulkano::VertexMemberTy as T;
+    match ty {
+        T::I8 | T::U8 => 1,
+        T::I16 | T::U16 => 2,
+        T::I32 | T::U32 | T::F32 => 4,
+        T::F64 => 8,
+    }
+}
+
+fn member_scalar_type(ty: vulkano::VertexMemberTy) -> vulkano::ShaderScalarType {
+    use vulkano::VertexMemberTy as T;
+    match ty {
+        T::I8 | T::I16 | T::I32 => vulkano::ShaderScalarType::Sint,
+        T::U8 | T::U16 | T::U32 => vulkano::ShaderScalarType::Uint,
+        T::F32 | T::F64 => vulkano::ShaderScalarType::Float,
+    }
+}
+
+#[derive(Debug)]
+pub enum ReflectError {
+    /// The module has no entry point with this name.
+    NoEntryPoint(&'static str),
+}
+
+impl fmt::Display for ReflectError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoEntryPoint(name) => write!(f, "the shader has no entry point `{}`", name),
+        }
+    }
+}
+
+impl std::error::Error for ReflectError {}
+
+#[cfg(test)]
+mod tests {
+    use super::vulkano::Format;
+    use super::*;
+    use bytemuck::{Pod, Zeroable};
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Default, Zeroable, Pod)]
+    struct Vertex {
+        position: [f32; 3],
+        uv: [f32; 2],
+        // A mat2 in the shader.
+        transform: [f32; 4],
+    }
+    ::vulkano::impl_vertex!(Vertex, position, uv, transform);
+
+    fn input(location: u32, name: &str, format: Format) -> VertexInput {
+        VertexInput {
+            location,
+            name: Some(name.to_owned()),
+            format,
+        }
+    }
+
+    #[test]
+    fn matches_members_by_location() {
+        // The names of the shader differ from the members.
+        let inputs = [
+            input(0, "in_position", Format::R32G32B32_SFLOAT),
+            input(1, "in_uv", Format::R32G32_SFLOAT),
+            input(2, "in_transform", Format::R32G32_SFLOAT),
+            input(3, "in_transform", Format::R32G32_SFLOAT),
+        ];
+        let locations =
+            VertexLocations::of::<Vertex>(&[(0, "position"), (1, "uv"), (2, "transform")]);
+        let state = locations.vertex_input_state(&inputs).unwrap();
+        assert_eq!(state.bindings[&0].stride, 36);
+        let attributes: Vec<_> = (0..4)
+            .map(|location| {
+                let attribute = &state.attributes[&location];
+                (attribute.binding, attribute.format, attribute.offset)
+            })
+            .collect();
+        assert_eq!(
+            attributes,
+            [
+                (0, Format::R32G32B32_SFLOAT, 0),
+                (0, Format::R32G32_SFLOAT, 12),
+                (0, Format::R32G32_SFLOAT, 20),
+                (0, Format::R32G32_SFLOAT, 28),
+            ]
+        );
+    }
+
+    #[test]
+    fn unused_members_are_ignored() {
+        let inputs = [input(1, "in_uv", Format::R32G32_SFLOAT)];
+        let locations = VertexLocations::of::<Vertex>(&[(0, "position"), (1, "uv")]);
+        let state = locations.vertex_input_state(&inputs).unwrap();
+        assert_eq!(state.attributes.len(), 1);
+        assert_eq!(state.attributes[&1].offset, 12);
+    }
+
+    #[test]
+    fn reports_mismatches_by_location() {
+        let inputs = [
+            input(0, "in_id", Format::R32_UINT),
+            input(1, "in_position", Format::R32G32B32_SFLOAT),
+            input(2, "in_uv", Format::R32G32B32A32_SFLOAT),
+            input(3, "in_color", Format::R32G32B32A32_SFLOAT),
+        ];
+        let locations = VertexLocations::of::<Vertex>(&[(1, "position"), (2, "uv"), (3, "color")]);
+        let mismatches = locations.vertex_input_state(&inputs).unwrap_err();
+        let reported: Vec<_> = mismatches
+            .iter()
+            .map(|mismatch| (mismatch.location, mismatch.member))
+            .collect();
+        assert_eq!(
+            reported,
+            [
+                // No member is given for location 0.
+                (0, None),
+                (2, Some((super::vulkano::VertexMemberTy::F32, 2))),
+                // `Vertex` has no member `color`.
+                (3, None),
+            ]
+        );
+        assert_eq!(mismatches[0].name.as_deref(), Some("in_id"));
+        assert_eq!(mismatches[1].format, Format::R32G32B32A32_SFLOAT);
+    }
+}
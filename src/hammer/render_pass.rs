@@ -0,0 +1,815 @@
+use std::fmt;
+use std::sync::Arc;
+
+use super::RenderTarget;
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::command_buffer::pool::*;
+    pub use vulkano::command_buffer::*;
+    pub use vulkano::device::*;
+    pub use vulkano::format::*;
+    pub use vulkano::image::*;
+    pub use vulkano::render_pass::*;
+    pub use vulkano::sync::*;
+}
+
+/// Format, load and store operations and sample count of an attachment for `RenderPassBuilder`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AttachmentDesc {
+    /// Name to set the clear value of the attachment by, see `ClearValues`.
+    pub name: Option<&'static str>,
+    pub format: vulkano::Format,
+    pub load: vulkano::LoadOp,
+    pub store: vulkano::StoreOp,
+    pub samples: vulkano::SampleCount,
+}
+
+impl AttachmentDesc {
+    /// Cleared and stored, with one sample per pixel.
+    pub fn new(format: vulkano::Format) -> Self {
+        Self {
+            name: None,
+            format,
+            load: vulkano::LoadOp::Clear,
+            store: vulkano::StoreOp::Store,
+            samples: vulkano::SampleCount::Sample1,
+        }
+    }
+    /// The format and sample count of the images of `target`, queried when the render pass is
+    /// built so it follows format changes on recreation.
+    pub fn from_target<T: RenderTarget>(target: &T) -> Result<Self, RenderPassBuildError> {
+        let format = target
+            .image_format()
+            .ok_or(RenderPassBuildError::NoTargetFormat)?;
+        Ok(Self {
+            samples: target
+                .sample_count()
+                .unwrap_or(vulkano::SampleCount::Sample1),
+            ..Self::new(format)
+        })
+    }
+    pub fn name(self, name: &'static str) -> Self {
+        Self {
+            name: Some(name),
+            ..self
+        }
+    }
+    pub fn load(self, load: vulkano::LoadOp) -> Self {
+        Self { load, ..self }
+    }
+    pub fn store(self, store: vulkano::StoreOp) -> Self {
+        Self { store, ..self }
+    }
+    pub fn samples(self, samples: vulkano::SampleCount) -> Self {
+        Self { samples, ..self }
+    }
+}
+
+/// Attachments used by one subpass of a `RenderPassBuilder`, as indices into the attachments of
+/// the render pass.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SubpassDesc {
+    pub color: Vec<u32>,
+    /// Attachments read with `subpassLoad`, which an earlier subpass has to write or the render
+    /// pass has to load.
+    pub input: Vec<u32>,
+    pub depth: Option<u32>,
+    /// Attachments the color attachments are resolved into, either none or one per color
+    /// attachment.
+    pub resolve: Vec<u32>,
+}
+
+/// Builds a render pass from formats known at runtime, instead of the `single_pass_renderpass!`
+/// and `ordered_passes_renderpass!` macros.
+///
+/// The attachments are ordered color, resolve, depth and then those added with `attachment`, the
+/// order framebuffers have to list them in. Without any `subpass`, the render pass has a single
+/// subpass drawing into the color, resolve and depth attachments.
+#[derive(Clone, Debug)]
+pub struct RenderPassBuilder {
+    color: AttachmentDesc,
+    resolve: bool,
+    depth: Option<AttachmentDesc>,
+    attachments: Vec<AttachmentDesc>,
+    subpasses: Vec<SubpassDesc>,
+}
+
+impl RenderPassBuilder {
+    pub fn new(color: AttachmentDesc) -> Self {
+        Self {
+            color,
+            resolve: false,
+            depth: None,
+            attachments: Vec::new(),
+            subpasses: Vec::new(),
+        }
+    }
+    /// Color attachment matching the images of `target`, resolved into them if it has more than
+    /// one sample per pixel, like `Swapchain::create_render_pass`.
+    pub fn for_target<T: RenderTarget>(target: &T) -> Result<Self, RenderPassBuildError> {
+        let color = AttachmentDesc::from_target(target)?;
+        let resolve = color.samples != vulkano::SampleCount::Sample1;
+        Ok(Self {
+            resolve,
+            ..Self::new(color)
+        })
+    }
+    /// Resolves the multisampled color attachment into a single sampled one of the same format,
+    /// which is stored while the multisampled one is discarded.
+    pub fn resolve(mut self) -> Self {
+        self.resolve = true;
+        self
+    }
+    pub fn depth(mut self, depth: AttachmentDesc) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+    /// Adds an attachment after the color, resolve and depth attachments, e.g. for a G-buffer.
+    pub fn attachment(mut self, attachment: AttachmentDesc) -> Self {
+        self.attachments.push(attachment);
+        self
+    }
+    /// Index the next attachment added with `attachment` will have.
+    pub fn next_attachment(&self) -> u32 {
+        (1 + self.resolve as usize + self.depth.is_some() as usize + self.attachments.len()) as u32
+    }
+    /// Adds a subpass, executed after the subpasses added before.
+    ///
+    /// Every subpass depends on the previous one, so it can read what the previous subpasses
+    /// wrote as input attachments.
+    pub fn subpass(mut self, subpass: SubpassDesc) -> Self {
+        self.subpasses.push(subpass);
+        self
+    }
+    /// Clear values for the attachments of the render pass, to be set by name or index.
+    ///
+    /// Unnamed color, resolve and depth attachments are named `"color"`, `"resolve"` and
+    /// `"depth"`.
+    pub fn clear_values(&self) -> ClearValues {
+        let (attachments, _) = self.attachments();
+        ClearValues {
+            attachments: attachments
+                .into_iter()
+                .map(|attachment| (attachment, None))
+                .collect(),
+            error: None,
+        }
+    }
+    /// All attachments in the order of the render pass and the subpass used without `subpass`.
+    fn attachments(&self) -> (Vec<AttachmentDesc>, SubpassDesc) {
+        let color = AttachmentDesc {
+            name: self.color.name.or(Some("color")),
+            ..self.color
+        };
+        let mut attachments = vec![color];
+        let mut default_subpass = SubpassDesc {
+            color: vec![0],
+            ..Default::default()
+        };
+        if self.resolve {
+            // Every pixel of the resolve target is written, so its contents are not loaded.
+            default_subpass.resolve = vec![attachments.len() as u32];
+            attachments.push(AttachmentDesc {
+                name: Some("resolve"),
+                load: vulkano::LoadOp::DontCare,
+                store: vulkano::StoreOp::Store,
+                samples: vulkano::SampleCount::Sample1,
+                ..color
+            });
+        }
+        if let Some(depth) = self.depth {
+            default_subpass.depth = Some(attachments.len() as u32);
+            attachments.push(AttachmentDesc {
+                name: depth.name.or(Some("depth")),
+                ..depth
+            });
+        }
+        attachments.extend(self.attachments.iter().copied());
+        (attachments, default_subpass)
+    }
+    /// Checks the formats, sample counts and attachment indices and creates the render pass.
+    pub fn build(
+        &self,
+        device: Arc<vulkano::Device>,
+    ) -> Result<Arc<vulkano::RenderPass>, RenderPassBuildError> {
+        let color = self.color;
+        if color.format.type_color().is_none() {
+            return Err(RenderPassBuildError::NotColor(color.format));
+        }
+        if self.resolve && color.samples == vulkano::SampleCount::Sample1 {
+            return Err(RenderPassBuildError::ResolveSingleSampled);
+        }
+        if let Some(depth) = self.depth {
+            if depth.format.type_depth().is_none() {
+                return Err(RenderPassBuildError::NotDepth(depth.format));
+            }
+            if depth.samples != color.samples {
+                return Err(RenderPassBuildError::SampleCountMismatch {
+                    color: color.samples,
+                    depth: depth.samples,
+                });
+            }
+        }
+
+        let (attachments, default_subpass) = self.attachments();
+        let subpasses = match self.subpasses.is_empty() {
+            true => vec![default_subpass],
+            false => self.subpasses.clone(),
+        };
+
+        // Layout of every attachment in the first and the last subpass using it, like the macros
+        // of vulkano choose them.
+        let mut layouts = vec![None; attachments.len()];
+        // Attachments with contents, either loaded or written by an earlier subpass.
+        let mut written: Vec<bool> = attachments
+            .iter()
+            .map(|attachment| attachment.load == vulkano::LoadOp::Load)
+            .collect();
+        let mut descriptions = Vec::with_capacity(subpasses.len());
+        for (index, subpass) in subpasses.iter().enumerate() {
+            let index = index as u32;
+            let mut reference = |attachment: u32, layout| -> Result<_, RenderPassBuildError> {
+                let (_, last) = layouts
+                    .get_mut(attachment as usize)
+                    .ok_or(RenderPassBuildError::NoAttachment {
+                        subpass: index,
+                        attachment,
+                    })?
+                    .get_or_insert((layout, layout));
+                *last = layout;
+                Ok(Some(vulkano::AttachmentReference {
+                    attachment,
+                    layout,
+                    ..Default::default()
+                }))
+            };
+
+            let input_attachments = subpass
+                .input
+                .iter()
+                .map(|&attachment| {
+                    let reference =
+                        reference(attachment, vulkano::ImageLayout::ShaderReadOnlyOptimal)?;
+                    if !written[attachment as usize] {
+                        return Err(RenderPassBuildError::InputNotWritten {
+                            subpass: index,
+                            attachment,
+                        });
+                    }
+                    Ok(reference)
+                })
+                .collect::<Result<_, _>>()?;
+            let color_attachments = subpass
+                .color
+                .iter()
+                .map(|&attachment| {
+                    reference(attachment, vulkano::ImageLayout::ColorAttachmentOptimal)
+                })
+                .collect::<Result<_, _>>()?;
+            if !subpass.resolve.is_empty() && subpass.resolve.len() != subpass.color.len() {
+                return Err(RenderPassBuildError::ResolveCount { subpass: index });
+            }
+            let resolve_attachments = subpass
+                .resolve
+                .iter()
+                .map(|&attachment| reference(attachment, vulkano::ImageLayout::TransferDstOptimal))
+                .collect::<Result<_, _>>()?;
+            let depth_stencil_attachment = match subpass.depth {
+                Some(attachment) => reference(
+                    attachment,
+                    vulkano::ImageLayout::DepthStencilAttachmentOptimal,
+                )?,
+                None => None,
+            };
+
+            for &attachment in subpass.color.iter().chain(&subpass.resolve) {
+                let format = attachments[attachment as usize].format;
+                if format.type_color().is_none() {
+                    return Err(RenderPassBuildError::NotColor(format));
+                }
+            }
+            if let Some(attachment) = subpass.depth {
+                let format = attachments[attachment as usize].format;
+                if format.type_depth().is_none() {
+                    return Err(RenderPassBuildError::NotDepth(format));
+                }
+            }
+            for &attachment in subpass
+                .color
+                .iter()
+                .chain(&subpass.resolve)
+                .chain(&subpass.depth)
+            {
+                written[attachment as usize] = true;
+            }
+
+            descriptions.push(vulkano::SubpassDescription {
+                input_attachments,
+                color_attachments,
+                resolve_attachments,
+                depth_stencil_attachment,
+                ..Default::default()
+            });
+        }
+
+        let attachments = attachments
+            .iter()
+            .zip(layouts)
+            .enumerate()
+            .map(|(index, (desc, layouts))| {
+                let (initial_layout, final_layout) =
+                    layouts.ok_or(RenderPassBuildError::UnusedAttachment(index as u32))?;
+                Ok(vulkano::AttachmentDescription {
+                    format: Some(desc.format),
+                    samples: desc.samples,
+                    load_op: desc.load,
+                    store_op: desc.store,
+                    stencil_load_op: desc.load,
+                    stencil_store_op: desc.store,
+                    initial_layout,
+                    final_layout,
+                    ..Default::default()
+                })
+            })
+            .collect::<Result<_, RenderPassBuildError>>()?;
+
+        // Conservative dependencies on everything before the render pass and between consecutive
+        // subpasses, like the macros of vulkano create them.
+        let all_graphics = vulkano::PipelineStages {
+            all_graphics: true,
+            ..vulkano::PipelineStages::none()
+        };
+        let dependency = |source_subpass: Option<u32>, destination_subpass| {
+            vulkano::SubpassDependency {
+                source_subpass,
+                destination_subpass: Some(destination_subpass),
+                source_stages: all_graphics,
+                destination_stages: all_graphics,
+                source_access: all_graphics.supported_access(),
+                destination_access: all_graphics.supported_access(),
+                // Between subpasses every pixel only depends on the same pixel.
+                by_region: source_subpass.is_some(),
+                ..Default::default()
+            }
+        };
+        let dependencies = std::iter::once(dependency(None, 0))
+            .chain((1..descriptions.len() as u32).map(|id| dependency(Some(id - 1), id)))
+            .collect();
+
+        Ok(vulkano::RenderPass::new(
+            device,
+            vulkano::RenderPassCreateInfo {
+                attachments,
+                subpasses: descriptions,
+                dependencies,
+                ..Default::default()
+            },
+        )?)
+    }
+}
+
+/// The subpasses of `render_pass` in order, e.g. for `GraphicsPipelineBuilder::render_pass`.
+pub fn subpasses(render_pass: &Arc<vulkano::RenderPass>) -> Vec<vulkano::Subpass> {
+    (0..render_pass.subpasses().len() as u32)
+        .map(|id| vulkano::Subpass::from(render_pass.clone(), id).unwrap())
+        .collect()
+}
+
+#[derive(Debug)]
+pub enum RenderPassBuildError {
+    /// `AttachmentDesc::from_target` was called before the target has images.
+    NoTargetFormat,
+    /// The format of the color attachment has no color aspect.
+    NotColor(vulkano::Format),
+    /// The format of the depth attachment has no depth aspect.
+    NotDepth(vulkano::Format),
+    SampleCountMismatch {
+        color: vulkano::SampleCount,
+        depth: vulkano::SampleCount,
+    },
+    /// `RenderPassBuilder::resolve` was called for a color attachment with one sample.
+    ResolveSingleSampled,
+    /// A subpass refers to an attachment the render pass does not have.
+    NoAttachment {
+        subpass: u32,
+        attachment: u32,
+    },
+    /// A subpass reads an input attachment that is neither loaded nor written by an earlier
+    /// subpass.
+    InputNotWritten {
+        subpass: u32,
+        attachment: u32,
+    },
+    /// A subpass resolves a different number of attachments than it has color attachments.
+    ResolveCount {
+        subpass: u32,
+    },
+    /// No subpass uses the attachment.
+    UnusedAttachment(u32),
+    Creation(vulkano::RenderPassCreationError),
+}
+
+impl From<vulkano::RenderPassCreationError> for RenderPassBuildError {
+    fn from(err: vulkano::RenderPassCreationError) -> Self {
+        Self::Creation(err)
+    }
+}
+
+impl fmt::Display for RenderPassBuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoTargetFormat => write!(f, "the target has no images yet"),
+            Self::NotColor(format) => write!(f, "{:?} is not a color format", format),
+            Self::NotDepth(format) => write!(f, "{:?} is not a depth format", format),
+            Self::SampleCountMismatch { color, depth } => write!(
+                f,
+                "the color attachment has {:?} but the depth attachment {:?}",
+                color, depth
+            ),
+            Self::ResolveSingleSampled => {
+                write!(f, "only multisampled color attachments can be resolved")
+            }
+            Self::NoAttachment {
+                subpass,
+                attachment,
+            } => write!(
+                f,
+                "subpass {} uses attachment {}, which does not exist",
+                subpass, attachment
+            ),
+            Self::InputNotWritten {
+                subpass,
+                attachment,
+            } => write!(
+                f,
+                "subpass {} reads attachment {} before it has been written",
+                subpass, attachment
+            ),
+            Self::ResolveCount { subpass } => write!(
+                f,
+                "subpass {} has to resolve all or none of its color attachments",
+                subpass
+            ),
+            Self::UnusedAttachment(attachment) => {
+                write!(f, "attachment {} is not used by any subpass", attachment)
+            }
+            Self::Creation(err) => write!(f, "failed to create the render pass: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for RenderPassBuildError {}
+
+/// An attachment of a render pass, by name or index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AttachmentKey {
+    Name(String),
+    Index(u32),
+}
+
+impl From<&str> for AttachmentKey {
+    fn from(name: &str) -> Self {
+        Self::Name(name.to_owned())
+    }
+}
+
+impl From<String> for AttachmentKey {
+    fn from(name: String) -> Self {
+        Self::Name(name)
+    }
+}
+
+impl From<u32> for AttachmentKey {
+    fn from(index: u32) -> Self {
+        Self::Index(index)
+    }
+}
+
+impl fmt::Display for AttachmentKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Name(name) => write!(f, "attachment {:?}", name),
+            Self::Index(index) => write!(f, "attachment {}", index),
+        }
+    }
+}
+
+/// Clear values of the attachments of a render pass, set by attachment name or index instead of
+/// by their position in a list, see `RenderPassBuilder::clear_values`.
+///
+/// Setting a value again replaces it, so the same `ClearValues` can be reused with e.g. another
+/// color every frame. Attachments that are not cleared get `ClearValue::None` automatically.
+#[derive(Clone, Debug)]
+pub struct ClearValues {
+    attachments: Vec<(AttachmentDesc, Option<vulkano::ClearValue>)>,
+    // The first invalid value that was set, returned by `build`.
+    error: Option<ClearValuesError>,
+}
+
+impl ClearValues {
+    /// Clears the color attachment `attachment` to `color`.
+    pub fn color(self, attachment: impl Into<AttachmentKey>, color: [f32; 4]) -> Self {
+        self.set(attachment.into(), color.into(), |format| {
+            // Integer formats need integer clear values.
+            matches!(
+                format.type_color(),
+                Some(
+                    vulkano::NumericType::SFLOAT
+                        | vulkano::NumericType::UFLOAT
+                        | vulkano::NumericType::SNORM
+                        | vulkano::NumericType::UNORM
+                        | vulkano::NumericType::SSCALED
+                        | vulkano::NumericType::USCALED
+                        | vulkano::NumericType::SRGB
+                )
+            )
+        })
+    }
+    /// Clears the depth attachment `attachment` to `depth`, and its stencil to 0 if it has one.
+    pub fn depth(self, attachment: impl Into<AttachmentKey>, depth: f32) -> Self {
+        let key = attachment.into();
+        let format = self
+            .find(&key)
+            .map(|index| self.attachments[index].0.format);
+        match format {
+            Some(format) => self.set(key, depth_clear_value(format, depth), |format| {
+                format.type_depth().is_some()
+            }),
+            None => self.set(key, depth.into(), |_| false),
+        }
+    }
+    /// The clear values in the order of the attachments.
+    ///
+    /// Fails if a value was set for an attachment the render pass does not have or does not
+    /// clear, or with a type not matching its format, or if a cleared attachment has no value.
+    pub fn build(&self) -> Result<Vec<vulkano::ClearValue>, ClearValuesError> {
+        if let Some(err) = &self.error {
+            return Err(err.clone());
+        }
+        self.attachments
+            .iter()
+            .enumerate()
+            .map(
+                |(index, (attachment, value))| match (attachment.load, value) {
+                    (vulkano::LoadOp::Clear, Some(value)) => Ok(*value),
+                    (vulkano::LoadOp::Clear, None) => {
+                        Err(ClearValuesError::Missing(key(index, attachment)))
+                    }
+                    (_, _) => Ok(vulkano::ClearValue::None),
+                },
+            )
+            .collect()
+    }
+    /// Begins the render pass of `framebuffer` on `builder`, clearing the attachments with these
+    /// values.
+    ///
+    /// The framebuffer has to belong to the render pass the values were created for.
+    pub fn begin_render_pass<P>(
+        &self,
+        builder: &mut vulkano::AutoCommandBufferBuilder<
+            vulkano::PrimaryAutoCommandBuffer<P::Alloc>,
+            P,
+        >,
+        framebuffer: Arc<vulkano::Framebuffer>,
+        contents: vulkano::SubpassContents,
+    ) -> Result<(), BeginRenderPassError>
+    where
+        P: vulkano::CommandPoolBuilderAlloc,
+    {
+        let expected = framebuffer.render_pass().attachments().len();
+        if expected != self.attachments.len() {
+            return Err(BeginRenderPassError::AttachmentCount {
+                expected,
+                provided: self.attachments.len(),
+            });
+        }
+        builder.begin_render_pass(framebuffer, contents, self.build()?)?;
+        Ok(())
+    }
+    fn find(&self, key: &AttachmentKey) -> Option<usize> {
+        match key {
+            AttachmentKey::Name(name) => self
+                .attachments
+                .iter()
+                .position(|(attachment, _)| attachment.name == Some(name.as_str())),
+            AttachmentKey::Index(index) => {
+                Some(*index as usize).filter(|&index| index < self.attachments.len())
+            }
+        }
+    }
+    fn set(
+        mut self,
+        key: AttachmentKey,
+        value: vulkano::ClearValue,
+        matches_format: impl FnOnce(vulkano::Format) -> bool,
+    ) -> Self {
+        let result = match self.find(&key) {
+            None => Err(ClearValuesError::Unknown(key)),
+            Some(index) => {
+                let attachment = self.attachments[index].0;
+                if attachment.load != vulkano::LoadOp::Clear {
+                    Err(ClearValuesError::NotCleared(key))
+                } else if !matches_format(attachment.format) {
+                    Err(ClearValuesError::WrongType(key, attachment.format))
+                } else {
+                    self.attachments[index].1 = Some(value);
+                    Ok(())
+                }
+            }
+        };
+        if let Err(err) = result {
+            self.error.get_or_insert(err);
+        }
+        self
+    }
+}
+
+/// The name of `attachment` if it has one, otherwise its index.
+fn key(index: usize, attachment: &AttachmentDesc) -> AttachmentKey {
+    match attachment.name {
+        Some(name) => name.into(),
+        None => (index as u32).into(),
+    }
+}
+
+/// Clear value for a depth attachment of `format`, which needs a stencil value as well if the
+/// format has a stencil aspect.
+pub(crate) fn depth_clear_value(format: vulkano::Format, depth: f32) -> vulkano::ClearValue {
+    match format.aspects().stencil {
+        true => vulkano::ClearValue::DepthStencil((depth, 0)),
+        false => vulkano::ClearValue::Depth(depth),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ClearValuesError {
+    /// The render pass has no such attachment.
+    Unknown(AttachmentKey),
+    /// The attachment is loaded or discarded instead of cleared.
+    NotCleared(AttachmentKey),
+    /// A color value was set for a depth attachment or the other way around, or a float color
+    /// for an integer format.
+    WrongType(AttachmentKey, vulkano::Format),
+    /// The attachment is cleared but no value was set.
+    Missing(AttachmentKey),
+}
+
+impl fmt::Display for ClearValuesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Unknown(key) => write!(f, "the render pass has no {}", key),
+            Self::NotCleared(key) => write!(f, "{} is not cleared", key),
+            Self::WrongType(key, format) => write!(
+                f,
+                "the clear value of {} does not match its format {:?}",
+                key, format
+            ),
+            Self::Missing(key) => write!(f, "no clear value for {}", key),
+        }
+    }
+}
+
+impl std::error::Error for ClearValuesError {}
+
+#[derive(Debug)]
+pub enum BeginRenderPassError {
+    /// The framebuffer has a different number of attachments than the clear values.
+    AttachmentCount {
+        expected: usize,
+        provided: usize,
+    },
+    ClearValues(ClearValuesError),
+    Begin(vulkano::BeginRenderPassError),
+}
+
+impl From<ClearValuesError> for BeginRenderPassError {
+    fn from(err: ClearValuesError) -> Self {
+        Self::ClearValues(err)
+    }
+}
+
+impl From<vulkano::BeginRenderPassError> for BeginRenderPassError {
+    fn from(err: vulkano::BeginRenderPassError) -> Self {
+        Self::Begin(err)
+    }
+}
+
+impl fmt::Display for BeginRenderPassError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::AttachmentCount { expected, provided } => write!(
+                f,
+                "the framebuffer has {} attachments but there are {} clear values",
+                expected, provided
+            ),
+            Self::ClearValues(err) => write!(f, "invalid clear values: {}", err),
+            Self::Begin(err) => write!(f, "failed to begin the render pass: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for BeginRenderPassError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLOR: [f32; 4] = [0.1, 0.2, 0.3, 1.0];
+
+    fn gbuffer(depth_first: bool) -> RenderPassBuilder {
+        let builder = RenderPassBuilder::new(AttachmentDesc::new(vulkano::Format::B8G8R8A8_SRGB));
+        let depth =
+            AttachmentDesc::new(vulkano::Format::D32_SFLOAT).store(vulkano::StoreOp::DontCare);
+        let normals = AttachmentDesc::new(vulkano::Format::R16G16B16A16_SFLOAT).name("normals");
+        match depth_first {
+            true => builder.depth(depth).attachment(normals),
+            false => builder.attachment(normals).depth(depth),
+        }
+    }
+
+    #[test]
+    fn clear_values_follow_attachment_order() {
+        let expected = vec![
+            vulkano::ClearValue::Float(COLOR),
+            vulkano::ClearValue::Depth(1.0),
+            vulkano::ClearValue::Float([0.0; 4]),
+        ];
+        for depth_first in [true, false] {
+            let builder = gbuffer(depth_first);
+            let depth_first = builder
+                .clear_values()
+                .depth("depth", 1.0)
+                .color("normals", [0.0; 4])
+                .color("color", COLOR);
+            let color_first = builder
+                .clear_values()
+                .color("color", COLOR)
+                .color(2, [0.0; 4])
+                .depth(1, 1.0);
+            assert_eq!(depth_first.build().unwrap(), expected);
+            assert_eq!(color_first.build().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn clear_values_with_resolve_and_stencil() {
+        let builder = RenderPassBuilder::new(
+            AttachmentDesc::new(vulkano::Format::R8G8B8A8_UNORM)
+                .samples(vulkano::SampleCount::Sample4),
+        )
+        .resolve()
+        .depth(
+            AttachmentDesc::new(vulkano::Format::D24_UNORM_S8_UINT)
+                .samples(vulkano::SampleCount::Sample4),
+        );
+        let values = builder
+            .clear_values()
+            .depth("depth", 0.5)
+            .color("color", COLOR);
+        // The resolve target is not cleared, the stencil is cleared to 0.
+        assert_eq!(
+            values.build().unwrap(),
+            vec![
+                vulkano::ClearValue::Float(COLOR),
+                vulkano::ClearValue::None,
+                vulkano::ClearValue::DepthStencil((0.5, 0)),
+            ]
+        );
+        assert_eq!(
+            builder.clear_values().color("resolve", COLOR).build(),
+            Err(ClearValuesError::NotCleared("resolve".into()))
+        );
+    }
+
+    #[test]
+    fn clear_values_errors() {
+        let builder = gbuffer(true);
+        assert_eq!(
+            builder.clear_values().color("color", COLOR).build(),
+            Err(ClearValuesError::Missing("depth".into()))
+        );
+        assert_eq!(
+            builder.clear_values().color("depth", COLOR).build(),
+            Err(ClearValuesError::WrongType(
+                "depth".into(),
+                vulkano::Format::D32_SFLOAT
+            ))
+        );
+        assert_eq!(
+            builder.clear_values().depth(3, 1.0).build(),
+            Err(ClearValuesError::Unknown(3.into()))
+        );
+        // Setting a value again replaces it.
+        let values = builder
+            .clear_values()
+            .color("color", [0.0; 4])
+            .color("color", COLOR)
+            .depth("depth", 1.0)
+            .color("normals", [0.0; 4]);
+        assert_eq!(
+            values.build().unwrap()[0],
+            vulkano::ClearValue::Float(COLOR)
+        );
+    }
+}
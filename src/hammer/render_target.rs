@@ -0,0 +1,295 @@
+use derive_more::*;
+use std::fmt;
+use std::sync::Arc;
+
+use super::{
+    depth_clear_value, AcquireImageError, AttachmentDesc, FramebufferCache, PresentError, Queue,
+    Rect, RenderPassBuildError, RenderPassBuilder, SwapchainError,
+};
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::device::*;
+    pub use vulkano::format::*;
+    pub use vulkano::image::view::*;
+    pub use vulkano::image::*;
+    pub use vulkano::pipeline::graphics::viewport::*;
+    pub use vulkano::render_pass::*;
+    pub use vulkano::sync::*;
+}
+
+use vulkano::GpuFuture;
+
+/// Something a frame can be rendered into and presented on, implemented by `Surface` and
+/// `OffscreenTarget`.
+///
+/// Keeps a render loop independent of whether it draws to a window or, e.g. in CI, to images
+/// that are read back afterwards.
+pub trait RenderTarget {
+    type Image: TargetImage;
+
+    /// Acquires the next image to render into, see `Surface::acquire`.
+    fn acquire(&mut self) -> Result<Self::Image, AcquireImageError>;
+    /// Format of the images, `None` if there are no images yet.
+    fn image_format(&self) -> Option<vulkano::Format>;
+    fn extent(&self) -> Option<[u32; 2]>;
+    /// Samples per pixel of the attachments, see `Swapchain::sample_count`.
+    fn sample_count(&self) -> Option<vulkano::SampleCount>;
+    /// Creates a render pass matching the framebuffers, see `Swapchain::create_render_pass`.
+    fn create_render_pass(&self) -> Result<Arc<vulkano::RenderPass>, SwapchainError>;
+    /// Clear values for the attachments, see `Swapchain::clear_values`.
+    fn clear_values(&self, color: [f32; 4]) -> Option<Vec<vulkano::ClearValue>>;
+    /// The cached framebuffer of `image` for `render_pass`.
+    fn framebuffer(
+        &self,
+        image: &Self::Image,
+        render_pass: &Arc<vulkano::RenderPass>,
+    ) -> Result<Arc<vulkano::Framebuffer>, vulkano::FramebufferCreationError>;
+    /// Presents `image` on `queue` after `after` and flushes, returning a future whose fence is
+    /// signaled once the presentation has been submitted.
+    fn present(
+        &mut self,
+        queue: &Queue,
+        image: Self::Image,
+        after: Box<dyn GpuFuture>,
+    ) -> Result<vulkano::FenceSignalFuture<Box<dyn GpuFuture>>, PresentError>;
+    /// Presents `image` like `present`, hinting that only `regions` changed, see
+    /// `SurfaceImage::present_with_regions`. Targets without incremental presentation ignore the
+    /// regions.
+    fn present_with_regions(
+        &mut self,
+        queue: &Queue,
+        image: Self::Image,
+        after: Box<dyn GpuFuture>,
+        regions: &[Rect],
+    ) -> Result<vulkano::FenceSignalFuture<Box<dyn GpuFuture>>, PresentError> {
+        let _ = regions;
+        self.present(queue, image, after)
+    }
+}
+
+/// An image acquired from a `RenderTarget`.
+pub trait TargetImage {
+    /// Takes the future the work rendering into the image has to wait for.
+    fn take_future(&mut self) -> Option<Box<dyn GpuFuture>>;
+    /// Viewport covering the whole image.
+    fn viewport(&self) -> vulkano::Viewport;
+    /// Skips presenting this image, e.g. because the frame turned out to be unnecessary.
+    fn abandon(self);
+}
+
+/// A single image that can be rendered into, e.g. a swapchain image or an intermediate texture of
+/// a post-processing chain.
+///
+/// Lets the same draw function render the scene into an `OffscreenTexture` and composite the
+/// result onto the window through `Surface::draw_target`.
+pub trait DrawTarget {
+    fn extent(&self) -> [u32; 2];
+    fn format(&self) -> vulkano::Format;
+    /// View of the image, e.g. to sample it in the next pass.
+    fn view(&self) -> Arc<dyn vulkano::ImageViewAbstract>;
+    /// Creates a render pass whose framebuffers `framebuffer` can create.
+    fn create_render_pass(&self) -> Result<Arc<vulkano::RenderPass>, SwapchainError>;
+    /// Clear values for the attachments of `create_render_pass`.
+    fn clear_values(&self, color: [f32; 4]) -> Vec<vulkano::ClearValue>;
+    /// The cached framebuffer of the image for `render_pass`.
+    fn framebuffer(
+        &self,
+        render_pass: &Arc<vulkano::RenderPass>,
+    ) -> Result<Arc<vulkano::Framebuffer>, vulkano::FramebufferCreationError>;
+    /// Viewport covering the whole image.
+    fn viewport(&self) -> vulkano::Viewport {
+        let extent = self.extent();
+        vulkano::Viewport {
+            origin: [0.0, 0.0],
+            dimensions: [extent[0] as f32, extent[1] as f32],
+            depth_range: 0.0..1.0,
+        }
+    }
+}
+
+/// Color, multisampled color and depth attachments for a set of images, together with the
+/// framebuffers created for them.
+pub(crate) struct Attachments {
+    device: Arc<vulkano::Device>,
+    extent: [u32; 2],
+    format: vulkano::Format,
+    depth_format: Option<vulkano::Format>,
+    samples: vulkano::SampleCount,
+    views: Vec<Arc<dyn vulkano::ImageViewAbstract>>,
+    // Multisampled color images resolved into the images, one per image.
+    msaa: Vec<Arc<dyn vulkano::ImageViewAbstract>>,
+    // One per image, like the multisampled images, so frames in flight do not share them.
+    depth: Vec<Arc<dyn vulkano::ImageViewAbstract>>,
+    // Shared with the owner of the attachments, which invalidates it when they are replaced.
+    framebuffer_cache: Arc<FramebufferCache>,
+}
+
+impl Attachments {
+    /// Creates the multisampled color images and the depth buffer for `views`, which all have
+    /// the given `extent` and `format`.
+    pub(crate) fn new(
+        device: Arc<vulkano::Device>,
+        views: Vec<Arc<dyn vulkano::ImageViewAbstract>>,
+        extent: [u32; 2],
+        format: vulkano::Format,
+        samples: vulkano::SampleCount,
+        depth_format: Option<vulkano::Format>,
+        framebuffer_cache: Arc<FramebufferCache>,
+    ) -> Result<Self, AttachmentError> {
+        let mut attachments = Self::without_targets(device, views, extent, format, framebuffer_cache);
+        attachments.create_targets(samples, depth_format)?;
+        Ok(attachments)
+    }
+    /// Attachments of only `views`, until `create_targets` adds the multisampled color images
+    /// and the depth buffer.
+    pub(crate) fn without_targets(
+        device: Arc<vulkano::Device>,
+        views: Vec<Arc<dyn vulkano::ImageViewAbstract>>,
+        extent: [u32; 2],
+        format: vulkano::Format,
+        framebuffer_cache: Arc<FramebufferCache>,
+    ) -> Self {
+        Self {
+            device,
+            extent,
+            format,
+            depth_format: None,
+            samples: vulkano::SampleCount::Sample1,
+            views,
+            msaa: Vec::new(),
+            depth: Vec::new(),
+            framebuffer_cache,
+        }
+    }
+    /// Replaces the multisampled color images and the depth buffers with ones for `samples` and
+    /// `depth_format`, one per view.
+    pub(crate) fn create_targets(
+        &mut self,
+        samples: vulkano::SampleCount,
+        depth_format: Option<vulkano::Format>,
+    ) -> Result<(), AttachmentError> {
+        let target = |format| -> Result<Arc<dyn vulkano::ImageViewAbstract>, AttachmentError> {
+            let image = vulkano::AttachmentImage::transient_multisampled(
+                self.device.clone(),
+                self.extent,
+                samples,
+                format,
+            )?;
+            Ok(vulkano::ImageView::new_default(image)?)
+        };
+        let msaa = match samples {
+            vulkano::SampleCount::Sample1 => Vec::new(),
+            _ => self
+                .views
+                .iter()
+                .map(|_| target(self.format))
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+        let depth = match depth_format {
+            Some(depth_format) => self
+                .views
+                .iter()
+                .map(|_| target(depth_format))
+                .collect::<Result<Vec<_>, _>>()?,
+            None => Vec::new(),
+        };
+        self.samples = samples;
+        self.depth_format = depth_format;
+        self.msaa = msaa;
+        self.depth = depth;
+        self.framebuffer_cache.invalidate();
+        Ok(())
+    }
+    pub(crate) fn views(&self) -> &[Arc<dyn vulkano::ImageViewAbstract>] {
+        &self.views
+    }
+    pub(crate) fn depth_view(&self, index: usize) -> Option<&Arc<dyn vulkano::ImageViewAbstract>> {
+        self.depth.get(index)
+    }
+    pub(crate) fn depth_format(&self) -> Option<vulkano::Format> {
+        self.depth_format
+    }
+    pub(crate) fn sample_count(&self) -> vulkano::SampleCount {
+        self.samples
+    }
+    /// Framebuffers for `render_pass`, one per image, taken from the framebuffer cache.
+    pub(crate) fn framebuffers(
+        &self,
+        render_pass: &Arc<vulkano::RenderPass>,
+    ) -> Result<Arc<[Arc<vulkano::Framebuffer>]>, vulkano::FramebufferCreationError> {
+        self.views
+            .iter()
+            .enumerate()
+            .map(|(i, view)| {
+                let attachments = self
+                    .msaa
+                    .get(i)
+                    .cloned()
+                    .into_iter()
+                    .chain(std::iter::once(view.clone()))
+                    .chain(self.depth.get(i).cloned())
+                    .collect();
+                self.framebuffer_cache
+                    .get_or_create(render_pass, attachments)
+            })
+            .collect()
+    }
+    /// Builder for a render pass matching the framebuffers, see `create_render_pass`.
+    pub(crate) fn render_pass_builder(&self) -> RenderPassBuilder {
+        let color = AttachmentDesc::new(self.format).samples(self.samples);
+        let mut builder = match self.samples {
+            vulkano::SampleCount::Sample1 => RenderPassBuilder::new(color),
+            // The multisampled image is only needed until it is resolved.
+            _ => RenderPassBuilder::new(color.store(vulkano::StoreOp::DontCare)).resolve(),
+        };
+        if let Some(depth_format) = self.depth_format {
+            builder = builder.depth(
+                AttachmentDesc::new(depth_format)
+                    .store(vulkano::StoreOp::DontCare)
+                    .samples(self.samples),
+            );
+        }
+        builder
+    }
+    pub(crate) fn create_render_pass(
+        &self,
+    ) -> Result<Arc<vulkano::RenderPass>, vulkano::RenderPassCreationError> {
+        self.render_pass_builder()
+            .build(self.device.clone())
+            .map_err(|err| match err {
+                RenderPassBuildError::Creation(err) => err,
+                // The attachments are created with these formats and sample counts.
+                err => unreachable!("invalid attachments: {}", err),
+            })
+    }
+    pub(crate) fn clear_values(&self, color: [f32; 4]) -> Vec<vulkano::ClearValue> {
+        let mut values = vec![color.into()];
+        if !self.msaa.is_empty() {
+            // The resolve target is not cleared.
+            values.push(vulkano::ClearValue::None);
+        }
+        if let Some(depth_format) = self.depth_format {
+            values.push(depth_clear_value(depth_format, 1.0));
+        }
+        values
+    }
+}
+
+/// The images or views of the multisampled color or depth attachments could not be created.
+#[derive(Debug, From)]
+pub enum AttachmentError {
+    Image(vulkano::ImageCreationError),
+    ImageView(vulkano::ImageViewCreationError),
+}
+
+impl fmt::Display for AttachmentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Image(err) => write!(f, "failed to create an attachment: {}", err),
+            Self::ImageView(err) => write!(f, "failed to create an attachment view: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for AttachmentError {}
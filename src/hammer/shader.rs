@@ -0,0 +1,441 @@
+use derive_more::*;
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::Arc;
+
+use super::{Device, UniformBlocks};
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::shader::spirv::*;
+    pub use vulkano::shader::*;
+}
+
+#[cfg(feature = "shaderc")]
+pub use shaderc::ShaderKind;
+
+/// First word of every SPIR-V module.
+const SPIRV_MAGIC: u32 = 0x0723_0203;
+/// Number of words in the SPIR-V header.
+const SPIRV_HEADER_WORDS: usize = 5;
+
+impl Device {
+    /// Creates a shader module from SPIR-V loaded at runtime, in either byte order.
+    ///
+    /// Only the size and header are checked before the module is handed to vulkano. The driver
+    /// trusts the code to be valid, so only load SPIR-V produced by a compiler.
+    pub fn create_shader_module(
+        &self,
+        spirv: &[u8],
+    ) -> Result<Arc<vulkano::ShaderModule>, ShaderError> {
+        if !spirv.len().is_multiple_of(4) {
+            return Err(ShaderError::InvalidLength(spirv.len()));
+        }
+        let words = spirv
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect::<Vec<_>>();
+        self.create_shader_module_from_words(&words)
+    }
+    /// Like `create_shader_module`, for SPIR-V that is already split into words.
+    pub fn create_shader_module_from_words(
+        &self,
+        words: &[u32],
+    ) -> Result<Arc<vulkano::ShaderModule>, ShaderError> {
+        let words = native_words(words)?;
+        Ok(unsafe { vulkano::ShaderModule::from_words(self.handle().clone(), &words)? })
+    }
+    /// Like `create_shader_module`, but also lists the entry points of the module, e.g. for
+    /// SPIR-V with an entry point per stage.
+    pub fn create_shader(&self, spirv: &[u8]) -> Result<Shader, ShaderError> {
+        if !spirv.len().is_multiple_of(4) {
+            return Err(ShaderError::InvalidLength(spirv.len()));
+        }
+        let words = spirv
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect::<Vec<_>>();
+        self.create_shader_from_words(&words)
+    }
+    /// Like `create_shader`, for SPIR-V that is already split into words.
+    pub fn create_shader_from_words(&self, words: &[u32]) -> Result<Shader, ShaderError> {
+        let words = native_words(words)?;
+        let spirv = vulkano::Spirv::new(&words).map_err(vulkano::ShaderCreationError::from)?;
+        Ok(Shader {
+            module: unsafe { vulkano::ShaderModule::from_words(self.handle().clone(), &words)? },
+            entry_points: entry_points(&spirv),
+            uniform_blocks: UniformBlocks::reflect(&spirv),
+        })
+    }
+}
+
+// The entry points `spirv` declares, in order.
+fn entry_points(spirv: &vulkano::Spirv) -> Vec<ShaderEntryPoint> {
+    spirv
+        .iter_entry_point()
+        .filter_map(|instruction| match instruction {
+            vulkano::Instruction::EntryPoint {
+                execution_model,
+                name,
+                ..
+            } => Some(ShaderEntryPoint {
+                name: name.clone(),
+                execution_model: *execution_model,
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+// Checks the header of `words` and swaps them to the native byte order if needed.
+fn native_words(words: &[u32]) -> Result<Cow<'_, [u32]>, ShaderError> {
+    if words.len() < SPIRV_HEADER_WORDS {
+        return Err(ShaderError::InvalidLength(words.len() * 4));
+    }
+    match words[0] {
+        SPIRV_MAGIC => Ok(Cow::Borrowed(words)),
+        magic if magic.swap_bytes() == SPIRV_MAGIC => {
+            Ok(Cow::Owned(words.iter().map(|w| w.swap_bytes()).collect()))
+        }
+        magic => Err(ShaderError::InvalidMagic(magic)),
+    }
+}
+
+/// An entry point declared by a SPIR-V module.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShaderEntryPoint {
+    pub name: String,
+    pub execution_model: vulkano::ExecutionModel,
+}
+
+impl fmt::Display for ShaderEntryPoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}` ({:?})", self.name, self.execution_model)
+    }
+}
+
+/// A shader module with the entry points it declares, created with `Device::create_shader`.
+///
+/// vulkano only looks entry points up by name, so the list is read from the SPIR-V when the
+/// module is created, e.g. to show it in a debug UI or in errors. The layouts of its uniform
+/// blocks are read at the same time, see `BindGroupBuilder::uniform`.
+#[derive(Clone)]
+pub struct Shader {
+    module: Arc<vulkano::ShaderModule>,
+    entry_points: Vec<ShaderEntryPoint>,
+    uniform_blocks: UniformBlocks,
+}
+
+impl Shader {
+    pub fn module(&self) -> &Arc<vulkano::ShaderModule> {
+        &self.module
+    }
+    /// The entry points in the order the module declares them.
+    pub fn entry_points(&self) -> &[ShaderEntryPoint] {
+        &self.entry_points
+    }
+    pub fn uniform_blocks(&self) -> &UniformBlocks {
+        &self.uniform_blocks
+    }
+    /// The entry point `name` for `execution_model`, e.g. `"vs_main"` for
+    /// `ExecutionModel::Vertex`. A module can use the same name for several stages.
+    pub fn entry_point(
+        &self,
+        name: &str,
+        execution_model: vulkano::ExecutionModel,
+    ) -> Result<vulkano::EntryPoint<'_>, EntryPointError> {
+        self.module
+            .entry_point_with_execution(name, execution_model)
+            .ok_or_else(|| EntryPointError {
+                name: name.to_string(),
+                execution_model,
+                available: self.entry_points.clone(),
+            })
+    }
+}
+
+/// The module has no entry point with this name and execution model.
+#[derive(Clone, Debug)]
+pub struct EntryPointError {
+    pub name: String,
+    pub execution_model: vulkano::ExecutionModel,
+    /// The entry points the module declares.
+    pub available: Vec<ShaderEntryPoint>,
+}
+
+impl fmt::Display for EntryPointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "the shader has no {:?} entry point `{}`",
+            self.execution_model, self.name
+        )?;
+        if self.available.is_empty() {
+            return write!(f, ", it has no entry points");
+        }
+        write!(f, ", it has ")?;
+        for (index, entry_point) in self.available.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", entry_point)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for EntryPointError {}
+
+#[derive(Debug, From)]
+pub enum ShaderError {
+    /// The SPIR-V is not a whole number of words or shorter than the header, in bytes.
+    #[from(ignore)]
+    InvalidLength(usize),
+    /// The first word is not the SPIR-V magic number.
+    #[from(ignore)]
+    InvalidMagic(u32),
+    Creation(vulkano::ShaderCreationError),
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidLength(len) => write!(f, "{} bytes is not a valid SPIR-V length", len),
+            Self::InvalidMagic(magic) => {
+                write!(f, "not SPIR-V, the magic number is {:#010x}", magic)
+            }
+            Self::Creation(err) => write!(f, "failed to create the shader module: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+#[cfg(feature = "shaderc")]
+impl Device {
+    /// Compiles GLSL to SPIR-V at runtime and creates a shader module from it.
+    ///
+    /// `#include` directives are rejected, use `create_shader_glsl_with_includes` to resolve them.
+    pub fn create_shader_glsl(
+        &self,
+        source: &str,
+        kind: ShaderKind,
+        entry: &str,
+    ) -> Result<Arc<vulkano::ShaderModule>, GlslError> {
+        self.create_shader_glsl_with_includes(source, kind, entry, "shader.glsl", |name, _| {
+            Err(format!(
+                "cannot include {}, no include resolver was given",
+                name
+            ))
+        })
+    }
+    /// Like `create_shader_glsl`, resolving `#include` directives with `resolve`.
+    ///
+    /// `resolve` is called with the requested name and the name of the file containing the
+    /// directive, `file_name` for the top level source, and returns the content to include.
+    pub fn create_shader_glsl_with_includes<R>(
+        &self,
+        source: &str,
+        kind: ShaderKind,
+        entry: &str,
+        file_name: &str,
+        resolve: R,
+    ) -> Result<Arc<vulkano::ShaderModule>, GlslError>
+    where
+        R: Fn(&str, &str) -> Result<String, String>,
+    {
+        let mut compiler = shaderc::Compiler::new().ok_or(GlslError::CompilerUnavailable)?;
+        let mut options = shaderc::CompileOptions::new().ok_or(GlslError::CompilerUnavailable)?;
+        options.set_include_callback(|requested, _, requesting, _| {
+            Ok(shaderc::ResolvedInclude {
+                resolved_name: requested.to_string(),
+                content: resolve(requested, requesting)?,
+            })
+        });
+
+        let artifact = compiler
+            .compile_into_spirv(source, kind, file_name, entry, Some(&options))
+            .map_err(|err| match err {
+                shaderc::Error::CompilationError(_, log) => {
+                    GlslError::Compile(GlslDiagnostic::parse(&log))
+                }
+                err => GlslError::Internal(err.to_string()),
+            })?;
+        if artifact.get_num_warnings() > 0 {
+            log::warn!(
+                "GLSL compiler warnings:\n{}",
+                artifact.get_warning_messages()
+            );
+        }
+        Ok(self.create_shader_module_from_words(artifact.as_binary())?)
+    }
+}
+
+/// A single error reported by the GLSL compiler.
+#[cfg(feature = "shaderc")]
+#[derive(Clone, Debug)]
+pub struct GlslDiagnostic {
+    /// Name of the source or included file, empty if the compiler did not name one.
+    pub file: String,
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+#[cfg(feature = "shaderc")]
+impl GlslDiagnostic {
+    /// Parses glslang's `file:line: error: message` lines.
+    fn parse(log: &str) -> Vec<Self> {
+        log.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.ends_with("generated."))
+            .map(|line| match line.split_once(": error: ") {
+                Some((location, message)) => {
+                    let (file, line) = match location.rsplit_once(':') {
+                        Some((file, line)) if line.parse::<u32>().is_ok() => {
+                            (file.to_string(), line.parse().ok())
+                        }
+                        _ => (location.to_string(), None),
+                    };
+                    Self {
+                        file,
+                        line,
+                        message: message.to_string(),
+                    }
+                }
+                None => Self {
+                    file: String::new(),
+                    line: None,
+                    message: line.to_string(),
+                },
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "shaderc")]
+impl fmt::Display for GlslDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.file, line, self.message),
+            None if !self.file.is_empty() => write!(f, "{}: {}", self.file, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+#[cfg(feature = "shaderc")]
+#[derive(Debug, From)]
+pub enum GlslError {
+    /// The shaderc compiler could not be initialized.
+    #[from(ignore)]
+    CompilerUnavailable,
+    /// The source failed to compile.
+    #[from(ignore)]
+    Compile(Vec<GlslDiagnostic>),
+    /// Any other error reported by shaderc.
+    #[from(ignore)]
+    Internal(String),
+    Shader(ShaderError),
+}
+
+#[cfg(feature = "shaderc")]
+impl fmt::Display for GlslError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::CompilerUnavailable => write!(f, "failed to initialize the GLSL compiler"),
+            Self::Compile(diagnostics) => {
+                write!(f, "failed to compile GLSL:")?;
+                for diagnostic in diagnostics {
+                    write!(f, "\n{}", diagnostic)?;
+                }
+                Ok(())
+            }
+            Self::Internal(err) => write!(f, "GLSL compiler error: {}", err),
+            Self::Shader(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+#[cfg(feature = "shaderc")]
+impl std::error::Error for GlslError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMPUTE: &[u8] = include_bytes!("../../tests/data/compute.spv");
+    // `vs_main` and `fs_main`.
+    const MULTI_ENTRY: &[u8] = include_bytes!("../../tests/data/multi_entry.spv");
+
+    fn words(bytes: &[u8]) -> Vec<u32> {
+        bytes
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn native_words_keeps_native_order() {
+        let words = words(COMPUTE);
+        assert!(matches!(native_words(&words), Ok(Cow::Borrowed(_))));
+        vulkano::Spirv::new(&words).unwrap();
+    }
+
+    #[test]
+    fn native_words_swaps_byte_order() {
+        let swapped = words(COMPUTE)
+            .iter()
+            .map(|word| word.swap_bytes())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            native_words(&swapped).unwrap().as_ref(),
+            &words(COMPUTE)[..]
+        );
+    }
+
+    #[test]
+    fn native_words_rejects_malformed() {
+        let words = words(COMPUTE);
+        assert!(matches!(
+            native_words(&words[..4]),
+            Err(ShaderError::InvalidLength(16))
+        ));
+        let mut wrong_magic = words.clone();
+        wrong_magic[0] = 0x1234_5678;
+        assert!(matches!(
+            native_words(&wrong_magic),
+            Err(ShaderError::InvalidMagic(0x1234_5678))
+        ));
+    }
+
+    #[test]
+    fn lists_entry_points_in_order() {
+        let spirv = vulkano::Spirv::new(&words(MULTI_ENTRY)).unwrap();
+        let entry_points = entry_points(&spirv)
+            .into_iter()
+            .map(|entry_point| (entry_point.name, entry_point.execution_model))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entry_points,
+            [
+                ("vs_main".to_string(), vulkano::ExecutionModel::Vertex),
+                ("fs_main".to_string(), vulkano::ExecutionModel::Fragment),
+            ]
+        );
+    }
+
+    #[test]
+    fn entry_point_error_names_the_available_ones() {
+        let spirv = vulkano::Spirv::new(&words(MULTI_ENTRY)).unwrap();
+        let err = EntryPointError {
+            name: "main".to_string(),
+            execution_model: vulkano::ExecutionModel::Vertex,
+            available: entry_points(&spirv),
+        };
+        assert_eq!(
+            err.to_string(),
+            "the shader has no Vertex entry point `main`, it has `vs_main` (Vertex), \
+             `fs_main` (Fragment)"
+        );
+    }
+}
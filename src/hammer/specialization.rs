@@ -0,0 +1,196 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::shader::*;
+}
+
+/// Constant ids a `SpecializationMap` can set are below this.
+pub const MAX_SPECIALIZATION_CONSTANT_ID: u32 = 64;
+
+/// Value of a specialization constant, all of them are 4 bytes like vulkano expects.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpecializationValue {
+    Bool(bool),
+    I32(i32),
+    U32(u32),
+    F32(f32),
+}
+
+impl SpecializationValue {
+    fn to_bits(self) -> u32 {
+        match self {
+            Self::Bool(value) => value as u32,
+            Self::I32(value) => value as u32,
+            Self::U32(value) => value,
+            Self::F32(value) => value.to_bits(),
+        }
+    }
+}
+
+impl From<bool> for SpecializationValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<i32> for SpecializationValue {
+    fn from(value: i32) -> Self {
+        Self::I32(value)
+    }
+}
+
+impl From<u32> for SpecializationValue {
+    fn from(value: u32) -> Self {
+        Self::U32(value)
+    }
+}
+
+impl From<f32> for SpecializationValue {
+    fn from(value: f32) -> Self {
+        Self::F32(value)
+    }
+}
+
+/// Values of the specialization constants of a shader stage by constant id, see
+/// `PipelineDescriptor::vertex_specialization`.
+///
+/// vulkano needs a value for every specialization constant the shader declares, the defaults in
+/// the shader are not used.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SpecializationMap {
+    values: BTreeMap<u32, SpecializationValue>,
+}
+
+impl SpecializationMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Sets constant `constant_id` to `value`.
+    pub fn set(mut self, constant_id: u32, value: impl Into<SpecializationValue>) -> Self {
+        self.insert(constant_id, value);
+        self
+    }
+    pub fn insert(&mut self, constant_id: u32, value: impl Into<SpecializationValue>) {
+        self.values.insert(constant_id, value.into());
+    }
+    pub fn get(&self, constant_id: u32) -> Option<SpecializationValue> {
+        self.values.get(&constant_id).copied()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+    /// Checks the map against the specialization constants reflected from `entry_point`.
+    pub fn check(&self, entry_point: &vulkano::EntryPoint) -> Result<(), SpecializationError> {
+        let requirements = entry_point
+            .specialization_constant_requirements()
+            .map(|(constant_id, requirements)| (constant_id, requirements.size))
+            .collect::<BTreeMap<_, _>>();
+        if let Some(&constant_id) = self.values.keys().find(|id| !requirements.contains_key(id)) {
+            return Err(SpecializationError::UnknownConstant(constant_id));
+        }
+        for (&constant_id, &size) in &requirements {
+            if size != 4 {
+                return Err(SpecializationError::UnsupportedSize { constant_id, size });
+            }
+            if constant_id >= MAX_SPECIALIZATION_CONSTANT_ID {
+                return Err(SpecializationError::ConstantIdTooLarge(constant_id));
+            }
+            if !self.values.contains_key(&constant_id) {
+                return Err(SpecializationError::MissingConstant(constant_id));
+            }
+        }
+        Ok(())
+    }
+    /// The values in the layout vulkano reads them in.
+    pub(crate) fn constants(&self) -> SpecializationConstants {
+        let mut data = [0; MAX_SPECIALIZATION_CONSTANT_ID as usize];
+        for (&constant_id, value) in &self.values {
+            if let Some(slot) = data.get_mut(constant_id as usize) {
+                *slot = value.to_bits();
+            }
+        }
+        SpecializationConstants(data)
+    }
+}
+
+impl<V: Into<SpecializationValue>> FromIterator<(u32, V)> for SpecializationMap {
+    fn from_iter<T: IntoIterator<Item = (u32, V)>>(iter: T) -> Self {
+        Self {
+            values: iter
+                .into_iter()
+                .map(|(constant_id, value)| (constant_id, value.into()))
+                .collect(),
+        }
+    }
+}
+
+/// vulkano describes the layout of specialization constants with a `'static` table, so every
+/// possible constant id has a slot at `4 * constant_id`. Vulkan ignores the slots of ids the
+/// shader does not declare.
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub(crate) struct SpecializationConstants([u32; MAX_SPECIALIZATION_CONSTANT_ID as usize]);
+
+static ENTRIES: [vulkano::SpecializationMapEntry; MAX_SPECIALIZATION_CONSTANT_ID as usize] = {
+    let mut entries = [vulkano::SpecializationMapEntry {
+        constant_id: 0,
+        offset: 0,
+        size: 4,
+    }; MAX_SPECIALIZATION_CONSTANT_ID as usize];
+    let mut constant_id = 0;
+    while constant_id < MAX_SPECIALIZATION_CONSTANT_ID {
+        entries[constant_id as usize].constant_id = constant_id;
+        entries[constant_id as usize].offset = constant_id * 4;
+        constant_id += 1;
+    }
+    entries
+};
+
+unsafe impl vulkano::SpecializationConstants for SpecializationConstants {
+    fn descriptors() -> &'static [vulkano::SpecializationMapEntry] {
+        &ENTRIES
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpecializationError {
+    /// The shader declares the constant, but the map has no value for it.
+    MissingConstant(u32),
+    /// The map has a value for a constant the shader does not declare.
+    UnknownConstant(u32),
+    /// The constant is not 4 bytes, e.g. a double, which a map cannot hold.
+    UnsupportedSize { constant_id: u32, size: u64 },
+    /// The constant id is not below `MAX_SPECIALIZATION_CONSTANT_ID`.
+    ConstantIdTooLarge(u32),
+}
+
+impl fmt::Display for SpecializationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MissingConstant(constant_id) => write!(
+                f,
+                "the shader declares specialization constant {}, but it has no value",
+                constant_id
+            ),
+            Self::UnknownConstant(constant_id) => write!(
+                f,
+                "the shader does not declare specialization constant {}",
+                constant_id
+            ),
+            Self::UnsupportedSize { constant_id, size } => write!(
+                f,
+                "specialization constant {} has {} bytes, only 4 byte constants are supported",
+                constant_id, size
+            ),
+            Self::ConstantIdTooLarge(constant_id) => write!(
+                f,
+                "specialization constant id {} is not below {}",
+                constant_id, MAX_SPECIALIZATION_CONSTANT_ID
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SpecializationError {}
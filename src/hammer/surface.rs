@@ -1,8 +1,11 @@
 
-use std::sync::Arc;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use derive_more::*;
 
-use super::GetPhysicalDevice;
+use super::{clamp_sample_count, framebuffer_sample_counts, Attachments, AttachmentError, DeferredDeletionQueue, Device, DrawTarget, FramebufferCache, FrameError, FrameLimiter, FrameStats, GetPhysicalDevice, MisuseGuard, Queue, RenderPassBuilder, RenderTarget, TargetImage};
+use std::sync::atomic::AtomicBool;
 
 // Getting rust analyzer problems when not defining the module here again.
 mod vulkano{
@@ -15,8 +18,12 @@ mod vulkano{
     pub use vulkano::swapchain::*;
     pub use vulkano::render_pass::*;
     pub use vulkano::pipeline::graphics::viewport::*;
+    pub use vulkano::sync::*;
+    pub use vulkano::format::*;
 }
 
+use vulkano::GpuFuture;
+
 #[derive(Deref, DerefMut)]
 pub struct Swapchain<W>{
     pub device: Arc<vulkano::Device>,
@@ -24,16 +31,491 @@ pub struct Swapchain<W>{
     #[deref_mut]
     pub swapchain: Arc<vulkano::Swapchain<W>>,
     pub images: Vec<Arc<vulkano::SwapchainImage<W>>>,
+    /// Descriptor the swapchain was created with, reused when recreating it.
+    pub descriptor: SwapchainDescriptor,
+    // Views of the images and the multisampled color and depth attachments, whose sample count
+    // can differ from the descriptor until the next recreation.
+    attachments: Attachments,
+}
+
+impl<W> Swapchain<W>{
+    /// Number of images, e.g. to size per-frame resources.
+    pub fn image_count(&self) -> u32{
+        self.images.len() as u32
+    }
+    pub fn extent(&self) -> [u32; 2]{
+        self.swapchain.image_extent()
+    }
+    pub fn images(&self) -> &[Arc<vulkano::SwapchainImage<W>>]{
+        &self.images
+    }
+    /// Default views of all images.
+    pub fn image_views(&self) -> &[Arc<dyn vulkano::ImageViewAbstract>]{
+        self.attachments.views()
+    }
+    /// View of the depth buffer of image `index`, if the descriptor has a depth format.
+    pub fn depth_view(&self, index: usize) -> Option<&Arc<dyn vulkano::ImageViewAbstract>>{
+        self.attachments.depth_view(index)
+    }
+    pub fn depth_format(&self) -> Option<vulkano::Format>{
+        self.attachments.depth_format()
+    }
+    /// Samples per pixel of the attachments.
+    pub fn sample_count(&self) -> vulkano::SampleCount{
+        self.attachments.sample_count()
+    }
+    /// Framebuffers for `render_pass`, one per image.
+    ///
+    /// With multisampling the multisampled color image is attachment 0, followed by the swapchain
+    /// image it is resolved into. Otherwise the swapchain image is attachment 0. The depth buffer
+    /// comes last if there is one. `create_render_pass` and `clear_values` use the same order.
+    ///
+    /// The framebuffers are created on the first call for `render_pass` and reused afterwards,
+    /// until the swapchain is recreated, see `Surface::framebuffer_cache`.
+    pub fn framebuffers(&self, render_pass: &Arc<vulkano::RenderPass>) -> Result<Arc<[Arc<vulkano::Framebuffer>]>, vulkano::FramebufferCreationError>{
+        self.attachments.framebuffers(render_pass)
+    }
+    /// Creates a render pass with a single subpass matching the attachments of `framebuffers`.
+    ///
+    /// The color attachment is cleared and stored, the depth buffer is cleared and discarded
+    /// afterwards. With multisampling the multisampled image is cleared instead and resolved into
+    /// the swapchain image at the end of the subpass.
+    pub fn create_render_pass(&self) -> Result<Arc<vulkano::RenderPass>, vulkano::RenderPassCreationError>{
+        self.attachments.create_render_pass()
+    }
+    /// Builder for the render pass `create_render_pass` creates, e.g. to get its `ClearValues`
+    /// or add attachments and subpasses.
+    pub fn render_pass_builder(&self) -> RenderPassBuilder{
+        self.attachments.render_pass_builder()
+    }
+    /// Clear values for the attachments of `framebuffers`, clearing the color to `color` and the
+    /// depth to the far plane.
+    pub fn clear_values(&self, color: [f32; 4]) -> Vec<vulkano::ClearValue>{
+        self.attachments.clear_values(color)
+    }
+    /// Creates the multisampled color images and the depth buffers the descriptor asks for, with
+    /// the extent of the images.
+    fn create_targets(&mut self) -> Result<(), SwapchainError>{
+        self.attachments.create_targets(self.descriptor.sample_count, self.descriptor.depth_format)?;
+        Ok(())
+    }
+}
+
+impl<W: Send + Sync + 'static> Swapchain<W>{
+    fn new(
+        device: Arc<vulkano::Device>,
+        swapchain: Arc<vulkano::Swapchain<W>>,
+        images: Vec<Arc<vulkano::SwapchainImage<W>>>,
+        descriptor: SwapchainDescriptor,
+        framebuffer_cache: Arc<FramebufferCache>,
+    ) -> Result<Self, SwapchainError>{
+        let views = images.iter()
+            .map(|image| {
+                let view: Arc<dyn vulkano::ImageViewAbstract> = vulkano::ImageView::new_default(image.clone())?;
+                Ok(view)
+            })
+            .collect::<Result<Vec<_>, vulkano::ImageViewCreationError>>()?;
+        // The multisampled images and the depth buffer are created by the recreate hooks of the
+        // surface, see `create_targets`.
+        let attachments = Attachments::without_targets(
+            device.clone(),
+            views,
+            swapchain.image_extent(),
+            swapchain.image_format(),
+            framebuffer_cache,
+        );
+        Ok(Self{
+            device,
+            swapchain,
+            images,
+            descriptor,
+            attachments,
+        })
+    }
+}
+
+/// Image format to pick among the ones the surface supports.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FormatPreference{
+    /// The first format the surface reports.
+    First,
+    /// A format with sRGB encoding in the sRGB color space, so shaders can output linear colors.
+    /// `B8G8R8A8_SRGB` and `R8G8B8A8_SRGB` are preferred over other sRGB formats.
+    #[default]
+    Srgb,
+    /// A linear format without sRGB encoding.
+    Unorm,
+    Exact(vulkano::Format),
+}
+
+/// Picks the format and color space matching `preference` from `formats`, as reported by the
+/// surface, or `None` if there is no match.
+pub fn select_format(
+    formats: &[(vulkano::Format, vulkano::ColorSpace)],
+    preference: FormatPreference,
+) -> Option<(vulkano::Format, vulkano::ColorSpace)>{
+    let find = |matches: &dyn Fn(vulkano::Format, vulkano::ColorSpace) -> bool| {
+        formats.iter().copied().find(|&(format, color_space)| matches(format, color_space))
+    };
+    match preference{
+        FormatPreference::First => formats.first().copied(),
+        FormatPreference::Srgb => {
+            let srgb = |format: vulkano::Format, color_space| {
+                color_space == vulkano::ColorSpace::SrgbNonLinear
+                    && format.type_color() == Some(vulkano::NumericType::SRGB)
+            };
+            find(&|format, color_space| srgb(format, color_space) && format == vulkano::Format::B8G8R8A8_SRGB)
+                .or_else(|| find(&|format, color_space| srgb(format, color_space) && format == vulkano::Format::R8G8B8A8_SRGB))
+                .or_else(|| find(&srgb))
+        },
+        FormatPreference::Unorm => find(&|format, _| format.type_color() == Some(vulkano::NumericType::UNORM)),
+        FormatPreference::Exact(exact) => find(&|format, _| format == exact),
+    }
+}
+
+/// Color space of the swapchain images, for HDR and wide gamut displays.
+///
+/// Surfaces only report HDR color spaces if the instance has the `ext_swapchain_colorspace`
+/// extension enabled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorSpacePreference{
+    /// sRGB color space, chosen with the `FormatPreference` of the descriptor.
+    #[default]
+    SdrSrgb,
+    /// Extended sRGB with linear encoding (scRGB), where 1.0 is the SDR white level and HDR
+    /// colors go beyond it. Prefers `R16G16B16A16_SFLOAT`.
+    HdrScRgbLinear,
+    /// BT.2020 primaries with the ST 2084 (PQ) transfer function, which shaders have to apply
+    /// themselves. Prefers 10 bit formats.
+    Hdr10,
 }
 
+/// Picks the format and color space for an HDR `preference` from `formats`, as reported by the
+/// surface, or `None` if there is no match or `preference` is `SdrSrgb`.
+pub fn select_hdr_format(
+    formats: &[(vulkano::Format, vulkano::ColorSpace)],
+    preference: ColorSpacePreference,
+) -> Option<(vulkano::Format, vulkano::ColorSpace)>{
+    let (color_space, preferred_formats): (_, &[vulkano::Format]) = match preference{
+        ColorSpacePreference::SdrSrgb => return None,
+        ColorSpacePreference::HdrScRgbLinear => (
+            vulkano::ColorSpace::ExtendedSrgbLinear,
+            &[vulkano::Format::R16G16B16A16_SFLOAT],
+        ),
+        ColorSpacePreference::Hdr10 => (
+            vulkano::ColorSpace::Hdr10St2084,
+            &[
+                vulkano::Format::A2B10G10R10_UNORM_PACK32,
+                vulkano::Format::A2R10G10B10_UNORM_PACK32,
+                vulkano::Format::R16G16B16A16_SFLOAT,
+            ],
+        ),
+    };
+    let candidates = || formats.iter().copied().filter(move |&(_, space)| space == color_space);
+    preferred_formats
+        .iter()
+        .find_map(|&preferred| candidates().find(|&(format, _)| format == preferred))
+        .or_else(|| candidates().next())
+}
+
+/// Settings for `Surface::create_swapchain_with`.
+///
+/// Requests the surface does not support fall back to a supported value with a warning, or fail
+/// with `SwapchainError::Unsupported` if `strict` is set.
+#[derive(Clone, Debug)]
+pub struct SwapchainDescriptor{
+    /// Falls back to `Fifo`, which every surface supports.
+    pub present_mode: vulkano::PresentMode,
+    /// Falls back to the first reported format.
+    pub format: FormatPreference,
+    /// For HDR color spaces `format` is ignored. Falls back to `SdrSrgb` if the surface reports
+    /// no matching format, see `select_hdr_format`.
+    pub color_space: ColorSpacePreference,
+    /// Defaults to the minimum the surface supports, out of range counts are clamped.
+    pub min_image_count: Option<u32>,
+    /// Unsupported usages are removed.
+    pub image_usage: vulkano::ImageUsage,
+    /// Format of a depth buffer created with the swapchain and attached to its framebuffers, e.g.
+    /// `D16_UNORM` which every device supports.
+    pub depth_format: Option<vulkano::Format>,
+    /// Samples per pixel, above one a multisampled color image is rendered to and resolved into
+    /// the swapchain image. Unsupported counts are rounded down like `Adapter::clamp_samples`
+    /// does.
+    pub sample_count: vulkano::SampleCount,
+    /// How the window system blends the images with what is behind the window, set
+    /// `PreMultiplied` or `PostMultiplied` for transparent windows.
+    ///
+    /// Defaults to `Opaque`. An unsupported mode falls back to `Inherit`, which leaves the
+    /// choice to the window system, then `Opaque` and then the first supported mode. The
+    /// preferred `Srgb` formats all have an alpha channel.
+    pub composite_alpha: Option<vulkano::CompositeAlpha>,
+    /// Whether the driver may use exclusive fullscreen (`VK_EXT_full_screen_exclusive`), e.g.
+    /// `Allowed` for presentation at the refresh rate of an exclusive video mode.
+    ///
+    /// Falls back to `Default` if the device does not have the extension enabled.
+    /// `ApplicationControlled` needs a Win32 monitor handle hammer does not pass, so it falls
+    /// back to `Allowed`.
+    pub full_screen_exclusive: vulkano::FullScreenExclusive,
+    pub strict: bool,
+}
+
+impl Default for SwapchainDescriptor{
+    fn default() -> Self{
+        Self{
+            present_mode: vulkano::PresentMode::Fifo,
+            format: FormatPreference::Srgb,
+            color_space: ColorSpacePreference::SdrSrgb,
+            min_image_count: None,
+            // Transfer destination allows blitting into the swapchain images, e.g. from a
+            // `VirtualResolution` target, transfer source copying them for `Surface::capture`.
+            image_usage: vulkano::ImageUsage{
+                color_attachment: true,
+                transfer_source: true,
+                transfer_destination: true,
+                ..vulkano::ImageUsage::none()
+            },
+            depth_format: None,
+            sample_count: vulkano::SampleCount::Sample1,
+            composite_alpha: None,
+            full_screen_exclusive: vulkano::FullScreenExclusive::Default,
+            strict: false,
+        }
+    }
+}
+
+/// Extent of the swapchain images for a window of `window_extent` presented with `transform`.
+///
+/// Images of a rotated surface are in the orientation of the display, so width and height swap
+/// for rotations by 90 and 270 degrees.
+fn transformed_extent(window_extent: [u32; 2], transform: vulkano::SurfaceTransform) -> [u32; 2]{
+    match transform{
+        vulkano::SurfaceTransform::Rotate90
+        | vulkano::SurfaceTransform::Rotate270
+        | vulkano::SurfaceTransform::HorizontalMirrorRotate90
+        | vulkano::SurfaceTransform::HorizontalMirrorRotate270 => [window_extent[1], window_extent[0]],
+        _ => window_extent,
+    }
+}
+
+/// Logs that `what` is unsupported, or fails if the descriptor is strict.
+fn fall_back(strict: bool, what: String) -> Result<(), SwapchainError>{
+    if strict{
+        return Err(SwapchainError::Unsupported(what));
+    }
+    log::warn!("{}", what);
+    Ok(())
+}
+
+/// Chooses from the `formats` a surface reports, the selection of `preferred_format` and the
+/// swapchain creation.
+fn choose_surface_format(
+    formats: &[(vulkano::Format, vulkano::ColorSpace)],
+    preference: FormatPreference,
+    color_space: ColorSpacePreference,
+    strict: bool,
+) -> Result<(vulkano::Format, vulkano::ColorSpace), SwapchainError>{
+    if color_space != ColorSpacePreference::SdrSrgb{
+        match select_hdr_format(formats, color_space){
+            Some(format) => return Ok(format),
+            None => fall_back(strict, format!("The surface has no format for {:?}, using SDR", color_space))?,
+        }
+    }
+    match select_format(formats, preference){
+        Some(format) => Ok(format),
+        None => {
+            let first = *formats.first().ok_or(SwapchainError::NoFormat)?;
+            fall_back(strict, format!("The surface has no format matching {:?}, using {:?}", preference, first.0))?;
+            Ok(first)
+        },
+    }
+}
+
+/// The format of the `swapchain` if there is one, else the `chosen` one, else the result of
+/// `query`.
+fn resolve_current_format(
+    swapchain: Option<(vulkano::Format, vulkano::ColorSpace)>,
+    chosen: Option<(vulkano::Format, vulkano::ColorSpace)>,
+    query: impl FnOnce() -> Option<(vulkano::Format, vulkano::ColorSpace)>,
+) -> Option<(vulkano::Format, vulkano::ColorSpace)>{
+    swapchain.or(chosen).or_else(query)
+}
+
+/// Number of frames a swapchain is kept alive after it has been replaced, and the number of
+/// replaced swapchains kept alive at most, so resizing every frame does not pile them up.
+const RETIRED_SWAPCHAINS: usize = 3;
+
+/// Number of times `Surface::acquire` recreates the swapchain before giving up.
+const ACQUIRE_ATTEMPTS: usize = 3;
+
 #[derive(Deref, DerefMut)]
 pub struct Surface<W>{
     #[deref]
     #[deref_mut]
     pub surface: Arc<vulkano::Surface<W>>,
     pub swapchain: Option<Swapchain<W>>,
+    // Old swapchains might still be used by the presentation engine, so they are only destroyed
+    // a few frames after recreation.
+    // Locked so acquiring an image only needs `&self`.
+    retired: Mutex<DeferredDeletionQueue<Swapchain<W>>>,
+    // Set while an acquired image has neither been presented nor abandoned.
+    image_outstanding: Arc<AtomicBool>,
+    // Format chosen by `select_format`, so it is known before the swapchain exists.
+    format: Option<(vulkano::Format, vulkano::ColorSpace)>,
+    // Index of the physical device set by `set_adapter` or the last swapchain creation, queried
+    // by `image_format` while there is neither a swapchain nor a chosen format.
+    adapter: Option<usize>,
+    // Set when the window was resized, the last acquired image was suboptimal or the swapchain
+    // was out of date.
+    needs_recreate: bool,
+    // Present mode set by `set_present_mode`, applied with the next recreation.
+    pending_present_mode: Option<vulkano::PresentMode>,
+    // Size of the window set with `set_extent`, takes precedence over `inner_size`.
+    extent: Option<[u32; 2]>,
+    // Queries the size of the window, if `W` implements `WithInnerIsize`.
+    inner_size: Option<fn(&W) -> [u32; 2]>,
+    // Invalid from `invalidate` until `rebuild`, with the device and descriptor of the dropped
+    // swapchain so it can be created again.
+    lifecycle: Lifecycle<(Arc<vulkano::Device>, SwapchainDescriptor)>,
+    // Creates a new surface for the window once the current one is lost, see
+    // `set_surface_factory`.
+    surface_factory: Option<Box<SurfaceFactory<W>>>,
+    // Create the multisampled images and depth buffers of new swapchains, then call the callbacks
+    // registered with `on_recreate`.
+    recreate_hooks: RecreateHooks<Swapchain<W>>,
+    // Framebuffers of the swapchain images, invalidated on recreation.
+    framebuffer_cache: Arc<FramebufferCache>,
+    // Signaled once the last frame presented through `Frame::present` has been submitted, joined
+    // into the next frame by `begin_frame`.
+    previous_frame: Option<vulkano::FenceSignalFuture<Box<dyn GpuFuture>>>,
+    // Set by `enable_frame_stats`, updated by `Frame`.
+    frame_stats: Option<FrameStats>,
+    // Paces `Frame::present`, see `set_target_fps`.
+    frame_limiter: FrameLimiter,
+}
+
+/// Runs `recreate` on `surface`, and if the surface has been lost and `can_recover` is set,
+/// `recover` once instead of giving up. Other errors, e.g. the retryable `UnsupportedExtent`, are
+/// returned to the caller, which tries again with the next frame.
+fn recreate_or_recover<S>(
+    surface: &mut S,
+    can_recover: bool,
+    recreate: impl FnOnce(&mut S) -> Result<(), SwapchainError>,
+    recover: impl FnOnce(&mut S) -> Result<(), SwapchainError>,
+) -> Result<(), SwapchainError>{
+    match recreate(surface){
+        Err(SwapchainError::SurfaceLost) if can_recover => recover(surface),
+        result => result,
+    }
+}
+
+/// What runs whenever a swapchain `S` has been created: first `targets`, which creates the
+/// resources of the swapchain that depend on its extent, then the callbacks of
+/// `Surface::on_recreate`.
+struct RecreateHooks<S>{
+    targets: fn(&mut S) -> Result<(), SwapchainError>,
+    callbacks: Vec<RecreateCallback<S>>,
+}
+
+type RecreateCallback<S> = Box<dyn FnMut(&S) + Send>;
+
+impl<S> RecreateHooks<S>{
+    fn new(targets: fn(&mut S) -> Result<(), SwapchainError>) -> Self{
+        Self{
+            targets,
+            callbacks: Vec::new(),
+        }
+    }
+    fn push(&mut self, callback: RecreateCallback<S>){
+        self.callbacks.push(callback);
+    }
+    /// Creates the targets of a new `swapchain`, before it replaces the previous one.
+    fn create_targets(&self, swapchain: &mut S) -> Result<(), SwapchainError>{
+        (self.targets)(swapchain)
+    }
+    /// Calls the callbacks once `swapchain` is in use.
+    fn notify(&mut self, swapchain: &S){
+        for callback in &mut self.callbacks{
+            callback(swapchain);
+        }
+    }
+}
+
+/// Whether a surface can be rendered to, and what is needed to create the swapchain dropped by
+/// `Surface::invalidate` again.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Lifecycle<T>{
+    invalid: bool,
+    dropped: Option<T>,
 }
 
+impl<T> Default for Lifecycle<T>{
+    fn default() -> Self{
+        Self{
+            invalid: false,
+            dropped: None,
+        }
+    }
+}
+
+impl<T> Lifecycle<T>{
+    /// Marks the surface as unusable. Invalidating again keeps the swapchain dropped first.
+    fn invalidate(&mut self, swapchain: Option<T>){
+        if let Some(swapchain) = swapchain{
+            self.dropped = Some(swapchain);
+        }
+        self.invalid = true;
+    }
+    /// Marks the surface as usable again, the dropped swapchain stays pending until `restored`.
+    fn rebuild(&mut self){
+        self.invalid = false;
+    }
+    fn is_valid(&self) -> bool{
+        !self.invalid
+    }
+    /// Fails with `NotReady` between `invalidate` and `rebuild`.
+    fn check_ready(&self) -> Result<(), AcquireImageError>{
+        if self.invalid{
+            return Err(AcquireImageError::NotReady);
+        }
+        Ok(())
+    }
+    /// The swapchain to create again, `None` while invalid or once it has been restored.
+    fn pending(&self) -> Option<&T>{
+        self.dropped.as_ref().filter(|_| !self.invalid)
+    }
+    fn restored(&mut self){
+        self.dropped = None;
+    }
+}
+
+type SurfaceFactory<W> = dyn Fn(&W) -> Result<Arc<vulkano::Surface<W>>, vulkano::SurfaceCreationError> + Send + Sync;
+
+impl<W> Drop for Surface<W>{
+    fn drop(&mut self){
+        // The swapchain can only be destroyed once the rendering into its images and their
+        // presentation have been submitted and finished.
+        if let Some(previous) = self.previous_frame.take(){
+            if let Err(err) = previous.wait(None){
+                log::warn!("Failed to wait for the last frame while dropping the surface: {}", err);
+            }
+        }
+        if super::guard::is_outstanding(&self.image_outstanding){
+            log::warn!("Dropping the surface while an acquired image was neither presented nor abandoned");
+        }
+    }
+}
+
+/// How a window covers the screen, see `Surface::set_fullscreen`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FullscreenMode{
+    Windowed,
+    /// A borderless window covering the monitor it is on, without changing the video mode.
+    Borderless,
+    /// Switches the monitor to the video mode, e.g. one chosen by `Surface::select_video_mode`.
+    Exclusive(winit::monitor::VideoMode),
+}
+
+/// Windows that can report their size, so the swapchain follows it without `Surface::set_extent`.
 pub trait WithInnerIsize{
     fn inner_size(&self) -> [u32; 2];
 }
@@ -44,101 +526,981 @@ impl WithInnerIsize for winit::window::Window{
     }
 }
 
+impl WithInnerIsize for Arc<winit::window::Window>{
+    fn inner_size(&self) -> [u32; 2] {
+        winit::window::Window::inner_size(self).into()
+    }
+}
+
+/// SDL windows are neither `Send` nor `Sync`, so their swapchains are usually created for a
+/// surface wrapped with `Surface::with_extent` instead. The size of the drawable is reported,
+/// which differs from the window size on high DPI displays.
+#[cfg(feature = "sdl2")]
+impl WithInnerIsize for sdl2::video::Window{
+    fn inner_size(&self) -> [u32; 2] {
+        let (width, height) = self.vulkan_drawable_size();
+        [width, height]
+    }
+}
+
+/// Stand-in for a window, e.g. for a surface created from a raw handle of another toolkit.
+///
+/// Reports a zero size, so the swapchain is only created once the real size is passed to
+/// `Surface::set_extent`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DummyWindow;
+
+impl WithInnerIsize for DummyWindow{
+    fn inner_size(&self) -> [u32; 2] {
+        [0, 0]
+    }
+}
+
 impl Surface<winit::window::Window>{
-    pub fn new(window: winit::window::Window, instance: Arc<vulkano::Instance>) -> Surface<winit::window::Window>{
-        let surface = vulkano_win::create_surface_from_winit(window, instance).unwrap();
+    /// Creates a surface for `window`, taking either a `hammer::Instance` or a vulkano one.
+    pub fn new(window: winit::window::Window, instance: impl Into<Arc<vulkano::Instance>>) -> Surface<winit::window::Window>{
+        let surface = vulkano_win::create_surface_from_winit(window, instance.into()).unwrap();
+        Surface::from_surface(surface)
+    }
+    /// Switches the window between windowed, borderless and exclusive fullscreen, recreating the
+    /// swapchain with the new size of the window on the next `acquire`.
+    pub fn set_fullscreen(&mut self, mode: FullscreenMode){
+        let window = self.surface.window();
+        window.set_fullscreen(match mode{
+            FullscreenMode::Windowed => None,
+            FullscreenMode::Borderless => Some(winit::window::Fullscreen::Borderless(window.current_monitor())),
+            FullscreenMode::Exclusive(video_mode) => Some(winit::window::Fullscreen::Exclusive(video_mode)),
+        });
+        self.needs_recreate = true;
+    }
+    pub fn fullscreen_mode(&self) -> FullscreenMode{
+        match self.surface.window().fullscreen(){
+            None => FullscreenMode::Windowed,
+            Some(winit::window::Fullscreen::Borderless(_)) => FullscreenMode::Borderless,
+            Some(winit::window::Fullscreen::Exclusive(video_mode)) => FullscreenMode::Exclusive(video_mode),
+        }
+    }
+    /// Video modes of the monitor the window is on, empty if the monitor is unknown.
+    pub fn video_modes(&self) -> Vec<winit::monitor::VideoMode>{
+        self.surface.window().current_monitor()
+            .map(|monitor| monitor.video_modes().collect())
+            .unwrap_or_default()
+    }
+    /// Picks a video mode of the monitor the window is on for exclusive fullscreen.
+    ///
+    /// Only modes of `size` are considered if it is set. With a `refresh_rate` the mode with the
+    /// closest refresh rate is chosen, preferring larger sizes and bit depths among those.
+    /// Otherwise the largest mode with the highest refresh rate is chosen.
+    pub fn select_video_mode(&self, size: Option<[u32; 2]>, refresh_rate: Option<u16>) -> Option<winit::monitor::VideoMode>{
+        let modes = self.video_modes()
+            .into_iter()
+            .filter(|mode| size.is_none_or(|size| <[u32; 2]>::from(mode.size()) == size));
+        let area = |mode: &winit::monitor::VideoMode| mode.size().width as u64 * mode.size().height as u64;
+        match refresh_rate{
+            Some(refresh_rate) => modes.max_by_key(|mode| (
+                std::cmp::Reverse((mode.refresh_rate() as i32 - refresh_rate as i32).abs()),
+                area(mode),
+                mode.bit_depth(),
+            )),
+            None => modes.max_by_key(|mode| (area(mode), mode.refresh_rate(), mode.bit_depth())),
+        }
+    }
+    /// Creates the surface again for the window an Android app gets when it resumes, after
+    /// `invalidate`, see `rebuild_with`.
+    #[cfg(target_os = "android")]
+    pub fn rebuild(&mut self, window: winit::window::Window, instance: impl Into<Arc<vulkano::Instance>>) -> Result<(), SwapchainError>{
+        let surface = vulkano_win::create_surface_from_winit(window, instance.into())?;
+        self.rebuild_with(surface)
+    }
+}
+
+impl Surface<Arc<winit::window::Window>>{
+    /// Creates a surface for a window that is shared with the application.
+    ///
+    /// Since the window can be handed to vulkano again, a lost surface is created again
+    /// automatically, e.g. after `SurfaceLost` during monitor hot-plugging on X11.
+    pub fn new_shared(window: Arc<winit::window::Window>, instance: impl Into<Arc<vulkano::Instance>>) -> Result<Self, vulkano::SurfaceCreationError>{
+        let instance = instance.into();
+        let surface = vulkano_win::create_surface_from_winit(window, instance.clone())?;
+        let mut surface = Surface::from_surface(surface);
+        surface.set_surface_factory(move |window: &Arc<winit::window::Window>| {
+            vulkano_win::create_surface_from_winit(window.clone(), instance.clone())
+        });
+        Ok(surface)
+    }
+}
+
+impl<W: WithInnerIsize> Surface<W>{
+    /// Wraps a surface whose window reports its size itself.
+    pub fn from_surface(surface: Arc<vulkano::Surface<W>>) -> Self{
+        Self::from_parts(surface, None, Some(|window: &W| window.inner_size()))
+    }
+    /// Same as `from_surface`, e.g. for a surface created by vulkano directly.
+    pub fn from_vulkano(surface: Arc<vulkano::Surface<W>>) -> Self{
+        Self::from_surface(surface)
+    }
+}
+
+#[cfg(feature = "raw-window-handle")]
+impl<W: raw_window_handle::HasRawWindowHandle> Surface<W>{
+    /// Creates a surface for a window of another toolkit, e.g. Qt, from its raw handle.
+    ///
+    /// The size of the window cannot be queried, so the swapchain is created with `extent` until
+    /// the toolkit reports a new size through `set_extent`. Panics for platforms vulkano cannot
+    /// create surfaces on, e.g. the web.
+    pub fn from_raw(window: W, extent: [u32; 2], instance: impl Into<Arc<vulkano::Instance>>) -> Result<Self, vulkano::SurfaceCreationError>{
+        let surface = vulkano_win::create_surface_from_handle(window, instance.into())?;
+        Ok(Self::from_parts(surface, Some(extent), None))
+    }
+}
+
+impl<W> Surface<W>{
+    /// Wraps a surface whose window cannot report its size, e.g. one of a toolkit other than
+    /// winit. The swapchain is created with `extent` until a new one is set with `set_extent`.
+    pub fn with_extent(surface: Arc<vulkano::Surface<W>>, extent: [u32; 2]) -> Self{
+        Self::from_parts(surface, Some(extent), None)
+    }
+    fn from_parts(surface: Arc<vulkano::Surface<W>>, extent: Option<[u32; 2]>, inner_size: Option<fn(&W) -> [u32; 2]>) -> Self{
         Surface{
             surface,
             swapchain: None,
+            retired: Mutex::new(DeferredDeletionQueue::bounded(RETIRED_SWAPCHAINS, RETIRED_SWAPCHAINS)),
+            image_outstanding: Arc::new(AtomicBool::new(false)),
+            format: None,
+            adapter: None,
+            needs_recreate: false,
+            pending_present_mode: None,
+            extent,
+            inner_size,
+            lifecycle: Lifecycle::default(),
+            surface_factory: None,
+            recreate_hooks: RecreateHooks::new(Swapchain::create_targets),
+            framebuffer_cache: Arc::new(FramebufferCache::default()),
+            previous_frame: None,
+            frame_stats: None,
+            frame_limiter: FrameLimiter::default(),
         }
     }
+    /// Size of the window, the one set with `set_extent` if there is one.
+    fn window_extent(&self) -> [u32; 2]{
+        window_extent(self.extent, self.inner_size, self.surface.window())
+    }
 }
 
-impl<W: WithInnerIsize> Surface<W>{
+/// The `extent` set with `Surface::set_extent`, otherwise the size `inner_size` reports for
+/// `window`.
+fn window_extent<W>(extent: Option<[u32; 2]>, inner_size: Option<fn(&W) -> [u32; 2]>, window: &W) -> [u32; 2]{
+    match (extent, inner_size){
+        (Some(extent), _) => extent,
+        (None, Some(inner_size)) => inner_size(window),
+        // Treated like a minimized window until `set_extent` is called.
+        (None, None) => [0, 0],
+    }
+}
+
+impl<W: Send + Sync + 'static> Surface<W>{
+    /// Chooses the image format for swapchains created afterwards, falling back to the first
+    /// format the surface reports if none matches `preference`.
+    ///
+    /// The choice is returned by `image_format` even before a swapchain exists, e.g. to create
+    /// render passes up front. `create_swapchain_with` chooses again with the descriptor's
+    /// preference.
+    pub fn select_format<P: GetPhysicalDevice>(
+        &mut self,
+        pdevice: P,
+        preference: FormatPreference,
+    ) -> Result<vulkano::Format, SwapchainError>{
+        Ok(self.resolve_format(pdevice.get_physical_device(), preference, ColorSpacePreference::SdrSrgb, false)?.0)
+    }
+    /// The image format and color space `create_swapchain_with` chooses for `desc`, without
+    /// creating a swapchain, e.g. to build render passes and pipelines first.
+    ///
+    /// Applies the same selection as the swapchain creation, so the two always agree as long as
+    /// the surface reports the same formats.
+    pub fn preferred_format<P: GetPhysicalDevice>(
+        &self,
+        pdevice: P,
+        desc: &SwapchainDescriptor,
+    ) -> Result<(vulkano::Format, vulkano::ColorSpace), SwapchainError>{
+        self.choose_format(pdevice.get_physical_device(), desc.format, desc.color_space, desc.strict)
+    }
+    /// Sets the physical device `image_format` and `color_space` query the preferred format
+    /// through while there is no swapchain yet.
+    pub fn set_adapter<P: GetPhysicalDevice>(&mut self, pdevice: P){
+        self.adapter = Some(pdevice.get_physical_device().index());
+    }
+    fn resolve_format(
+        &mut self,
+        pdevice: vulkano::PhysicalDevice,
+        preference: FormatPreference,
+        color_space: ColorSpacePreference,
+        strict: bool,
+    ) -> Result<(vulkano::Format, vulkano::ColorSpace), SwapchainError>{
+        let format = self.choose_format(pdevice, preference, color_space, strict)?;
+        self.format = Some(format);
+        self.adapter = Some(pdevice.index());
+        Ok(format)
+    }
+    fn choose_format(
+        &self,
+        pdevice: vulkano::PhysicalDevice,
+        preference: FormatPreference,
+        color_space: ColorSpacePreference,
+        strict: bool,
+    ) -> Result<(vulkano::Format, vulkano::ColorSpace), SwapchainError>{
+        let formats = pdevice.surface_formats(&self.surface, Default::default())?;
+        choose_surface_format(&formats, preference, color_space, strict)
+    }
+    // The format `preferred_format` reports for the default descriptor, if an adapter is known.
+    fn query_format(&self) -> Option<(vulkano::Format, vulkano::ColorSpace)>{
+        let pdevice = vulkano::PhysicalDevice::from_index(self.surface.instance(), self.adapter?)?;
+        self.preferred_format(&pdevice, &SwapchainDescriptor::default()).ok()
+    }
+    // The format of the swapchain, the one chosen by `select_format` or the preferred one, in
+    // this order.
+    fn current_format(&self) -> Option<(vulkano::Format, vulkano::ColorSpace)>{
+        let swapchain = self.swapchain.as_ref().map(|swapchain| (swapchain.image_format(), swapchain.image_color_space()));
+        resolve_current_format(swapchain, self.format, || self.query_format())
+    }
+    /// Creates the swapchain with the default `SwapchainDescriptor`.
+    ///
+    /// Fails with `SwapchainError::UnsupportedExtent` while the window has a zero size, e.g.
+    /// because it is minimized.
     pub fn create_swapchain<P: GetPhysicalDevice>(
         &mut self, 
         device: Arc<vulkano::Device>, 
         pdevice: P
-    ) -> bool{
-        let (swapchain, images) = {
-            let surface_capabilities = pdevice.get_physical_device()
-                .surface_capabilities(&self.surface, Default::default())
-                .unwrap();
-
-            let image_format = Some(
-                pdevice.get_physical_device()
-                .surface_formats(&self.surface, Default::default())
-                .unwrap()[0]
-                .0,
-            );
-
-            vulkano::Swapchain::new(
+    ) -> Result<(), SwapchainError>{
+        self.create_swapchain_with(device, pdevice, &SwapchainDescriptor::default())
+    }
+    /// Creates the swapchain, retiring the previous one if there is one.
+    pub fn create_swapchain_with<P: GetPhysicalDevice>(
+        &mut self, 
+        device: Arc<vulkano::Device>, 
+        pdevice: P,
+        desc: &SwapchainDescriptor,
+    ) -> Result<(), SwapchainError>{
+        if !self.lifecycle.is_valid(){
+            return Err(SwapchainError::SurfaceLost);
+        }
+        let (swapchain, images, sample_count) = {
+            let pdevice = pdevice.get_physical_device();
+            let surface_capabilities = pdevice
+                .surface_capabilities(&self.surface, Default::default())?;
+
+            let (image_format, image_color_space) = self.resolve_format(pdevice, desc.format, desc.color_space, desc.strict)?;
+
+            let present_mode = if pdevice.surface_present_modes(&self.surface)?.any(|mode| mode == desc.present_mode){
+                desc.present_mode
+            } else{
+                fall_back(desc.strict, format!("Present mode {:?} is not supported, using Fifo", desc.present_mode))?;
+                vulkano::PresentMode::Fifo
+            };
+
+            let max_image_count = surface_capabilities.max_image_count.unwrap_or(u32::MAX);
+            let min_image_count = match desc.min_image_count{
+                Some(count) if count < surface_capabilities.min_image_count || count > max_image_count => {
+                    let clamped = count.clamp(surface_capabilities.min_image_count, max_image_count);
+                    fall_back(desc.strict, format!("Swapchain image count {} is not supported, using {}", count, clamped))?;
+                    clamped
+                },
+                Some(count) => count,
+                None => surface_capabilities.min_image_count,
+            };
+
+            let requested_usage = ash::vk::ImageUsageFlags::from(desc.image_usage);
+            let supported_usage = ash::vk::ImageUsageFlags::from(surface_capabilities.supported_usage_flags);
+            let image_usage = vulkano::ImageUsage::from(requested_usage & supported_usage);
+            if !supported_usage.contains(requested_usage){
+                fall_back(desc.strict, format!("Swapchain image usage {:?} is not supported, using {:?}", desc.image_usage, image_usage))?;
+            }
+
+            let supported_alpha = surface_capabilities.supported_composite_alpha;
+            // Every surface supports at least one mode, opaque is only a placeholder that lets
+            // the creation fail properly otherwise.
+            let first_alpha = supported_alpha
+                .iter()
+                .next()
+                .unwrap_or(vulkano::CompositeAlpha::Opaque);
+            let composite_alpha = match desc.composite_alpha{
+                Some(alpha) if supported_alpha.supports(alpha) => alpha,
+                Some(alpha) => {
+                    let fallback = [vulkano::CompositeAlpha::Inherit, vulkano::CompositeAlpha::Opaque]
+                        .into_iter()
+                        .find(|&fallback| supported_alpha.supports(fallback))
+                        .unwrap_or(first_alpha);
+                    fall_back(desc.strict, format!("Composite alpha {:?} is not supported, using {:?}", alpha, fallback))?;
+                    fallback
+                },
+                None if supported_alpha.opaque => vulkano::CompositeAlpha::Opaque,
+                None => first_alpha,
+            };
+
+            // Without a depth buffer only the color attachments limit the sample count.
+            let supported_samples = match desc.depth_format{
+                Some(_) => framebuffer_sample_counts(pdevice.properties()),
+                None => pdevice.properties().framebuffer_color_sample_counts,
+            };
+            let sample_count = clamp_sample_count(supported_samples, desc.sample_count);
+            if sample_count != desc.sample_count{
+                fall_back(desc.strict, format!("Sample count {:?} is not supported, using {:?}", desc.sample_count, sample_count))?;
+            }
+
+            let full_screen_exclusive = match desc.full_screen_exclusive{
+                vulkano::FullScreenExclusive::Default => vulkano::FullScreenExclusive::Default,
+                mode if !device.enabled_extensions().ext_full_screen_exclusive => {
+                    fall_back(desc.strict, format!("Full screen exclusive {:?} needs the ext_full_screen_exclusive extension, using Default", mode))?;
+                    vulkano::FullScreenExclusive::Default
+                },
+                vulkano::FullScreenExclusive::ApplicationControlled => {
+                    fall_back(desc.strict, "Full screen exclusive ApplicationControlled is not supported, using Allowed".to_string())?;
+                    vulkano::FullScreenExclusive::Allowed
+                },
+                mode => mode,
+            };
+
+            let window_extent = self.window_extent();
+            if window_extent.contains(&0){
+                return Err(SwapchainError::UnsupportedExtent(window_extent));
+            }
+            // Presenting in the orientation of the display avoids a slow path in the compositor,
+            // the renderer compensates with `Surface::pre_transform`.
+            let pre_transform = surface_capabilities.current_transform;
+            let image_extent = transformed_extent(window_extent, pre_transform);
+
+            // With a separate present queue the images are used by two queue families. Sharing
+            // them concurrently avoids ownership transfers between rendering and presentation.
+            let families = device.active_queue_families()
+                .map(|q| q.id())
+                .collect::<Vec<_>>();
+            let image_sharing = if families.len() > 1{
+                vulkano::Sharing::Concurrent(families.iter().copied().collect())
+            } else{
+                vulkano::Sharing::Exclusive
+            };
+
+            let (swapchain, images) = vulkano::Swapchain::new(
                 device.clone(),
                 self.surface.clone(),
                 vulkano::SwapchainCreateInfo {
-                    min_image_count: surface_capabilities.min_image_count,
+                    min_image_count,
+
+                    image_format: Some(image_format),
+                    image_color_space,
+                    image_extent,
+
+                    image_usage,
 
-                    image_format,
-                    image_extent: self.surface.window().inner_size().into(),
+                    pre_transform,
 
-                    image_usage: vulkano::ImageUsage::color_attachment(),
+                    composite_alpha,
 
-                    composite_alpha: surface_capabilities
-                        .supported_composite_alpha
-                        .iter()
-                        .next()
-                        .unwrap(),
+                    present_mode,
+
+                    image_sharing,
+
+                    full_screen_exclusive,
 
                         ..Default::default()
                 },
-                )
-                    .unwrap()
+                )?;
+            (swapchain, images, sample_count)
+        };
+        if let Some(old) = self.swapchain.take(){
+            self.framebuffer_cache.invalidate();
+            self.retired.lock().unwrap().retire(old);
+        }
+        let desc = SwapchainDescriptor{
+            sample_count,
+            ..desc.clone()
         };
-        self.swapchain = Some(
-            Swapchain{
-                device,
-                swapchain,
-                images,
+        let mut swapchain = Swapchain::new(device, swapchain, images, desc, self.framebuffer_cache.clone())?;
+        self.recreate_hooks.create_targets(&mut swapchain)?;
+        self.swapchain = Some(swapchain);
+        self.notify_recreated();
+        Ok(())
+    }
+    /// Recreates the swapchain with the current size of the window, e.g. after a resize.
+    ///
+    /// The settings resolved from the `SwapchainDescriptor` are kept, the pre-transform follows
+    /// the current orientation of the display. On failure the previous swapchain is kept.
+    ///
+    /// If the surface has been lost and a factory is set with `set_surface_factory`, the surface
+    /// is created again before giving up with `SwapchainError::SurfaceLost`.
+    pub fn recreate_swapchain(&mut self) -> Result<(), SwapchainError>{
+        let can_recover = self.surface_factory.is_some();
+        recreate_or_recover(self, can_recover, Self::recreate_swapchain_once, Self::recover_surface)
+    }
+    fn recreate_swapchain_once(&mut self) -> Result<(), SwapchainError>{
+        let window_extent = self.window_extent();
+        let swapchain = self.swapchain.as_mut().ok_or(SwapchainError::NoSwapchain)?;
+        if window_extent.contains(&0){
+            return Err(SwapchainError::UnsupportedExtent(window_extent));
+        }
+        let pre_transform = swapchain.device.physical_device()
+            .surface_capabilities(&self.surface, Default::default())?
+            .current_transform;
+        let create_info = swapchain.create_info();
+        // `recreate` passes the current swapchain as the old one, so the presentation engine can
+        // reuse its resources and images still being presented stay valid.
+        let (new_swapchain, new_images) = swapchain.recreate(vulkano::SwapchainCreateInfo{
+            image_extent: transformed_extent(window_extent, pre_transform),
+            pre_transform,
+            present_mode: self.pending_present_mode.unwrap_or(create_info.present_mode),
+            ..create_info
+        })?;
+        self.pending_present_mode = None;
+        let mut new_swapchain = Swapchain::new(
+            swapchain.device.clone(),
+            new_swapchain,
+            new_images,
+            swapchain.descriptor.clone(),
+            self.framebuffer_cache.clone(),
+        )?;
+        self.recreate_hooks.create_targets(&mut new_swapchain)?;
+        // The cached views belong to the old images and are retired with them.
+        let old = std::mem::replace(swapchain, new_swapchain);
+        // The framebuffers of the old images are not needed anymore, the frames in flight keep
+        // the ones they use alive.
+        self.framebuffer_cache.invalidate();
+        self.retired.lock().unwrap().retire(old);
+        self.notify_recreated();
+        Ok(())
+    }
+    /// Registers `callback` to be called with the new swapchain at the end of every successful
+    /// `create_swapchain_with` and `recreate_swapchain`, including the ones done by `acquire`.
+    ///
+    /// Meant for resources that depend on the extent, format or image count of the swapchain,
+    /// e.g. full screen intermediate images. The callbacks are called in the order they were
+    /// registered and cannot change the surface while it is recreating the swapchain. The
+    /// multisampled images and the depth buffer of the swapchain already exist when they run.
+    pub fn on_recreate<F>(&mut self, callback: F)
+    where
+        F: FnMut(&Swapchain<W>) + Send + 'static,
+    {
+        self.recreate_hooks.push(Box::new(callback));
+    }
+    fn notify_recreated(&mut self){
+        if let Some(swapchain) = &self.swapchain{
+            self.recreate_hooks.notify(swapchain);
+        }
+    }
+    /// Switches the present mode, e.g. to toggle vsync between `Fifo` and `Mailbox` or
+    /// `Immediate`.
+    ///
+    /// The swapchain is recreated with the new mode by the next `acquire` or
+    /// `recreate_swapchain`, since an image of the current one might still be in use.
+    pub fn set_present_mode(&mut self, mode: vulkano::PresentMode) -> Result<(), SwapchainError>{
+        let swapchain = self.swapchain.as_mut().ok_or(SwapchainError::NoSwapchain)?;
+        let supported = swapchain.device.physical_device()
+            .surface_present_modes(&self.surface)?
+            .collect::<Vec<_>>();
+        if !supported.contains(&mode){
+            return Err(SwapchainError::UnsupportedPresentMode{
+                requested: mode,
+                supported,
+            });
+        }
+        swapchain.descriptor.present_mode = mode;
+        self.pending_present_mode = Some(mode);
+        self.needs_recreate = true;
+        Ok(())
+    }
+    /// Switches the number of samples per pixel, e.g. to toggle MSAA.
+    ///
+    /// Like `set_present_mode`, the attachments are recreated by the next `acquire` or
+    /// `recreate_swapchain`. Render passes and pipelines have to be created again for the new
+    /// sample count.
+    pub fn set_sample_count(&mut self, samples: vulkano::SampleCount) -> Result<(), SwapchainError>{
+        let swapchain = self.swapchain.as_mut().ok_or(SwapchainError::NoSwapchain)?;
+        let properties = swapchain.device.physical_device().properties();
+        let supported = properties.framebuffer_color_sample_counts.contains(samples)
+            && (swapchain.descriptor.depth_format.is_none() || properties.framebuffer_depth_sample_counts.contains(samples));
+        if !supported{
+            return Err(SwapchainError::UnsupportedSampleCount(samples));
+        }
+        swapchain.descriptor.sample_count = samples;
+        self.needs_recreate = true;
+        Ok(())
+    }
+    /// Switches the format of the depth buffer created with the swapchain, or removes it with
+    /// `None`.
+    ///
+    /// Like `set_sample_count`, the depth buffer changes when the swapchain is recreated.
+    pub fn set_depth_format(&mut self, depth_format: Option<vulkano::Format>) -> Result<(), SwapchainError>{
+        let swapchain = self.swapchain.as_mut().ok_or(SwapchainError::NoSwapchain)?;
+        if let Some(format) = depth_format{
+            let physical_device = swapchain.device.physical_device();
+            let supported = physical_device.format_properties(format).optimal_tiling_features.depth_stencil_attachment
+                && physical_device.properties().framebuffer_depth_sample_counts.contains(swapchain.descriptor.sample_count);
+            if !supported{
+                return Err(SwapchainError::UnsupportedDepthFormat(format));
             }
+        }
+        swapchain.descriptor.depth_format = depth_format;
+        self.needs_recreate = true;
+        Ok(())
+    }
+    /// Format of the depth buffer of the current swapchain, see `set_depth_format`.
+    pub fn depth_format(&self) -> Option<vulkano::Format>{
+        self.swapchain.as_ref()?.depth_format()
+    }
+    /// Samples per pixel of the current swapchain, which can differ from the one passed to
+    /// `set_sample_count` until the swapchain is recreated.
+    pub fn sample_count(&self) -> Option<vulkano::SampleCount>{
+        Some(self.swapchain.as_ref()?.sample_count())
+    }
+    /// Records window events that invalidate the swapchain, so the next `acquire` recreates it.
+    ///
+    /// Pass every `WindowEvent` of the window the surface was created for, events that do not
+    /// concern the swapchain are ignored.
+    pub fn handle_window_event(&mut self, event: &winit::event::WindowEvent){
+        match event{
+            winit::event::WindowEvent::Resized(_) |
+            winit::event::WindowEvent::ScaleFactorChanged{..} => self.needs_recreate = true,
+            _ => (),
+        }
+    }
+    /// Drops the swapchain and marks the surface as unusable until `rebuild_with`, e.g. because
+    /// the window is destroyed when an Android app is suspended.
+    ///
+    /// Like `shutdown` this waits for the last frame and the device first, since the swapchains
+    /// are destroyed right away. `acquire` fails with `AcquireImageError::NotReady` in the
+    /// meantime. The swapchain is created again with its previous descriptor after `rebuild_with`.
+    pub fn invalidate(&mut self, device: &Device) -> Result<(), FrameError>{
+        if let Some(previous) = self.previous_frame.take(){
+            previous.wait(None).map_err(FrameError::Wait)?;
+        }
+        device.wait_idle().map_err(FrameError::WaitIdle)?;
+        self.drop_swapchain();
+        // Retired swapchains still refer to the window as well.
+        self.retired.lock().unwrap().clear();
+        Ok(())
+    }
+    /// Marks the surface as unusable and retires the swapchain, which is destroyed once the
+    /// frames using it have finished.
+    fn drop_swapchain(&mut self){
+        let swapchain = self.swapchain.take();
+        self.lifecycle.invalidate(
+            swapchain.as_ref().map(|swapchain| (swapchain.device.clone(), swapchain.descriptor.clone()))
         );
-        true
-    }
-    pub fn recreate_swapchain(&mut self) -> bool{
-        match self.swapchain{
-            Some(ref mut swapchain) => {
-                let (new_swapchain, new_images) = 
-                    match swapchain.recreate(vulkano::SwapchainCreateInfo{
-                        image_extent: self.surface.window().inner_size().into(),
-                        ..swapchain.create_info()
-                    }){
-                        Ok(r) => r,
-                        Err(vulkano::SwapchainCreationError::ImageExtentNotSupported{..}) => return false,
-                        Err(e) => panic!("Failed to recreate swapchain: {:?}", e),
-                    };
-                swapchain.swapchain = new_swapchain;
-                swapchain.images = new_images;
-                true
-            },
-            _ => false,
+        if let Some(swapchain) = swapchain{
+            self.retired.lock().unwrap().retire(swapchain);
         }
+        self.framebuffer_cache.invalidate();
     }
-    pub fn get_current_image(&self) -> SurfaceImage<W>{
-
-        let (image_num, suboptimal, acquire_future) = 
-            match vulkano::acquire_next_image(self.swapchain.as_ref().unwrap().swapchain.clone(), None){
-                Ok(r) => r,
-                Err(e) => panic!("Failed to acquire next image: {:?}", e),
-            };
+    /// Cache of the framebuffers of the swapchain images, e.g. to check its statistics.
+    pub fn framebuffer_cache(&self) -> &Arc<FramebufferCache>{
+        &self.framebuffer_cache
+    }
+    /// Whether the surface can be rendered to, `false` between `invalidate` and `rebuild`.
+    pub fn is_valid(&self) -> bool{
+        self.lifecycle.is_valid()
+    }
+    /// Replaces the vulkano surface after `invalidate`, e.g. with the one of the window that is
+    /// recreated when an Android app resumes, and creates the swapchain again.
+    ///
+    /// If the swapchain cannot be created yet, e.g. because the window has no size yet, the next
+    /// `acquire` tries again.
+    pub fn rebuild_with(&mut self, surface: Arc<vulkano::Surface<W>>) -> Result<(), SwapchainError>{
+        self.surface = surface;
+        self.lifecycle.rebuild();
+        self.restore_swapchain()
+    }
+    /// Sets how a new surface is created for the window once the current one is lost, e.g. when
+    /// the window is an `Arc` that can be handed to vulkano again, see `Surface::new_shared`.
+    ///
+    /// `recreate_swapchain` and `acquire` use it before failing with `SurfaceLost`.
+    pub fn set_surface_factory<F>(&mut self, factory: F)
+    where
+        F: Fn(&W) -> Result<Arc<vulkano::Surface<W>>, vulkano::SurfaceCreationError> + Send + Sync + 'static,
+    {
+        self.surface_factory = Some(Box::new(factory));
+    }
+    /// Replaces a lost surface with one from the surface factory and creates the swapchain
+    /// again with its previous descriptor.
+    fn recover_surface(&mut self) -> Result<(), SwapchainError>{
+        let factory = self.surface_factory.as_ref().ok_or(SwapchainError::SurfaceLost)?;
+        log::warn!("The surface has been lost, creating it again");
+        let surface = factory(self.surface.window())?;
+        // The swapchains of the lost surface cannot be the old swapchain of the new one.
+        self.drop_swapchain();
+        self.rebuild_with(surface)
+    }
+    /// Creates the swapchain dropped by `invalidate` again.
+    fn restore_swapchain(&mut self) -> Result<(), SwapchainError>{
+        if let Some((device, desc)) = self.lifecycle.pending().cloned(){
+            self.create_swapchain_with(device.clone(), &device.physical_device(), &desc)?;
+            self.lifecycle.restored();
+            self.pending_present_mode = None;
+            self.needs_recreate = false;
+        }
+        Ok(())
+    }
+    /// Sets the size of the window, e.g. when a toolkit other than winit reports a resize, and
+    /// makes the next `acquire` recreate the swapchain with it.
+    ///
+    /// Afterwards the size is no longer queried from the window.
+    pub fn set_extent(&mut self, extent: [u32; 2]){
+        self.extent = Some(extent);
+        self.needs_recreate = true;
+    }
+    /// Takes the future of the last frame presented through `Frame::present`, freeing the
+    /// resources of the work that has finished since.
+    pub(crate) fn take_previous_frame(&mut self) -> Option<vulkano::FenceSignalFuture<Box<dyn GpuFuture>>>{
+        let mut previous = self.previous_frame.take()?;
+        previous.cleanup_finished();
+        Some(previous)
+    }
+    pub(crate) fn set_previous_frame(&mut self, future: vulkano::FenceSignalFuture<Box<dyn GpuFuture>>){
+        self.previous_frame = Some(future);
+    }
+    /// Waits for the last frame presented through `Frame::present` and then for all other work on
+    /// `device`, call this before dropping the surface or the device when exiting.
+    ///
+    /// Swapchains retired by earlier recreations are destroyed afterwards.
+    pub fn shutdown(&mut self, device: &Device) -> Result<(), FrameError>{
+        if let Some(previous) = self.previous_frame.take(){
+            previous.wait(None).map_err(FrameError::Wait)?;
+        }
+        device.wait_idle().map_err(FrameError::WaitIdle)?;
+        self.retired.lock().unwrap().clear();
+        Ok(())
+    }
+    /// Starts collecting the times of the frames begun with `begin_frame`, see `FrameStats`.
+    pub fn enable_frame_stats(&mut self, device: &Device){
+        if self.frame_stats.is_none(){
+            self.frame_stats = Some(FrameStats::new(device));
+        }
+    }
+    pub fn frame_stats(&self) -> Option<&FrameStats>{
+        self.frame_stats.as_ref()
+    }
+    pub(crate) fn frame_stats_mut(&mut self) -> Option<&mut FrameStats>{
+        self.frame_stats.as_mut()
+    }
+    /// Limits `Frame::present` to `fps` frames per second, see `FrameLimiter`. `None` removes the
+    /// limit.
+    pub fn set_target_fps(&mut self, fps: Option<f32>){
+        self.frame_limiter.set_target_fps(fps);
+    }
+    pub fn target_fps(&self) -> Option<f32>{
+        self.frame_limiter.target_fps()
+    }
+    /// Waits for the frame limiter and records the present in the frame stats, called right
+    /// before `Frame::present` presents.
+    pub(crate) fn pace_present(&mut self){
+        self.frame_limiter.wait();
+        if let Some(stats) = &mut self.frame_stats{
+            stats.presented();
+        }
+    }
+    /// Whether old swapchains are destroyed once a present fence signals that their last present
+    /// completed.
+    ///
+    /// Always `false`, present fences need `VK_EXT_swapchain_maintenance1`, which vulkano 0.29 does
+    /// not expose. Old swapchains are kept alive for a few frames instead.
+    pub fn present_fence_supported(&self) -> bool{
+        false
+    }
+    /// Whether the next `acquire` recreates the swapchain.
+    pub fn needs_recreate(&self) -> bool{
+        self.needs_recreate
+    }
+    /// Makes the next `acquire` recreate the swapchain, e.g. after `get_current_image` returned
+    /// an image with `SurfaceImage::suboptimal` set.
+    pub fn mark_suboptimal(&mut self){
+        self.needs_recreate = true;
+    }
+    /// Acquires the next image of the swapchain, waiting at most `timeout` for one to become
+    /// available.
+    ///
+    /// `AcquireImageError::OutOfDate` is expected while the window is resized, recreate the
+    /// swapchain and skip the frame in that case.
+    #[track_caller]
+    pub fn get_current_image(&self, timeout: Option<Duration>) -> Result<SurfaceImage<W>, AcquireImageError>{
+        self.lifecycle.check_ready()?;
+        self.advance_frame();
+        self.acquire_image(timeout)
+    }
+    /// Acquires the next image, recreating the swapchain whenever it is out of date, suboptimal or
+    /// does not match the size of the window anymore.
+    ///
+    /// Resizes are usually noticed through `handle_window_event`, comparing the extent with the
+    /// window only catches the ones that were not passed to it.
+    ///
+    /// A suboptimal image is still returned and the swapchain is only recreated with the next
+    /// call. While the window is minimized this fails with `SwapchainError::UnsupportedExtent`,
+    /// skip the frame in that case.
+    #[track_caller]
+    pub fn acquire(&mut self) -> Result<SurfaceImage<W>, AcquireImageError>{
+        self.lifecycle.check_ready()?;
+        self.advance_frame();
+        self.restore_swapchain()?;
+        for _ in 0..ACQUIRE_ATTEMPTS{
+            let window_extent = self.window_extent();
+            let resized = self.swapchain.as_ref().is_some_and(|swapchain| {
+                swapchain.image_extent() != transformed_extent(window_extent, swapchain.pre_transform())
+            });
+            if self.needs_recreate || resized{
+                self.recreate_swapchain()?;
+                self.needs_recreate = false;
+            }
+            match self.acquire_image(None){
+                Ok(image) => {
+                    if image.suboptimal{
+                        self.mark_suboptimal();
+                    }
+                    return Ok(image);
+                },
+                Err(AcquireImageError::OutOfDate) => self.needs_recreate = true,
+                Err(AcquireImageError::SurfaceLost) if self.surface_factory.is_some() => self.recover_surface()?,
+                Err(AcquireImageError::FullScreenExclusiveLost) => {
+                    self.needs_recreate = true;
+                    return Err(AcquireImageError::FullScreenExclusiveLost);
+                },
+                Err(err) => return Err(err),
+            }
+        }
+        Err(AcquireImageError::OutOfDate)
+    }
+    fn advance_frame(&self){
+        self.retired.lock().unwrap().advance_frame();
+        if super::guard::is_outstanding(&self.image_outstanding){
+            log::warn!("Acquiring a new image while the previous one was neither presented nor abandoned");
+        }
+    }
+    #[track_caller]
+    fn acquire_image(&self, timeout: Option<Duration>) -> Result<SurfaceImage<W>, AcquireImageError>{
+        let swapchain = self.swapchain.as_ref().ok_or(AcquireImageError::NoSwapchain)?;
+        let (image_num, suboptimal, acquire_future) =
+            vulkano::acquire_next_image(swapchain.swapchain.clone(), timeout)?;
 
-        SurfaceImage{
-            image: self.swapchain.as_ref().unwrap().images[image_num].clone(),
+        Ok(SurfaceImage{
+            image: swapchain.images[image_num].clone(),
+            view: swapchain.image_views()[image_num].clone(),
+            framebuffer_cache: self.framebuffer_cache.clone(),
             suboptimal,
-            acquire_future,
+            acquire_future: Some(acquire_future),
             image_num,
-        }
+            guard: MisuseGuard::with_flag(
+                "SurfaceImage",
+                "present it or call `abandon` to skip the frame",
+                Some(self.image_outstanding.clone()),
+            ),
+        })
     }
+    /// Presents `image` on `queue` after `after`, see `SurfaceImage::present`.
+    pub fn present<F>(&self, queue: &Queue, image: SurfaceImage<W>, after: F) -> Result<vulkano::FenceSignalFuture<Box<dyn GpuFuture>>, PresentError>
+    where
+        F: GpuFuture + 'static,
+        W: Send + Sync + 'static,
+    {
+        image.present(queue, after)
+    }
+    /// Presents `image` on `queue` after `after`, see `SurfaceImage::present_with_regions`.
+    pub fn present_with_regions<F>(&self, queue: &Queue, image: SurfaceImage<W>, after: F, regions: &[Rect]) -> Result<vulkano::FenceSignalFuture<Box<dyn GpuFuture>>, PresentError>
+    where
+        F: GpuFuture + 'static,
+        W: Send + Sync + 'static,
+    {
+        image.present_with_regions(queue, after, regions)
+    }
+    /// Number of swapchain images, `None` if there is no swapchain yet.
+    pub fn image_count(&self) -> Option<u32>{
+        Some(self.swapchain.as_ref()?.image_count())
+    }
+    pub fn extent(&self) -> Option<[u32; 2]>{
+        Some(self.swapchain.as_ref()?.extent())
+    }
+    pub fn images(&self) -> Option<&[Arc<vulkano::SwapchainImage<W>>]>{
+        Some(self.swapchain.as_ref()?.images())
+    }
+    pub fn image_views(&self) -> Option<&[Arc<dyn vulkano::ImageViewAbstract>]>{
+        Some(self.swapchain.as_ref()?.image_views())
+    }
+    /// Clear values for the attachments of the swapchain, see `Swapchain::clear_values`.
+    pub fn clear_values(&self, color: [f32; 4]) -> Option<Vec<vulkano::ClearValue>>{
+        Some(self.swapchain.as_ref()?.clear_values(color))
+    }
+    /// Creates a render pass matching the framebuffers of the swapchain, see
+    /// `Swapchain::create_render_pass`.
+    pub fn create_render_pass(&self) -> Result<Arc<vulkano::RenderPass>, SwapchainError>{
+        Ok(self.swapchain.as_ref().ok_or(SwapchainError::NoSwapchain)?.create_render_pass()?)
+    }
+    /// Builder for the render pass `create_render_pass` creates, see
+    /// `Swapchain::render_pass_builder`.
+    pub fn render_pass_builder(&self) -> Option<RenderPassBuilder>{
+        Some(self.swapchain.as_ref()?.render_pass_builder())
+    }
+    /// Rotation the presentation engine applies to the swapchain images, which the renderer has
+    /// to compensate for, e.g. by rotating the projection in the opposite direction.
+    pub fn pre_transform(&self) -> Option<vulkano::SurfaceTransform>{
+        Some(self.swapchain.as_ref()?.pre_transform())
+    }
+    /// Color space of the swapchain images, see `image_format` for the fallbacks if there is no
+    /// swapchain yet. Shaders writing to an HDR color space have to apply its transfer function.
+    pub fn color_space(&self) -> Option<vulkano::ColorSpace>{
+        self.current_format().map(|(_, color_space)| color_space)
+    }
+    /// Format of the swapchain images. Without a swapchain, the one chosen by `select_format`,
+    /// or else the `preferred_format` of the default descriptor on the adapter set with
+    /// `set_adapter`. `None` if neither is known.
     pub fn image_format(&self) -> Option<vulkano::format::Format>{
-        Some(self.swapchain.as_ref()?.image_format())
+        self.current_format().map(|(format, _)| format)
+    }
+    /// Viewport covering the current swapchain images, `None` if there is no swapchain.
+    pub fn viewport(&self) -> Option<vulkano::Viewport>{
+        let extent = self.extent()?;
+        Some(vulkano::Viewport{
+            origin: [0.0, 0.0],
+            dimensions: [extent[0] as f32, extent[1] as f32],
+            depth_range: 0.0..1.0,
+        })
+    }
+    /// Scissor covering the current swapchain images, `None` if there is no swapchain.
+    pub fn scissor(&self) -> Option<vulkano::Scissor>{
+        Some(vulkano::Scissor{
+            origin: [0, 0],
+            dimensions: self.extent()?,
+        })
+    }
+    /// Viewport like `viewport` with the Y axis pointing up, as in OpenGL, e.g. for porting
+    /// content that assumes it.
+    ///
+    /// The viewport has a negative height starting at the bottom, which needs Vulkan 1.1 or
+    /// `VK_KHR_maintenance1`. `None` if the device has neither or there is no swapchain.
+    pub fn flipped_viewport(&self) -> Option<vulkano::Viewport>{
+        let device = &self.swapchain.as_ref()?.device;
+        if device.api_version() < vulkano::Version::V1_1 && !device.enabled_extensions().khr_maintenance1{
+            return None;
+        }
+        let viewport = self.viewport()?;
+        Some(vulkano::Viewport{
+            origin: [0.0, viewport.dimensions[1]],
+            dimensions: [viewport.dimensions[0], -viewport.dimensions[1]],
+            ..viewport
+        })
+    }
+}
+
+impl<W: Send + Sync + 'static> RenderTarget for Surface<W>{
+    type Image = SurfaceImage<W>;
+
+    #[track_caller]
+    fn acquire(&mut self) -> Result<SurfaceImage<W>, AcquireImageError>{
+        Surface::acquire(self)
+    }
+    fn image_format(&self) -> Option<vulkano::Format>{
+        Surface::image_format(self)
+    }
+    fn extent(&self) -> Option<[u32; 2]>{
+        Surface::extent(self)
+    }
+    fn sample_count(&self) -> Option<vulkano::SampleCount>{
+        Surface::sample_count(self)
+    }
+    fn create_render_pass(&self) -> Result<Arc<vulkano::RenderPass>, SwapchainError>{
+        Surface::create_render_pass(self)
+    }
+    fn clear_values(&self, color: [f32; 4]) -> Option<Vec<vulkano::ClearValue>>{
+        Surface::clear_values(self, color)
+    }
+    fn framebuffer(&self, image: &SurfaceImage<W>, render_pass: &Arc<vulkano::RenderPass>) -> Result<Arc<vulkano::Framebuffer>, vulkano::FramebufferCreationError>{
+        image.framebuffer(self, render_pass)
+    }
+    fn present(&mut self, queue: &Queue, image: SurfaceImage<W>, after: Box<dyn GpuFuture>) -> Result<vulkano::FenceSignalFuture<Box<dyn GpuFuture>>, PresentError>{
+        image.present(queue, after)
+    }
+    fn present_with_regions(&mut self, queue: &Queue, image: SurfaceImage<W>, after: Box<dyn GpuFuture>, regions: &[Rect]) -> Result<vulkano::FenceSignalFuture<Box<dyn GpuFuture>>, PresentError>{
+        image.present_with_regions(queue, after, regions)
+    }
+}
+
+/// An acquired image of a surface as a `DrawTarget`, see `Surface::draw_target`.
+pub struct SurfaceTarget<'a, W>{
+    swapchain: &'a Swapchain<W>,
+    image_num: usize,
+}
+
+impl<W: Send + Sync + 'static> Surface<W>{
+    /// `image` as a `DrawTarget`, rendering into it with the attachments of the swapchain.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the image was not acquired from the current swapchain of this surface.
+    #[track_caller]
+    pub fn draw_target<'a>(&'a self, image: &SurfaceImage<W>) -> SurfaceTarget<'a, W>{
+        let swapchain = self.swapchain.as_ref().expect("the image was acquired from this surface");
+        assert!(
+            Arc::ptr_eq(image.image.swapchain(), &swapchain.swapchain),
+            "the swapchain has been recreated since the image was acquired",
+        );
+        SurfaceTarget{
+            swapchain,
+            image_num: image.image_num,
+        }
+    }
+    /// Image `image_num` of the current swapchain as a `DrawTarget` without acquiring it, e.g. to
+    /// record the command buffers of every image up front for a `CommandBufferCache`.
+    ///
+    /// `None` if there is no swapchain or it has fewer images.
+    pub fn image_target(&self, image_num: usize) -> Option<SurfaceTarget<'_, W>>{
+        let swapchain = self.swapchain.as_ref()?;
+        (image_num < swapchain.images.len()).then_some(SurfaceTarget{
+            swapchain,
+            image_num,
+        })
+    }
+}
+
+impl<'a, W: Send + Sync + 'static> DrawTarget for SurfaceTarget<'a, W>{
+    fn extent(&self) -> [u32; 2]{
+        self.swapchain.extent()
+    }
+    fn format(&self) -> vulkano::Format{
+        self.swapchain.image_format()
+    }
+    fn view(&self) -> Arc<dyn vulkano::ImageViewAbstract>{
+        self.swapchain.image_views()[self.image_num].clone()
+    }
+    fn create_render_pass(&self) -> Result<Arc<vulkano::RenderPass>, SwapchainError>{
+        Ok(self.swapchain.create_render_pass()?)
+    }
+    fn clear_values(&self, color: [f32; 4]) -> Vec<vulkano::ClearValue>{
+        self.swapchain.clear_values(color)
+    }
+    fn framebuffer(&self, render_pass: &Arc<vulkano::RenderPass>) -> Result<Arc<vulkano::Framebuffer>, vulkano::FramebufferCreationError>{
+        Ok(self.swapchain.framebuffers(render_pass)?[self.image_num].clone())
+    }
+}
+
+/// A rectangle of an image in pixels, counted from the top left corner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rect{
+    pub offset: [i32; 2],
+    pub extent: [u32; 2],
+}
+
+impl Rect{
+    /// The part of the rectangle inside an image of `extent`, `None` if it is empty.
+    fn clamp(&self, extent: [u32; 2]) -> Option<vulkano::RectangleLayer>{
+        let mut offset = [0; 2];
+        let mut size = [0; 2];
+        for i in 0..2{
+            let start = (self.offset[i] as i64).clamp(0, extent[i] as i64);
+            let end = (self.offset[i] as i64 + self.extent[i] as i64).clamp(0, extent[i] as i64);
+            if end <= start{
+                return None;
+            }
+            offset[i] = start as i32;
+            size[i] = (end - start) as u32;
+        }
+        Some(vulkano::RectangleLayer{
+            offset,
+            extent: size,
+            layer: 0,
+        })
     }
 }
 
@@ -148,28 +1510,662 @@ pub struct SurfaceImage<W>{
     #[deref_mut]
     pub image: Arc<vulkano::SwapchainImage<W>>,
     pub suboptimal: bool,
-    pub acquire_future: vulkano::SwapchainAcquireFuture<W>,
+    /// Signaled once the image is available, `None` after `take_acquire_future`.
+    pub acquire_future: Option<vulkano::SwapchainAcquireFuture<W>>,
     pub image_num: usize, 
+    // The cached view of the image and the framebuffers of the swapchain, used by
+    // `create_framebuffer`.
+    view: Arc<dyn vulkano::ImageViewAbstract>,
+    framebuffer_cache: Arc<FramebufferCache>,
+    guard: MisuseGuard,
+}
+
+impl<W> SurfaceImage<W>{
+    /// Takes the future the work rendering into the image has to wait for.
+    pub fn take_acquire_future(&mut self) -> Option<vulkano::SwapchainAcquireFuture<W>>{
+        self.acquire_future.take()
+    }
+    /// Marks the image as presented, call this when presenting it through
+    /// `then_swapchain_present` yourself, after the present was flushed successfully.
+    pub fn mark_presented(&mut self){
+        self.guard.consume();
+    }
+    /// Skips presenting this image, e.g. because the frame turned out to be unnecessary.
+    pub fn abandon(mut self){
+        self.guard.consume();
+    }
 }
 
 impl<W: 'static + Send + Sync> SurfaceImage<W>{
+    /// Presents the image on `queue` after `after` and flushes, returning a future whose fence
+    /// is signaled once the presentation has been submitted.
+    ///
+    /// If the acquire future has not been taken it is joined with `after`. If `after` ran on
+    /// another queue, e.g. because presentation uses a separate queue family, a semaphore makes
+    /// the presentation wait for it.
+    pub fn present<F>(self, queue: &Queue, after: F) -> Result<vulkano::FenceSignalFuture<Box<dyn GpuFuture>>, PresentError>
+    where
+        F: GpuFuture + 'static,
+    {
+        self.present_region(queue, after, None)
+    }
+    /// Presents the image like `present`, telling the presentation engine that only `regions`
+    /// changed since the last frame, which can save power when redrawing small parts of the image.
+    ///
+    /// The rectangles are clamped to the extent of the image and empty ones are dropped. If none
+    /// is left the whole image counts as changed. Without the `khr_incremental_present` device
+    /// extension, which `AdapterDescriptor::graphics` enables if available, the image is presented
+    /// like with `present`.
+    pub fn present_with_regions<F>(self, queue: &Queue, after: F, regions: &[Rect]) -> Result<vulkano::FenceSignalFuture<Box<dyn GpuFuture>>, PresentError>
+    where
+        F: GpuFuture + 'static,
+    {
+        let swapchain = self.image.swapchain();
+        if !vulkano::DeviceOwned::device(&**swapchain).enabled_extensions().khr_incremental_present{
+            return self.present(queue, after);
+        }
+        let extent = swapchain.image_extent();
+        let region = vulkano::PresentRegion{
+            rectangles: regions.iter().filter_map(|rect| rect.clamp(extent)).collect(),
+        };
+        self.present_region(queue, after, Some(region))
+    }
+    fn present_region<F>(mut self, queue: &Queue, after: F, region: Option<vulkano::PresentRegion>) -> Result<vulkano::FenceSignalFuture<Box<dyn GpuFuture>>, PresentError>
+    where
+        F: GpuFuture + 'static,
+    {
+        // The swapchain the image belongs to, which is not necessarily the current one.
+        let swapchain = self.image.swapchain().clone();
+        let after: Box<dyn GpuFuture> = match self.acquire_future.take(){
+            Some(acquire_future) => Box::new(after.join(acquire_future)),
+            None => Box::new(after),
+        };
+        let after: Box<dyn GpuFuture> = match after.queue(){
+            Some(previous) if previous != *queue.handle() => Box::new(after.then_signal_semaphore()),
+            _ => after,
+        };
+        let future: Box<dyn GpuFuture> = match region{
+            Some(region) => Box::new(after.then_swapchain_present_incremental(queue.handle().clone(), swapchain, self.image_num, region)),
+            None => Box::new(after.then_swapchain_present(queue.handle().clone(), swapchain, self.image_num)),
+        };
+        let _lock = queue.lock();
+        let presented = future.then_signal_fence_and_flush();
+        // Only consumed once the present has been submitted. A failed one is reported through
+        // the error instead, e.g. an out of date swapchain while resizing.
+        self.guard.consume();
+        Ok(presented?)
+    }
     pub fn create_view_default(&self) -> Result<Arc<vulkano::ImageView<vulkano::SwapchainImage<W>>>, vulkano::ImageViewCreationError>{
         vulkano::ImageView::new_default(self.image.clone())
     }
+    /// Creates a framebuffer for a render pass the swapchain does not manage the attachments
+    /// of, with the image as the first attachment followed by `extra_attachments`, e.g. a depth
+    /// buffer, in the order the render pass declares them.
+    ///
+    /// The image is attached with the view the swapchain caches, and the framebuffer is cached
+    /// like the ones of `framebuffer` until the swapchain is recreated.
+    pub fn create_framebuffer(&self, render_pass: &Arc<vulkano::RenderPass>, extra_attachments: &[Arc<dyn vulkano::ImageViewAbstract>]) -> Result<Arc<vulkano::Framebuffer>, FramebufferSetupError>{
+        let expected = render_pass.attachments().len();
+        let provided = extra_attachments.len() + 1;
+        if expected != provided{
+            return Err(FramebufferSetupError::AttachmentCount{expected, provided});
+        }
+        let attachments = std::iter::once(self.view.clone()).chain(extra_attachments.iter().cloned()).collect();
+        Ok(self.framebuffer_cache.get_or_create(render_pass, attachments)?)
+    }
+    /// Creates a framebuffer with the image as the only attachment, see `create_framebuffer`.
+    pub fn create_color_framebuffer(&self, render_pass: &Arc<vulkano::RenderPass>) -> Result<Arc<vulkano::Framebuffer>, FramebufferSetupError>{
+        self.create_framebuffer(render_pass, &[])
+    }
+    /// Framebuffer with only the image for `render_pass`, setting `viewport` to cover the image.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the render pass has more attachments or the framebuffer cannot be created.
+    #[deprecated(note = "use `create_color_framebuffer`, or `framebuffer` for render passes of the swapchain")]
     pub fn framebuffer_setup(&self, render_pass: Arc<vulkano::RenderPass>, viewport: &mut vulkano::Viewport) -> Arc<vulkano::Framebuffer>{
+        *viewport = self.viewport();
+        self.create_color_framebuffer(&render_pass).unwrap()
+    }
+    /// The cached framebuffer of this image for `render_pass`, see `Swapchain::framebuffers`.
+    ///
+    /// `surface` has to be the surface the image was acquired from, without recreating the
+    /// swapchain in between.
+    pub fn framebuffer(&self, surface: &Surface<W>, render_pass: &Arc<vulkano::RenderPass>) -> Result<Arc<vulkano::Framebuffer>, vulkano::FramebufferCreationError>{
+        let swapchain = surface.swapchain.as_ref().expect("the image was acquired from this surface");
+        debug_assert!(
+            Arc::ptr_eq(self.image.swapchain(), &swapchain.swapchain),
+            "the swapchain has been recreated since the image was acquired",
+        );
+        Ok(swapchain.framebuffers(render_pass)?[self.image_num].clone())
+    }
+    /// Viewport covering the whole image.
+    pub fn viewport(&self) -> vulkano::Viewport{
         let dimensions = vulkano::ImageAccess::dimensions(&self.image).width_height();
-        viewport.dimensions = [dimensions[0] as f32, dimensions[1] as f32];
+        vulkano::Viewport{
+            origin: [0.0, 0.0],
+            dimensions: [dimensions[0] as f32, dimensions[1] as f32],
+            depth_range: 0.0..1.0,
+        }
+    }
+}
 
-        let view = self.create_view_default().unwrap();
+impl<W: Send + Sync + 'static> TargetImage for SurfaceImage<W>{
+    fn take_future(&mut self) -> Option<Box<dyn GpuFuture>>{
+        Some(self.take_acquire_future()?.boxed())
+    }
+    fn viewport(&self) -> vulkano::Viewport{
+        SurfaceImage::viewport(self)
+    }
+    fn abandon(self){
+        SurfaceImage::abandon(self)
+    }
+}
 
-        let mut attachments: Vec<Arc<dyn vulkano::ImageViewAbstract>> = Vec::new();
-        attachments.push(view);
-        vulkano::Framebuffer::new(
-            render_pass,
-            vulkano::FramebufferCreateInfo{
-                attachments,
-                ..Default::default()
-            },
-        ).unwrap()
+#[derive(Debug)]
+pub enum SwapchainError{
+    /// The window has a size the surface does not support, usually zero because it is minimized.
+    /// Skip the frame and try again later.
+    UnsupportedExtent([u32; 2]),
+    /// The surface is no longer usable and has to be created again, or has been invalidated.
+    SurfaceLost,
+    /// `Surface::rebuild` or the surface factory failed to create the surface for the window.
+    SurfaceCreation(vulkano::SurfaceCreationError),
+    /// The surface does not report any image format.
+    NoFormat,
+    /// A setting of a strict `SwapchainDescriptor` is not supported by the surface.
+    Unsupported(String),
+    /// `recreate_swapchain` was called before `create_swapchain`.
+    NoSwapchain,
+    /// `set_present_mode` was called with a mode the surface does not support.
+    UnsupportedPresentMode{
+        requested: vulkano::PresentMode,
+        supported: Vec<vulkano::PresentMode>,
+    },
+    /// `set_sample_count` was called with a count the device does not support for framebuffers.
+    UnsupportedSampleCount(vulkano::SampleCount),
+    /// `set_depth_format` was called with a format the device does not support for depth
+    /// buffers.
+    UnsupportedDepthFormat(vulkano::Format),
+    /// The surface properties could not be queried.
+    Properties(vulkano::SurfacePropertiesError),
+    ImageView(vulkano::ImageViewCreationError),
+    /// The depth buffer or the multisampled color images could not be created.
+    Attachment(vulkano::ImageCreationError),
+    RenderPass(vulkano::RenderPassCreationError),
+    DeviceError(vulkano::SwapchainCreationError),
+}
+
+impl From<vulkano::SurfacePropertiesError> for SwapchainError{
+    fn from(err: vulkano::SurfacePropertiesError) -> Self{
+        match err{
+            vulkano::SurfacePropertiesError::SurfaceLost => Self::SurfaceLost,
+            err => Self::Properties(err),
+        }
+    }
+}
+
+impl From<vulkano::SurfaceCreationError> for SwapchainError{
+    fn from(err: vulkano::SurfaceCreationError) -> Self{
+        Self::SurfaceCreation(err)
+    }
+}
+
+impl From<vulkano::ImageViewCreationError> for SwapchainError{
+    fn from(err: vulkano::ImageViewCreationError) -> Self{
+        Self::ImageView(err)
+    }
+}
+
+impl From<vulkano::ImageCreationError> for SwapchainError{
+    fn from(err: vulkano::ImageCreationError) -> Self{
+        Self::Attachment(err)
+    }
+}
+
+impl From<AttachmentError> for SwapchainError{
+    fn from(err: AttachmentError) -> Self{
+        match err{
+            AttachmentError::Image(err) => Self::Attachment(err),
+            AttachmentError::ImageView(err) => Self::ImageView(err),
+        }
+    }
+}
+
+impl From<vulkano::RenderPassCreationError> for SwapchainError{
+    fn from(err: vulkano::RenderPassCreationError) -> Self{
+        Self::RenderPass(err)
+    }
+}
+
+impl From<vulkano::SwapchainCreationError> for SwapchainError{
+    fn from(err: vulkano::SwapchainCreationError) -> Self{
+        match err{
+            vulkano::SwapchainCreationError::SurfaceLost => Self::SurfaceLost,
+            vulkano::SwapchainCreationError::ImageExtentNotSupported{provided, ..} => Self::UnsupportedExtent(provided),
+            err => Self::DeviceError(err),
+        }
+    }
+}
+
+impl SwapchainError{
+    /// Whether the error is transient, so the frame should be skipped and the swapchain
+    /// recreated with the next one, as opposed to errors the surface does not recover from.
+    pub fn is_retryable(&self) -> bool{
+        matches!(self, Self::UnsupportedExtent(_))
+    }
+}
+
+impl fmt::Display for SwapchainError{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        match self{
+            Self::UnsupportedExtent([width, height]) => write!(f, "the surface does not support an extent of {}x{}", width, height),
+            Self::SurfaceLost => write!(f, "the surface has been lost"),
+            Self::SurfaceCreation(err) => write!(f, "failed to create the surface: {}", err),
+            Self::NoFormat => write!(f, "the surface does not support any image format"),
+            Self::Unsupported(what) => write!(f, "unsupported swapchain setting: {}", what),
+            Self::NoSwapchain => write!(f, "no swapchain has been created yet"),
+            Self::UnsupportedPresentMode{requested, supported} => write!(f, "present mode {:?} is not supported, the surface supports {:?}", requested, supported),
+            Self::UnsupportedSampleCount(samples) => write!(f, "{:?} samples per pixel are not supported", samples),
+            Self::UnsupportedDepthFormat(format) => write!(f, "{:?} is not supported for depth buffers", format),
+            Self::Properties(err) => write!(f, "failed to query the surface properties: {}", err),
+            Self::ImageView(err) => write!(f, "failed to create a swapchain image view: {}", err),
+            Self::Attachment(err) => write!(f, "failed to create an attachment: {}", err),
+            Self::RenderPass(err) => write!(f, "failed to create the render pass: {}", err),
+            Self::DeviceError(err) => write!(f, "failed to create the swapchain: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for SwapchainError{}
+
+#[derive(Debug)]
+pub enum AcquireImageError{
+    /// The swapchain no longer matches the surface, e.g. after a resize, and has to be recreated.
+    OutOfDate,
+    /// No image became available within the timeout.
+    Timeout,
+    /// The surface is no longer usable and has to be created again.
+    SurfaceLost,
+    /// `get_current_image` was called before `create_swapchain`.
+    NoSwapchain,
+    /// Exclusive fullscreen was lost, e.g. because another window got focus. The swapchain has
+    /// to be recreated, which `Surface::acquire` does with the next call.
+    FullScreenExclusiveLost,
+    /// The surface has been invalidated, e.g. while an Android app is suspended. Skip the frame
+    /// until `Surface::rebuild`.
+    NotReady,
+    Device(vulkano::AcquireError),
+    /// `Surface::acquire` failed to recreate the swapchain.
+    Swapchain(SwapchainError),
+}
+
+impl From<SwapchainError> for AcquireImageError{
+    fn from(err: SwapchainError) -> Self{
+        Self::Swapchain(err)
+    }
+}
+
+impl From<vulkano::AcquireError> for AcquireImageError{
+    fn from(err: vulkano::AcquireError) -> Self{
+        match err{
+            vulkano::AcquireError::OutOfDate => Self::OutOfDate,
+            vulkano::AcquireError::Timeout => Self::Timeout,
+            vulkano::AcquireError::SurfaceLost => Self::SurfaceLost,
+            vulkano::AcquireError::FullScreenExclusiveLost => Self::FullScreenExclusiveLost,
+            err => Self::Device(err),
+        }
+    }
+}
+
+impl fmt::Display for AcquireImageError{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        match self{
+            Self::OutOfDate => write!(f, "the swapchain is out of date"),
+            Self::Timeout => write!(f, "no swapchain image became available in time"),
+            Self::SurfaceLost => write!(f, "the surface has been lost"),
+            Self::NoSwapchain => write!(f, "no swapchain has been created yet"),
+            Self::FullScreenExclusiveLost => write!(f, "exclusive fullscreen has been lost"),
+            Self::NotReady => write!(f, "the surface has been invalidated"),
+            Self::Device(err) => write!(f, "failed to acquire the next image: {}", err),
+            Self::Swapchain(err) => write!(f, "failed to recreate the swapchain: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for AcquireImageError{}
+
+#[derive(Debug)]
+pub enum PresentError{
+    /// The swapchain no longer matches the surface. `Surface::acquire` recreates it, otherwise
+    /// call `recreate_swapchain`.
+    OutOfDate,
+    /// Exclusive fullscreen was lost while presenting, see
+    /// `AcquireImageError::FullScreenExclusiveLost`.
+    FullScreenExclusiveLost,
+    Flush(vulkano::FlushError),
+}
+
+impl From<vulkano::FlushError> for PresentError{
+    fn from(err: vulkano::FlushError) -> Self{
+        match err{
+            vulkano::FlushError::OutOfDate => Self::OutOfDate,
+            vulkano::FlushError::FullScreenExclusiveLost => Self::FullScreenExclusiveLost,
+            err => Self::Flush(err),
+        }
+    }
+}
+
+impl fmt::Display for PresentError{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        match self{
+            Self::OutOfDate => write!(f, "the swapchain is out of date"),
+            Self::FullScreenExclusiveLost => write!(f, "exclusive fullscreen has been lost"),
+            Self::Flush(err) => write!(f, "failed to present: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for PresentError{}
+
+#[derive(Debug, From)]
+pub enum FramebufferSetupError{
+    /// The render pass declares a different number of attachments than the image and the extra
+    /// attachments.
+    #[from(ignore)]
+    AttachmentCount{
+        expected: usize,
+        provided: usize,
+    },
+    ImageView(vulkano::ImageViewCreationError),
+    Framebuffer(vulkano::FramebufferCreationError),
+}
+
+impl fmt::Display for FramebufferSetupError{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        match self{
+            Self::AttachmentCount{expected, provided} => write!(f, "the render pass has {} attachments, but {} were provided", expected, provided),
+            Self::ImageView(err) => write!(f, "failed to create the image view: {}", err),
+            Self::Framebuffer(err) => write!(f, "failed to create the framebuffer: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for FramebufferSetupError{}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use super::vulkano::{ColorSpace, Format};
+
+    #[test]
+    fn select_format_prefers_bgra_srgb(){
+        let formats = [
+            (Format::B8G8R8A8_UNORM, ColorSpace::SrgbNonLinear),
+            (Format::R8G8B8A8_SRGB, ColorSpace::SrgbNonLinear),
+            (Format::B8G8R8A8_SRGB, ColorSpace::SrgbNonLinear),
+        ];
+        assert_eq!(
+            select_format(&formats, FormatPreference::Srgb),
+            Some((Format::B8G8R8A8_SRGB, ColorSpace::SrgbNonLinear))
+        );
+        assert_eq!(
+            select_format(&formats[..2], FormatPreference::Srgb),
+            Some((Format::R8G8B8A8_SRGB, ColorSpace::SrgbNonLinear))
+        );
+    }
+
+    #[test]
+    fn select_format_falls_back_to_other_srgb_formats(){
+        let formats = [
+            (Format::B8G8R8A8_SRGB, ColorSpace::DisplayP3NonLinear),
+            (Format::A8B8G8R8_SRGB_PACK32, ColorSpace::SrgbNonLinear),
+        ];
+        // sRGB encoding alone is not enough, the color space has to be sRGB as well.
+        assert_eq!(
+            select_format(&formats, FormatPreference::Srgb),
+            Some((Format::A8B8G8R8_SRGB_PACK32, ColorSpace::SrgbNonLinear))
+        );
+        assert_eq!(select_format(&formats[..1], FormatPreference::Srgb), None);
+    }
+
+    #[test]
+    fn select_format_first_unorm_and_exact(){
+        let formats = [
+            (Format::B8G8R8A8_SRGB, ColorSpace::SrgbNonLinear),
+            (Format::A2B10G10R10_UNORM_PACK32, ColorSpace::SrgbNonLinear),
+            (Format::B8G8R8A8_UNORM, ColorSpace::SrgbNonLinear),
+        ];
+        assert_eq!(select_format(&formats, FormatPreference::First), Some(formats[0]));
+        assert_eq!(select_format(&formats, FormatPreference::Unorm), Some(formats[1]));
+        assert_eq!(
+            select_format(&formats, FormatPreference::Exact(Format::B8G8R8A8_UNORM)),
+            Some(formats[2])
+        );
+        assert_eq!(select_format(&formats, FormatPreference::Exact(Format::R8G8B8A8_UNORM)), None);
+        assert_eq!(select_format(&[], FormatPreference::First), None);
+    }
+
+    #[test]
+    fn lifecycle_invalidate_and_rebuild(){
+        let mut lifecycle = Lifecycle::default();
+        assert!(lifecycle.is_valid());
+        assert!(lifecycle.check_ready().is_ok());
+        assert_eq!(lifecycle.pending(), None);
+
+        // Suspended, acquiring fails without panicking and nothing is restored yet.
+        lifecycle.invalidate(Some("swapchain"));
+        assert!(!lifecycle.is_valid());
+        assert!(matches!(lifecycle.check_ready(), Err(AcquireImageError::NotReady)));
+        assert_eq!(lifecycle.pending(), None);
+
+        // A second suspend without a swapchain keeps the first one.
+        lifecycle.invalidate(None);
+        lifecycle.rebuild();
+        assert!(lifecycle.check_ready().is_ok());
+        // Stays pending until the swapchain could be created, e.g. once the window has a size.
+        assert_eq!(lifecycle.pending(), Some(&"swapchain"));
+        lifecycle.restored();
+        assert_eq!(lifecycle.pending(), None);
+    }
+
+    #[test]
+    fn lifecycle_without_swapchain(){
+        let mut lifecycle = Lifecycle::<()>::default();
+        lifecycle.invalidate(None);
+        lifecycle.rebuild();
+        assert!(lifecycle.is_valid());
+        assert_eq!(lifecycle.pending(), None);
+    }
+
+    // Like the list a Windows driver reports for an HDR display with `ext_swapchain_colorspace`.
+    const HDR_DISPLAY: &[(Format, ColorSpace)] = &[
+        (Format::B8G8R8A8_UNORM, ColorSpace::SrgbNonLinear),
+        (Format::B8G8R8A8_SRGB, ColorSpace::SrgbNonLinear),
+        (Format::R8G8B8A8_UNORM, ColorSpace::SrgbNonLinear),
+        (Format::R8G8B8A8_SRGB, ColorSpace::SrgbNonLinear),
+        (Format::A2B10G10R10_UNORM_PACK32, ColorSpace::SrgbNonLinear),
+        (Format::A2R10G10B10_UNORM_PACK32, ColorSpace::SrgbNonLinear),
+        (Format::R16G16B16A16_SFLOAT, ColorSpace::SrgbNonLinear),
+        (Format::R16G16B16A16_SFLOAT, ColorSpace::ExtendedSrgbLinear),
+        (Format::A2R10G10B10_UNORM_PACK32, ColorSpace::Hdr10St2084),
+        (Format::A2B10G10R10_UNORM_PACK32, ColorSpace::Hdr10St2084),
+    ];
+    // Like the list of a Linux driver, which reports no HDR color spaces.
+    const SDR_DISPLAY: &[(Format, ColorSpace)] = &[
+        (Format::B8G8R8A8_SRGB, ColorSpace::SrgbNonLinear),
+        (Format::B8G8R8A8_UNORM, ColorSpace::SrgbNonLinear),
+    ];
+
+    #[test]
+    fn select_hdr_format_prefers_listed_formats(){
+        assert_eq!(
+            select_hdr_format(HDR_DISPLAY, ColorSpacePreference::HdrScRgbLinear),
+            Some((Format::R16G16B16A16_SFLOAT, ColorSpace::ExtendedSrgbLinear))
+        );
+        // The preference order wins over the order the driver reports the formats in.
+        assert_eq!(
+            select_hdr_format(HDR_DISPLAY, ColorSpacePreference::Hdr10),
+            Some((Format::A2B10G10R10_UNORM_PACK32, ColorSpace::Hdr10St2084))
+        );
+        assert_eq!(select_hdr_format(HDR_DISPLAY, ColorSpacePreference::SdrSrgb), None);
+    }
+
+    #[test]
+    fn select_hdr_format_falls_back(){
+        // Any format of the color space is better than none.
+        let formats = [(Format::R16G16B16A16_UNORM, ColorSpace::Hdr10St2084)];
+        assert_eq!(select_hdr_format(&formats, ColorSpacePreference::Hdr10), Some(formats[0]));
+        // Without HDR color spaces the descriptor falls back to `SdrSrgb`.
+        assert_eq!(select_hdr_format(SDR_DISPLAY, ColorSpacePreference::Hdr10), None);
+        assert_eq!(select_hdr_format(SDR_DISPLAY, ColorSpacePreference::HdrScRgbLinear), None);
+        assert_eq!(ColorSpacePreference::default(), ColorSpacePreference::SdrSrgb);
+    }
+
+    // Stands in for a surface, counting what the policy did to it.
+    #[derive(Default)]
+    struct Attempts{
+        recreated: usize,
+        recovered: usize,
+    }
+
+    fn recreate(result: Result<(), SwapchainError>) -> impl FnOnce(&mut Attempts) -> Result<(), SwapchainError>{
+        move |attempts| {
+            attempts.recreated += 1;
+            result
+        }
+    }
+
+    fn recover(result: Result<(), SwapchainError>) -> impl FnOnce(&mut Attempts) -> Result<(), SwapchainError>{
+        move |attempts| {
+            attempts.recovered += 1;
+            result
+        }
+    }
+
+    #[test]
+    fn recreate_recovers_lost_surface(){
+        let mut attempts = Attempts::default();
+        let result = recreate_or_recover(&mut attempts, true, recreate(Err(SwapchainError::SurfaceLost)), recover(Ok(())));
+        assert!(result.is_ok());
+        assert_eq!((attempts.recreated, attempts.recovered), (1, 1));
+
+        // The recovery is only attempted once, its error is returned.
+        let mut attempts = Attempts::default();
+        let result = recreate_or_recover(
+            &mut attempts,
+            true,
+            recreate(Err(SwapchainError::SurfaceLost)),
+            recover(Err(SwapchainError::SurfaceLost)),
+        );
+        assert!(matches!(result, Err(SwapchainError::SurfaceLost)));
+        assert_eq!((attempts.recreated, attempts.recovered), (1, 1));
+    }
+
+    #[test]
+    fn recreate_gives_up_without_factory(){
+        let mut attempts = Attempts::default();
+        let result = recreate_or_recover(&mut attempts, false, recreate(Err(SwapchainError::SurfaceLost)), recover(Ok(())));
+        assert!(matches!(result, Err(SwapchainError::SurfaceLost)));
+        assert!(!result.unwrap_err().is_retryable());
+        assert_eq!((attempts.recreated, attempts.recovered), (1, 0));
+    }
+
+    #[test]
+    fn recreate_retries_minimized_window_next_frame(){
+        let mut attempts = Attempts::default();
+        // Minimized, the caller skips the frame.
+        let result = recreate_or_recover(&mut attempts, true, recreate(Err(SwapchainError::UnsupportedExtent([0, 0]))), recover(Ok(())));
+        assert!(result.unwrap_err().is_retryable());
+        // Restored with the next frame.
+        let result = recreate_or_recover(&mut attempts, true, recreate(Ok(())), recover(Ok(())));
+        assert!(result.is_ok());
+        assert_eq!((attempts.recreated, attempts.recovered), (2, 0));
+    }
+
+    #[test]
+    fn set_extent_overrides_window_size(){
+        let inner_size: fn(&DummyWindow) -> [u32; 2] = WithInnerIsize::inner_size;
+        // The dummy window has no size, like a minimized one.
+        assert_eq!(window_extent(None, Some(inner_size), &DummyWindow), [0, 0]);
+        assert_eq!(window_extent(Some([640, 480]), Some(inner_size), &DummyWindow), [640, 480]);
+        // Windows without `WithInnerIsize` only have the size set with `set_extent`.
+        assert_eq!(window_extent::<()>(None, None, &()), [0, 0]);
+        assert_eq!(window_extent(Some([1, 2]), None, &()), [1, 2]);
+    }
+
+    #[test]
+    fn preferred_format_matches_image_format(){
+        let formats = [
+            (Format::B8G8R8A8_UNORM, ColorSpace::SrgbNonLinear),
+            (Format::B8G8R8A8_SRGB, ColorSpace::SrgbNonLinear),
+            (Format::R16G16B16A16_SFLOAT, ColorSpace::ExtendedSrgbLinear),
+        ];
+        let query = |desc: &SwapchainDescriptor| choose_surface_format(&formats, desc.format, desc.color_space, desc.strict).unwrap();
+        for desc in [
+            SwapchainDescriptor::default(),
+            SwapchainDescriptor{format: FormatPreference::Unorm, ..Default::default()},
+            SwapchainDescriptor{color_space: ColorSpacePreference::HdrScRgbLinear, ..Default::default()},
+        ]{
+            let preferred = query(&desc);
+            // Created with `desc`, the swapchain images have the format the creation chose.
+            assert_eq!(resolve_current_format(Some(preferred), Some(preferred), || unreachable!()), Some(preferred));
+            // Before, `image_format` reports the format `select_format` chose...
+            assert_eq!(resolve_current_format(None, Some(preferred), || unreachable!()), Some(preferred));
+        }
+        // ... or queries the preferred one of the default descriptor.
+        let preferred = query(&SwapchainDescriptor::default());
+        assert_eq!(preferred, formats[1]);
+        assert_eq!(resolve_current_format(None, None, || Some(query(&SwapchainDescriptor::default()))), Some(preferred));
+        assert_eq!(resolve_current_format(None, None, || None), None);
+    }
+
+    #[test]
+    fn choose_surface_format_strict(){
+        let formats = [(Format::B8G8R8A8_UNORM, ColorSpace::SrgbNonLinear)];
+        let chosen = choose_surface_format(&formats, FormatPreference::Srgb, ColorSpacePreference::Hdr10, false);
+        assert_eq!(chosen.unwrap(), formats[0]);
+        let chosen = choose_surface_format(&formats, FormatPreference::Srgb, ColorSpacePreference::SdrSrgb, true);
+        assert!(matches!(chosen, Err(SwapchainError::Unsupported(_))));
+        assert!(matches!(choose_surface_format(&[], FormatPreference::Srgb, ColorSpacePreference::SdrSrgb, false), Err(SwapchainError::NoFormat)));
+    }
+
+    #[derive(Default)]
+    struct Resized{
+        extent: [u32; 2],
+        targets: Option<[u32; 2]>,
+    }
+
+    #[test]
+    fn recreate_hooks_run_on_every_resize(){
+        let mut hooks = RecreateHooks::new(|resized: &mut Resized| {
+            if resized.extent.contains(&0){
+                return Err(SwapchainError::UnsupportedExtent(resized.extent));
+            }
+            resized.targets = Some(resized.extent);
+            Ok(())
+        });
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        for _ in 0..2{
+            let calls = calls.clone();
+            hooks.push(Box::new(move |resized: &Resized| calls.lock().unwrap().push(resized.targets)));
+        }
+        let mut recreate = |extent| -> Result<(), SwapchainError>{
+            let mut resized = Resized{extent, ..Default::default()};
+            hooks.create_targets(&mut resized)?;
+            hooks.notify(&resized);
+            Ok(())
+        };
+        recreate([640, 480]).unwrap();
+        recreate([800, 600]).unwrap();
+        // A failed recreation keeps the previous swapchain and calls no callback.
+        assert!(recreate([0, 0]).is_err());
+        recreate([1024, 768]).unwrap();
+        // Every callback sees the targets created for the new extent.
+        assert_eq!(*calls.lock().unwrap(), vec![
+            Some([640, 480]), Some([640, 480]),
+            Some([800, 600]), Some([800, 600]),
+            Some([1024, 768]), Some([1024, 768]),
+        ]);
     }
 }
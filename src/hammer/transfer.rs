@@ -0,0 +1,487 @@
+use bytemuck::Pod;
+use derive_more::*;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::sync::Arc;
+
+use super::{Device, Queue, SubmitError, SubmitFuture};
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::buffer::cpu_access::*;
+    pub use vulkano::buffer::*;
+    pub use vulkano::command_buffer::*;
+    pub use vulkano::device::*;
+    pub use vulkano::format::*;
+    pub use vulkano::image::*;
+    pub use vulkano::memory::*;
+    pub use vulkano::sync::*;
+    pub use vulkano::*;
+}
+
+use vulkano::{BufferAccess, GpuFuture};
+
+/// Future of a transfer submitted by one of the `Device` transfer helpers.
+pub type TransferFuture = SubmitFuture;
+
+/// Buffer created by `Device::create_buffer_init_async` and the future of the copy filling it.
+pub type BufferInit<T> = (Arc<vulkano::DeviceLocalBuffer<[T]>>, TransferFuture);
+
+/// Data returned by the function of `Device::read_buffer_async`.
+pub type ReadResult<T> = Result<Vec<T>, vulkano::ReadLockError>;
+
+/// Region of a buffer to buffer copy, in bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BufferCopy {
+    pub src_offset: vulkano::DeviceSize,
+    pub dst_offset: vulkano::DeviceSize,
+    pub size: vulkano::DeviceSize,
+}
+
+impl BufferCopy {
+    /// Copies the first `size` bytes.
+    pub fn whole(size: vulkano::DeviceSize) -> Self {
+        Self {
+            src_offset: 0,
+            dst_offset: 0,
+            size,
+        }
+    }
+}
+
+/// Records the copies of `regions` from `source` to `destination` into `builder`, which has to
+/// be outside of a render pass.
+pub fn record_copy_buffer<L, P>(
+    builder: &mut vulkano::AutoCommandBufferBuilder<L, P>,
+    source: &Arc<dyn vulkano::BufferAccess>,
+    destination: &Arc<dyn vulkano::BufferAccess>,
+    regions: &[BufferCopy],
+) -> Result<(), TransferError> {
+    let same_buffer = source.conflict_key() == destination.conflict_key();
+    for region in regions {
+        let src = checked_range(region.src_offset, region.size, source.size())?;
+        let dst = checked_range(region.dst_offset, region.size, destination.size())?;
+        if same_buffer {
+            let src_start = source.inner().offset + src.start;
+            let dst_start = destination.inner().offset + dst.start;
+            if src_start < dst_start + region.size && dst_start < src_start + region.size {
+                return Err(TransferError::Overlap(*region));
+            }
+        }
+    }
+
+    let source = TypedView::<u8>::new(source.clone(), source.size());
+    let destination = TypedView::<u8>::new(destination.clone(), destination.size());
+    for region in regions {
+        builder.copy_buffer_dimensions(
+            source.clone(),
+            region.src_offset,
+            destination.clone(),
+            region.dst_offset,
+            region.size,
+        )?;
+    }
+    Ok(())
+}
+
+/// Records filling the bytes in `range` of `destination` with copies of `value` into `builder`,
+/// which has to be outside of a render pass.
+///
+/// The start and length of `range` have to be multiples of 4.
+pub fn record_fill_buffer<L, P>(
+    builder: &mut vulkano::AutoCommandBufferBuilder<L, P>,
+    destination: &Arc<dyn vulkano::BufferAccess>,
+    range: Range<vulkano::DeviceSize>,
+    value: u32,
+) -> Result<(), TransferError> {
+    let size = range.end.saturating_sub(range.start);
+    let range = checked_range(range.start, size, destination.size())?;
+    if !range.start.is_multiple_of(4) || !size.is_multiple_of(4) {
+        return Err(TransferError::Misaligned {
+            offset: range.start,
+            size,
+        });
+    }
+
+    let view = TypedView::<u8>::new(destination.clone(), destination.size());
+    let slice = vulkano::BufferAccess::slice(&view, range).unwrap();
+    builder.fill_buffer(slice, value)?;
+    Ok(())
+}
+
+/// `offset..offset + size` if it is not empty and lies within a buffer of `available` bytes.
+fn checked_range(
+    offset: vulkano::DeviceSize,
+    size: vulkano::DeviceSize,
+    available: vulkano::DeviceSize,
+) -> Result<Range<vulkano::DeviceSize>, TransferError> {
+    if size == 0 {
+        return Err(TransferError::Empty);
+    }
+    match offset.checked_add(size) {
+        Some(end) if end <= available => Ok(offset..end),
+        end => Err(TransferError::OutOfBounds {
+            end: end.unwrap_or(vulkano::DeviceSize::MAX),
+            available,
+        }),
+    }
+}
+
+impl Device {
+    /// Creates a device local buffer containing `data`, blocking until the upload finished.
+    pub fn create_buffer_init<T: Pod + Send + Sync>(
+        &self,
+        data: &[T],
+        usage: vulkano::BufferUsage,
+    ) -> Result<Arc<vulkano::DeviceLocalBuffer<[T]>>, TransferError> {
+        let (buffer, future) = self.create_buffer_init_async(data, usage)?;
+        future.wait(None)?;
+        Ok(buffer)
+    }
+    /// Like `create_buffer_init`, but returns the future of the copy instead of waiting for it,
+    /// so it can be joined with other work.
+    ///
+    /// The copy runs on the transfer queue, or the graphics queue if there is none.
+    pub fn create_buffer_init_async<T: Pod + Send + Sync>(
+        &self,
+        data: &[T],
+        usage: vulkano::BufferUsage,
+    ) -> Result<BufferInit<T>, TransferError> {
+        let mut upload = self.upload_context()?;
+        match upload.create_buffer(data, usage) {
+            Ok(buffer) => Ok((buffer, upload.finish()?)),
+            Err(err) => {
+                upload.abandon();
+                Err(err)
+            }
+        }
+    }
+    /// Copies the first `count` elements of `source` to the host, blocking until they arrived.
+    ///
+    /// `source` needs to support `transfer_source` usage.
+    pub fn read_buffer<T: Pod + Send + Sync>(
+        &self,
+        source: &Arc<dyn vulkano::BufferAccess>,
+        count: usize,
+    ) -> Result<Vec<T>, TransferError> {
+        let (future, fetch) = self.read_buffer_async(source, count)?;
+        future.wait(None)?;
+        Ok(fetch()?)
+    }
+    /// Like `read_buffer`, but returns the future of the copy and a function returning the data
+    /// once the future is signaled. Calling it earlier fails with a lock error.
+    pub fn read_buffer_async<T: Pod + Send + Sync>(
+        &self,
+        source: &Arc<dyn vulkano::BufferAccess>,
+        count: usize,
+    ) -> Result<(TransferFuture, impl FnOnce() -> ReadResult<T>), TransferError> {
+        if count == 0 {
+            return Err(TransferError::Empty);
+        }
+        let size = count
+            .checked_mul(std::mem::size_of::<T>())
+            .and_then(|size| vulkano::DeviceSize::try_from(size).ok())
+            .ok_or(TransferError::OutOfBounds {
+                end: vulkano::DeviceSize::MAX,
+                available: source.size(),
+            })?;
+        checked_range(0, size, source.size())?;
+
+        let destination = unsafe {
+            vulkano::CpuAccessibleBuffer::<[T]>::uninitialized_array(
+                self.handle().clone(),
+                count as vulkano::DeviceSize,
+                vulkano::BufferUsage::transfer_destination(),
+                true,
+            )?
+        };
+        let view = TypedView::<T>::new(source.clone(), size);
+
+        let future = self.submit_transfer(self.upload_queue(), |builder| {
+            builder.copy_buffer(view, destination.clone())?;
+            Ok(())
+        })?;
+        let fetch = move || Ok(destination.read()?.to_vec());
+        Ok((future, fetch))
+    }
+    /// Copies the first mip level and array layer of `image` to the host, blocking until it
+    /// arrived. The texels are tightly packed, row by row.
+    ///
+    /// `image` needs to support `transfer_source` usage. The copy runs on `queue`, which should
+    /// be the queue that rendered into the image, e.g. to compare an `OffscreenTarget` with a
+    /// reference image.
+    pub fn read_image(
+        &self,
+        queue: &Queue,
+        image: Arc<dyn vulkano::ImageAccess>,
+    ) -> Result<Vec<u8>, TransferError> {
+        self.read_image_after(queue, image, vulkano::now(self.handle().clone()))
+    }
+    /// Like `read_image`, but copies only after `after`, e.g. the submission rendering into the
+    /// image.
+    pub fn read_image_after<F: GpuFuture + 'static>(
+        &self,
+        queue: &Queue,
+        image: Arc<dyn vulkano::ImageAccess>,
+        after: F,
+    ) -> Result<Vec<u8>, TransferError> {
+        let format = image.format();
+        let texel_size = format
+            .block_size()
+            .filter(|_| format.block_extent() == [1, 1, 1])
+            .ok_or(TransferError::UnsupportedFormat(format))?;
+        let [width, height, depth] = image.dimensions().width_height_depth();
+        let size = width as vulkano::DeviceSize
+            * height as vulkano::DeviceSize
+            * depth as vulkano::DeviceSize
+            * texel_size;
+        if size == 0 {
+            return Err(TransferError::Empty);
+        }
+
+        let destination = unsafe {
+            vulkano::CpuAccessibleBuffer::<[u8]>::uninitialized_array(
+                self.handle().clone(),
+                size,
+                vulkano::BufferUsage::transfer_destination(),
+                true,
+            )?
+        };
+        let mut builder = vulkano::AutoCommandBufferBuilder::primary(
+            self.handle().clone(),
+            queue.family(),
+            vulkano::CommandBufferUsage::OneTimeSubmit,
+        )?;
+        builder.copy_image_to_buffer(image, destination.clone())?;
+        let future = queue.submit_after(after, builder.build()?)?;
+        future.wait(None)?;
+        let data = destination.read()?.to_vec();
+        Ok(data)
+    }
+    /// Copies `regions` from `source` to `destination` on `queue`.
+    pub fn copy_buffer(
+        &self,
+        queue: &Queue,
+        source: &Arc<dyn vulkano::BufferAccess>,
+        destination: &Arc<dyn vulkano::BufferAccess>,
+        regions: &[BufferCopy],
+    ) -> Result<TransferFuture, TransferError> {
+        self.submit_transfer(queue, |builder| {
+            record_copy_buffer(builder, source, destination, regions)
+        })
+    }
+    /// Fills all of `destination`, whose size has to be a multiple of 4, with copies of `value`
+    /// on `queue`.
+    pub fn fill_buffer(
+        &self,
+        queue: &Queue,
+        destination: &Arc<dyn vulkano::BufferAccess>,
+        value: u32,
+    ) -> Result<TransferFuture, TransferError> {
+        self.submit_transfer(queue, |builder| {
+            record_fill_buffer(builder, destination, 0..destination.size(), value)
+        })
+    }
+    /// Queue used by the transfer helpers that do not take one.
+    pub(crate) fn upload_queue(&self) -> &Queue {
+        self.transfer_queue()
+            .or_else(|| self.graphics_queue())
+            .unwrap_or(&self.queues()[0])
+    }
+    /// Records a one time command buffer with `record` and submits it on `queue`.
+    fn submit_transfer(
+        &self,
+        queue: &Queue,
+        record: impl FnOnce(
+            &mut vulkano::AutoCommandBufferBuilder<vulkano::PrimaryAutoCommandBuffer>,
+        ) -> Result<(), TransferError>,
+    ) -> Result<TransferFuture, TransferError> {
+        let mut builder = vulkano::AutoCommandBufferBuilder::primary(
+            self.handle().clone(),
+            queue.family(),
+            vulkano::CommandBufferUsage::OneTimeSubmit,
+        )?;
+        record(&mut builder)?;
+        Ok(queue.submit(builder.build()?)?)
+    }
+}
+
+/// The first `size` bytes of an untyped buffer viewed as an array of `T`, so it can be passed to
+/// vulkano's typed copy commands.
+struct TypedView<T> {
+    buffer: Arc<dyn vulkano::BufferAccess>,
+    size: vulkano::DeviceSize,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> TypedView<T> {
+    fn new(buffer: Arc<dyn vulkano::BufferAccess>, size: vulkano::DeviceSize) -> Arc<Self> {
+        Arc::new(Self {
+            buffer,
+            size,
+            marker: PhantomData,
+        })
+    }
+}
+
+unsafe impl<T: Pod + Send + Sync> vulkano::BufferAccess for TypedView<T> {
+    fn inner(&self) -> vulkano::BufferInner<'_> {
+        self.buffer.inner()
+    }
+    fn size(&self) -> vulkano::DeviceSize {
+        self.size
+    }
+    fn conflict_key(&self) -> (u64, u64) {
+        self.buffer.conflict_key()
+    }
+    fn try_gpu_lock(
+        &self,
+        exclusive_access: bool,
+        queue: &vulkano::Queue,
+    ) -> Result<(), vulkano::AccessError> {
+        self.buffer.try_gpu_lock(exclusive_access, queue)
+    }
+    unsafe fn increase_gpu_lock(&self) {
+        self.buffer.increase_gpu_lock()
+    }
+    unsafe fn unlock(&self) {
+        self.buffer.unlock()
+    }
+}
+
+unsafe impl<T: Pod + Send + Sync> vulkano::TypedBufferAccess for TypedView<T> {
+    type Content = [T];
+}
+
+unsafe impl<T> vulkano::DeviceOwned for TypedView<T> {
+    fn device(&self) -> &Arc<vulkano::Device> {
+        self.buffer.device()
+    }
+}
+
+#[derive(Debug, From)]
+pub enum TransferError {
+    /// Nothing to transfer, zero sized buffers cannot be created.
+    Empty,
+    /// The transfer ends at byte `end` but the buffer only has `available` bytes.
+    #[from(ignore)]
+    OutOfBounds {
+        end: vulkano::DeviceSize,
+        available: vulkano::DeviceSize,
+    },
+    /// Source and destination of the copy overlap in the same buffer.
+    #[from(ignore)]
+    Overlap(BufferCopy),
+    /// Fills need an offset and size that are multiples of 4.
+    #[from(ignore)]
+    Misaligned {
+        offset: vulkano::DeviceSize,
+        size: vulkano::DeviceSize,
+    },
+    Allocation(vulkano::DeviceMemoryAllocationError),
+    CommandBuffer(vulkano::OomError),
+    Copy(vulkano::CopyBufferError),
+    Fill(vulkano::FillBufferError),
+    CopyImage(vulkano::CopyBufferImageError),
+    Blit(vulkano::BlitImageError),
+    Clear(vulkano::ClearColorImageError),
+    /// The image only has `levels` mip levels.
+    #[from(ignore)]
+    MipLevel {
+        level: u32,
+        levels: u32,
+    },
+    /// The region ends at array layer `end` but the image only has `available` layers.
+    #[from(ignore)]
+    ArrayLayers {
+        end: u32,
+        available: u32,
+    },
+    /// The region ends at texel `end` but mip level `mip_level` is only `available` texels large.
+    #[from(ignore)]
+    ImageOutOfBounds {
+        mip_level: u32,
+        end: [u32; 3],
+        available: [u32; 3],
+    },
+    /// Blits need the same number of array layers on both sides.
+    #[from(ignore)]
+    LayerCounts {
+        source: u32,
+        destination: u32,
+    },
+    /// Images of compressed or multi-planar formats cannot be read back.
+    #[from(ignore)]
+    UnsupportedFormat(vulkano::Format),
+    Build(vulkano::BuildError),
+    Submit(SubmitError),
+    Wait(vulkano::FlushError),
+    Read(vulkano::ReadLockError),
+}
+
+impl fmt::Display for TransferError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "cannot transfer zero bytes"),
+            Self::OutOfBounds { end, available } => write!(
+                f,
+                "the transfer ends at byte {} but the buffer only has {}",
+                end, available
+            ),
+            Self::Overlap(region) => write!(
+                f,
+                "the copy of {} bytes from offset {} to {} overlaps itself",
+                region.size, region.src_offset, region.dst_offset
+            ),
+            Self::Misaligned { offset, size } => write!(
+                f,
+                "cannot fill {} bytes at offset {}, both have to be multiples of 4",
+                size, offset
+            ),
+            Self::Allocation(err) => write!(f, "failed to allocate the buffer: {}", err),
+            Self::CommandBuffer(err) => write!(f, "failed to create the command buffer: {}", err),
+            Self::Copy(err) => write!(f, "failed to record the copy: {}", err),
+            Self::Fill(err) => write!(f, "failed to record the fill: {}", err),
+            Self::CopyImage(err) => write!(f, "failed to record the image copy: {}", err),
+            Self::Blit(err) => write!(f, "failed to record the blit: {}", err),
+            Self::Clear(err) => write!(f, "failed to record the image clear: {}", err),
+            Self::MipLevel { level, levels } => write!(
+                f,
+                "mip level {} does not exist, the image has {} levels",
+                level, levels
+            ),
+            Self::ArrayLayers { end, available } => write!(
+                f,
+                "the region ends at array layer {} but the image only has {}",
+                end, available
+            ),
+            Self::ImageOutOfBounds {
+                mip_level,
+                end,
+                available,
+            } => write!(
+                f,
+                "the region ends at texel {:?} but mip level {} is only {:?} large",
+                end, mip_level, available
+            ),
+            Self::LayerCounts {
+                source,
+                destination,
+            } => write!(
+                f,
+                "cannot blit {} array layers into {}",
+                source, destination
+            ),
+            Self::UnsupportedFormat(format) => {
+                write!(f, "cannot read back images of format {:?}", format)
+            }
+            Self::Build(err) => write!(f, "failed to build the command buffer: {}", err),
+            Self::Submit(err) => write!(f, "failed to submit the transfer: {}", err),
+            Self::Wait(err) => write!(f, "failed to wait for the transfer: {}", err),
+            Self::Read(err) => write!(f, "failed to map the downloaded data: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for TransferError {}
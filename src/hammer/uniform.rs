@@ -0,0 +1,365 @@
+use bytemuck::Pod;
+use std::collections::HashMap;
+use std::fmt;
+
+use super::Buffer;
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::descriptor_set::*;
+    pub use vulkano::shader::spirv::*;
+}
+
+/// Member of a uniform block as the shader lays it out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockMember {
+    pub name: String,
+    pub offset: u32,
+    /// Zero if the size could not be determined, e.g. for runtime arrays.
+    pub size: u32,
+}
+
+/// Field of a Rust type mirroring a uniform block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldLayout {
+    pub name: &'static str,
+    pub offset: usize,
+    pub size: usize,
+}
+
+/// Rust types whose fields can be checked against a uniform block, implemented with
+/// `uniform_layout!`.
+///
+/// Fields whose name starts with an underscore are treated as explicit padding and skipped.
+pub trait UniformLayout: Pod + Send + Sync {
+    fn fields() -> Vec<FieldLayout>;
+}
+
+/// Implements `UniformLayout` for a struct, listing its fields in declaration order.
+///
+/// ```ignore
+/// uniform_layout!(Globals { view_proj, light_dir, _pad, time });
+/// ```
+#[macro_export]
+macro_rules! uniform_layout {
+    ($ty:ty { $($field:ident),* $(,)? }) => {
+        impl $crate::hammer::UniformLayout for $ty {
+            fn fields() -> Vec<$crate::hammer::FieldLayout> {
+                vec![$(
+                    $crate::hammer::FieldLayout {
+                        name: stringify!($field),
+                        offset: memoffset::offset_of!($ty, $field),
+                        size: memoffset::span_of!($ty, $field).len(),
+                    },
+                )*]
+            }
+        }
+    };
+}
+
+/// Layouts of all uniform blocks of a shader, by descriptor set and binding.
+#[derive(Clone, Debug, Default)]
+pub struct UniformBlocks {
+    blocks: HashMap<(u32, u32), Vec<BlockMember>>,
+}
+
+impl UniformBlocks {
+    /// Reflects the uniform blocks of the SPIR-V module `words`.
+    pub fn from_words(words: &[u32]) -> Result<Self, vulkano::SpirvError> {
+        Ok(Self::reflect(&vulkano::Spirv::new(words)?))
+    }
+    pub fn reflect(spirv: &vulkano::Spirv) -> Self {
+        let mut blocks = HashMap::new();
+        for instruction in spirv.iter_global() {
+            let (pointer, id) = match *instruction {
+                vulkano::Instruction::Variable {
+                    result_type_id,
+                    result_id,
+                    storage_class: vulkano::StorageClass::Uniform,
+                    ..
+                } => (result_type_id, result_id),
+                _ => continue,
+            };
+            let (mut set, mut binding) = (None, None);
+            for decoration in spirv.id(id).iter_decoration() {
+                match decoration {
+                    vulkano::Instruction::Decorate {
+                        decoration: vulkano::Decoration::DescriptorSet { descriptor_set },
+                        ..
+                    } => set = Some(*descriptor_set),
+                    vulkano::Instruction::Decorate {
+                        decoration: vulkano::Decoration::Binding { binding_point },
+                        ..
+                    } => binding = Some(*binding_point),
+                    _ => (),
+                }
+            }
+            let block = match *spirv.id(pointer).instruction() {
+                vulkano::Instruction::TypePointer { ty, .. } => ty,
+                _ => continue,
+            };
+            if let (Some(set), Some(binding)) = (set, binding) {
+                blocks.insert((set, binding), members(spirv, block));
+            }
+        }
+        Self { blocks }
+    }
+    pub fn get(&self, set: u32, binding: u32) -> Option<&[BlockMember]> {
+        self.blocks.get(&(set, binding)).map(Vec::as_slice)
+    }
+    /// Checks the layout of `T` against the uniform block at `set` and `binding`, if the shader
+    /// has one there.
+    pub fn check<T: UniformLayout>(&self, set: u32, binding: u32) -> Result<(), LayoutMismatch> {
+        match self.get(set, binding) {
+            Some(members) => check_layout::<T>(members),
+            None => Ok(()),
+        }
+    }
+    /// Panics in debug builds if the layout of `T` does not match the uniform block at `set` and
+    /// `binding`, naming the first mismatching member.
+    pub fn debug_check<T: UniformLayout>(&self, set: u32, binding: u32) {
+        if cfg!(debug_assertions) {
+            if let Err(err) = self.check::<T>(set, binding) {
+                panic!("uniform block at set {} binding {}: {}", set, binding, err);
+            }
+        }
+    }
+    /// Creates the descriptor write for `buffer` at `binding`, after `debug_check`.
+    pub fn write<T: UniformLayout>(
+        &self,
+        set: u32,
+        binding: u32,
+        buffer: &Buffer<T>,
+    ) -> vulkano::WriteDescriptorSet {
+        self.debug_check::<T>(set, binding);
+        vulkano::WriteDescriptorSet::buffer(binding, buffer.buffer().clone())
+    }
+}
+
+/// Compares the fields of `T`, except for padding, one by one with `members`.
+pub fn check_layout<T: UniformLayout>(members: &[BlockMember]) -> Result<(), LayoutMismatch> {
+    let fields = T::fields()
+        .into_iter()
+        .filter(|field| !field.name.starts_with('_'))
+        .collect::<Vec<_>>();
+    if fields.len() != members.len() {
+        return Err(LayoutMismatch::MemberCount {
+            block: members.len(),
+            rust: fields.len(),
+        });
+    }
+    for (member, field) in members.iter().zip(fields) {
+        let size_differs = member.size != 0 && member.size as usize != field.size;
+        if member.offset as usize != field.offset || size_differs {
+            return Err(LayoutMismatch::Member {
+                member: member.clone(),
+                field,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn members(spirv: &vulkano::Spirv, block: vulkano::Id) -> Vec<BlockMember> {
+    let member_types = match spirv.id(block).instruction() {
+        vulkano::Instruction::TypeStruct { member_types, .. } => member_types,
+        _ => return Vec::new(),
+    };
+    spirv
+        .id(block)
+        .iter_members()
+        .zip(member_types)
+        .enumerate()
+        .map(|(index, (info, &ty))| {
+            let mut offset = 0;
+            let mut matrix_stride = None;
+            for decoration in info.iter_decoration() {
+                match decoration {
+                    vulkano::Instruction::MemberDecorate {
+                        decoration: vulkano::Decoration::Offset { byte_offset },
+                        ..
+                    } => offset = *byte_offset,
+                    vulkano::Instruction::MemberDecorate {
+                        decoration:
+                            vulkano::Decoration::MatrixStride {
+                                matrix_stride: stride,
+                            },
+                        ..
+                    } => matrix_stride = Some(*stride),
+                    _ => (),
+                }
+            }
+            let name = info
+                .iter_name()
+                .find_map(|name| match name {
+                    vulkano::Instruction::MemberName { name, .. } => Some(name.clone()),
+                    _ => None,
+                })
+                .unwrap_or_else(|| format!("member {}", index));
+            BlockMember {
+                name,
+                offset,
+                size: type_size(spirv, ty, matrix_stride),
+            }
+        })
+        .collect()
+}
+
+/// Size of `ty` in a block, zero if unknown.
+fn type_size(spirv: &vulkano::Spirv, ty: vulkano::Id, matrix_stride: Option<u32>) -> u32 {
+    match spirv.id(ty).instruction() {
+        vulkano::Instruction::TypeBool { .. } => 4,
+        vulkano::Instruction::TypeInt { width, .. }
+        | vulkano::Instruction::TypeFloat { width, .. } => width / 8,
+        vulkano::Instruction::TypeVector {
+            component_type,
+            component_count,
+            ..
+        } => component_count * type_size(spirv, *component_type, None),
+        vulkano::Instruction::TypeMatrix {
+            column_type,
+            column_count,
+            ..
+        } => column_count * matrix_stride.unwrap_or_else(|| type_size(spirv, *column_type, None)),
+        vulkano::Instruction::TypeArray {
+            element_type,
+            length,
+            ..
+        } => {
+            let length = match spirv.id(*length).instruction() {
+                vulkano::Instruction::Constant { value, .. } => value[0],
+                _ => return 0,
+            };
+            let stride = spirv
+                .id(ty)
+                .iter_decoration()
+                .find_map(|decoration| match decoration {
+                    vulkano::Instruction::Decorate {
+                        decoration: vulkano::Decoration::ArrayStride { array_stride },
+                        ..
+                    } => Some(*array_stride),
+                    _ => None,
+                })
+                .unwrap_or_else(|| type_size(spirv, *element_type, matrix_stride));
+            length * stride
+        }
+        vulkano::Instruction::TypeStruct { .. } => members(spirv, ty)
+            .last()
+            .map_or(0, |member| member.offset + member.size),
+        _ => 0,
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum LayoutMismatch {
+    /// The block and the Rust type, without padding fields, have a different number of members.
+    MemberCount { block: usize, rust: usize },
+    /// A member is at a different offset or has a different size than the corresponding field.
+    Member {
+        member: BlockMember,
+        field: FieldLayout,
+    },
+}
+
+impl fmt::Display for LayoutMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MemberCount { block, rust } => write!(
+                f,
+                "the block has {} members but the Rust type has {} fields",
+                block, rust
+            ),
+            Self::Member { member, field } => write!(
+                f,
+                "member `{}` is at offset {} with size {}, but field `{}` is at offset {} with size {}",
+                member.name, member.offset, member.size, field.name, field.offset, field.size
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LayoutMismatch {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    // `uniform Params { float scale; vec3 color; }` at set 0, binding 1.
+    const UNIFORM: &[u8] = include_bytes!("../../tests/data/uniform.spv");
+
+    fn blocks() -> UniformBlocks {
+        let words = UNIFORM
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect::<Vec<_>>();
+        UniformBlocks::from_words(&words).unwrap()
+    }
+
+    // Packs `color` right after `scale`, std140 aligns it to 16 bytes.
+    #[repr(C)]
+    #[derive(Clone, Copy, Zeroable, Pod)]
+    struct Packed {
+        scale: f32,
+        color: [f32; 3],
+    }
+    crate::uniform_layout!(Packed { scale, color });
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Zeroable, Pod)]
+    struct Padded {
+        scale: f32,
+        _pad: [f32; 3],
+        color: [f32; 3],
+    }
+    crate::uniform_layout!(Padded { scale, _pad, color });
+
+    #[test]
+    fn reflects_block() {
+        let blocks = blocks();
+        let members = blocks.get(0, 1).unwrap();
+        assert_eq!(
+            members,
+            &[
+                BlockMember {
+                    name: "scale".to_string(),
+                    offset: 0,
+                    size: 4,
+                },
+                BlockMember {
+                    name: "color".to_string(),
+                    offset: 16,
+                    size: 12,
+                },
+            ]
+        );
+        assert!(blocks.get(0, 0).is_none());
+    }
+
+    #[test]
+    fn misaligned_struct_is_caught() {
+        let err = blocks().check::<Packed>(0, 1).unwrap_err();
+        match &err {
+            LayoutMismatch::Member { member, field } => {
+                assert_eq!(member.name, "color");
+                assert_eq!((member.offset, field.offset), (16, 4));
+            }
+            _ => panic!("unexpected mismatch {:?}", err),
+        }
+        assert!(err.to_string().contains("`color`"));
+    }
+
+    #[test]
+    fn padded_struct_matches() {
+        blocks().check::<Padded>(0, 1).unwrap();
+        // Bindings without a uniform block are not checked.
+        blocks().check::<Packed>(0, 0).unwrap();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "member `color` is at offset 16")]
+    fn debug_check_panics() {
+        blocks().debug_check::<Packed>(0, 1);
+    }
+}
@@ -0,0 +1,82 @@
+use bytemuck::Pod;
+use std::sync::Arc;
+
+use super::{Buffer, Surface, SurfaceImage};
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::buffer::cpu_access::*;
+    pub use vulkano::device::*;
+    pub use vulkano::memory::*;
+}
+
+/// One uniform buffer per frame in flight, so the buffer of the current frame can be updated
+/// while the device still reads the ones of previous frames.
+///
+/// Frame indices wrap around, so any increasing counter can be used. When rendering to a
+/// swapchain, the `*_image` methods select the buffer by the image index instead.
+pub struct UniformRing<T: Pod + Send + Sync> {
+    buffers: Vec<Buffer<T>>,
+}
+
+impl<T: Pod + Send + Sync> UniformRing<T> {
+    /// Creates `frames` buffers, all initialized to `value`.
+    pub fn new(
+        device: Arc<vulkano::Device>,
+        frames: usize,
+        value: T,
+    ) -> Result<Self, vulkano::DeviceMemoryAllocationError> {
+        let buffers = (0..frames.max(1))
+            .map(|_| Buffer::uniform(device.clone(), value))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { buffers })
+    }
+    /// Creates one buffer per image of the surface's swapchain.
+    ///
+    /// The image count may change when the swapchain is recreated, in which case the ring has to be
+    /// recreated as well.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the surface has no swapchain.
+    pub fn for_surface<W>(
+        device: Arc<vulkano::Device>,
+        surface: &Surface<W>,
+        value: T,
+    ) -> Result<Self, vulkano::DeviceMemoryAllocationError> {
+        let frames = surface.swapchain.as_ref().unwrap().images.len();
+        Self::new(device, frames, value)
+    }
+    pub fn len(&self) -> usize {
+        self.buffers.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty()
+    }
+    /// Updates the buffer of `frame_index`.
+    ///
+    /// Fails if the device is still using the buffer, i.e. more frames are in flight than the
+    /// ring has buffers.
+    pub fn write(&self, frame_index: usize, value: &T) -> Result<(), vulkano::WriteLockError> {
+        self.buffers[frame_index % self.len()].write(0..1, std::slice::from_ref(value))
+    }
+    /// Buffer of `frame_index` to put into the descriptor set.
+    pub fn descriptor_buffer(&self, frame_index: usize) -> &Arc<vulkano::CpuAccessibleBuffer<[T]>> {
+        self.buffers[frame_index % self.len()].buffer()
+    }
+    /// Updates the buffer belonging to the swapchain image `image`.
+    pub fn write_image<W>(
+        &self,
+        image: &SurfaceImage<W>,
+        value: &T,
+    ) -> Result<(), vulkano::WriteLockError> {
+        self.write(image.image_num, value)
+    }
+    /// Buffer belonging to the swapchain image `image`.
+    pub fn descriptor_buffer_image<W>(
+        &self,
+        image: &SurfaceImage<W>,
+    ) -> &Arc<vulkano::CpuAccessibleBuffer<[T]>> {
+        self.descriptor_buffer(image.image_num)
+    }
+}
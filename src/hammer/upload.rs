@@ -0,0 +1,103 @@
+use bytemuck::Pod;
+use derive_more::*;
+use std::sync::Arc;
+
+use super::{BufferCopy, CommandEncoder, Device, Queue, TransferError, TransferFuture};
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::buffer::*;
+    pub use vulkano::DeviceSize;
+}
+
+/// Records transfers into a single command buffer that `finish` submits at once.
+///
+/// Created with `Device::upload_context`. Batching the uploads of e.g. a loading screen into one
+/// context needs one submission and one fence instead of one per upload. Staging buffers are kept
+/// alive by the command buffer until it finished executing.
+///
+/// Derefs to the `CommandEncoder` recording the transfers, so its copies, blits and clears can be
+/// used as well.
+#[derive(Deref, DerefMut)]
+pub struct UploadContext<'a> {
+    device: &'a Device,
+    queue: Queue,
+    #[deref]
+    #[deref_mut]
+    encoder: CommandEncoder,
+}
+
+impl Device {
+    /// Starts recording uploads for the transfer queue, or the graphics queue if there is none.
+    #[track_caller]
+    pub fn upload_context(&self) -> Result<UploadContext<'_>, TransferError> {
+        self.upload_context_on(self.upload_queue())
+    }
+    /// Like `upload_context`, but records for `queue`, e.g. the graphics queue for
+    /// `CommandEncoder::generate_mipmaps`.
+    #[track_caller]
+    pub fn upload_context_on(&self, queue: &Queue) -> Result<UploadContext<'_>, TransferError> {
+        Ok(UploadContext {
+            device: self,
+            queue: queue.clone(),
+            encoder: self.create_command_encoder(queue)?,
+        })
+    }
+}
+
+impl<'a> UploadContext<'a> {
+    /// The queue `finish` submits to.
+    pub fn queue(&self) -> &Queue {
+        &self.queue
+    }
+    /// Creates a device local buffer and records copying `data` into it.
+    ///
+    /// The buffer is shared with every queue family of the device, since the upload may not
+    /// happen on the family that uses the buffer later.
+    pub fn create_buffer<T: Pod + Send + Sync>(
+        &mut self,
+        data: &[T],
+        usage: vulkano::BufferUsage,
+    ) -> Result<Arc<vulkano::DeviceLocalBuffer<[T]>>, TransferError> {
+        if data.is_empty() {
+            return Err(TransferError::Empty);
+        }
+        let staging = vulkano::CpuAccessibleBuffer::from_iter(
+            self.device.handle().clone(),
+            vulkano::BufferUsage::transfer_source(),
+            false,
+            data.iter().copied(),
+        )?;
+        let buffer = vulkano::DeviceLocalBuffer::array(
+            self.device.handle().clone(),
+            data.len() as u64,
+            vulkano::BufferUsage {
+                transfer_destination: true,
+                ..usage
+            },
+            self.device.active_queue_families(),
+        )?;
+
+        let size = std::mem::size_of_val(data) as vulkano::DeviceSize;
+        let (source, destination): (
+            Arc<dyn vulkano::BufferAccess>,
+            Arc<dyn vulkano::BufferAccess>,
+        ) = (staging, buffer.clone());
+        self.encoder
+            .copy_buffer(&source, &destination, &[BufferCopy::whole(size)])?;
+        Ok(buffer)
+    }
+    /// Submits the recorded transfers and returns a future that is signaled once they finished.
+    pub fn finish(self) -> Result<TransferFuture, TransferError> {
+        Ok(self.queue.submit(self.encoder.finish()?)?)
+    }
+    /// Drops the recorded transfers without submitting them, e.g. after recording failed.
+    pub fn abandon(self) {
+        self.encoder.abandon();
+    }
+    /// Submits the recorded transfers and blocks until they finished, e.g. for a loading screen.
+    pub fn finish_and_wait(self) -> Result<(), TransferError> {
+        self.finish()?.wait(None)?;
+        Ok(())
+    }
+}
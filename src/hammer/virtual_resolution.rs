@@ -0,0 +1,263 @@
+use derive_more::*;
+use std::fmt;
+use std::sync::Arc;
+
+use super::{
+    AttachmentError, DrawTarget, OffscreenTexture, OffscreenTextureDescriptor, SwapchainError,
+};
+
+// Getting rust analyzer problems when not defining the module here again.
+mod vulkano {
+    pub use vulkano::command_buffer::*;
+    pub use vulkano::device::*;
+    pub use vulkano::format::*;
+    pub use vulkano::image::view::*;
+    pub use vulkano::image::*;
+    pub use vulkano::render_pass::*;
+    pub use vulkano::sampler::*;
+}
+
+/// Where the virtual render target ends up inside the window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Placement {
+    /// Top left corner in window pixels.
+    pub offset: [u32; 2],
+    /// Size in window pixels.
+    pub extent: [u32; 2],
+    /// Window pixels per virtual pixel. This is an integer unless the window is smaller than the
+    /// virtual resolution.
+    pub scale: f32,
+}
+
+impl Placement {
+    /// Computes the largest integer multiple of `virtual_extent` that fits into `window_extent`,
+    /// centered in the window.
+    ///
+    /// If the window is smaller than the virtual resolution, the target is scaled down to fit
+    /// while keeping its aspect ratio.
+    pub fn integer_scaled(virtual_extent: [u32; 2], window_extent: [u32; 2]) -> Self {
+        let scale_x = window_extent[0] as f32 / virtual_extent[0].max(1) as f32;
+        let scale_y = window_extent[1] as f32 / virtual_extent[1].max(1) as f32;
+        let fit = scale_x.min(scale_y);
+        let scale = if fit >= 1.0 { fit.floor() } else { fit };
+
+        let extent = [
+            ((virtual_extent[0] as f32 * scale) as u32).min(window_extent[0]),
+            ((virtual_extent[1] as f32 * scale) as u32).min(window_extent[1]),
+        ];
+        let offset = [
+            (window_extent[0] - extent[0]) / 2,
+            (window_extent[1] - extent[1]) / 2,
+        ];
+        Self {
+            offset,
+            extent,
+            scale,
+        }
+    }
+    /// Maps a position in window pixels to virtual pixels, `None` if it lies on the border.
+    pub fn window_to_virtual(&self, position: [f64; 2]) -> Option<[f64; 2]> {
+        let x = position[0] - self.offset[0] as f64;
+        let y = position[1] - self.offset[1] as f64;
+        if x < 0. || y < 0. || x >= self.extent[0] as f64 || y >= self.extent[1] as f64 {
+            return None;
+        }
+        Some([x / self.scale as f64, y / self.scale as f64])
+    }
+}
+
+#[derive(Debug, From)]
+pub enum VirtualBlitError {
+    Clear(vulkano::ClearColorImageError),
+    Blit(vulkano::BlitImageError),
+}
+
+impl fmt::Display for VirtualBlitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Clear(err) => write!(f, "failed to clear the border: {}", err),
+            Self::Blit(err) => write!(f, "failed to blit the virtual target: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for VirtualBlitError {}
+
+/// Fixed size render target that is scaled to the window with integer scaling, for pixel-art
+/// rendering.
+///
+/// The target is never recreated when the window is resized. Render into it as a `DrawTarget`,
+/// then call `blit` or `Frame::blit_virtual` to scale it onto the window image, which needs to
+/// support `transfer_destination` usage.
+pub struct VirtualResolution {
+    texture: OffscreenTexture,
+    pub border_color: [f32; 4],
+}
+
+impl VirtualResolution {
+    pub fn new(
+        device: Arc<vulkano::Device>,
+        extent: [u32; 2],
+        format: vulkano::Format,
+    ) -> Result<Self, AttachmentError> {
+        let texture = OffscreenTexture::new_with(
+            device,
+            extent,
+            &OffscreenTextureDescriptor {
+                image_usage: vulkano::ImageUsage {
+                    transfer_source: true,
+                    ..vulkano::ImageUsage::none()
+                },
+                ..OffscreenTextureDescriptor::new(format)
+            },
+        )?;
+        Ok(Self {
+            texture,
+            border_color: [0.0, 0.0, 0.0, 1.0],
+        })
+    }
+    pub fn texture(&self) -> &OffscreenTexture {
+        &self.texture
+    }
+    pub fn target(&self) -> &Arc<vulkano::AttachmentImage> {
+        self.texture.image()
+    }
+    pub fn extent(&self) -> [u32; 2] {
+        self.texture.extent()
+    }
+    pub fn placement(&self, window_extent: [u32; 2]) -> Placement {
+        Placement::integer_scaled(self.extent(), window_extent)
+    }
+    /// Maps a cursor position in window pixels to virtual pixels.
+    pub fn window_to_virtual(
+        &self,
+        window_extent: [u32; 2],
+        position: [f64; 2],
+    ) -> Option<[f64; 2]> {
+        self.placement(window_extent).window_to_virtual(position)
+    }
+    /// Records the commands that clear `destination` to the border color and scale the virtual
+    /// target into it using nearest neighbor filtering.
+    pub fn blit<L, P>(
+        &self,
+        builder: &mut vulkano::AutoCommandBufferBuilder<L, P>,
+        destination: Arc<dyn vulkano::ImageAccess>,
+    ) -> Result<(), VirtualBlitError> {
+        let window_extent = destination.dimensions().width_height();
+        let placement = self.placement(window_extent);
+        let extent = self.extent();
+
+        builder.clear_color_image(destination.clone(), self.border_color.into())?;
+        if placement.extent[0] == 0 || placement.extent[1] == 0 {
+            return Ok(());
+        }
+        builder.blit_image(
+            self.target().clone(),
+            [0, 0, 0],
+            [extent[0] as i32, extent[1] as i32, 1],
+            0,
+            0,
+            destination,
+            [placement.offset[0] as i32, placement.offset[1] as i32, 0],
+            [
+                (placement.offset[0] + placement.extent[0]) as i32,
+                (placement.offset[1] + placement.extent[1]) as i32,
+                1,
+            ],
+            0,
+            0,
+            1,
+            vulkano::Filter::Nearest,
+        )?;
+        Ok(())
+    }
+}
+
+impl DrawTarget for VirtualResolution {
+    fn extent(&self) -> [u32; 2] {
+        VirtualResolution::extent(self)
+    }
+    fn format(&self) -> vulkano::Format {
+        self.texture.format()
+    }
+    fn view(&self) -> Arc<dyn vulkano::ImageViewAbstract> {
+        self.texture.sampled_view()
+    }
+    fn create_render_pass(&self) -> Result<Arc<vulkano::RenderPass>, SwapchainError> {
+        Ok(self.texture.create_render_pass()?)
+    }
+    fn clear_values(&self, color: [f32; 4]) -> Vec<vulkano::ClearValue> {
+        self.texture.clear_values(color)
+    }
+    fn framebuffer(
+        &self,
+        render_pass: &Arc<vulkano::RenderPass>,
+    ) -> Result<Arc<vulkano::Framebuffer>, vulkano::FramebufferCreationError> {
+        self.texture.framebuffer(render_pass)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placement_integer_scaled() {
+        // 1920x1080 fits 320x180 exactly six times.
+        assert_eq!(
+            Placement::integer_scaled([320, 180], [1920, 1080]),
+            Placement {
+                offset: [0, 0],
+                extent: [1920, 1080],
+                scale: 6.0,
+            }
+        );
+        // 1280x1024 only fits four times because of the width, the rest is border.
+        assert_eq!(
+            Placement::integer_scaled([320, 180], [1280, 1024]),
+            Placement {
+                offset: [0, 152],
+                extent: [1280, 720],
+                scale: 4.0,
+            }
+        );
+        // 1000x700 fits three times, centered with borders on both axes.
+        assert_eq!(
+            Placement::integer_scaled([320, 180], [1000, 700]),
+            Placement {
+                offset: [20, 80],
+                extent: [960, 540],
+                scale: 3.0,
+            }
+        );
+    }
+
+    #[test]
+    fn placement_downscales_small_windows() {
+        // Smaller than the virtual resolution, scaled down to fit the width.
+        assert_eq!(
+            Placement::integer_scaled([320, 180], [160, 120]),
+            Placement {
+                offset: [0, 15],
+                extent: [160, 90],
+                scale: 0.5,
+            }
+        );
+        // A minimized window has nothing to blit into.
+        let minimized = Placement::integer_scaled([320, 180], [0, 0]);
+        assert_eq!(minimized.extent, [0, 0]);
+    }
+
+    #[test]
+    fn window_to_virtual() {
+        let placement = Placement::integer_scaled([320, 180], [1000, 700]);
+        assert_eq!(placement.window_to_virtual([20.0, 80.0]), Some([0.0, 0.0]));
+        assert_eq!(
+            placement.window_to_virtual([500.0, 350.0]),
+            Some([160.0, 90.0])
+        );
+        // On the border.
+        assert_eq!(placement.window_to_virtual([10.0, 350.0]), None);
+        assert_eq!(placement.window_to_virtual([500.0, 620.0]), None);
+    }
+}
@@ -0,0 +1,132 @@
+use std::sync::Arc;
+use test03::hammer;
+use vulkano::buffer::{BufferAccess, BufferUsage, CpuAccessibleBuffer};
+use vulkano::format::Format;
+use vulkano::image::{ImageAccess, ImageCreateFlags, ImageDimensions, ImageUsage, StorageImage};
+use vulkano::sampler::Filter;
+
+mod common;
+
+const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+const BLUE: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+
+fn image(
+    device: &hammer::Device,
+    queue: &hammer::Queue,
+    [width, height]: [u32; 2],
+) -> Arc<StorageImage> {
+    StorageImage::with_usage(
+        (*device).clone(),
+        ImageDimensions::Dim2d {
+            width,
+            height,
+            array_layers: 1,
+        },
+        Format::R8G8B8A8_UNORM,
+        ImageUsage {
+            transfer_source: true,
+            transfer_destination: true,
+            ..ImageUsage::none()
+        },
+        ImageCreateFlags::none(),
+        [queue.family()],
+    )
+    .unwrap()
+}
+
+#[test]
+fn blit_into_larger_image() {
+    let device = match common::device() {
+        Some(device) => device,
+        None => return,
+    };
+    let queue = device.graphics_queue().unwrap().clone();
+    let source = image(&device, &queue, [8, 8]);
+    let destination = image(&device, &queue, common::EXTENT);
+
+    // The solid red source is scaled up into the middle of the blue destination.
+    let region = hammer::ImageRegion {
+        offset: [16, 16, 0],
+        extent: [32, 32, 1],
+        ..hammer::ImageRegion::whole(&*destination)
+    };
+    let mut encoder = device.create_command_encoder(&queue).unwrap();
+    encoder
+        .clear_color_image(source.clone(), RED)
+        .unwrap()
+        .clear_color_image(destination.clone(), BLUE)
+        .unwrap()
+        .blit_image(
+            source.clone(),
+            hammer::ImageRegion::whole(&*source),
+            destination.clone(),
+            region,
+            Filter::Nearest,
+        )
+        .unwrap();
+    let future = queue.submit(encoder.finish().unwrap()).unwrap();
+    let data = device
+        .read_image_after(&queue, destination.clone(), future.into_future())
+        .unwrap();
+
+    for y in 0..common::EXTENT[1] {
+        for x in 0..common::EXTENT[0] {
+            let inside = (16..48).contains(&x) && (16..48).contains(&y);
+            let expected = match inside {
+                true => [255, 0, 0, 255],
+                false => [0, 0, 255, 255],
+            };
+            assert_eq!(common::texel(&data, [x, y]), expected, "texel {:?}", [x, y]);
+        }
+    }
+}
+
+#[test]
+fn blit_out_of_bounds_is_an_error() {
+    let device = match common::device() {
+        Some(device) => device,
+        None => return,
+    };
+    let queue = device.graphics_queue().unwrap().clone();
+    let source = image(&device, &queue, [8, 8]);
+    let destination = image(&device, &queue, common::EXTENT);
+    let mut encoder = device.create_command_encoder(&queue).unwrap();
+    let region = hammer::ImageRegion {
+        offset: [48, 0, 0],
+        extent: [32, 32, 1],
+        ..hammer::ImageRegion::whole(&*destination)
+    };
+    let result = encoder.blit_image(
+        source.clone(),
+        hammer::ImageRegion::whole(&*source),
+        destination.clone(),
+        region,
+        Filter::Linear,
+    );
+    assert!(matches!(
+        result,
+        Err(hammer::TransferError::ImageOutOfBounds { .. })
+    ));
+    // The mip level has to exist as well.
+    assert_eq!(destination.mip_levels(), 1);
+    let staging: Arc<dyn BufferAccess> = CpuAccessibleBuffer::from_iter(
+        (*device).clone(),
+        BufferUsage::transfer_destination(),
+        false,
+        [0u8; 64 * 64 * 4],
+    )
+    .unwrap();
+    let result = encoder.copy_image_to_buffer(
+        destination.clone(),
+        hammer::ImageRegion {
+            mip_level: 1,
+            ..hammer::ImageRegion::whole(&*destination)
+        },
+        staging,
+    );
+    assert!(matches!(
+        result,
+        Err(hammer::TransferError::MipLevel { .. })
+    ));
+    encoder.abandon();
+}
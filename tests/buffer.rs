@@ -0,0 +1,150 @@
+use std::sync::Arc;
+use test03::hammer;
+use vulkano::buffer::cpu_access::{ReadLockError, WriteLockError};
+use vulkano::buffer::{BufferAccess, BufferUsage};
+
+mod common;
+
+#[test]
+fn mapped_buffer_round_trip() {
+    const COUNT: usize = 1024;
+
+    let device = match common::device() {
+        Some(device) => device,
+        None => return,
+    };
+    let queue = device.compute_queue().unwrap().clone();
+    let data: Vec<u32> = (0..COUNT as u32).collect();
+    let usage = BufferUsage {
+        transfer_source: true,
+        transfer_destination: true,
+        ..BufferUsage::none()
+    };
+    let buffer = hammer::MappedBuffer::new(device.clone(), usage, &data).unwrap();
+    let access: Arc<dyn BufferAccess> = buffer.clone();
+
+    // Written without flushing, submitting the copy flushes the write.
+    buffer.write(10..13, &[7, 8, 9]);
+    let read = device.read_buffer::<u32>(&access, COUNT).unwrap();
+    let mut expected = data.clone();
+    expected[10..13].copy_from_slice(&[7, 8, 9]);
+    assert_eq!(read, expected);
+
+    // Written by the device, reading invalidates first.
+    device
+        .fill_buffer(&queue, &access, 5)
+        .unwrap()
+        .wait(None)
+        .unwrap();
+    assert_eq!(buffer.read(0..COUNT).unwrap(), vec![5; COUNT]);
+
+    // An explicit flush makes the write visible without another write in between.
+    buffer.write(0..1, &[42]);
+    buffer.flush().unwrap();
+    assert_eq!(buffer.read(0..2).unwrap(), vec![42, 5]);
+}
+
+#[test]
+fn buffer_lock_contention() {
+    let device = match common::device() {
+        Some(device) => device,
+        None => return,
+    };
+    let buffer = hammer::Buffer::storage(device.clone(), &[1u32, 2, 3, 4]).unwrap();
+
+    // Writing fails instead of blocking while the buffer is mapped for reading, and the other
+    // way around.
+    {
+        let _mapping = buffer.buffer().read().unwrap();
+        assert!(matches!(
+            buffer.write(0..2, &[5, 6]),
+            Err(WriteLockError::CpuLocked)
+        ));
+        // Several readers are fine.
+        assert_eq!(buffer.read(0..4).unwrap(), vec![1, 2, 3, 4]);
+    }
+    {
+        let _mapping = buffer.buffer().write().unwrap();
+        assert!(matches!(
+            buffer.read(0..4),
+            Err(ReadLockError::CpuWriteLocked)
+        ));
+        assert!(matches!(
+            buffer.write(0..2, &[5, 6]),
+            Err(WriteLockError::CpuLocked)
+        ));
+    }
+
+    // Both work again once the mappings are gone.
+    buffer.write(0..2, &[5, 6]).unwrap();
+    assert_eq!(buffer.read(0..4).unwrap(), vec![5, 6, 3, 4]);
+}
+
+#[test]
+fn read_buffer_count_overflow() {
+    let device = match common::device() {
+        Some(device) => device,
+        None => return,
+    };
+    let buffer = hammer::Buffer::storage(device.clone(), &[1u32, 2, 3, 4]).unwrap();
+    let access: Arc<dyn BufferAccess> = buffer.buffer().clone();
+
+    // The byte size of the read overflows and is reported instead of wrapping around.
+    assert!(matches!(
+        device.read_buffer::<u32>(&access, usize::MAX),
+        Err(hammer::TransferError::OutOfBounds { available: 16, .. })
+    ));
+}
+
+#[test]
+fn index_buffer_shares_binding() {
+    let device = match common::device() {
+        Some(device) => device,
+        None => return,
+    };
+    let buffer =
+        hammer::Buffer::new(device.clone(), BufferUsage::index_buffer(), &[0u16, 1, 2]).unwrap();
+    let index_buffer = hammer::IndexBuffer::from(&buffer);
+    assert_eq!(index_buffer.len(), 3);
+
+    // Clones, e.g. per draw list item, bind the same allocation.
+    match (&index_buffer, &index_buffer.clone()) {
+        (hammer::IndexBuffer::U16(a), hammer::IndexBuffer::U16(b)) => assert!(Arc::ptr_eq(a, b)),
+        _ => panic!("expected 16 bit indices"),
+    }
+}
+
+#[test]
+fn upload_context_batches_copies() {
+    let device = match common::device() {
+        Some(device) => device,
+        None => return,
+    };
+    let usage = BufferUsage {
+        transfer_source: true,
+        ..BufferUsage::none()
+    };
+    let positions: Vec<f32> = (0..64).map(|i| i as f32 * 0.5).collect();
+    let indices: Vec<u16> = (0..96).rev().collect();
+
+    // Two copies recorded into one command buffer, submitted together.
+    let mut upload = device.upload_context().unwrap();
+    let position_buffer = upload.create_buffer(&positions, usage).unwrap();
+    let index_buffer = upload.create_buffer(&indices, usage).unwrap();
+    upload.finish_and_wait().unwrap();
+
+    let position_buffer: Arc<dyn BufferAccess> = position_buffer;
+    let index_buffer: Arc<dyn BufferAccess> = index_buffer;
+    assert_eq!(
+        device
+            .read_buffer::<f32>(&position_buffer, positions.len())
+            .unwrap(),
+        positions
+    );
+    assert_eq!(
+        device
+            .read_buffer::<u16>(&index_buffer, indices.len())
+            .unwrap(),
+        indices
+    );
+}
@@ -0,0 +1,95 @@
+use test03::hammer;
+use vulkano::format::Format;
+use vulkano::image::{ImageAccess, ImageUsage};
+
+mod common;
+
+fn texture(
+    device: &hammer::Device,
+    format: Format,
+    transfer_source: bool,
+) -> hammer::OffscreenTexture {
+    hammer::OffscreenTexture::new_with(
+        (*device).clone(),
+        common::EXTENT,
+        &hammer::OffscreenTextureDescriptor {
+            image_usage: ImageUsage {
+                transfer_source,
+                transfer_destination: true,
+                ..ImageUsage::none()
+            },
+            ..hammer::OffscreenTextureDescriptor::new(format)
+        },
+    )
+    .unwrap()
+}
+
+#[test]
+fn capture_clear_color_round_trip() {
+    let device = match common::device() {
+        Some(device) => device,
+        None => return,
+    };
+    let queue = device.graphics_queue().unwrap().clone();
+
+    // 51 / 255 = 0.2 is exact in both directions.
+    let color = [1.0, 0.2, 0.0, 1.0];
+    for format in [Format::B8G8R8A8_UNORM, Format::R8G8B8A8_UNORM] {
+        let texture = texture(&device, format, true);
+        let mut encoder = device.create_command_encoder(&queue).unwrap();
+        encoder
+            .clear_color_image(texture.image().clone(), color)
+            .unwrap();
+        let future = queue.submit(encoder.finish().unwrap()).unwrap();
+
+        let data = device
+            .capture_image(&queue, texture.image().clone(), future.into_future())
+            .unwrap();
+        assert_eq!([data.width, data.height], common::EXTENT);
+        assert_eq!(
+            data.pixels.len(),
+            (common::EXTENT[0] * common::EXTENT[1] * 4) as usize
+        );
+        // BGRA images are swizzled, so both formats capture the same pixels.
+        assert!(
+            data.pixels
+                .chunks_exact(4)
+                .all(|pixel| pixel == [255, 51, 0, 255]),
+            "{:?} does not round-trip, first pixel {:?}",
+            format,
+            data.pixel(0, 0)
+        );
+    }
+}
+
+#[test]
+fn capture_rejects_unsupported_images() {
+    let device = match common::device() {
+        Some(device) => device,
+        None => return,
+    };
+    let queue = device.graphics_queue().unwrap().clone();
+
+    let float = texture(&device, Format::R16G16B16A16_SFLOAT, true);
+    assert!(matches!(
+        device.capture_image(
+            &queue,
+            float.image().clone(),
+            vulkano::sync::now((*device).clone())
+        ),
+        Err(hammer::CaptureError::UnsupportedFormat(
+            Format::R16G16B16A16_SFLOAT
+        ))
+    ));
+
+    let no_transfer = texture(&device, Format::R8G8B8A8_UNORM, false);
+    assert!(!no_transfer.image().inner().image.usage().transfer_source);
+    assert!(matches!(
+        device.capture_image(
+            &queue,
+            no_transfer.image().clone(),
+            vulkano::sync::now((*device).clone())
+        ),
+        Err(hammer::CaptureError::NoTransferSource)
+    ));
+}
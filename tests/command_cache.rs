@@ -0,0 +1,49 @@
+use std::cell::Cell;
+use std::sync::Arc;
+use test03::hammer;
+
+mod common;
+
+#[test]
+fn command_buffers_are_reused_until_invalidated() {
+    let device = match common::device() {
+        Some(device) => device,
+        None => return,
+    };
+    let queue = device.graphics_queue().unwrap().clone();
+    let cache = hammer::CommandBufferCache::new();
+    let recorded = Cell::new(0);
+    let record = || {
+        recorded.set(recorded.get() + 1);
+        device
+            .create_reusable_command_encoder(&queue)
+            .unwrap()
+            .finish()
+            .unwrap()
+    };
+
+    assert!(cache.get(0).is_none());
+    let first = cache.get_or_insert_with(0, record);
+    let second = cache.get_or_insert_with(1, record);
+    assert!(!Arc::ptr_eq(&first, &second));
+    // Every frame after the first resubmits the cached command buffers.
+    for _ in 0..3 {
+        assert!(Arc::ptr_eq(&cache.get_or_insert_with(0, record), &first));
+        assert!(Arc::ptr_eq(&cache.get_or_insert_with(1, record), &second));
+    }
+    assert_eq!(recorded.get(), 2);
+
+    // Simultaneous use allows submitting the same command buffer while it may still execute.
+    let futures = (0..2)
+        .map(|_| queue.submit(first.clone()).unwrap())
+        .collect::<Vec<_>>();
+    for future in futures {
+        future.wait(None).unwrap();
+    }
+
+    cache.invalidate();
+    assert!(cache.get(0).is_none());
+    let rerecorded = cache.get_or_insert_with(0, record);
+    assert!(!Arc::ptr_eq(&rerecorded, &first));
+    assert_eq!(recorded.get(), 3);
+}
@@ -0,0 +1,146 @@
+// Setup shared by the tests. Every test only uses some of it.
+#![allow(dead_code)]
+
+use std::sync::{Arc, Mutex};
+use test03::hammer;
+use vulkano::instance::debug::{DebugCallback, MessageSeverity, MessageType};
+use vulkano::instance::{layers_list, InstanceCreateInfo, InstanceExtensions};
+
+/// A device on the first adapter with a graphics and compute queue, `None` where no Vulkan
+/// implementation is available, e.g. on machines without a GPU, which skips the test.
+pub fn device() -> Option<hammer::Device> {
+    device_with_features(vulkano::device::Features::none())
+}
+
+/// Like `device`, with `features` enabled. `None` where no adapter supports them, which skips the
+/// test.
+pub fn device_with_features(features: vulkano::device::Features) -> Option<hammer::Device> {
+    // Fails without a Vulkan loader, where creating the instance would panic.
+    if InstanceExtensions::supported_by_core().is_err() {
+        eprintln!("skipped, no Vulkan implementation is available");
+        return None;
+    }
+    let instance = hammer::Instance::new(InstanceCreateInfo::default());
+    request_device(&instance, features)
+}
+
+/// Errors the validation layer reported for a device of `validated_device`.
+pub struct Validation {
+    errors: Arc<Mutex<Vec<String>>>,
+    _callback: DebugCallback,
+}
+
+impl Validation {
+    pub fn errors(&self) -> Vec<String> {
+        self.errors.lock().unwrap().clone()
+    }
+}
+
+/// Like `device`, with the Khronos validation layer enabled. `None` where the layer is not
+/// installed, which skips the test.
+pub fn validated_device() -> Option<(hammer::Device, Validation)> {
+    const LAYER: &str = "VK_LAYER_KHRONOS_validation";
+    if InstanceExtensions::supported_by_core().is_err() {
+        eprintln!("skipped, no Vulkan implementation is available");
+        return None;
+    }
+    let installed = match layers_list() {
+        Ok(mut layers) => layers.any(|layer| layer.name() == LAYER),
+        Err(_) => false,
+    };
+    if !installed {
+        eprintln!("skipped, the validation layer is not installed");
+        return None;
+    }
+    let instance = hammer::Instance::new(InstanceCreateInfo {
+        enabled_extensions: InstanceExtensions {
+            ext_debug_utils: true,
+            ..InstanceExtensions::none()
+        },
+        enabled_layers: vec![LAYER.to_owned()],
+        ..Default::default()
+    });
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let callback = {
+        let errors = errors.clone();
+        DebugCallback::new(
+            &(&instance).into(),
+            MessageSeverity::errors(),
+            MessageType::all(),
+            move |message| errors.lock().unwrap().push(message.description.to_owned()),
+        )
+        .unwrap()
+    };
+    let device = request_device(&instance, vulkano::device::Features::none())?;
+    Some((
+        device,
+        Validation {
+            errors,
+            _callback: callback,
+        },
+    ))
+}
+
+fn request_device(
+    instance: &hammer::Instance,
+    features: vulkano::device::Features,
+) -> Option<hammer::Device> {
+    let desc = hammer::AdapterDescriptor::<()> {
+        device_extensions: vulkano::device::DeviceExtensions::none(),
+        optional_extensions: vulkano::device::DeviceExtensions::none(),
+        required_features: features,
+        supports_compute: true,
+        ..hammer::AdapterDescriptor::graphics()
+    };
+    let adapter = match instance.request_adapter(&desc) {
+        Ok(adapter) => adapter,
+        Err(err) => {
+            eprintln!("skipped, {}", err);
+            return None;
+        }
+    };
+    // The device enables the features the adapter was required to have.
+    Some(
+        adapter
+            .request_device(vulkano::device::Features::none())
+            .unwrap(),
+    )
+}
+
+/// Extent of the textures the rendering tests draw into.
+pub const EXTENT: [u32; 2] = [64, 64];
+
+/// A texture the rendering tests draw into, in RGBA order so it can be compared byte wise.
+pub fn texture(device: &hammer::Device) -> hammer::OffscreenTexture {
+    hammer::OffscreenTexture::new_with(
+        (*device).clone(),
+        EXTENT,
+        &hammer::OffscreenTextureDescriptor {
+            image_usage: vulkano::image::ImageUsage {
+                transfer_source: true,
+                ..vulkano::image::ImageUsage::none()
+            },
+            ..hammer::OffscreenTextureDescriptor::new(vulkano::format::Format::R8G8B8A8_UNORM)
+        },
+    )
+    .unwrap()
+}
+
+/// Submits `encoder` and reads `texture` back once it finished.
+pub fn submit_and_read(
+    device: &hammer::Device,
+    queue: &hammer::Queue,
+    encoder: hammer::CommandEncoder,
+    texture: &hammer::OffscreenTexture,
+) -> Vec<u8> {
+    let future = queue.submit(encoder.finish().unwrap()).unwrap();
+    device
+        .read_image_after(queue, texture.image().clone(), future.into_future())
+        .unwrap()
+}
+
+/// The RGBA texel at `[x, y]` of an image read back with `submit_and_read`.
+pub fn texel(data: &[u8], [x, y]: [u32; 2]) -> [u8; 4] {
+    let offset = ((y * EXTENT[0] + x) * 4) as usize;
+    data[offset..offset + 4].try_into().unwrap()
+}
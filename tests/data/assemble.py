@@ -0,0 +1,141 @@
+#!/usr/bin/env python3
+"""Assembles the SPIR-V modules in this directory, run it from here after changing them.
+
+The modules are small enough to write by hand, which keeps the tests free of a shader compiler.
+"""
+import struct
+
+MAGIC = 0x07230203
+VERSION_1_0 = 0x00010000
+
+
+def string(text):
+    data = text.encode() + b"\0"
+    data += b"\0" * (-len(data) % 4)
+    return list(struct.unpack("<%dI" % (len(data) // 4), data))
+
+
+def op(opcode, *operands):
+    words = []
+    for operand in operands:
+        words += string(operand) if isinstance(operand, str) else [operand]
+    return [(len(words) + 1) << 16 | opcode] + words
+
+
+def module(bound, *instructions):
+    words = [MAGIC, VERSION_1_0, 0, bound, 0]
+    for instruction in instructions:
+        words += instruction
+    return struct.pack("<%dI" % len(words), *words)
+
+
+# Opcodes
+OP_NAME = 5
+OP_MEMBER_NAME = 6
+OP_MEMORY_MODEL = 14
+OP_ENTRY_POINT = 15
+OP_EXECUTION_MODE = 16
+OP_CAPABILITY = 17
+OP_TYPE_VOID = 19
+OP_TYPE_FLOAT = 22
+OP_TYPE_VECTOR = 23
+OP_TYPE_STRUCT = 30
+OP_TYPE_POINTER = 32
+OP_TYPE_FUNCTION = 33
+OP_FUNCTION = 54
+OP_FUNCTION_END = 56
+OP_VARIABLE = 59
+OP_DECORATE = 71
+OP_MEMBER_DECORATE = 72
+OP_LABEL = 248
+OP_RETURN = 253
+
+# Operands
+CAPABILITY_SHADER = 1
+ADDRESSING_LOGICAL = 0
+MEMORY_GLSL450 = 1
+MODEL_VERTEX = 0
+MODEL_FRAGMENT = 4
+MODEL_GL_COMPUTE = 5
+MODE_ORIGIN_UPPER_LEFT = 7
+MODE_LOCAL_SIZE = 17
+FUNCTION_CONTROL_NONE = 0
+STORAGE_UNIFORM = 2
+DECORATION_BLOCK = 2
+DECORATION_BINDING = 33
+DECORATION_DESCRIPTOR_SET = 34
+DECORATION_OFFSET = 35
+
+
+def empty_function(result, void, function_type, label):
+    return [
+        op(OP_FUNCTION, void, result, FUNCTION_CONTROL_NONE, function_type),
+        op(OP_LABEL, label),
+        op(OP_RETURN),
+        op(OP_FUNCTION_END),
+    ]
+
+
+# A compute shader `main` with an empty body.
+VOID, FUNCTION_TYPE, MAIN, LABEL = 1, 2, 3, 4
+COMPUTE = module(
+    5,
+    op(OP_CAPABILITY, CAPABILITY_SHADER),
+    op(OP_MEMORY_MODEL, ADDRESSING_LOGICAL, MEMORY_GLSL450),
+    op(OP_ENTRY_POINT, MODEL_GL_COMPUTE, MAIN, "main"),
+    op(OP_EXECUTION_MODE, MAIN, MODE_LOCAL_SIZE, 1, 1, 1),
+    op(OP_TYPE_VOID, VOID),
+    op(OP_TYPE_FUNCTION, FUNCTION_TYPE, VOID),
+    *empty_function(MAIN, VOID, FUNCTION_TYPE, LABEL),
+)
+
+# The compute shader with the std140 block `uniform Params { float scale; vec3 color; }` at set 0,
+# binding 1. `color` is aligned to 16 bytes, so it starts at 16 instead of right after `scale`.
+FLOAT, VEC3, PARAMS, PARAMS_POINTER, PARAMS_VARIABLE = 6, 7, 8, 9, 10
+UNIFORM = module(
+    11,
+    op(OP_CAPABILITY, CAPABILITY_SHADER),
+    op(OP_MEMORY_MODEL, ADDRESSING_LOGICAL, MEMORY_GLSL450),
+    op(OP_ENTRY_POINT, MODEL_GL_COMPUTE, MAIN, "main"),
+    op(OP_EXECUTION_MODE, MAIN, MODE_LOCAL_SIZE, 1, 1, 1),
+    op(OP_NAME, PARAMS, "Params"),
+    op(OP_MEMBER_NAME, PARAMS, 0, "scale"),
+    op(OP_MEMBER_NAME, PARAMS, 1, "color"),
+    op(OP_DECORATE, PARAMS, DECORATION_BLOCK),
+    op(OP_MEMBER_DECORATE, PARAMS, 0, DECORATION_OFFSET, 0),
+    op(OP_MEMBER_DECORATE, PARAMS, 1, DECORATION_OFFSET, 16),
+    op(OP_DECORATE, PARAMS_VARIABLE, DECORATION_DESCRIPTOR_SET, 0),
+    op(OP_DECORATE, PARAMS_VARIABLE, DECORATION_BINDING, 1),
+    op(OP_TYPE_VOID, VOID),
+    op(OP_TYPE_FUNCTION, FUNCTION_TYPE, VOID),
+    op(OP_TYPE_FLOAT, FLOAT, 32),
+    op(OP_TYPE_VECTOR, VEC3, FLOAT, 3),
+    op(OP_TYPE_STRUCT, PARAMS, FLOAT, VEC3),
+    op(OP_TYPE_POINTER, PARAMS_POINTER, STORAGE_UNIFORM, PARAMS),
+    op(OP_VARIABLE, PARAMS_POINTER, PARAMS_VARIABLE, STORAGE_UNIFORM),
+    *empty_function(MAIN, VOID, FUNCTION_TYPE, LABEL),
+)
+
+# A vertex shader `vs_main` and a fragment shader `fs_main` in one module, both with an empty body.
+VS_MAIN, VS_LABEL, FS_MAIN, FS_LABEL = 3, 4, 5, 6
+MULTI_ENTRY = module(
+    7,
+    op(OP_CAPABILITY, CAPABILITY_SHADER),
+    op(OP_MEMORY_MODEL, ADDRESSING_LOGICAL, MEMORY_GLSL450),
+    op(OP_ENTRY_POINT, MODEL_VERTEX, VS_MAIN, "vs_main"),
+    op(OP_ENTRY_POINT, MODEL_FRAGMENT, FS_MAIN, "fs_main"),
+    op(OP_EXECUTION_MODE, FS_MAIN, MODE_ORIGIN_UPPER_LEFT),
+    op(OP_TYPE_VOID, VOID),
+    op(OP_TYPE_FUNCTION, FUNCTION_TYPE, VOID),
+    *empty_function(VS_MAIN, VOID, FUNCTION_TYPE, VS_LABEL),
+    *empty_function(FS_MAIN, VOID, FUNCTION_TYPE, FS_LABEL),
+)
+
+if __name__ == "__main__":
+    for name, spirv in [
+        ("compute.spv", COMPUTE),
+        ("uniform.spv", UNIFORM),
+        ("multi_entry.spv", MULTI_ENTRY),
+    ]:
+        with open(name, "wb") as file:
+            file.write(spirv)
@@ -0,0 +1,189 @@
+use bytemuck::{Pod, Zeroable};
+use test03::hammer::{self, DrawTarget};
+use vulkano::render_pass::Subpass;
+
+mod common;
+
+mod vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        src: "
+            #version 450
+            layout(push_constant) uniform Item {
+                vec4 color;
+                vec2 offset;
+            };
+            const vec2 positions[3] = vec2[](vec2(-0.1, -0.1), vec2(0.1, -0.1), vec2(0.0, 0.1));
+            void main() {
+                gl_Position = vec4(positions[gl_VertexIndex] + offset, 0.0, 1.0);
+            }
+        "
+    }
+}
+
+mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        src: "
+            #version 450
+            layout(push_constant) uniform Item {
+                vec4 color;
+                vec2 offset;
+            };
+            layout(location = 0) out vec4 f_color;
+            void main() {
+                f_color = color;
+            }
+        "
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct Item {
+    color: [f32; 4],
+    offset: [f32; 2],
+}
+
+#[test]
+fn batched_matches_unbatched() {
+    let device = match common::device() {
+        Some(device) => device,
+        None => return,
+    };
+    let queue = device.graphics_queue().unwrap().clone();
+    let texture = common::texture(&device);
+    let render_pass = texture.create_render_pass().unwrap();
+    let subpass = Subpass::from(render_pass.clone(), 0).unwrap();
+    let (vs, fs) = (
+        vs::load(device.clone()).unwrap(),
+        fs::load(device.clone()).unwrap(),
+    );
+    let desc = hammer::PipelineDescriptor::new(
+        vs.entry_point("main").unwrap(),
+        fs.entry_point("main").unwrap(),
+    );
+    // Two pipelines with the same state, so only their binds tell them apart.
+    let pipelines = [
+        device
+            .create_graphics_pipeline(&desc, subpass.clone())
+            .unwrap(),
+        device.create_graphics_pipeline(&desc, subpass).unwrap(),
+    ];
+
+    // A grid of triangles in scene order, alternating between the pipelines.
+    let items: Vec<_> = (0..16)
+        .map(|i| {
+            let item = Item {
+                color: [i as f32 / 16.0, 1.0 - i as f32 / 16.0, 0.5, 1.0],
+                offset: [-0.75 + (i % 4) as f32 * 0.5, -0.75 + (i / 4) as f32 * 0.5],
+            };
+            (&pipelines[i % 2], item)
+        })
+        .collect();
+
+    let mut encoder = device.create_command_encoder(&queue).unwrap();
+    {
+        let mut pass = encoder
+            .begin_render_pass_with(
+                texture.framebuffer(&render_pass).unwrap(),
+                texture.clear_values([0.0, 0.0, 0.0, 1.0]),
+            )
+            .unwrap();
+        pass.set_viewport(texture.viewport());
+        for (pipeline, item) in &items {
+            pass.bind_pipeline(pipeline)
+                .push_constants(pipeline, item)
+                .unwrap()
+                .draw(3, 1, 0, 0)
+                .unwrap();
+        }
+    }
+    let unbatched = common::submit_and_read(&device, &queue, encoder, &texture);
+
+    let mut list = hammer::DrawList::new();
+    for (i, (pipeline, item)) in items.iter().enumerate() {
+        list.push_opaque(hammer::DrawItem {
+            pipeline: (*pipeline).clone(),
+            descriptor_sets: Vec::new(),
+            vertex_buffers: Vec::new(),
+            index_buffer: None,
+            count: 3,
+            instance_count: 1,
+            push_constants: Some(*item),
+            sort_key: i as u64,
+        });
+    }
+    let mut encoder = device.create_command_encoder(&queue).unwrap();
+    let stats = {
+        let mut pass = encoder
+            .begin_render_pass_with(
+                texture.framebuffer(&render_pass).unwrap(),
+                texture.clear_values([0.0, 0.0, 0.0, 1.0]),
+            )
+            .unwrap();
+        pass.set_viewport(texture.viewport());
+        pass.draw_list(&mut list).unwrap()
+    };
+    let batched = common::submit_and_read(&device, &queue, encoder, &texture);
+
+    assert_eq!(stats.draws, 16);
+    assert_eq!(stats.pipeline_binds, 2);
+    assert_eq!(stats.pipeline_binds_saved, 14);
+    assert!(list.is_empty());
+    // The triangles do not overlap, so the order they are drawn in does not change the image.
+    assert_ne!(common::texel(&batched, [8, 8]), [0, 0, 0, 255]);
+    assert!(batched == unbatched, "the batched scene differs");
+}
+
+#[test]
+fn push_constants_of_the_wrong_size() {
+    let device = match common::device() {
+        Some(device) => device,
+        None => return,
+    };
+    let queue = device.graphics_queue().unwrap().clone();
+    let texture = common::texture(&device);
+    let render_pass = texture.create_render_pass().unwrap();
+    let subpass = Subpass::from(render_pass.clone(), 0).unwrap();
+    let (vs, fs) = (
+        vs::load(device.clone()).unwrap(),
+        fs::load(device.clone()).unwrap(),
+    );
+    let desc = hammer::PipelineDescriptor::new(
+        vs.entry_point("main").unwrap(),
+        fs.entry_point("main").unwrap(),
+    );
+    let pipeline = device.create_graphics_pipeline(&desc, subpass).unwrap();
+
+    // Only the color of `Item`, without its offset.
+    let mut list = hammer::DrawList::new();
+    list.push_opaque(hammer::DrawItem {
+        pipeline,
+        descriptor_sets: Vec::new(),
+        vertex_buffers: Vec::new(),
+        index_buffer: None,
+        count: 3,
+        instance_count: 1,
+        push_constants: Some([1.0f32; 4]),
+        sort_key: 0,
+    });
+    let mut encoder = device.create_command_encoder(&queue).unwrap();
+    let mut pass = encoder
+        .begin_render_pass_with(
+            texture.framebuffer(&render_pass).unwrap(),
+            texture.clear_values([0.0; 4]),
+        )
+        .unwrap();
+    pass.set_viewport(texture.viewport());
+    let result = pass.draw_list(&mut list);
+    if cfg!(debug_assertions) {
+        match result {
+            Err(hammer::DrawListError::PushConstants(
+                hammer::PushConstantsError::SizeMismatch { size, expected },
+            )) => assert_eq!((size, expected), (16, 24)),
+            Err(err) => panic!("unexpected error: {}", err),
+            Ok(_) => panic!("pushed 16 bytes to a range of 24"),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::time::{Duration, Instant};
+use test03::hammer::{self, RenderTarget};
+
+mod common;
+
+/// Clears the image of one frame and presents it through `frame_sync`.
+fn render_frame(
+    device: &hammer::Device,
+    queue: &hammer::Queue,
+    frame_sync: &mut hammer::FrameSync,
+    target: &mut hammer::OffscreenTarget,
+    render_pass: &std::sync::Arc<vulkano::render_pass::RenderPass>,
+) {
+    let frame = frame_sync.begin_frame(target).unwrap();
+    let framebuffer = target.framebuffer(&frame.image, render_pass).unwrap();
+    let mut encoder = device.create_command_encoder(queue).unwrap();
+    encoder
+        .begin_render_pass_with(framebuffer, target.clear_values([0.0; 4]).unwrap())
+        .unwrap();
+    frame_sync
+        .end_frame(target, frame, encoder.finish().unwrap(), queue)
+        .unwrap();
+}
+
+fn offscreen_target(device: &hammer::Device) -> hammer::OffscreenTarget {
+    hammer::OffscreenTarget::new(
+        (*device).clone(),
+        common::EXTENT,
+        &hammer::OffscreenDescriptor::default(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn frame_limiter_spaces_presents() {
+    let device = match common::device() {
+        Some(device) => device,
+        None => return,
+    };
+    let queue = device.graphics_queue().unwrap().clone();
+    let mut target = offscreen_target(&device);
+    let render_pass = RenderTarget::create_render_pass(&target).unwrap();
+    let mut frame_sync = hammer::FrameSync::new(&device, 2);
+    frame_sync.enable_stats(&device);
+
+    let fps = 250.0;
+    frame_sync.set_target_fps(Some(fps));
+    for _ in 0..300 {
+        render_frame(&device, &queue, &mut frame_sync, &mut target, &render_pass);
+    }
+    let interval = Duration::from_secs_f32(1.0 / fps);
+    let present = frame_sync.stats().unwrap().average().present;
+    assert!(
+        present >= interval.mul_f32(0.95) && present < interval * 2,
+        "presents are {:?} apart instead of {:?}",
+        present,
+        interval
+    );
+    frame_sync.shutdown(&device).unwrap();
+}
+
+#[test]
+fn frame_limiter_disables_immediately() {
+    let device = match common::device() {
+        Some(device) => device,
+        None => return,
+    };
+    let queue = device.graphics_queue().unwrap().clone();
+    let mut target = offscreen_target(&device);
+    let render_pass = RenderTarget::create_render_pass(&target).unwrap();
+    let mut frame_sync = hammer::FrameSync::new(&device, 2);
+
+    frame_sync.set_target_fps(Some(5.0));
+    for _ in 0..2 {
+        render_frame(&device, &queue, &mut frame_sync, &mut target, &render_pass);
+    }
+    // The next present would be due 200 ms after the last one.
+    frame_sync.set_target_fps(None);
+    let start = Instant::now();
+    for _ in 0..3 {
+        render_frame(&device, &queue, &mut frame_sync, &mut target, &render_pass);
+    }
+    assert!(start.elapsed() < Duration::from_millis(200));
+    frame_sync.shutdown(&device).unwrap();
+}
+
+#[test]
+fn shutdown_with_frames_in_flight() {
+    let (device, validation) = match common::validated_device() {
+        Some(device) => device,
+        None => return,
+    };
+    let queue = device.graphics_queue().unwrap().clone();
+    let mut target = offscreen_target(&device);
+    let render_pass = RenderTarget::create_render_pass(&target).unwrap();
+    let mut frame_sync = hammer::FrameSync::new(&device, 2);
+
+    render_frame(&device, &queue, &mut frame_sync, &mut target, &render_pass);
+    // The window closes while the next frame is being recorded.
+    let frame = frame_sync.begin_frame(&mut target).unwrap();
+    frame_sync.shutdown(&device).unwrap();
+    drop(frame);
+    drop(target);
+    drop(frame_sync);
+    drop(render_pass);
+    drop(queue);
+    drop(device);
+    assert_eq!(validation.errors(), Vec::<String>::new());
+}
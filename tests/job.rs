@@ -0,0 +1,81 @@
+use std::time::{Duration, Instant};
+use test03::hammer;
+use vulkano::descriptor_set::{PersistentDescriptorSet, WriteDescriptorSet};
+use vulkano::pipeline::Pipeline;
+
+mod common;
+
+mod cs {
+    vulkano_shaders::shader! {
+        ty: "compute",
+        src: "
+            #version 450
+            layout(local_size_x = 64) in;
+            layout(push_constant) uniform Chunk {
+                uint offset;
+            };
+            layout(set = 0, binding = 0) buffer Data {
+                uint data[];
+            };
+            void main() {
+                uint index = offset + gl_GlobalInvocationID.x;
+                if (index < data.length()) {
+                    data[index] = index * 2;
+                }
+            }
+        "
+    }
+}
+
+#[test]
+fn cancel_halfway() {
+    const COUNT: u32 = 64 * 1024;
+    const CHUNK: u32 = 64 * 64;
+
+    let device = match common::device() {
+        Some(device) => device,
+        None => return,
+    };
+    let queue = device.compute_queue().unwrap().clone();
+    let cs = cs::load(device.clone()).unwrap();
+    let pipeline = device
+        .create_compute_pipeline(&cs, "main", &hammer::SpecializationMap::new())
+        .unwrap();
+    let buffer = hammer::Buffer::<u32>::storage(device.clone(), &[0; COUNT as usize]).unwrap();
+    let set = PersistentDescriptorSet::new(
+        pipeline.layout().set_layouts()[0].clone(),
+        [WriteDescriptorSet::buffer(0, buffer.buffer().clone())],
+    )
+    .unwrap();
+
+    let token = hammer::CancelToken::new();
+    let cancel = token.clone();
+    let job = hammer::CancellableJob::new(queue.handle().clone(), COUNT, CHUNK, |range| {
+        // Cancelled while recording the chunk after the first half, which is still submitted.
+        if range.start >= COUNT / 2 {
+            cancel.cancel();
+        }
+        let mut encoder = device.create_command_encoder(&queue).unwrap();
+        encoder
+            .dispatch(&pipeline, set.clone(), &range.start, [CHUNK / 64, 1, 1])
+            .unwrap();
+        encoder.finish().unwrap()
+    })
+    .with_max_in_flight(1)
+    .with_cancel_token(token);
+    let progress = job.progress();
+
+    let start = Instant::now();
+    assert_eq!(job.wait().unwrap(), hammer::JobStatus::Cancelled);
+    assert!(start.elapsed() < Duration::from_secs(10));
+
+    // The first half and the chunk in flight when cancelling ran, nothing after them.
+    let done = COUNT / 2 + CHUNK;
+    assert_eq!(progress.completed(), (done / CHUNK) as usize);
+    assert_eq!(progress.total(), (COUNT / CHUNK) as usize);
+    let data = buffer.read(0..COUNT as usize).unwrap();
+    for i in 0..COUNT {
+        let expected = if i < done { i * 2 } else { 0 };
+        assert_eq!(data[i as usize], expected, "element {}", i);
+    }
+}
@@ -0,0 +1,83 @@
+// The misuse reports only exist in debug builds, and panic instead of logging with `strict`.
+#![cfg(all(debug_assertions, not(feature = "strict")))]
+
+use std::sync::Mutex;
+use std::thread::{self, ThreadId};
+use test03::hammer;
+
+mod common;
+
+/// Collects the errors logged by each thread, since the tests run in parallel.
+struct Reports(Mutex<Vec<(ThreadId, String)>>);
+
+impl log::Log for Reports {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::Level::Error
+    }
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            let report = (thread::current().id(), record.args().to_string());
+            self.0.lock().unwrap().push(report);
+        }
+    }
+    fn flush(&self) {}
+}
+
+static REPORTS: Reports = Reports(Mutex::new(Vec::new()));
+
+/// The errors logged by the current thread while running `f`.
+fn collect_reports(f: impl FnOnce()) -> Vec<String> {
+    // Only the first test sets the logger.
+    let _ = log::set_logger(&REPORTS);
+    log::set_max_level(log::LevelFilter::Error);
+    f();
+    let id = thread::current().id();
+    let mut reports = REPORTS.0.lock().unwrap();
+    let (own, others) = reports.drain(..).partition(|(thread, _)| *thread == id);
+    *reports = others;
+    own.into_iter().map(|(_, report)| report).collect()
+}
+
+#[test]
+fn dropped_guard_reports_creation_site() {
+    let line = line!() + 1;
+    let reports = collect_reports(|| drop(hammer::MisuseGuard::new("Thing", "consume it")));
+    assert_eq!(reports.len(), 1);
+    assert!(reports[0].starts_with("Thing created at tests/misuse.rs:"));
+    assert!(reports[0].contains(&format!(":{}:", line)));
+    assert!(reports[0].ends_with("was dropped without being consumed; consume it"));
+}
+
+#[test]
+fn consumed_guard_is_silent() {
+    let reports = collect_reports(|| {
+        let mut guard = hammer::MisuseGuard::new("Thing", "consume it");
+        guard.consume();
+    });
+    assert!(reports.is_empty());
+}
+
+#[test]
+fn dropped_command_encoder_reported() {
+    let device = match common::device() {
+        Some(device) => device,
+        None => return,
+    };
+    let queue = device.graphics_queue().unwrap().clone();
+    let line = line!() + 1;
+    let reports = collect_reports(|| drop(device.create_command_encoder(&queue).unwrap()));
+    assert_eq!(reports.len(), 1);
+    assert!(reports[0].starts_with("CommandEncoder created at tests/misuse.rs:"));
+    assert!(reports[0].contains(&format!(":{}:", line)));
+
+    // Finishing or abandoning the encoder consumes it.
+    let reports = collect_reports(|| {
+        device
+            .create_command_encoder(&queue)
+            .unwrap()
+            .finish()
+            .unwrap();
+        device.create_command_encoder(&queue).unwrap().abandon();
+    });
+    assert!(reports.is_empty());
+}
@@ -0,0 +1,160 @@
+use std::sync::Arc;
+use test03::hammer::{self, RenderTarget, TargetImage};
+use vulkano::pipeline::GraphicsPipeline;
+use vulkano::render_pass::{RenderPass, Subpass};
+use vulkano::sync::GpuFuture;
+
+mod common;
+
+mod vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        src: "
+            #version 450
+            const vec2 positions[3] = vec2[](vec2(-0.5, 0.5), vec2(0.5, 0.5), vec2(0.0, -0.5));
+            void main() {
+                gl_Position = vec4(positions[gl_VertexIndex], 0.0, 1.0);
+            }
+        "
+    }
+}
+
+mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        src: "
+            #version 450
+            layout(location = 0) out vec4 f_color;
+            void main() {
+                f_color = vec4(1.0, 0.0, 0.0, 1.0);
+            }
+        "
+    }
+}
+
+/// The triangle of the vertex shader in normalized device coordinates, y pointing down.
+const TRIANGLE: [[f32; 2]; 3] = [[-0.5, 0.5], [0.5, 0.5], [0.0, -0.5]];
+const CLEAR: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+
+/// Renders a frame the way a windowed render loop would, only through `RenderTarget`.
+fn render_frame<T: RenderTarget>(
+    device: &hammer::Device,
+    queue: &hammer::Queue,
+    target: &mut T,
+    render_pass: &Arc<RenderPass>,
+    pipeline: &Arc<GraphicsPipeline>,
+) {
+    let mut image = target.acquire().unwrap();
+    let framebuffer = target.framebuffer(&image, render_pass).unwrap();
+    let mut encoder = device.create_command_encoder(queue).unwrap();
+    {
+        let mut pass = encoder
+            .begin_render_pass_with(framebuffer, target.clear_values(CLEAR).unwrap())
+            .unwrap();
+        pass.set_viewport(image.viewport())
+            .bind_pipeline(pipeline)
+            .draw(3, 1, 0, 0)
+            .unwrap();
+    }
+    let after = image
+        .take_future()
+        .unwrap()
+        .then_execute(queue.handle().clone(), encoder.finish().unwrap())
+        .unwrap()
+        .boxed();
+    target
+        .present(queue, image, after)
+        .unwrap()
+        .wait(None)
+        .unwrap();
+}
+
+/// The expected color of the texel at `[x, y]`, `None` if its center is too close to an edge of
+/// the triangle for the rasterization rules to decide it reliably.
+fn golden_texel([x, y]: [u32; 2]) -> Option<[u8; 4]> {
+    let [width, height] = common::EXTENT;
+    let point = [
+        (x as f32 + 0.5) / width as f32 * 2.0 - 1.0,
+        (y as f32 + 0.5) / height as f32 * 2.0 - 1.0,
+    ];
+    // One texel in normalized device coordinates.
+    let margin = 2.0 / width.min(height) as f32;
+    let mut inside = true;
+    for i in 0..3 {
+        let [a, b] = [TRIANGLE[i], TRIANGLE[(i + 1) % 3]];
+        let edge = [b[0] - a[0], b[1] - a[1]];
+        let length = (edge[0] * edge[0] + edge[1] * edge[1]).sqrt();
+        let distance = (edge[0] * (point[1] - a[1]) - edge[1] * (point[0] - a[0])) / length;
+        if distance.abs() < margin {
+            return None;
+        }
+        inside &= distance < 0.0;
+    }
+    Some(if inside {
+        [255, 0, 0, 255]
+    } else {
+        [0, 0, 255, 255]
+    })
+}
+
+#[test]
+fn offscreen_target_matches_golden_image() {
+    let device = match common::device() {
+        Some(device) => device,
+        None => return,
+    };
+    let queue = device.graphics_queue().unwrap().clone();
+    let mut target = hammer::OffscreenTarget::new(
+        (*device).clone(),
+        common::EXTENT,
+        &hammer::OffscreenDescriptor {
+            format: vulkano::format::Format::R8G8B8A8_UNORM,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let render_pass = RenderTarget::create_render_pass(&target).unwrap();
+    let (vs, fs) = (
+        vs::load(device.clone()).unwrap(),
+        fs::load(device.clone()).unwrap(),
+    );
+    let pipeline = device
+        .create_graphics_pipeline(
+            &hammer::PipelineDescriptor::new(
+                vs.entry_point("main").unwrap(),
+                fs.entry_point("main").unwrap(),
+            ),
+            Subpass::from(render_pass.clone(), 0).unwrap(),
+        )
+        .unwrap();
+
+    // Two frames, so both images of the target are rendered into.
+    let mut frames = Vec::new();
+    for _ in 0..2 {
+        render_frame(&device, &queue, &mut target, &render_pass, &pipeline);
+        let image = target.presented_image().unwrap().clone();
+        frames.push(device.read_image(&queue, image).unwrap());
+    }
+    assert!(!Arc::ptr_eq(&target.images()[0], &target.images()[1]));
+
+    let mut checked = 0;
+    for y in 0..common::EXTENT[1] {
+        for x in 0..common::EXTENT[0] {
+            let expected = match golden_texel([x, y]) {
+                Some(expected) => expected,
+                None => continue,
+            };
+            for frame in &frames {
+                assert_eq!(
+                    common::texel(frame, [x, y]),
+                    expected,
+                    "texel {:?} differs",
+                    [x, y]
+                );
+            }
+            checked += 1;
+        }
+    }
+    // Only the texels along the edges are skipped.
+    assert!(checked > 3500, "only {} texels were checked", checked);
+}
@@ -0,0 +1,574 @@
+use std::sync::Arc;
+use test03::hammer::{self, DrawTarget};
+use vulkano::descriptor_set::layout::DescriptorType;
+use vulkano::pipeline::{DynamicState, GraphicsPipeline};
+use vulkano::render_pass::{RenderPass, Subpass};
+use vulkano::shader::ShaderModule;
+
+mod common;
+
+mod vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        src: "
+            #version 450
+            // A triangle covering the whole viewport.
+            const vec2 positions[3] = vec2[](vec2(-1.0, -1.0), vec2(3.0, -1.0), vec2(-1.0, 3.0));
+            void main() {
+                gl_Position = vec4(positions[gl_VertexIndex], 0.0, 1.0);
+            }
+        "
+    }
+}
+
+mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        src: "
+            #version 450
+            layout(push_constant) uniform Color {
+                vec4 color;
+            };
+            layout(location = 0) out vec4 f_color;
+            void main() {
+                f_color = color;
+            }
+        "
+    }
+}
+
+mod specialized_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        src: "
+            #version 450
+            layout(constant_id = 0) const float RED = 1.0;
+            layout(constant_id = 1) const bool GREEN = false;
+            layout(location = 0) out vec4 f_color;
+            void main() {
+                f_color = vec4(RED, GREEN ? 1.0 : 0.0, 0.0, 1.0);
+            }
+        "
+    }
+}
+
+// The quad of `examples/tessellation.rs` as a single patch, without the bump.
+mod quad_vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        src: "
+            #version 450
+            const vec2 corners[4] = vec2[](vec2(-0.5, 0.5), vec2(0.5, 0.5), vec2(0.5, -0.5), vec2(-0.5, -0.5));
+            void main() {
+                gl_Position = vec4(corners[gl_VertexIndex], 0.0, 1.0);
+            }
+        "
+    }
+}
+
+mod quad_tcs {
+    vulkano_shaders::shader! {
+        ty: "tess_ctrl",
+        src: "
+            #version 450
+            layout(vertices = 4) out;
+            void main() {
+                gl_out[gl_InvocationID].gl_Position = gl_in[gl_InvocationID].gl_Position;
+                if (gl_InvocationID == 0) {
+                    gl_TessLevelOuter[0] = 4.0;
+                    gl_TessLevelOuter[1] = 4.0;
+                    gl_TessLevelOuter[2] = 4.0;
+                    gl_TessLevelOuter[3] = 4.0;
+                    gl_TessLevelInner[0] = 4.0;
+                    gl_TessLevelInner[1] = 4.0;
+                }
+            }
+        "
+    }
+}
+
+mod quad_tes {
+    vulkano_shaders::shader! {
+        ty: "tess_eval",
+        src: "
+            #version 450
+            layout(quads, equal_spacing, ccw) in;
+            void main() {
+                vec4 bottom = mix(gl_in[0].gl_Position, gl_in[1].gl_Position, gl_TessCoord.x);
+                vec4 top = mix(gl_in[3].gl_Position, gl_in[2].gl_Position, gl_TessCoord.x);
+                gl_Position = mix(bottom, top, gl_TessCoord.y);
+            }
+        "
+    }
+}
+
+// Passes the triangles through, colored by the geometry shader.
+mod gs {
+    vulkano_shaders::shader! {
+        ty: "geometry",
+        src: "
+            #version 450
+            layout(triangles) in;
+            layout(triangle_strip, max_vertices = 3) out;
+            layout(location = 0) out vec4 color;
+            void main() {
+                for (int i = 0; i < 3; i++) {
+                    gl_Position = gl_in[i].gl_Position;
+                    color = vec4(1.0, 1.0, 0.0, 1.0);
+                    EmitVertex();
+                }
+                EndPrimitive();
+            }
+        "
+    }
+}
+
+mod color_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        src: "
+            #version 450
+            layout(location = 0) in vec4 color;
+            layout(location = 0) out vec4 f_color;
+            void main() {
+                f_color = color;
+            }
+        "
+    }
+}
+
+mod uniform_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        src: "
+            #version 450
+            layout(set = 0, binding = 0) uniform Color {
+                vec4 color;
+            };
+            layout(location = 0) out vec4 f_color;
+            void main() {
+                f_color = color;
+            }
+        "
+    }
+}
+
+/// Renders full screen draws into a `common::texture`.
+struct Scene {
+    device: hammer::Device,
+    queue: hammer::Queue,
+    texture: hammer::OffscreenTexture,
+    render_pass: Arc<RenderPass>,
+    vs: Arc<ShaderModule>,
+    fs: Arc<ShaderModule>,
+}
+
+impl Scene {
+    /// `None` where no Vulkan implementation is available, which skips the test.
+    fn new() -> Option<Self> {
+        Some(Self::with_device(common::device()?))
+    }
+    fn with_device(device: hammer::Device) -> Self {
+        let queue = device.graphics_queue().unwrap().clone();
+        let texture = common::texture(&device);
+        let render_pass = texture.create_render_pass().unwrap();
+        let (vs, fs) = (
+            vs::load(device.clone()).unwrap(),
+            fs::load(device.clone()).unwrap(),
+        );
+        Self {
+            device,
+            queue,
+            texture,
+            render_pass,
+            vs,
+            fs,
+        }
+    }
+    /// A descriptor drawing the color of the push constants.
+    fn desc(&self) -> hammer::PipelineDescriptor<'_> {
+        hammer::PipelineDescriptor::new(
+            self.vs.entry_point("main").unwrap(),
+            self.fs.entry_point("main").unwrap(),
+        )
+    }
+    fn subpass(&self) -> Subpass {
+        Subpass::from(self.render_pass.clone(), 0).unwrap()
+    }
+    fn pipeline(&self, desc: &hammer::PipelineDescriptor) -> Arc<GraphicsPipeline> {
+        self.device
+            .create_graphics_pipeline(desc, self.subpass())
+            .unwrap()
+    }
+    /// Clears the texture to transparent black, records `draws` and reads back the texel in the
+    /// center.
+    fn render(&self, draws: impl FnOnce(&mut hammer::RenderPassEncoder)) -> [u8; 4] {
+        let mut encoder = self.device.create_command_encoder(&self.queue).unwrap();
+        {
+            let mut pass = encoder
+                .begin_render_pass_with(
+                    self.texture.framebuffer(&self.render_pass).unwrap(),
+                    self.texture.clear_values([0.0; 4]),
+                )
+                .unwrap();
+            pass.set_viewport(self.texture.viewport());
+            draws(&mut pass);
+        }
+        let data = common::submit_and_read(&self.device, &self.queue, encoder, &self.texture);
+        common::texel(&data, common::EXTENT.map(|extent| extent / 2))
+    }
+    /// Draws the viewport in each of `colors` with `pipeline`.
+    fn render_colors(&self, pipeline: &Arc<GraphicsPipeline>, colors: &[[f32; 4]]) -> [u8; 4] {
+        self.render(|pass| {
+            pass.bind_pipeline(pipeline);
+            for color in colors {
+                pass.push_constants(pipeline, color)
+                    .unwrap()
+                    .draw(3, 1, 0, 0)
+                    .unwrap();
+            }
+        })
+    }
+}
+
+/// Asserts that the channels of `texel` differ from `expected` by at most one, the rounding of
+/// the intermediate results to 8 bits.
+fn assert_texel(texel: [u8; 4], expected: [u8; 4]) {
+    let close = (0..4).all(|i| (texel[i] as i32 - expected[i] as i32).abs() <= 1);
+    assert!(close, "expected {:?}, got {:?}", expected, texel);
+}
+
+#[test]
+fn blend_presets() {
+    let scene = match Scene::new() {
+        Some(scene) => scene,
+        None => return,
+    };
+    // Translucent red, then translucent blue over it, both read as premultiplied by
+    // `PremultipliedAlpha`.
+    let colors = [[1.0, 0.0, 0.0, 0.5], [0.0, 0.0, 1.0, 0.5]];
+    let cases = [
+        (hammer::BlendPreset::Opaque, [0, 0, 255, 128]),
+        // red: 1 * 0.5 * 0.5, blue: 1 * 0.5, alpha: 0.5 + 0.5 * 0.5
+        (hammer::BlendPreset::AlphaBlend, [64, 0, 128, 191]),
+        // red: 1 * 0.5, blue: 1
+        (hammer::BlendPreset::PremultipliedAlpha, [128, 0, 255, 191]),
+        // alpha: max(0.5, 0.5)
+        (hammer::BlendPreset::Additive, [255, 0, 255, 128]),
+    ];
+    for (blend, expected) in cases {
+        let pipeline = scene.pipeline(&hammer::PipelineDescriptor {
+            blend,
+            ..scene.desc()
+        });
+        let texel = scene.render_colors(&pipeline, &colors);
+        assert_texel(texel, expected);
+    }
+
+    // The override of the attachment replaces `blend`.
+    let pipeline = scene.pipeline(&hammer::PipelineDescriptor {
+        blend: hammer::BlendPreset::Opaque,
+        attachment_blend: vec![Some(hammer::BlendPreset::Additive)],
+        ..scene.desc()
+    });
+    assert_texel(scene.render_colors(&pipeline, &colors), [255, 0, 255, 128]);
+}
+
+#[test]
+fn push_constants_color_the_draw() {
+    let scene = match Scene::new() {
+        Some(scene) => scene,
+        None => return,
+    };
+    let pipeline = scene.pipeline(&scene.desc());
+    let texel = scene.render_colors(&pipeline, &[[0.0, 1.0, 0.0, 1.0]]);
+    assert_texel(texel, [0, 255, 0, 255]);
+}
+
+#[test]
+fn push_constants_of_the_wrong_size() {
+    let scene = match Scene::new() {
+        Some(scene) => scene,
+        None => return,
+    };
+    let pipeline = scene.pipeline(&scene.desc());
+    scene.render(|pass| {
+        pass.bind_pipeline(&pipeline);
+        let result = pass.push_constants(&pipeline, &[1.0f32, 0.0, 0.0]);
+        // The size is only checked in debug builds.
+        if cfg!(debug_assertions) {
+            match result {
+                Err(hammer::PushConstantsError::SizeMismatch { size, expected }) => {
+                    assert_eq!((size, expected), (12, 16))
+                }
+                Err(err) => panic!("unexpected error: {}", err),
+                Ok(_) => panic!("the wrong size was pushed"),
+            }
+        }
+        assert!(matches!(
+            pass.push_constants_at(&pipeline, 4, &[0.0f32; 4]),
+            Err(hammer::PushConstantsError::NoRangeAt(4))
+        ));
+    });
+}
+
+#[test]
+fn specializations_render_differently() {
+    let scene = match Scene::new() {
+        Some(scene) => scene,
+        None => return,
+    };
+    let fs = specialized_fs::load(scene.device.clone()).unwrap();
+    let render = |specialization: hammer::SpecializationMap| {
+        let pipeline = scene.pipeline(&hammer::PipelineDescriptor {
+            fragment_shader: fs.entry_point("main").unwrap(),
+            fragment_specialization: specialization,
+            ..scene.desc()
+        });
+        scene.render(|pass| {
+            pass.bind_pipeline(&pipeline).draw(3, 1, 0, 0).unwrap();
+        })
+    };
+    let full = render(hammer::SpecializationMap::new().set(0, 1.0).set(1, false));
+    let half = render(hammer::SpecializationMap::new().set(0, 0.5).set(1, true));
+    assert_texel(full, [255, 0, 0, 255]);
+    assert_texel(half, [128, 255, 0, 255]);
+}
+
+#[test]
+fn tessellated_quad() {
+    let device = common::device_with_features(vulkano::device::Features {
+        tessellation_shader: true,
+        ..vulkano::device::Features::none()
+    });
+    let scene = match device {
+        Some(device) => Scene::with_device(device),
+        None => return,
+    };
+    let (vs, tcs, tes) = (
+        quad_vs::load(scene.device.clone()).unwrap(),
+        quad_tcs::load(scene.device.clone()).unwrap(),
+        quad_tes::load(scene.device.clone()).unwrap(),
+    );
+    let desc = hammer::PipelineDescriptor {
+        vertex_shader: vs.entry_point("main").unwrap(),
+        tessellation: Some(hammer::TessellationStages::new(
+            tcs.entry_point("main").unwrap(),
+            tes.entry_point("main").unwrap(),
+            4,
+        )),
+        ..scene.desc()
+    };
+    let pipeline = scene.pipeline(&desc);
+    // The four corners are a single patch.
+    let texel = scene.render(|pass| {
+        pass.bind_pipeline(&pipeline)
+            .push_constants(&pipeline, &[0.0f32, 1.0, 0.0, 1.0])
+            .unwrap()
+            .draw(4, 1, 0, 0)
+            .unwrap();
+    });
+    assert_texel(texel, [0, 255, 0, 255]);
+
+    let err = scene
+        .device
+        .create_graphics_pipeline(
+            &hammer::PipelineDescriptor {
+                tessellation: Some(hammer::TessellationStages::new(
+                    tcs.entry_point("main").unwrap(),
+                    tes.entry_point("main").unwrap(),
+                    0,
+                )),
+                ..desc
+            },
+            scene.subpass(),
+        )
+        .err()
+        .unwrap();
+    assert!(matches!(
+        err,
+        hammer::PipelineError::PatchControlPoints { count: 0, .. }
+    ));
+    assert_eq!(err.field(), Some("tessellation"));
+}
+
+#[test]
+fn geometry_shader_stage() {
+    let device = common::device_with_features(vulkano::device::Features {
+        geometry_shader: true,
+        ..vulkano::device::Features::none()
+    });
+    let scene = match device {
+        Some(device) => Scene::with_device(device),
+        None => return,
+    };
+    let (gs, fs) = (
+        gs::load(scene.device.clone()).unwrap(),
+        color_fs::load(scene.device.clone()).unwrap(),
+    );
+    let pipeline = scene.pipeline(&hammer::PipelineDescriptor {
+        geometry_shader: Some(gs.entry_point("main").unwrap()),
+        fragment_shader: fs.entry_point("main").unwrap(),
+        ..scene.desc()
+    });
+    let texel = scene.render(|pass| {
+        pass.bind_pipeline(&pipeline).draw(3, 1, 0, 0).unwrap();
+    });
+    assert_texel(texel, [255, 255, 0, 255]);
+}
+
+#[test]
+fn dynamic_states_have_to_be_declared() {
+    let scene = match Scene::new() {
+        Some(scene) => scene,
+        None => return,
+    };
+    let fixed = scene.pipeline(&scene.desc());
+    let dynamic = scene.pipeline(&hammer::PipelineDescriptor {
+        dynamic_states: hammer::DynamicStates {
+            depth_bias: true,
+            line_width: true,
+            blend_constants: true,
+        },
+        ..scene.desc()
+    });
+    scene.render(|pass| {
+        pass.bind_pipeline(&dynamic);
+        pass.set_depth_bias(1.0, 0.0, 1.0).unwrap();
+        pass.set_line_width(1.0).unwrap();
+        pass.set_blend_constants([0.5; 4]).unwrap();
+
+        // The bound pipeline is only checked in debug builds.
+        pass.bind_pipeline(&fixed);
+        if cfg!(debug_assertions) {
+            let not_dynamic = |result: Result<_, hammer::DynamicStateError>| match result {
+                Err(hammer::DynamicStateError::NotDynamic(state)) => state,
+                Err(err) => panic!("unexpected error: {}", err),
+                Ok(_) => panic!("the state is not dynamic"),
+            };
+            assert_eq!(
+                not_dynamic(pass.set_depth_bias(1.0, 0.0, 1.0).map(|_| ())),
+                DynamicState::DepthBias
+            );
+            assert_eq!(
+                not_dynamic(pass.set_line_width(1.0).map(|_| ())),
+                DynamicState::LineWidth
+            );
+            assert_eq!(
+                not_dynamic(pass.set_blend_constants([0.5; 4]).map(|_| ())),
+                DynamicState::BlendConstants
+            );
+        }
+    });
+}
+
+#[test]
+fn bind_group_checks_binding_types() {
+    let scene = match Scene::new() {
+        Some(scene) => scene,
+        None => return,
+    };
+    let fs = uniform_fs::load(scene.device.clone()).unwrap();
+    let pipeline = scene.pipeline(&hammer::PipelineDescriptor {
+        fragment_shader: fs.entry_point("main").unwrap(),
+        ..scene.desc()
+    });
+    let color = [0.0f32, 0.0, 1.0, 1.0];
+    let uniform = hammer::Buffer::uniform(scene.device.clone(), color).unwrap();
+    let storage = hammer::Buffer::storage(scene.device.clone(), &[color]).unwrap();
+
+    let err = hammer::BindGroup::builder(&pipeline, 0)
+        .storage_buffer(0, &storage)
+        .build()
+        .err()
+        .unwrap();
+    match err {
+        hammer::BindGroupError::TypeMismatch {
+            binding,
+            expected,
+            provided,
+        } => {
+            assert_eq!(binding, 0);
+            assert_eq!(expected, DescriptorType::UniformBuffer);
+            assert_eq!(provided, DescriptorType::StorageBuffer);
+        }
+        err => panic!("unexpected error: {}", err),
+    }
+    assert!(matches!(
+        hammer::BindGroup::builder(&pipeline, 0).build(),
+        Err(hammer::BindGroupError::MissingBinding { binding: 0, .. })
+    ));
+    assert!(matches!(
+        hammer::BindGroup::builder(&pipeline, 0)
+            .buffer(0, &uniform)
+            .buffer(1, &uniform)
+            .build(),
+        Err(hammer::BindGroupError::UnknownBinding(1))
+    ));
+    assert!(matches!(
+        hammer::BindGroup::builder(&pipeline, 1).build(),
+        Err(hammer::BindGroupError::NoSet(1))
+    ));
+
+    let group = hammer::BindGroup::builder(&pipeline, 0)
+        .buffer(0, &uniform)
+        .build()
+        .unwrap();
+    let texel = scene.render(|pass| {
+        pass.bind_pipeline(&pipeline)
+            .bind_group(0, &group)
+            .draw(3, 1, 0, 0)
+            .unwrap();
+    });
+    assert_texel(texel, [0, 0, 255, 255]);
+}
+
+#[test]
+fn bind_groups_reuse_cached_descriptor_sets() {
+    let scene = match Scene::new() {
+        Some(scene) => scene,
+        None => return,
+    };
+    let fs = uniform_fs::load(scene.device.clone()).unwrap();
+    let pipeline = scene.pipeline(&hammer::PipelineDescriptor {
+        fragment_shader: fs.entry_point("main").unwrap(),
+        ..scene.desc()
+    });
+    let blue = hammer::Buffer::uniform(scene.device.clone(), [0.0f32, 0.0, 1.0, 1.0]).unwrap();
+    let red = hammer::Buffer::uniform(scene.device.clone(), [1.0f32, 0.0, 0.0, 1.0]).unwrap();
+    let build = |buffer: &hammer::Buffer<[f32; 4]>| {
+        hammer::BindGroup::builder(&pipeline, 0)
+            .buffer(0, buffer)
+            .build()
+            .unwrap()
+    };
+    let cache = scene.device.descriptor_cache();
+
+    let first = build(&blue);
+    let second = build(&blue);
+    assert!(Arc::ptr_eq(first.descriptor_set(), second.descriptor_set()));
+    assert_eq!((cache.hits(), cache.misses()), (1, 1));
+    let other = build(&red);
+    assert!(!Arc::ptr_eq(first.descriptor_set(), other.descriptor_set()));
+    assert_eq!(cache.len(), 2);
+
+    cache.mark_dirty(blue.buffer());
+    let rebuilt = build(&blue);
+    assert!(!Arc::ptr_eq(
+        first.descriptor_set(),
+        rebuilt.descriptor_set()
+    ));
+    let uncached = hammer::BindGroup::builder(&pipeline, 0)
+        .buffer(0, &blue)
+        .uncached()
+        .build()
+        .unwrap();
+    assert!(!Arc::ptr_eq(
+        rebuilt.descriptor_set(),
+        uncached.descriptor_set()
+    ));
+
+    // Dropping a buffer drops its sets.
+    drop(red);
+    assert_eq!(cache.len(), 1);
+}
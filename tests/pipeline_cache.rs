@@ -0,0 +1,138 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use test03::hammer;
+use vulkano::render_pass::Subpass;
+
+mod common;
+
+mod vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        src: "
+            #version 450
+            const vec2 positions[3] = vec2[](vec2(-1.0, -1.0), vec2(3.0, -1.0), vec2(-1.0, 3.0));
+            void main() {
+                gl_Position = vec4(positions[gl_VertexIndex], 0.0, 1.0);
+            }
+        "
+    }
+}
+
+mod fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        src: "
+            #version 450
+            // Every value is a different pipeline for the driver to compile.
+            layout(constant_id = 0) const int OCTAVES = 1;
+            layout(location = 0) out vec4 f_color;
+            void main() {
+                vec2 p = gl_FragCoord.xy / 64.0;
+                float value = 0.0;
+                for (int i = 0; i < OCTAVES; i++) {
+                    p = mat2(0.8, 0.6, -0.6, 0.8) * p * 2.0;
+                    value += sin(p.x) * cos(p.y) / float(i + 1);
+                }
+                f_color = vec4(value, 0.0, 0.0, 1.0);
+            }
+        "
+    }
+}
+
+/// A file in the temporary directory, removed when dropped.
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str) -> Self {
+        let name = format!("hammer-{}-{}", std::process::id(), name);
+        Self(std::env::temp_dir().join(name))
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Creates a pipeline per value of `OCTAVES` in `octaves` and returns how long it took.
+fn create_pipelines(device: &hammer::Device, octaves: std::ops::Range<i32>) -> Duration {
+    let texture = common::texture(device);
+    let render_pass = texture.create_render_pass().unwrap();
+    let (vs, fs) = (
+        vs::load((*device).clone()).unwrap(),
+        fs::load((*device).clone()).unwrap(),
+    );
+    let start = Instant::now();
+    for octaves in octaves {
+        let desc = hammer::PipelineDescriptor {
+            fragment_specialization: hammer::SpecializationMap::new().set(0, octaves),
+            ..hammer::PipelineDescriptor::new(
+                vs.entry_point("main").unwrap(),
+                fs.entry_point("main").unwrap(),
+            )
+        };
+        device
+            .create_graphics_pipeline(&desc, Subpass::from(render_pass.clone(), 0).unwrap())
+            .unwrap();
+    }
+    start.elapsed()
+}
+
+#[test]
+fn corrupt_cache_files_are_ignored() {
+    let device = match common::device() {
+        Some(device) => device,
+        None => return,
+    };
+    if device.pipeline_cache().is_none() {
+        return;
+    }
+    let file = TempFile::new("corrupt.cache");
+    assert!(!device.load_pipeline_cache(&file.0).unwrap());
+
+    // A file sharing the stem of the cache is left alone by the save.
+    let sibling = TempFile::new("corrupt.tmp");
+    std::fs::write(&sibling.0, b"unrelated").unwrap();
+    create_pipelines(&device, 1..2);
+    device.save_pipeline_cache(&file.0).unwrap();
+    assert!(device.load_pipeline_cache(&file.0).unwrap());
+    assert_eq!(std::fs::read(&sibling.0).unwrap(), b"unrelated");
+
+    let mut data = std::fs::read(&file.0).unwrap();
+    let last = data.len() - 1;
+    data[last] ^= 0xff;
+    std::fs::write(&file.0, &data).unwrap();
+    assert!(!device.load_pipeline_cache(&file.0).unwrap());
+
+    std::fs::write(&file.0, b"not a pipeline cache").unwrap();
+    assert!(!device.load_pipeline_cache(&file.0).unwrap());
+}
+
+/// Run with `cargo test --release --test pipeline_cache -- --ignored --nocapture`. Drivers
+/// with their own shader cache on disk, e.g. Mesa, make cold runs after the first one warm too.
+#[test]
+#[ignore]
+fn warm_cache_speeds_up_pipeline_creation() {
+    const PIPELINES: std::ops::Range<i32> = 1..65;
+    let device = match common::device() {
+        Some(device) => device,
+        None => return,
+    };
+    let file = TempFile::new("warm.cache");
+    let cold = create_pipelines(&device, PIPELINES);
+    device.save_pipeline_cache(&file.0).unwrap();
+
+    // A new device starts with an empty cache.
+    let device = common::device().unwrap();
+    assert!(device.load_pipeline_cache(&file.0).unwrap());
+    let warm = create_pipelines(&device, PIPELINES);
+
+    println!(
+        "{} pipelines: cold {:?}, warm {:?}, {:.1}x faster",
+        PIPELINES.len(),
+        cold,
+        warm,
+        cold.as_secs_f64() / warm.as_secs_f64()
+    );
+}
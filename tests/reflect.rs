@@ -0,0 +1,215 @@
+// Reflection is only available with the `reflect` feature.
+#![cfg(feature = "reflect")]
+
+use bytemuck::{Pod, Zeroable};
+use test03::hammer;
+use vulkano::descriptor_set::layout::DescriptorType;
+use vulkano::format::Format;
+use vulkano::render_pass::Subpass;
+use vulkano::shader::ShaderStages;
+
+mod common;
+
+// The shaders of `examples/triangle_detailed.rs`, `examples/bloom.rs` and `examples/compute.rs`,
+// and the ones `examples/hot_reload.rs` loads.
+mod instanced_vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        src: "
+            #version 450
+            layout(location = 0) in vec2 position;
+            layout(location = 1) in vec2 offset;
+            void main() {
+                gl_Position = vec4(position + offset, 0.0, 1.0);
+            }
+        "
+    }
+}
+
+mod blur_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        src: "
+            #version 450
+            layout(location = 0) in vec2 uv;
+            layout(location = 0) out vec4 f_color;
+            layout(set = 0, binding = 0) uniform sampler2D glow;
+            void main() {
+                vec2 texel = 1.0 / vec2(textureSize(glow, 0));
+                vec3 sum = vec3(0.0);
+                for (int x = -2; x <= 2; x++) {
+                    for (int y = -2; y <= 2; y++) {
+                        sum += texture(glow, uv + vec2(x, y) * texel).rgb;
+                    }
+                }
+                f_color = vec4(sum / 25.0, 1.0);
+            }
+        "
+    }
+}
+
+mod cs {
+    vulkano_shaders::shader! {
+        ty: "compute",
+        src: "
+            #version 450
+            layout(local_size_x = 64) in;
+            // Index of the first element of the chunk.
+            layout(push_constant) uniform Chunk {
+                uint offset;
+            };
+            layout(set = 0, binding = 0) buffer Data {
+                uint data[];
+            };
+            void main() {
+                uint index = offset + gl_GlobalInvocationID.x;
+                if (index < data.length()) {
+                    data[index] = index * 2;
+                }
+            }
+        "
+    }
+}
+
+mod hot_reload_vs {
+    vulkano_shaders::shader! {
+        ty: "vertex",
+        path: "shaders/hot_reload.vert"
+    }
+}
+
+mod hot_reload_fs {
+    vulkano_shaders::shader! {
+        ty: "fragment",
+        path: "shaders/hot_reload.frag"
+    }
+}
+
+#[test]
+fn reflects_the_example_shaders() {
+    let device = match common::device() {
+        Some(device) => device,
+        None => return,
+    };
+
+    let interface = device
+        .reflect_shader(&instanced_vs::load(device.clone()).unwrap())
+        .unwrap();
+    let inputs: Vec<_> = interface
+        .vertex_inputs
+        .iter()
+        .map(|input| (input.location, input.name.as_deref(), input.format))
+        .collect();
+    assert_eq!(
+        inputs,
+        [
+            (0, Some("position"), Format::R32G32_SFLOAT),
+            (1, Some("offset"), Format::R32G32_SFLOAT),
+        ]
+    );
+    assert!(interface.descriptor_bindings.is_empty());
+    assert!(interface.push_constant_ranges.is_empty());
+
+    let interface = device
+        .reflect_shader(&blur_fs::load(device.clone()).unwrap())
+        .unwrap();
+    assert!(interface.vertex_inputs.is_empty());
+    assert_eq!(interface.descriptor_bindings.len(), 1);
+    let glow = &interface.descriptor_bindings[&0];
+    assert_eq!(glow.len(), 1);
+    assert_eq!(glow[0].binding, 0);
+    assert_eq!(
+        glow[0].descriptor_types,
+        [DescriptorType::CombinedImageSampler]
+    );
+    assert_eq!(glow[0].descriptor_count, 1);
+    assert_eq!(
+        glow[0].stages,
+        ShaderStages {
+            fragment: true,
+            ..ShaderStages::none()
+        }
+    );
+
+    let interface = device
+        .reflect_shader(&cs::load(device.clone()).unwrap())
+        .unwrap();
+    let data = &interface.descriptor_bindings[&0];
+    assert_eq!(data[0].binding, 0);
+    assert_eq!(data[0].descriptor_types, [DescriptorType::StorageBuffer]);
+    assert_eq!(interface.push_constant_ranges.len(), 1);
+    let chunk = &interface.push_constant_ranges[0];
+    assert_eq!((chunk.offset, chunk.size), (0, 4));
+    assert!(chunk.stages.compute);
+
+    // The hot reloaded triangle generates its vertices.
+    let interface = device
+        .reflect_shader(&hot_reload_vs::load(device.clone()).unwrap())
+        .unwrap();
+    assert!(interface.vertex_inputs.is_empty());
+    let interface = device
+        .reflect_shader(&hot_reload_fs::load(device.clone()).unwrap())
+        .unwrap();
+    assert!(interface.descriptor_bindings.is_empty());
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Zeroable, Pod)]
+struct Vertex {
+    // Named differently than the inputs of the shader.
+    corner: [f32; 2],
+    shift: [f32; 2],
+}
+vulkano::impl_vertex!(Vertex, corner, shift);
+
+#[test]
+fn vertex_layout_by_location() {
+    let device = match common::device() {
+        Some(device) => device,
+        None => return,
+    };
+    let texture = common::texture(&device);
+    let render_pass = texture.create_render_pass().unwrap();
+    let subpass = Subpass::from(render_pass, 0).unwrap();
+    let (vs, fs) = (
+        instanced_vs::load(device.clone()).unwrap(),
+        hot_reload_fs::load(device.clone()).unwrap(),
+    );
+    let desc = hammer::PipelineDescriptor::new(
+        vs.entry_point("main").unwrap(),
+        fs.entry_point("main").unwrap(),
+    );
+
+    let layout = hammer::VertexLayout::from_reflection::<Vertex>(&[(0, "corner"), (1, "shift")]);
+    device
+        .create_graphics_pipeline(
+            &hammer::PipelineDescriptor {
+                vertex_layout: layout,
+                ..desc.clone()
+            },
+            subpass.clone(),
+        )
+        .unwrap();
+
+    // `shift` is given for the wrong location, so nothing feeds location 1.
+    let layout = hammer::VertexLayout::from_reflection::<Vertex>(&[(0, "corner"), (2, "shift")]);
+    let err = device
+        .create_graphics_pipeline(
+            &hammer::PipelineDescriptor {
+                vertex_layout: layout,
+                ..desc
+            },
+            subpass,
+        )
+        .err()
+        .unwrap();
+    assert_eq!(err.field(), Some("vertex_layout"));
+    match err {
+        hammer::PipelineError::VertexInputs(mismatches) => {
+            assert_eq!(mismatches.len(), 1);
+            assert_eq!(mismatches[0].location, 1);
+            assert_eq!(mismatches[0].name.as_deref(), Some("offset"));
+        }
+        err => panic!("unexpected error: {}", err),
+    }
+}
@@ -0,0 +1,82 @@
+use test03::hammer;
+
+mod common;
+
+const COMPUTE: &[u8] = include_bytes!("data/compute.spv");
+
+#[test]
+fn create_shader_module_from_blob() {
+    let device = match common::device() {
+        Some(device) => device,
+        None => return,
+    };
+    let module = device.create_shader_module(COMPUTE).unwrap();
+    device
+        .create_compute_pipeline(&module, "main", &hammer::SpecializationMap::new())
+        .unwrap();
+}
+
+#[test]
+fn create_shader_module_rejects_malformed() {
+    let device = match common::device() {
+        Some(device) => device,
+        None => return,
+    };
+    assert!(matches!(
+        device.create_shader_module(&COMPUTE[..COMPUTE.len() - 1]),
+        Err(hammer::ShaderError::InvalidLength(_))
+    ));
+    assert!(matches!(
+        device.create_shader_module(&[0; 32]),
+        Err(hammer::ShaderError::InvalidMagic(0))
+    ));
+}
+
+// `vs_main` and `fs_main` in one module.
+const MULTI_ENTRY: &[u8] = include_bytes!("data/multi_entry.spv");
+
+#[test]
+fn pipeline_from_multi_entry_module() {
+    let device = match common::device() {
+        Some(device) => device,
+        None => return,
+    };
+    let shader = device.create_shader(MULTI_ENTRY).unwrap();
+    let names: Vec<_> = shader
+        .entry_points()
+        .iter()
+        .map(|entry_point| entry_point.name.as_str())
+        .collect();
+    assert_eq!(names, ["vs_main", "fs_main"]);
+
+    let texture = common::texture(&device);
+    let render_pass = texture.create_render_pass().unwrap();
+    let desc = hammer::PipelineDescriptor::from_shader(&shader, "vs_main", "fs_main").unwrap();
+    device
+        .create_graphics_pipeline(
+            &desc,
+            vulkano::render_pass::Subpass::from(render_pass, 0).unwrap(),
+        )
+        .unwrap();
+}
+
+#[test]
+fn missing_entry_point() {
+    let device = match common::device() {
+        Some(device) => device,
+        None => return,
+    };
+    let shader = device.create_shader(MULTI_ENTRY).unwrap();
+    let err = hammer::PipelineDescriptor::from_shader(&shader, "main", "fs_main")
+        .err()
+        .unwrap();
+    assert_eq!(err.name, "main");
+    assert_eq!(err.available, shader.entry_points());
+
+    // The names exist, but for the other stage.
+    let err = shader
+        .entry_point("fs_main", vulkano::shader::spirv::ExecutionModel::Vertex)
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("`fs_main` (Fragment)"));
+}
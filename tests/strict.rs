@@ -0,0 +1,10 @@
+// With `strict` misuse panics instead of being logged, see `tests/misuse.rs`.
+#![cfg(feature = "strict")]
+
+use test03::hammer;
+
+#[test]
+#[should_panic(expected = "was dropped without being consumed; consume it")]
+fn dropped_guard_panics() {
+    drop(hammer::MisuseGuard::new("Thing", "consume it"));
+}
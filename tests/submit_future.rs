@@ -0,0 +1,74 @@
+use std::time::Duration;
+use test03::hammer;
+use vulkano::descriptor_set::{PersistentDescriptorSet, WriteDescriptorSet};
+use vulkano::pipeline::Pipeline;
+
+mod common;
+
+mod cs {
+    vulkano_shaders::shader! {
+        ty: "compute",
+        src: "
+            #version 450
+            layout(local_size_x = 64) in;
+            layout(push_constant) uniform Work {
+                uint iterations;
+            };
+            layout(set = 0, binding = 0) buffer Data {
+                uint data[];
+            };
+            void main() {
+                uint index = gl_GlobalInvocationID.x;
+                uint hash = index;
+                for (uint i = 0; i < iterations; i++) {
+                    hash = hash * 1664525u + 1013904223u;
+                }
+                data[index] = hash;
+            }
+        "
+    }
+}
+
+#[test]
+fn wait_with_timeout_while_pending() {
+    const COUNT: u32 = 256 * 64;
+    const ITERATIONS: u32 = 1 << 14;
+
+    let device = match common::device() {
+        Some(device) => device,
+        None => return,
+    };
+    let queue = device.compute_queue().unwrap().clone();
+    let cs = cs::load(device.clone()).unwrap();
+    let pipeline = device
+        .create_compute_pipeline(&cs, "main", &hammer::SpecializationMap::new())
+        .unwrap();
+    let buffer = hammer::Buffer::<u32>::storage(device.clone(), &[0; COUNT as usize]).unwrap();
+    let set = PersistentDescriptorSet::new(
+        pipeline.layout().set_layouts()[0].clone(),
+        [WriteDescriptorSet::buffer(0, buffer.buffer().clone())],
+    )
+    .unwrap();
+
+    let mut encoder = device.create_command_encoder(&queue).unwrap();
+    encoder
+        .dispatch(&pipeline, set, &ITERATIONS, [COUNT / 64, 1, 1])
+        .unwrap();
+    let future = queue.submit(encoder.finish().unwrap()).unwrap();
+
+    // The dispatch takes far longer than it takes to get here.
+    assert!(!future.wait(Some(Duration::ZERO)).unwrap());
+    assert!(!future.is_complete());
+    assert!(future.wait(None).unwrap());
+    assert!(future.is_complete());
+
+    let expected = |index: u32| {
+        (0..ITERATIONS).fold(index, |hash, _| {
+            hash.wrapping_mul(1664525).wrapping_add(1013904223)
+        })
+    };
+    let data = buffer.read(0..COUNT as usize).unwrap();
+    for index in [0, 1, COUNT / 2, COUNT - 1] {
+        assert_eq!(data[index as usize], expected(index));
+    }
+}
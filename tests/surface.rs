@@ -0,0 +1,72 @@
+use std::sync::Arc;
+use test03::hammer;
+use vulkano::instance::{Instance, InstanceCreateInfo, InstanceExtensions};
+use vulkano::swapchain::{Surface, SurfaceApi};
+use vulkano::VulkanObject;
+
+/// A vulkano surface that is not backed by a window, `None` where `VK_EXT_headless_surface` is
+/// not available, which skips the test.
+fn headless_surface() -> Option<Arc<Surface<hammer::DummyWindow>>> {
+    let supported = match InstanceExtensions::supported_by_core() {
+        Ok(supported) => supported,
+        Err(_) => {
+            eprintln!("skipped, no Vulkan implementation is available");
+            return None;
+        }
+    };
+    if !supported.ext_headless_surface {
+        eprintln!("skipped, VK_EXT_headless_surface is not supported");
+        return None;
+    }
+    let instance = hammer::Instance::new(InstanceCreateInfo {
+        enabled_extensions: InstanceExtensions {
+            khr_surface: true,
+            ext_headless_surface: true,
+            ..InstanceExtensions::none()
+        },
+        ..Default::default()
+    });
+    let instance: Arc<Instance> = (&instance).into();
+    let handle = unsafe {
+        let info = ash::vk::HeadlessSurfaceCreateInfoEXT::default();
+        let mut handle = std::mem::MaybeUninit::uninit();
+        let result = (instance
+            .fns()
+            .ext_headless_surface
+            .create_headless_surface_ext)(
+            instance.internal_object(),
+            &info,
+            std::ptr::null(),
+            handle.as_mut_ptr(),
+        );
+        assert_eq!(result, ash::vk::Result::SUCCESS);
+        handle.assume_init()
+    };
+    // vulkano has no API for headless surfaces, it only treats Win32 surfaces differently.
+    let surface = unsafe {
+        Surface::from_raw_surface(
+            instance,
+            handle,
+            SurfaceApi::DisplayPlane,
+            hammer::DummyWindow,
+        )
+    };
+    Some(Arc::new(surface))
+}
+
+#[test]
+fn wraps_a_vulkano_surface() {
+    let vulkano_surface = match headless_surface() {
+        Some(surface) => surface,
+        None => return,
+    };
+    for surface in [
+        hammer::Surface::from_vulkano(vulkano_surface.clone()),
+        hammer::Surface::from_surface(vulkano_surface.clone()),
+    ] {
+        assert!(Arc::ptr_eq(&surface.surface, &vulkano_surface));
+        assert!(surface.swapchain.is_none());
+        assert!(surface.is_valid());
+        assert_eq!(surface.image_format(), None);
+    }
+}